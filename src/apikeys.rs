@@ -0,0 +1,252 @@
+//! Scoped API keys, mapping a bearer token to a wallet.
+//!
+//! An API key grants a subset of the operations on a wallet (see
+//! [`ApiScope`]), so integrations don't have to hold the wallet secret. The
+//! wallet secret is stored encrypted with a key derived from the API key,
+//! and the wallet keeps the index of its keys: revoking a key only removes
+//! it from the index.
+use std::collections::{BTreeMap, BTreeSet};
+
+use bitcoin_30::secp256k1::SecretKey;
+use postcard::{from_bytes, to_allocvec};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+    carbonado::{retrieve, store},
+    constants::storage_keys::{API_KEYS, API_KEY_GRANT},
+};
+
+/// Prefix of the API keys, other bearer tokens are wallet secrets
+pub const API_KEY_PREFIX: &str = "bmk_";
+
+const API_KEY_CONTEXT: &str = "bitmask-core 2023 api key";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ApiKeyError {
+    /// API key is unknown or was revoked
+    UnknownKey,
+    /// API key requires at least one scope
+    NoScopes,
+    /// API key '{0}' not found
+    NotFound(String),
+    /// API key scope '{0}' is required for this operation
+    Forbidden(ApiScope),
+    /// API keys cannot be read. {0}
+    Retrieve(String),
+    /// API keys cannot be written. {0}
+    Write(String),
+}
+
+/// Operations granted by an API key
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+#[display(lowercase)]
+pub enum ApiScope {
+    /// Read contracts, interfaces, watchers and transfers
+    Read,
+    /// Create invoices, pay and accept transfers
+    Pay,
+    /// Issue and import contracts
+    Issue,
+    /// Every operation, including API keys management
+    Admin,
+}
+
+impl ApiScope {
+    /// Whether the scope grants the operations of the `required` scope.
+    /// Every scope can read, and only admin grants other scopes.
+    pub fn grants(self, required: ApiScope) -> bool {
+        self == required || self == ApiScope::Admin || required == ApiScope::Read
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyRequest {
+    /// Label of the key (to recognize it when listing)
+    #[serde(default)]
+    pub label: String,
+    /// Scopes granted by the key
+    pub scopes: BTreeSet<ApiScope>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyDetail {
+    /// Key ID
+    pub id: String,
+    /// Label of the key
+    pub label: String,
+    /// Scopes granted by the key
+    pub scopes: BTreeSet<ApiScope>,
+    /// creation date (timestamp)
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    /// Bearer token (only returned when the key is issued)
+    pub token: String,
+    /// Key details
+    pub key: ApiKeyDetail,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    /// Keys of the wallet (without their tokens)
+    pub keys: Vec<ApiKeyDetail>,
+}
+
+// Keys of a wallet (by key id)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct ApiKeyIndex {
+    keys: BTreeMap<String, ApiKeyDetail>,
+}
+
+// Wallet secret, stored with the key derived from the API key
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct ApiKeyGrant {
+    wallet_sk: String,
+}
+
+/// Wallet and scopes granted by a bearer token
+pub struct ApiKeyAccess {
+    pub wallet_sk: Zeroizing<String>,
+    pub scopes: BTreeSet<ApiScope>,
+}
+
+impl ApiKeyAccess {
+    /// Check the token grants the `required` scope
+    pub fn require(&self, required: ApiScope) -> Result<(), ApiKeyError> {
+        if self.scopes.iter().any(|scope| scope.grants(required)) {
+            Ok(())
+        } else {
+            Err(ApiKeyError::Forbidden(required))
+        }
+    }
+}
+
+/// Whether the bearer token is an API key (instead of a wallet secret)
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+fn api_key_id(token: &str) -> String {
+    blake3::hash(token.as_bytes()).to_hex()[..16].to_string()
+}
+
+// Secret key encrypting the grant of the API key, only known by the key holder
+fn api_key_sk(token: &str) -> Result<Zeroizing<String>, ApiKeyError> {
+    let derived = Zeroizing::new(blake3::derive_key(API_KEY_CONTEXT, token.as_bytes()));
+    let sk = SecretKey::from_slice(derived.as_ref()).map_err(|_| ApiKeyError::UnknownKey)?;
+    Ok(Zeroizing::new(hex::encode(sk.secret_bytes())))
+}
+
+async fn retrieve_index(sk: &str) -> Result<ApiKeyIndex, ApiKeyError> {
+    let (data, _) = retrieve(sk, API_KEYS, vec![])
+        .await
+        .map_err(|op| ApiKeyError::Retrieve(op.to_string()))?;
+    if data.is_empty() {
+        return Ok(ApiKeyIndex::default());
+    }
+
+    from_bytes(&data).map_err(|op| ApiKeyError::Retrieve(op.to_string()))
+}
+
+async fn store_index(sk: &str, index: &ApiKeyIndex) -> Result<(), ApiKeyError> {
+    let data = to_allocvec(index).map_err(|op| ApiKeyError::Write(op.to_string()))?;
+    store(sk, API_KEYS, &data, true, None)
+        .await
+        .map_err(|op| ApiKeyError::Write(op.to_string()))
+}
+
+/// Issue a new API key for the wallet, granting the requested scopes
+pub async fn issue_api_key(
+    sk: &str,
+    request: ApiKeyRequest,
+) -> Result<ApiKeyResponse, ApiKeyError> {
+    let ApiKeyRequest { label, scopes } = request;
+    if scopes.is_empty() {
+        return Err(ApiKeyError::NoScopes);
+    }
+
+    let mut rng = StdRng::from_entropy();
+    let secret = Zeroizing::new(rng.gen::<[u8; 32]>());
+    let token = format!("{API_KEY_PREFIX}{}", hex::encode(secret.as_ref()));
+
+    let grant = ApiKeyGrant {
+        wallet_sk: sk.to_string(),
+    };
+    let data =
+        Zeroizing::new(to_allocvec(&grant).map_err(|op| ApiKeyError::Write(op.to_string()))?);
+    store(&api_key_sk(&token)?, API_KEY_GRANT, &data, true, None)
+        .await
+        .map_err(|op| ApiKeyError::Write(op.to_string()))?;
+
+    let key = ApiKeyDetail {
+        id: api_key_id(&token),
+        label,
+        scopes,
+        created_at: chrono::Local::now().naive_utc().timestamp(),
+    };
+    let mut index = retrieve_index(sk).await?;
+    index.keys.insert(key.id.clone(), key.clone());
+    store_index(sk, &index).await?;
+
+    Ok(ApiKeyResponse { token, key })
+}
+
+/// API keys of the wallet
+pub async fn list_api_keys(sk: &str) -> Result<ApiKeysResponse, ApiKeyError> {
+    let index = retrieve_index(sk).await?;
+    Ok(ApiKeysResponse {
+        keys: index.keys.into_values().collect(),
+    })
+}
+
+/// Revoke an API key of the wallet, the token is rejected afterwards
+pub async fn revoke_api_key(sk: &str, id: &str) -> Result<ApiKeyDetail, ApiKeyError> {
+    let mut index = retrieve_index(sk).await?;
+    let key = index
+        .keys
+        .remove(id)
+        .ok_or_else(|| ApiKeyError::NotFound(id.to_string()))?;
+    store_index(sk, &index).await?;
+
+    Ok(key)
+}
+
+/// Wallet and scopes granted by an API key (issued and not revoked)
+pub async fn resolve_api_key(token: &str) -> Result<ApiKeyAccess, ApiKeyError> {
+    if !is_api_key(token) {
+        return Err(ApiKeyError::UnknownKey);
+    }
+
+    let (data, _) = retrieve(&api_key_sk(token)?, API_KEY_GRANT, vec![])
+        .await
+        .map_err(|_| ApiKeyError::UnknownKey)?;
+    let data = Zeroizing::new(data);
+    if data.is_empty() {
+        return Err(ApiKeyError::UnknownKey);
+    }
+    let grant: ApiKeyGrant = from_bytes(&data).map_err(|_| ApiKeyError::UnknownKey)?;
+    let wallet_sk = Zeroizing::new(grant.wallet_sk.clone());
+
+    let index = retrieve_index(&wallet_sk).await?;
+    let key = index
+        .keys
+        .get(&api_key_id(token))
+        .ok_or(ApiKeyError::UnknownKey)?;
+
+    Ok(ApiKeyAccess {
+        wallet_sk,
+        scopes: key.scopes.clone(),
+    })
+}
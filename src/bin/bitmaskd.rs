@@ -2,89 +2,148 @@
 #![cfg(feature = "server")]
 #![cfg(not(target_arch = "wasm32"))]
 use std::{
+    collections::{BTreeMap, HashSet},
+    convert::Infallible,
     env,
     fs::OpenOptions,
+    future::Future,
     io::ErrorKind,
     net::SocketAddr,
     path,
     str::FromStr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
 use amplify::hex::FromHex;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use axum::{
-    body::Bytes,
-    extract::Path,
+    async_trait,
+    body::{boxed, Body, Bytes, Full, HttpBody, StreamBody},
+    extract::{FromRequest, FromRequestParts, Path, Query},
     headers::{authorization::Bearer, Authorization, CacheControl},
-    http::StatusCode,
+    http::{
+        header::{ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE},
+        request::Parts,
+        HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode,
+    },
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{delete, get, post, put},
-    Json, Router, TypedHeader,
+    routing::{delete, get, head, post, put},
+    BoxError, Json, Router, TypedHeader,
 };
-use bitcoin_30::secp256k1::{ecdh::SharedSecret, PublicKey, SecretKey};
+use bitcoin_30::secp256k1::{ecdh::SharedSecret, PublicKey, Secp256k1, SecretKey};
+use futures::StreamExt;
 use log::{debug, error, info};
+use once_cell::sync::{Lazy, OnceCell};
 use rgb_core::{
-    bitcoin::{save_mnemonic, sign_and_publish_psbt_file},
+    apikeys::{
+        is_api_key, issue_api_key, list_api_keys, resolve_api_key, revoke_api_key, ApiKeyError,
+        ApiKeyRequest, ApiScope,
+    },
+    bitcoin::{response_message, save_mnemonic, sign_response},
+    cache::{cache_stats, CacheStats, LruCache},
     carbonado::{
-        auctions_retrieve, auctions_store, handle_file, marketplace_retrieve, marketplace_store,
-        metrics, store,
+        auctions_retrieve, auctions_store, clear_namespace, delete_namespaced_file,
+        error::CarbonadoError, handle_file, handle_namespaced_file, list_namespace,
+        marketplace_retrieve, marketplace_store, metrics, store, CARBONADO_FILE_ROUTE,
+        CARBONADO_FORCE_ROUTE, CARBONADO_METADATA_ROUTE, CARBONADO_NAMESPACED_FILE_ROUTE,
+        CARBONADO_NAMESPACE_ROUTE,
     },
     constants::{
-        get_marketplace_nostr_key, get_marketplace_seed, get_network, get_udas_utxo, switch_network,
+        get_marketplace_nostr_key, get_marketplace_seed, get_network, get_udas_utxo,
+        switch_network, with_network,
     },
+    keystore::{key_endpoint_enabled, KeySource},
     proxy::{
         handle_file as proxy_handle_file, proxy_consig_retrieve, proxy_consig_store,
         proxy_media_data_store, proxy_media_retrieve, proxy_metadata_retrieve,
     },
     rgb::{
-        accept_transfer,
+        accept_transfer, capabilities,
         carbonado::retrieve_auctions_offers,
-        clear_watcher as rgb_clear_watcher, create_invoice, create_psbt, create_watcher,
-        full_transfer_asset, get_contract, import as rgb_import, issue_contract, list_contracts,
-        list_interfaces, list_schemas, list_transfers as list_rgb_transfers, reissue_contract,
+        clear_watcher as rgb_clear_watcher,
+        constants::{RGB_CONFIRMATION_INTERVAL, RGB_REINDEX_INTERVAL},
+        create_invoice, create_psbt, create_watcher, fetch_schema, full_transfer_asset,
+        get_contract, get_contract_terms, import as rgb_import,
+        index::reindex_all,
+        issue_contract, list_contracts, list_contracts_ndjson, list_interfaces, list_schemas,
+        list_transfers as list_rgb_transfers, rebind_invoice, rebroadcast_pending,
+        recover_accept_transfers, reindex as rgb_reindex, reissue_contract,
         remove_transfer as remove_rgb_transfer, save_transfer as save_rgb_transfer,
+        sign_and_publish_transfer_psbt, simulate_accept, storage_stats,
         structs::{
             RgbProxyConsigCarbonadoReq, RgbProxyConsigFileReq, RgbProxyConsigUpload,
             RgbProxyMediaCarbonadoReq, RgbProxyMediaFileReq,
         },
-        transfer_asset, watcher_address, watcher_details as rgb_watcher_details,
-        watcher_next_address, watcher_next_utxo, watcher_utxo,
+        transfer::check_consignment_structure,
+        transfer_asset_until, transfer_history as rgb_transfer_history, verify_transfers,
+        watcher_address, watcher_details as rgb_watcher_details, watcher_next_address,
+        watcher_next_utxo, watcher_utxo, TransferError,
     },
     structs::{
-        AcceptRequest, FileMetadata, FullRgbTransferRequest, ImportRequest, InvoiceRequest,
-        IssueRequest, MediaEncode, MediaExtractRequest, MediaItemRequest, PsbtFeeRequest,
-        PsbtRequest, ReIssueRequest, RgbRemoveTransferRequest, RgbSaveTransferRequest,
+        AcceptRequest, AcceptResponse, BatchRgbTransferResponse, FileMetadata,
+        FullRgbTransferRequest, ImportRequest, InvoicePaymentStatus, InvoiceRequest, IssueRequest,
+        MediaEncode, MediaExtractRequest, MediaItemRequest, PsbtFeeRequest, PsbtRequest,
+        ReIssueRequest, RebindInvoiceRequest, RgbRemoveTransferRequest, RgbSaveTransferRequest,
         RgbTransferRequest, SecretString, SelfFullRgbTransferRequest, SelfInvoiceRequest,
         SelfIssueRequest, SignPsbtRequest, WatcherRequest,
     },
+    util::{cbor_decode, cbor_encode},
+    webhooks::{WebhookEvent, Webhooks, WEBHOOKS_VAR},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{
+    fs,
+    time::{sleep, timeout_at},
 };
-use tokio::{fs, time::sleep};
 use tower_http::cors::CorsLayer;
+use zeroize::Zeroizing;
 
 async fn issue(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(request): Json<IssueRequest>,
+    format: WireFormat,
+    Wire(request): Wire<IssueRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /issue {request:?}");
 
     let nostr_hex_sk = auth.token();
     let issue_res = issue_contract(nostr_hex_sk, request).await?;
-    Ok((StatusCode::OK, Json(issue_res)))
+    notify_webhooks(
+        WebhookEvent::IssuanceComplete,
+        nostr_hex_sk,
+        issue_res.clone(),
+    )
+    .await;
+    Ok((StatusCode::OK, format.reply(issue_res)))
 }
 
 async fn reissue(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(request): Json<ReIssueRequest>,
+    format: WireFormat,
+    Wire(request): Wire<ReIssueRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /reissue {request:?}");
 
     let nostr_hex_sk = auth.token();
     let issue_res = reissue_contract(nostr_hex_sk, request).await?;
-    Ok((StatusCode::OK, Json(issue_res)))
+    notify_webhooks(
+        WebhookEvent::IssuanceComplete,
+        nostr_hex_sk,
+        issue_res.clone(),
+    )
+    .await;
+    Ok((StatusCode::OK, format.reply(issue_res)))
 }
 
-async fn self_issue(Json(issue): Json<SelfIssueRequest>) -> Result<impl IntoResponse, AppError> {
+async fn self_issue(
+    format: WireFormat,
+    Wire(issue): Wire<SelfIssueRequest>,
+) -> Result<impl IntoResponse, AppError> {
     info!("POST /self_issue {issue:?}");
     let issuer_keys = save_mnemonic(
         &SecretString(get_marketplace_seed().await),
@@ -103,27 +162,44 @@ async fn self_issue(Json(issue): Json<SelfIssueRequest>) -> Result<impl IntoResp
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta: issue.meta,
+        terms: None,
     };
 
     let issue_res = issue_contract(sk, request).await?;
+    notify_webhooks(WebhookEvent::IssuanceComplete, sk, issue_res.clone()).await;
 
-    Ok((StatusCode::OK, Json(issue_res)))
+    Ok((StatusCode::OK, format.reply(issue_res)))
 }
 
 async fn invoice(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(invoice): Json<InvoiceRequest>,
+    format: WireFormat,
+    Wire(invoice): Wire<InvoiceRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /invoice {invoice:?}");
 
     let nostr_hex_sk = auth.token();
     let invoice_res = create_invoice(nostr_hex_sk, invoice).await?;
 
-    Ok((StatusCode::OK, Json(invoice_res)))
+    Ok((StatusCode::OK, format.reply(invoice_res)))
+}
+
+async fn rebind(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+    Wire(request): Wire<RebindInvoiceRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /invoice/rebind {request:?}");
+
+    let nostr_hex_sk = auth.token();
+    let invoice_res = rebind_invoice(nostr_hex_sk, &request.invoice).await?;
+
+    Ok((StatusCode::OK, format.reply(invoice_res)))
 }
 
 async fn self_invoice(
-    Json(self_invoice): Json<SelfInvoiceRequest>,
+    format: WireFormat,
+    Wire(self_invoice): Wire<SelfInvoiceRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /self_invoice {self_invoice:?}");
 
@@ -142,15 +218,18 @@ async fn self_invoice(
         amount: "1".to_string(),
         seal: invoice_seal.to_owned(),
         params: self_invoice.params,
+        iface_version: None,
+        origin: false,
     };
     let invoice_res = create_invoice(nostr_hex_sk, invoice).await?;
 
-    Ok((StatusCode::OK, Json(invoice_res)))
+    Ok((StatusCode::OK, format.reply(invoice_res)))
 }
 
 async fn _psbt(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(psbt_req): Json<PsbtRequest>,
+    format: WireFormat,
+    Wire(psbt_req): Wire<PsbtRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /psbt {psbt_req:?}");
 
@@ -158,36 +237,65 @@ async fn _psbt(
 
     let psbt_res = create_psbt(nostr_hex_sk, psbt_req).await?;
 
-    Ok((StatusCode::OK, Json(psbt_res)))
+    Ok((StatusCode::OK, format.reply(psbt_res)))
 }
 
 async fn _sign_psbt(
-    TypedHeader(_auth): TypedHeader<Authorization<Bearer>>,
-    Json(psbt_req): Json<SignPsbtRequest>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+    Wire(psbt_req): Wire<SignPsbtRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /sign {psbt_req:?}");
-    let psbt_res = sign_and_publish_psbt_file(psbt_req).await?;
 
-    Ok((StatusCode::OK, Json(psbt_res)))
+    let nostr_hex_sk = auth.token();
+
+    let psbt_res = sign_and_publish_transfer_psbt(nostr_hex_sk, psbt_req).await?;
+
+    Ok((StatusCode::OK, format.reply(psbt_res)))
 }
 
 #[axum_macros::debug_handler]
 async fn pay(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(pay_req): Json<RgbTransferRequest>,
+    timeout: RequestTimeout,
+    format: WireFormat,
+    Wire(pay_req): Wire<RgbTransferRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /pay {pay_req:?}");
 
-    let nostr_hex_sk = auth.token();
+    let nostr_hex_sk = auth.token().to_owned();
+    watch_confirmations(&nostr_hex_sk).await;
 
-    let transfer_res = transfer_asset(nostr_hex_sk, pay_req).await?;
+    // The transfer runs in its own task (on the network of the request), so a
+    // timed out request never drops it halfway through storing. The commit and
+    // the deadline race through the gate: once the deadline wins nothing is
+    // stored, and once the commit wins the transfer is returned, even late,
+    // so clients never retry (and pay twice) a committed transfer.
+    let network = get_network().await;
+    let gate = CommitGate::default();
+    let commit_gate = gate.clone();
+    let mut task = tokio::spawn(async move {
+        let transfer = transfer_asset_until(&nostr_hex_sk, pay_req, || {
+            !timeout.expired() && commit_gate.commit()
+        });
+        with_network(&network, transfer).await
+    });
+    let transfer_res = match timeout.0 {
+        Some(deadline) => match timeout_at(deadline.into(), &mut task).await {
+            Ok(transfer_res) => transfer_res???,
+            Err(_) if gate.expire() => return Err(DeadlineExceeded.into()),
+            Err(_) => task.await???,
+        },
+        None => task.await???,
+    };
 
-    Ok((StatusCode::OK, Json(transfer_res)))
+    Ok((StatusCode::OK, format.reply(transfer_res)))
 }
 
 #[axum_macros::debug_handler]
 async fn self_pay(
-    Json(self_pay_req): Json<SelfFullRgbTransferRequest>,
+    format: WireFormat,
+    Wire(self_pay_req): Wire<SelfFullRgbTransferRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /self_pay {self_pay_req:?}");
 
@@ -211,26 +319,48 @@ async fn self_pay(
         fee,
         change_terminal: self_pay_req.terminal,
         bitcoin_changes: self_pay_req.bitcoin_changes,
+        from_seals: vec![],
+        max_fee: None,
+        receipt: true,
     };
 
     let transfer_res = full_transfer_asset(nostr_hex_sk, request).await?;
+    watch_confirmations(nostr_hex_sk).await;
 
-    Ok((StatusCode::OK, Json(transfer_res)))
+    Ok((StatusCode::OK, format.reply(transfer_res)))
 }
 
 async fn accept(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(accept_req): Json<AcceptRequest>,
+    format: WireFormat,
+    Wire(accept_req): Wire<AcceptRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /accept {accept_req:?}");
 
     let nostr_hex_sk = auth.token();
     let transfer_res = accept_transfer(nostr_hex_sk, accept_req).await?;
+    notify_invoice_paid(nostr_hex_sk, &transfer_res).await;
 
-    Ok((StatusCode::OK, Json(transfer_res)))
+    Ok((StatusCode::OK, format.reply(transfer_res)))
 }
 
-async fn self_accept(Json(accept_req): Json<AcceptRequest>) -> Result<impl IntoResponse, AppError> {
+async fn simulate(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+    Wire(accept_req): Wire<AcceptRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /accept/simulate {accept_req:?}");
+
+    let nostr_hex_sk = auth.token();
+    let simulate_res = simulate_accept(nostr_hex_sk, accept_req.consignment).await?;
+
+    Ok((StatusCode::OK, format.reply(simulate_res)))
+}
+
+async fn self_accept(
+    format: WireFormat,
+    Wire(accept_req): Wire<AcceptRequest>,
+) -> Result<impl IntoResponse, AppError> {
     info!("POST /self_accept {accept_req:?}");
 
     let issuer_keys = save_mnemonic(
@@ -241,39 +371,80 @@ async fn self_accept(Json(accept_req): Json<AcceptRequest>) -> Result<impl IntoR
 
     let nostr_hex_sk = issuer_keys.private.nostr_prv.as_ref();
     let transfer_res = accept_transfer(nostr_hex_sk, accept_req).await?;
+    notify_invoice_paid(nostr_hex_sk, &transfer_res).await;
 
-    Ok((StatusCode::OK, Json(transfer_res)))
+    Ok((StatusCode::OK, format.reply(transfer_res)))
 }
 
 async fn contracts(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    timeout: RequestTimeout,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /contracts");
 
     let nostr_hex_sk = auth.token();
 
-    let contracts_res = list_contracts(nostr_hex_sk, true).await?;
+    // Streamed one contract per line, when the client accepts NDJSON
+    if accepts_ndjson(&headers) {
+        let lines = timeout
+            .run(list_contracts_ndjson(nostr_hex_sk, true))
+            .await?;
+        let body = StreamBody::new(lines);
+        return Ok((StatusCode::OK, [(CONTENT_TYPE, NDJSON_MIME)], body).into_response());
+    }
 
-    Ok((StatusCode::OK, Json(contracts_res)))
+    let contracts_res = timeout.run(list_contracts(nostr_hex_sk, true)).await?;
+
+    Ok((StatusCode::OK, format.reply(contracts_res)).into_response())
 }
 
 async fn contract_detail(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path(id): Path<String>,
+    timeout: RequestTimeout,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /contracts/{id:?}");
 
     let nostr_hex_sk = auth.token();
-    let contracts_res = list_contracts(nostr_hex_sk, false).await?;
+    let contracts_res = timeout.run(list_contracts(nostr_hex_sk, false)).await?;
     let contract = contracts_res
         .contracts
         .into_iter()
         .find(|x| x.contract_id == id);
-    Ok((StatusCode::OK, Json(contract)))
+    Ok((StatusCode::OK, format.reply(contract)))
+}
+
+async fn contract_terms(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(id): Path<String>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("GET /contracts/{id:?}/terms");
+
+    let nostr_hex_sk = auth.token();
+    let terms_res = get_contract_terms(nostr_hex_sk, &id).await?;
+
+    Ok((StatusCode::OK, format.reply(terms_res)))
+}
+
+async fn storage(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("GET /storage");
+
+    let nostr_hex_sk = auth.token();
+    let storage_res = storage_stats(nostr_hex_sk).await?;
+
+    Ok((StatusCode::OK, format.reply(storage_res)))
 }
 
 async fn interfaces(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /interfaces");
 
@@ -281,11 +452,12 @@ async fn interfaces(
 
     let interfaces_res = list_interfaces(nostr_hex_sk).await?;
 
-    Ok((StatusCode::OK, Json(interfaces_res)))
+    Ok((StatusCode::OK, format.reply(interfaces_res)))
 }
 
 async fn schemas(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /schemas");
 
@@ -293,60 +465,79 @@ async fn schemas(
 
     let schemas_res = list_schemas(nostr_hex_sk).await?;
 
-    Ok((StatusCode::OK, Json(schemas_res)))
+    Ok((StatusCode::OK, format.reply(schemas_res)))
+}
+
+async fn schema_fetch(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(schema_id): Path<String>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /schemas/{schema_id:?}");
+
+    let nostr_hex_sk = auth.token();
+
+    let schema_res = fetch_schema(nostr_hex_sk, &schema_id).await?;
+
+    Ok((StatusCode::OK, format.reply(schema_res)))
 }
 
 async fn import(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(import_req): Json<ImportRequest>,
+    format: WireFormat,
+    Wire(import_req): Wire<ImportRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /import {import_req:?}");
 
     let nostr_hex_sk = auth.token();
     let import_res = rgb_import(nostr_hex_sk, import_req).await?;
 
-    Ok((StatusCode::OK, Json(import_res)))
+    Ok((StatusCode::OK, format.reply(import_res)))
 }
 
 async fn watcher(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(request): Json<WatcherRequest>,
+    format: WireFormat,
+    Wire(request): Wire<WatcherRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /watcher {request:?}");
 
     let nostr_hex_sk = auth.token();
     let resp = create_watcher(nostr_hex_sk, request).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn watcher_details(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path(name): Path<String>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /watcher/{name:?}");
 
     let nostr_hex_sk = auth.token();
     let resp = rgb_watcher_details(nostr_hex_sk, &name).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn clear_watcher(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path(name): Path<String>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("DELETE /watcher/{name:?}");
 
     let nostr_hex_sk = auth.token();
     let resp = rgb_clear_watcher(nostr_hex_sk, &name).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn next_address(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path((name, asset)): Path<(String, String)>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /watcher/{name:?}/address");
     info!("GET /watcher/{name:?}/{asset:?}/address");
@@ -354,96 +545,216 @@ async fn next_address(
     let nostr_hex_sk = auth.token();
     let resp = watcher_next_address(nostr_hex_sk, &name, &asset).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn next_utxo(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path((name, asset)): Path<(String, String)>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /watcher/{name:?}/{asset:?}/utxo");
 
     let nostr_hex_sk = auth.token();
     let resp = watcher_next_utxo(nostr_hex_sk, &name, &asset).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn register_address(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path((name, address)): Path<(String, String)>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("PUT /watcher/{name:?}/address/{address:?}");
 
     let nostr_hex_sk = auth.token();
     let resp = watcher_address(nostr_hex_sk, &name, &address).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
 }
 
 async fn register_utxo(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path((name, utxo)): Path<(String, String)>,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("PUT /watcher/{name:?}/utxo/{utxo:?}");
 
     let nostr_hex_sk = auth.token();
     let resp = watcher_utxo(nostr_hex_sk, &name, &utxo).await?;
 
-    Ok((StatusCode::OK, Json(resp)))
+    Ok((StatusCode::OK, format.reply(resp)))
+}
+
+async fn rebroadcast_transfers(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    timeout: RequestTimeout,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /transfers/rebroadcast");
+
+    let nostr_hex_sk = auth.token();
+    let rebroadcast_res = timeout.run(rebroadcast_pending(nostr_hex_sk)).await?;
+
+    Ok((StatusCode::OK, format.reply(rebroadcast_res)))
+}
+
+async fn verify_rgb_transfers(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    timeout: RequestTimeout,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /transfers/verify");
+
+    let nostr_hex_sk = auth.token();
+    let verify_res = timeout.run(verify_transfers(nostr_hex_sk)).await?;
+    notify_verified_transfers(nostr_hex_sk, &verify_res).await;
+
+    Ok((StatusCode::OK, format.reply(verify_res)))
 }
 
 async fn list_transfers(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Path(contract_id): Path<String>,
+    timeout: RequestTimeout,
+    format: WireFormat,
 ) -> Result<impl IntoResponse, AppError> {
     info!("GET /transfers/{contract_id:?}");
 
     let nostr_hex_sk = auth.token();
-    let transfers_res = list_rgb_transfers(nostr_hex_sk, contract_id).await?;
+    let transfers_res = timeout
+        .run(list_rgb_transfers(nostr_hex_sk, contract_id))
+        .await?;
+
+    Ok((StatusCode::OK, format.reply(transfers_res)))
+}
+
+async fn transfer_history(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(contract_id): Path<String>,
+    timeout: RequestTimeout,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("GET /history/{contract_id:?}");
+
+    let nostr_hex_sk = auth.token();
+    let history_res = timeout
+        .run(rgb_transfer_history(nostr_hex_sk, &contract_id))
+        .await?;
+
+    Ok((StatusCode::OK, format.reply(history_res)))
+}
+
+async fn reindex(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /reindex");
+
+    let nostr_hex_sk = auth.token();
+    let reindex_res = rgb_reindex(nostr_hex_sk).await?;
 
-    Ok((StatusCode::OK, Json(transfers_res)))
+    Ok((StatusCode::OK, format.reply(reindex_res)))
 }
 
 async fn save_transfer(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(request): Json<RgbSaveTransferRequest>,
+    format: WireFormat,
+    Wire(request): Wire<RgbSaveTransferRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("POST /transfers {request:?}");
 
     let nostr_hex_sk = auth.token();
     let import_res = save_rgb_transfer(nostr_hex_sk, request).await?;
+    watch_confirmations(nostr_hex_sk).await;
 
-    Ok((StatusCode::OK, Json(import_res)))
+    Ok((StatusCode::OK, format.reply(import_res)))
 }
 
 async fn remove_transfer(
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(request): Json<RgbRemoveTransferRequest>,
+    format: WireFormat,
+    Wire(request): Wire<RgbRemoveTransferRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("DELETE /transfers {request:?}");
 
     let nostr_hex_sk = auth.token();
     let import_res = remove_rgb_transfer(nostr_hex_sk, request).await?;
 
-    Ok((StatusCode::OK, Json(import_res)))
+    Ok((StatusCode::OK, format.reply(import_res)))
+}
+
+#[derive(Deserialize)]
+struct StoreQuery {
+    /// Parse and check the file as a consignment before storing it
+    #[serde(default)]
+    validate: bool,
+}
+
+/// Check a carbonado file holds a well-formed transfer consignment.
+///
+/// Only files encoded without encryption can be inspected by the server.
+fn check_consignment_file(header: &carbonado::file::Header, body: &[u8]) -> Result<String> {
+    if header.format.contains(carbonado::constants::Format::Ecc) {
+        return Err(anyhow!("encrypted files cannot be validated"));
+    }
+
+    let (_, decoded) = carbonado::file::decode(&[], body)?;
+    let contract_id = check_consignment_structure(&decoded)?;
+    Ok(contract_id.to_string())
 }
 
 async fn co_store(
     Path((pk, name)): Path<(String, String)>,
+    Query(query): Query<StoreQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    co_store_file(pk, None, name, query, body).await
+}
+
+async fn co_namespace_store(
+    Path((pk, namespace, name)): Path<(String, String, String)>,
+    Query(query): Query<StoreQuery>,
     body: Bytes,
 ) -> Result<impl IntoResponse, AppError> {
+    co_store_file(pk, Some(namespace), name, query, body).await
+}
+
+async fn co_store_file(
+    pk: String,
+    namespace: Option<String>,
+    name: String,
+    query: StoreQuery,
+    body: Bytes,
+) -> Result<(StatusCode, TypedHeader<CacheControl>, &'static str), AppError> {
     let cc = CacheControl::new().with_no_cache();
+    let route = carbonado_route(&pk, namespace.as_deref(), &name);
 
     let incoming_header = carbonado::file::Header::try_from(&body)?;
     if incoming_header.pubkey.to_string() != pk {
         return Ok((StatusCode::UNAUTHORIZED, TypedHeader(cc), "Unauthorized"));
     }
 
+    if query.validate {
+        match check_consignment_file(&incoming_header, &body) {
+            Ok(contract_id) => debug!("valid consignment of contract {contract_id}"),
+            Err(err) => {
+                error!("invalid consignment in POST /carbonado/{route}: {err}");
+                return Ok((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    TypedHeader(cc),
+                    "Invalid consignment",
+                ));
+            }
+        }
+    }
+
     let body_len = incoming_header.encoded_len - incoming_header.padding_len;
-    info!("POST /carbonado/{pk}/{name}, {body_len} bytes");
+    info!("POST /carbonado/{route}, {body_len} bytes");
 
-    let filepath = handle_file(&pk, &name, body_len.try_into()?).await?;
+    let filepath =
+        handle_namespaced_file(&pk, namespace.as_deref(), &name, body_len.try_into()?).await?;
 
     match OpenOptions::new()
         .read(true)
@@ -472,7 +783,7 @@ async fn co_store(
                 fs::write(&filepath, &body).await?;
             }
             _ => {
-                error!("error in POST /carbonado/{pk}/{name}: {err}");
+                error!("error in POST /carbonado/{route}: {err}");
                 return Err(err.into());
             }
         },
@@ -569,9 +880,45 @@ async fn co_server_store(
 async fn co_retrieve(
     Path((pk, name)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, AppError> {
-    info!("GET /carbonado/{pk}/{name}");
+    co_retrieve_file(pk, None, name).await
+}
+
+// Size of the stored file, without sending it (missing files are empty)
+async fn co_size(Path((pk, name)): Path<(String, String)>) -> Result<impl IntoResponse, AppError> {
+    info!("HEAD /carbonado/{pk}/{name}");
 
     let filepath = &handle_file(&pk, &name, 0).await?;
+    let size = match fs::metadata(filepath).await {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == ErrorKind::NotFound => 0,
+        Err(err) => return Err(err.into()),
+    };
+    let cc = CacheControl::new().with_no_cache();
+
+    Ok((
+        StatusCode::OK,
+        TypedHeader(cc),
+        [(CONTENT_LENGTH, size.to_string())],
+    ))
+}
+
+async fn co_namespace_retrieve(
+    Path((pk, namespace, name)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    co_retrieve_file(pk, Some(namespace), name).await
+}
+
+async fn co_retrieve_file(
+    pk: String,
+    namespace: Option<String>,
+    name: String,
+) -> Result<(StatusCode, TypedHeader<CacheControl>, Vec<u8>), AppError> {
+    info!(
+        "GET /carbonado/{}",
+        carbonado_route(&pk, namespace.as_deref(), &name)
+    );
+
+    let filepath = &handle_namespaced_file(&pk, namespace.as_deref(), &name, 0).await?;
     let fullpath = filepath.to_string_lossy();
     let bytes = fs::read(filepath).await;
     let cc = CacheControl::new().with_no_cache();
@@ -592,6 +939,65 @@ async fn co_retrieve(
     }
 }
 
+// Only the owner of the pubkey can list and delete the files of its namespaces
+fn owns_pubkey(auth: &Bearer, pk: &str) -> Result<bool> {
+    let mut sk = SecretKey::from_str(auth.token())?;
+    let owner = PublicKey::from_secret_key(&Secp256k1::signing_only(), &sk);
+    sk.non_secure_erase();
+    Ok(owner.to_string() == pk)
+}
+
+async fn co_namespace_list(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path((pk, namespace)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("GET /carbonado/ns/{pk}/{namespace}/");
+
+    if !owns_pubkey(&auth, &pk)? {
+        return Ok((StatusCode::UNAUTHORIZED, Json(vec![])));
+    }
+
+    let names = list_namespace(&pk, &namespace).await?;
+    Ok((StatusCode::OK, Json(names)))
+}
+
+async fn co_namespace_delete(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path((pk, namespace, name)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("DELETE /carbonado/ns/{pk}/{namespace}/{name}");
+
+    if !owns_pubkey(&auth, &pk)? {
+        return Ok((StatusCode::UNAUTHORIZED, "Unauthorized"));
+    }
+
+    match delete_namespaced_file(&pk, &namespace, &name).await? {
+        true => Ok((StatusCode::OK, "Success")),
+        false => Ok((StatusCode::NOT_FOUND, "Not found")),
+    }
+}
+
+async fn co_namespace_clear(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path((pk, namespace)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("DELETE /carbonado/ns/{pk}/{namespace}/");
+
+    if !owns_pubkey(&auth, &pk)? {
+        return Ok((StatusCode::UNAUTHORIZED, Json(0)));
+    }
+
+    let removed = clear_namespace(&pk, &namespace).await?;
+    Ok((StatusCode::OK, Json(removed)))
+}
+
+fn carbonado_route(pk: &str, namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("ns/{pk}/{namespace}/{name}"),
+        None => format!("{pk}/{name}"),
+    }
+}
+
 async fn co_metadata(
     Path((pk, name)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -764,16 +1170,215 @@ async fn status() -> Result<impl IntoResponse, AppError> {
     Ok((StatusCode::OK, TypedHeader(cc), BMC_VERSION.to_string()))
 }
 
+// Library capabilities, with the features of the server
+async fn server_capabilities(format: WireFormat) -> Result<impl IntoResponse, AppError> {
+    info!("GET /capabilities");
+
+    let mut resp = capabilities().await;
+    let signed_responses = matches!(env::var(SIGN_RESPONSES_VAR).as_deref(), Ok("true" | "1"));
+    resp.features
+        .insert("signed_responses".to_string(), signed_responses);
+    resp.features.insert("cbor_wire".to_string(), true);
+    resp.features.insert("ndjson_contracts".to_string(), true);
+    resp.features
+        .insert("carbonado_namespaces".to_string(), true);
+    resp.features.insert("api_keys".to_string(), true);
+    resp.features
+        .insert("ecdh_key".to_string(), key_endpoint_enabled());
+
+    let cc = CacheControl::new().with_no_cache();
+    Ok((StatusCode::OK, TypedHeader(cc), format.reply(resp)))
+}
+
+// Server key, loaded once from the configured key source
+static NOSTR_SK: OnceCell<SecretString> = OnceCell::new();
+
+// Webhooks of the server events (see `BITMASK_WEBHOOKS`), set on startup
+static WEBHOOKS: OnceCell<Webhooks> = OnceCell::new();
+
+// Deliver the event in the background, so slow or failing receivers
+// (retried, then dead-lettered) never delay the response
+async fn notify_webhooks<T>(event: WebhookEvent, wallet_sk: &str, data: T)
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let webhooks = match WEBHOOKS.get() {
+        Some(webhooks) => webhooks,
+        None => return,
+    };
+
+    let network = get_network().await;
+    let wallet_sk = wallet_sk.to_owned();
+    tokio::spawn(async move {
+        match with_network(&network, webhooks.notify(event, &wallet_sk, &data)).await {
+            Ok(Ok(report)) => debug!(
+                "Webhook {event} delivered to {} URLs, {} failed",
+                report.delivered.len(),
+                report.failed.len()
+            ),
+            Ok(Err(err)) => error!("Webhook {event} cannot be sent: {err}"),
+            Err(err) => error!("Webhook {event} cannot be sent: {err}"),
+        }
+    });
+}
+
+const PAID_TRANSFERS_CAPACITY: usize = 10_000;
+
+// Transfers the invoice payment was handled for, by blake3 hash of the sk,
+// contract and transfer ID. Transfers accepted by `/accept` are accepted
+// again once their witness transaction confirms
+static PAID_TRANSFERS: Lazy<tokio::sync::Mutex<LruCache<String, ()>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(LruCache::new("paid_transfers", PAID_TRANSFERS_CAPACITY)));
+
+// Mark the payment of the transfer as handled, returns `false` when it was
+// handled before
+async fn mark_paid_transfer(wallet_sk: &str, contract_id: &str, transfer_id: &str) -> bool {
+    let wallet = blake3::hash(wallet_sk.as_bytes()).to_hex();
+    let key = format!("{wallet}-{contract_id}-{transfer_id}");
+    PAID_TRANSFERS.lock().await.insert(key, ()).is_none()
+}
+
+// Accepted transfers only pay the invoice in full (or more)
+async fn notify_invoice_paid(wallet_sk: &str, accept_res: &AcceptResponse) {
+    if !mark_paid_transfer(wallet_sk, &accept_res.contract_id, &accept_res.transfer_id).await {
+        return;
+    }
+    if matches!(
+        accept_res.payment_status,
+        Some(InvoicePaymentStatus::Exact | InvoicePaymentStatus::Overpaid)
+    ) {
+        notify_webhooks(WebhookEvent::InvoicePaid, wallet_sk, accept_res.clone()).await;
+    }
+}
+
+// Transfers accepted once their witness transaction confirmed. Received
+// transfers pay an invoice of the wallet, unless handled by `/accept`
+async fn notify_verified_transfers(wallet_sk: &str, verify_res: &BatchRgbTransferResponse) {
+    for transfer in verify_res.transfers.iter().filter(|x| x.is_accept) {
+        notify_webhooks(WebhookEvent::TransferConfirmed, wallet_sk, transfer.clone()).await;
+        if !transfer.is_mine
+            && mark_paid_transfer(wallet_sk, &transfer.contract_id, &transfer.consig_id).await
+        {
+            notify_webhooks(WebhookEvent::InvoicePaid, wallet_sk, transfer.clone()).await;
+        }
+    }
+}
+
+struct PendingWallet {
+    network: String,
+    sk: SecretString,
+    watched_at: Instant,
+}
+
+// Wallets with transfers waiting for confirmation, by network and blake3 hash
+// of the sk
+static PENDING_WALLETS: Lazy<tokio::sync::Mutex<BTreeMap<String, PendingWallet>>> =
+    Lazy::new(Default::default);
+
+// Check the transfers of the wallet in the background (see
+// `verify_pending_wallets`) until they are confirmed
+async fn watch_confirmations(wallet_sk: &str) {
+    if WEBHOOKS.get().is_none() {
+        return;
+    }
+
+    let network = get_network().await;
+    let key = format!("{network}-{}", blake3::hash(wallet_sk.as_bytes()).to_hex());
+    let wallet = PendingWallet {
+        network,
+        sk: SecretString(wallet_sk.to_owned()),
+        watched_at: Instant::now(),
+    };
+    PENDING_WALLETS.lock().await.insert(key, wallet);
+}
+
+// Verify the pending transfers of the watched wallets, and stop watching the
+// wallets without pending transfers (unless watched again meanwhile)
+async fn verify_pending_wallets() {
+    let wallets: Vec<(String, String, SecretString)> = PENDING_WALLETS
+        .lock()
+        .await
+        .iter()
+        .map(|(key, wallet)| (key.clone(), wallet.network.clone(), wallet.sk.clone()))
+        .collect();
+
+    for (key, network, sk) in wallets {
+        let started = Instant::now();
+        let verify = async {
+            let verify_res = verify_transfers(&sk.0).await;
+            if let Ok(verify_res) = &verify_res {
+                notify_verified_transfers(&sk.0, verify_res).await;
+            }
+            verify_res
+        };
+
+        match with_network(&network, verify).await {
+            Ok(Ok(verify_res)) if verify_res.transfers.iter().all(|x| x.is_accept) => {
+                let mut wallets = PENDING_WALLETS.lock().await;
+                if wallets
+                    .get(&key)
+                    .map_or(false, |wallet| wallet.watched_at < started)
+                {
+                    wallets.remove(&key);
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => error!("Cannot verify the pending transfers: {err}"),
+            Err(err) => error!("Cannot verify the pending transfers: {err}"),
+        }
+    }
+}
+
 async fn key(Path(pk): Path<String>) -> Result<impl IntoResponse, AppError> {
-    let sk = env::var("NOSTR_SK")?;
-    let sk = SecretKey::from_str(&sk)?;
+    let sk = NOSTR_SK.get_or_try_init(|| KeySource::from_config("NOSTR_SK")?.load())?;
+    let mut sk = SecretKey::from_str(&sk.0)?;
 
     let pk = PublicKey::from_str(&pk)?;
 
-    let ss = SharedSecret::new(&pk, &sk);
-    let ss = ss.display_secret();
+    // The shared secret is returned by design, the copies are wiped once the
+    // response is rendered
+    let ss = Zeroizing::new(SharedSecret::new(&pk, &sk).secret_bytes());
+    sk.non_secure_erase();
 
-    Ok(ss.to_string())
+    Ok(hex::encode(&*ss))
+}
+
+async fn issue_key(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+    Wire(request): Wire<ApiKeyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("POST /keys {request:?}");
+
+    let nostr_hex_sk = auth.token();
+    let key_res = issue_api_key(nostr_hex_sk, request).await?;
+
+    Ok((StatusCode::OK, format.reply(key_res)))
+}
+
+async fn list_keys(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("GET /keys");
+
+    let nostr_hex_sk = auth.token();
+    let keys_res = list_api_keys(nostr_hex_sk).await?;
+
+    Ok((StatusCode::OK, format.reply(keys_res)))
+}
+
+async fn revoke_key(
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(id): Path<String>,
+    format: WireFormat,
+) -> Result<impl IntoResponse, AppError> {
+    info!("DELETE /keys/{id:?}");
+
+    let nostr_hex_sk = auth.token();
+    let key_res = revoke_api_key(nostr_hex_sk, &id).await?;
+
+    Ok((StatusCode::OK, format.reply(key_res)))
 }
 
 async fn new_block() -> Result<impl IntoResponse, AppError> {
@@ -810,6 +1415,38 @@ async fn csv_metrics() -> Result<impl IntoResponse, AppError> {
     Ok((StatusCode::OK, [("content-type", "text/csv")], metrics_csv))
 }
 
+// Usage of the in-memory caches, in the Prometheus text format
+async fn cache_metrics() -> Result<impl IntoResponse, AppError> {
+    let series: [(&str, &str, fn(&CacheStats) -> u64); 6] = [
+        ("hits_total", "counter", |stats| stats.hits),
+        ("misses_total", "counter", |stats| stats.misses),
+        ("evictions_total", "counter", |stats| stats.evictions),
+        ("invalidations_total", "counter", |stats| {
+            stats.invalidations
+        }),
+        ("entries", "gauge", |stats| stats.size as u64),
+        ("capacity", "gauge", |stats| stats.capacity as u64),
+    ];
+
+    let caches = cache_stats();
+    let mut metrics = vec![];
+    for (name, kind, value) in series {
+        metrics.push(format!("# TYPE bitmask_cache_{name} {kind}"));
+        for (cache, stats) in &caches {
+            metrics.push(format!(
+                "bitmask_cache_{name}{{cache=\"{cache}\"}} {}",
+                value(stats)
+            ));
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "text/plain; version=0.0.4")],
+        metrics.join("\n") + "\n",
+    ))
+}
+
 async fn init_metrics() -> Result<()> {
     let dir = env::var("CARBONADO_DIR").unwrap_or("/tmp/bitmaskd/carbonado".to_owned());
     let dir = path::Path::new(&dir);
@@ -843,17 +1480,22 @@ async fn main() -> Result<()> {
         .route("/reissue", post(reissue))
         .route("/selfissue", post(self_issue))
         .route("/invoice", post(invoice))
+        .route("/invoice/rebind", post(rebind))
         .route("/selfinvoice", post(self_invoice))
         // .route("/psbt", post(psbt))
         // .route("/sign", post(sign_psbt))
         .route("/pay", post(pay))
         .route("/selfpay", post(self_pay))
         .route("/accept", post(accept))
+        .route("/accept/simulate", post(simulate))
         .route("/selfaccept", post(self_accept))
         .route("/contracts", get(contracts))
         .route("/contracts/:id", get(contract_detail))
+        .route("/contracts/:id/terms", get(contract_terms))
         .route("/interfaces", get(interfaces))
         .route("/schemas", get(schemas))
+        .route("/schemas/:schema_id", post(schema_fetch))
+        .route("/storage", get(storage))
         .route("/import", post(import))
         .route("/watcher", post(watcher))
         .route("/watcher/:name", get(watcher_details))
@@ -868,14 +1510,27 @@ async fn main() -> Result<()> {
         .route("/transfers/:id", get(list_transfers))
         .route("/transfers/", post(save_transfer))
         .route("/transfers/", delete(remove_transfer))
-        .route("/key/:pk", get(key))
+        .route("/transfers/rebroadcast", post(rebroadcast_transfers))
+        .route("/transfers/verify", post(verify_rgb_transfers))
+        .route("/history/:id", get(transfer_history))
+        .route("/reindex", post(reindex))
+        .route("/keys", post(issue_key))
+        .route("/keys", get(list_keys))
+        .route("/keys/:id", delete(revoke_key))
+        .route("/capabilities", get(server_capabilities))
         .route("/carbonado/status", get(status))
         .route("/carbonado/server/:name", get(co_server_retrieve))
         .route("/carbonado/server/:name", post(co_server_store))
-        .route("/carbonado/:pk/:name", get(co_retrieve))
-        .route("/carbonado/:pk/:name", post(co_store))
-        .route("/carbonado/:pk/:name/force", post(co_force_store))
-        .route("/carbonado/:pk/:name/metadata", get(co_metadata))
+        .route(CARBONADO_FILE_ROUTE, get(co_retrieve))
+        .route(CARBONADO_FILE_ROUTE, head(co_size))
+        .route(CARBONADO_FILE_ROUTE, post(co_store))
+        .route(CARBONADO_FORCE_ROUTE, post(co_force_store))
+        .route(CARBONADO_METADATA_ROUTE, get(co_metadata))
+        .route(CARBONADO_NAMESPACE_ROUTE, get(co_namespace_list))
+        .route(CARBONADO_NAMESPACE_ROUTE, delete(co_namespace_clear))
+        .route(CARBONADO_NAMESPACED_FILE_ROUTE, get(co_namespace_retrieve))
+        .route(CARBONADO_NAMESPACED_FILE_ROUTE, post(co_namespace_store))
+        .route(CARBONADO_NAMESPACED_FILE_ROUTE, delete(co_namespace_delete))
         .route("/proxy/consignment/:id", post(rgb_proxy_consig_save))
         .route("/proxy/consignment/:id", get(rgb_proxy_consig_retrieve))
         .route("/proxy/media-metadata", post(rgb_proxy_media_data_save))
@@ -884,24 +1539,69 @@ async fn main() -> Result<()> {
         .route("/auction/:bundle_id/:name", get(rgb_retrieve_auction))
         .route("/auction/:bundle_id/:name", post(rgb_store_auction))
         .route("/auction/:bundle_id/:name", delete(rgb_destroy_auction))
+        .route("/metrics", get(cache_metrics))
         .route("/metrics.json", get(json_metrics))
         .route("/metrics.csv", get(csv_metrics));
 
     let network = get_network().await;
     switch_network(&network).await?;
 
+    // Operators opt in to the ECDH endpoint (see `BITMASK_ENABLE_KEY`)
+    if key_endpoint_enabled() {
+        app = app.route("/key/:pk", get(key));
+    } else {
+        info!("ECDH /key endpoint is disabled");
+    }
+
     if network == "regtest" {
         app = app
             .route("/regtest/block", get(new_block))
             .route("/regtest/send/:address/:amount", get(send_coins));
     }
 
+    if env::var(WEBHOOKS_VAR).is_ok() {
+        let sk = NOSTR_SK.get_or_try_init(|| KeySource::from_config("NOSTR_SK")?.load())?;
+        if let Some(webhooks) = Webhooks::from_env(&sk.0)? {
+            info!("Sending webhooks to {} URLs", webhooks.urls().len());
+            WEBHOOKS.get_or_init(|| webhooks);
+        }
+    }
+
     tokio::spawn(async {
         if let Err(e) = init_metrics().await {
             error!("Error in init metrics: {e}");
         }
     });
 
+    // Confirmations of the pending transfers, notified to the webhooks
+    if WEBHOOKS.get().is_some() {
+        tokio::spawn(async {
+            loop {
+                sleep(Duration::from_secs(RGB_CONFIRMATION_INTERVAL)).await;
+                verify_pending_wallets().await;
+            }
+        });
+    }
+
+    // Periodic rebuild of the transfer indexes, which may drift from the stocks
+    tokio::spawn(async {
+        loop {
+            sleep(Duration::from_secs(RGB_REINDEX_INTERVAL)).await;
+            let requested = reindex_all().await;
+            info!("Rebuilding {requested} transfer indexes on their next read");
+        }
+    });
+
+    app = app
+        .layer(middleware::from_fn(recover_wallet))
+        .layer(middleware::from_fn(route_network))
+        .layer(middleware::from_fn(authorize_api_key));
+
+    if matches!(env::var(SIGN_RESPONSES_VAR).as_deref(), Ok("true" | "1")) {
+        info!("Signing responses with the server key");
+        app = app.layer(middleware::from_fn(sign_response_body));
+    }
+
     let app = app.layer(CorsLayer::permissive());
     let addr = SocketAddr::from(([0, 0, 0, 0], 7070));
 
@@ -921,11 +1621,31 @@ struct AppError(anyhow::Error);
 // Tell axum how to convert `AppError` into a response.
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
-        )
-            .into_response()
+        let expired = self.0.is::<DeadlineExceeded>()
+            || matches!(
+                self.0.downcast_ref::<TransferError>(),
+                Some(TransferError::Expired)
+            );
+        let unsafe_path = matches!(
+            self.0.downcast_ref::<CarbonadoError>(),
+            Some(CarbonadoError::UnsafePathSegment(_))
+        );
+        let api_key = self.0.downcast_ref::<ApiKeyError>();
+        let status = if expired {
+            StatusCode::GATEWAY_TIMEOUT
+        } else if unsafe_path {
+            StatusCode::BAD_REQUEST
+        } else if matches!(api_key, Some(ApiKeyError::UnknownKey)) {
+            StatusCode::UNAUTHORIZED
+        } else if matches!(api_key, Some(ApiKeyError::Forbidden(_))) {
+            StatusCode::FORBIDDEN
+        } else if matches!(api_key, Some(ApiKeyError::NotFound(_))) {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+
+        (status, format!("Something went wrong: {}", self.0)).into_response()
     }
 }
 
@@ -939,3 +1659,388 @@ where
         Self(err.into())
     }
 }
+
+const CBOR_MIME: &str = "application/cbor";
+const NDJSON_MIME: &str = "application/x-ndjson";
+
+fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.contains(NDJSON_MIME))
+        .unwrap_or_default()
+}
+
+// Wire encoding negotiated from `Content-Type` (request) and `Accept` (response) headers.
+// JSON remains the default encoding when the client doesn't ask for CBOR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WireFormat {
+    Json,
+    Cbor,
+}
+
+impl WireFormat {
+    fn from_header(headers: &HeaderMap, name: HeaderName) -> Self {
+        match headers.get(name).and_then(|value| value.to_str().ok()) {
+            Some(value) if value.contains(CBOR_MIME) => Self::Cbor,
+            _ => Self::Json,
+        }
+    }
+
+    fn reply<T: Serialize>(self, value: T) -> Response {
+        match self {
+            Self::Json => Json(value).into_response(),
+            Self::Cbor => match cbor_encode(&value) {
+                Ok(body) => ([(CONTENT_TYPE, CBOR_MIME)], body).into_response(),
+                Err(err) => AppError(err).into_response(),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for WireFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_header(&parts.headers, ACCEPT))
+    }
+}
+
+// Request body decoded as JSON or CBOR, according to the `Content-Type` header.
+struct Wire<T>(T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Wire<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        match WireFormat::from_header(req.headers(), CONTENT_TYPE) {
+            WireFormat::Json => {
+                let Json(value) = Json::<T>::from_request(req, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                Ok(Self(value))
+            }
+            WireFormat::Cbor => {
+                let body = Bytes::from_request(req, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+                cbor_decode(body.as_ref())
+                    .map(Self)
+                    .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()).into_response())
+            }
+        }
+    }
+}
+
+const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+#[derive(Debug, Error)]
+#[error("Request deadline exceeded")]
+struct DeadlineExceeded;
+
+// Client deadline, taken from the `X-Request-Timeout` header (in milliseconds).
+// Requests without the header run to completion.
+#[derive(Debug, Clone, Copy)]
+struct RequestTimeout(Option<Instant>);
+
+impl RequestTimeout {
+    fn expired(&self) -> bool {
+        matches!(self.0, Some(deadline) if Instant::now() >= deadline)
+    }
+
+    async fn run<F, T, E>(self, operation: F) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        let result = match self.0 {
+            Some(deadline) => timeout_at(deadline.into(), operation)
+                .await
+                .map_err(|_| DeadlineExceeded)?,
+            None => operation.await,
+        };
+        result.map_err(AppError::from)
+    }
+}
+
+const GATE_OPEN: u8 = 0;
+const GATE_COMMITTED: u8 = 1;
+const GATE_EXPIRED: u8 = 2;
+
+// Decides once between committing an operation and expiring its request
+#[derive(Debug, Clone, Default)]
+struct CommitGate(Arc<AtomicU8>);
+
+impl CommitGate {
+    fn commit(&self) -> bool {
+        self.close(GATE_COMMITTED)
+    }
+
+    fn expire(&self) -> bool {
+        self.close(GATE_EXPIRED)
+    }
+
+    fn close(&self, state: u8) -> bool {
+        self.0
+            .compare_exchange(GATE_OPEN, state, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestTimeout
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = match parts.headers.get(REQUEST_TIMEOUT_HEADER) {
+            Some(value) => value,
+            None => return Ok(Self(None)),
+        };
+
+        value
+            .to_str()
+            .ok()
+            .and_then(|millis| millis.trim().parse::<u64>().ok())
+            .map(|millis| Self(Some(Instant::now() + Duration::from_millis(millis))))
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid {REQUEST_TIMEOUT_HEADER} header (expected: milliseconds)"),
+                )
+                    .into_response()
+            })
+    }
+}
+
+const NETWORK_HEADER: &str = "x-bitmask-network";
+// JSON bodies are only peeked for their network up to this size
+const NETWORK_BODY_LIMIT: usize = 1024 * 1024;
+
+// Network of the request, from the `network` query parameter, the
+// `x-bitmask-network` header or the `network` field of a JSON body (up to
+// `NETWORK_BODY_LIMIT`, larger bodies are passed through unread). The request
+// runs on that network (with its own storage, wallets and backends) without
+// switching the network of the server. Requests without a network run on the
+// server network.
+async fn route_network(request: Request<Body>, next: Next<Body>) -> Result<Response, AppError> {
+    let query_network = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "network")
+            .map(|(_, value)| value.to_string())
+    });
+    let header_network = request
+        .headers()
+        .get(NETWORK_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let fits = request.body().size_hint().lower() <= NETWORK_BODY_LIMIT as u64;
+    let (request, network) = match query_network.or(header_network) {
+        Some(network) => (request, Some(network)),
+        None if is_json && fits => {
+            let (parts, mut body) = request.into_parts();
+            let mut bytes = vec![];
+            let mut complete = true;
+            while let Some(chunk) = body.data().await {
+                bytes.extend_from_slice(&chunk?);
+                if bytes.len() > NETWORK_BODY_LIMIT {
+                    complete = false;
+                    break;
+                }
+            }
+
+            if complete {
+                let network = serde_json::from_slice::<serde_json::Value>(&bytes)
+                    .ok()
+                    .and_then(|value| value.get("network")?.as_str().map(str::to_string));
+                (Request::from_parts(parts, Body::from(bytes)), network)
+            } else {
+                // Peeked bytes go first, then the rest of the body as it streams
+                let peeked = Ok::<_, <Body as HttpBody>::Error>(Bytes::from(bytes));
+                let body = Body::wrap_stream(futures::stream::iter([peeked]).chain(body));
+                (Request::from_parts(parts, body), None)
+            }
+        }
+        None => (request, None),
+    };
+
+    let network = match network {
+        Some(network) => network,
+        None => return Ok(next.run(request).await),
+    };
+    match with_network(&network, next.run(request)).await {
+        Ok(response) => Ok(response),
+        Err(_) => Ok((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown network '{network}'"),
+        )
+            .into_response()),
+    }
+}
+
+const RESPONSE_SIGNATURE_HEADER: &str = "x-bitmask-signature";
+const RESPONSE_TIMESTAMP_HEADER: &str = "x-bitmask-timestamp";
+const SIGN_RESPONSES_VAR: &str = "BITMASK_SIGN_RESPONSES";
+// Larger bodies are sent unsigned, instead of buffered in memory
+const SIGNED_RESPONSE_LIMIT: u64 = 16 * 1024 * 1024;
+
+// Sign the responses with the server key (opt-in, see `BITMASK_SIGN_RESPONSES`).
+// The `X-Bitmask-Signature` header is a BIP-340 Schnorr signature (hex) of the
+// server nostr key over the SHA-256 of the `response_message`: the method,
+// path, status, `X-Bitmask-Timestamp` header and the SHA-256 of the body.
+// Clients check it with `verify_response`. Streamed (NDJSON, chunked) responses
+// are not buffered and go unsigned, clients can tell them by the missing header.
+async fn sign_response_body<B>(request: Request<B>, next: Next<B>) -> Result<Response, AppError> {
+    let method = request.method().to_string();
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| request.uri().path().to_owned());
+
+    let response = next.run(request).await;
+    let streamed = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(NDJSON_MIME));
+    let size = response.body().size_hint().exact();
+    if streamed || !size.is_some_and(|size| size <= SIGNED_RESPONSE_LIMIT) {
+        return Ok(response);
+    }
+
+    let sk = NOSTR_SK.get_or_try_init(|| KeySource::from_config("NOSTR_SK")?.load())?;
+    let (mut parts, mut body) = response.into_parts();
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk?);
+    }
+
+    let timestamp = chrono::Utc::now().timestamp();
+    let message = response_message(&method, &path, parts.status.as_u16(), timestamp, &bytes);
+    let signature = sign_response(&message, &sk.0)?;
+    parts.headers.insert(
+        RESPONSE_SIGNATURE_HEADER,
+        HeaderValue::from_str(&signature)?,
+    );
+    parts.headers.insert(
+        RESPONSE_TIMESTAMP_HEADER,
+        HeaderValue::from_str(&timestamp.to_string())?,
+    );
+
+    Ok(Response::from_parts(parts, boxed(Full::from(bytes))))
+}
+
+// Scope required by the route for API keys. Routes not listed here require
+// the admin scope, including the API keys management.
+fn route_scope(method: &Method, path: &str) -> ApiScope {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["contracts", ..])
+        | (&Method::GET, ["interfaces" | "schemas" | "capabilities" | "storage"])
+        | (&Method::GET, ["watcher", ..])
+        | (&Method::GET, ["transfers" | "history", _])
+        | (&Method::POST, ["accept", "simulate"]) => ApiScope::Read,
+        (&Method::POST, ["issue" | "reissue" | "selfissue" | "import"])
+        | (&Method::POST, ["schemas", _]) => ApiScope::Issue,
+        (&Method::POST, ["invoice", ..])
+        | (&Method::POST, ["selfinvoice" | "pay" | "selfpay" | "accept" | "selfaccept"])
+        | (&Method::POST | &Method::DELETE, ["transfers", ..])
+        | (&Method::PUT, ["watcher", _, _, "utxo", _]) => ApiScope::Pay,
+        _ => ApiScope::Admin,
+    }
+}
+
+// Wallets whose interrupted transfers were recovered since the server
+// started, by network and hash of the wallet key
+static RECOVERED_WALLETS: Lazy<tokio::sync::Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+// Recover the interrupted transfers of a wallet on its first request after
+// startup (see `recover_accept_transfers`), before the request reads its stock
+async fn recover_wallet<B>(request: Request<B>, next: Next<B>) -> Result<Response, AppError> {
+    let sk = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|sk| sk.len() == 64 && sk.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|sk| sk.to_string());
+
+    if let Some(sk) = sk {
+        let key = format!(
+            "{}-{}",
+            get_network().await,
+            blake3::hash(sk.as_bytes()).to_hex()
+        );
+        // Not held while recovering, so other wallets aren't blocked. Failed
+        // recoveries are retried on the next request
+        let recovered = RECOVERED_WALLETS.lock().await.contains(&key);
+        if !recovered {
+            match recover_accept_transfers(&sk).await {
+                Ok(resp) => {
+                    if !resp.recovered.is_empty() || !resp.quarantined.is_empty() {
+                        info!(
+                            "Recovered {} interrupted transfers, {} quarantined",
+                            resp.recovered.len(),
+                            resp.quarantined.len()
+                        );
+                    }
+                    RECOVERED_WALLETS.lock().await.insert(key);
+                }
+                Err(err) => error!("Cannot recover the interrupted transfers: {err}"),
+            }
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+// Bearer tokens which are API keys are checked against the scope of the route,
+// and replaced by the secret of their wallet before reaching the handlers.
+// Other bearer tokens (wallet secrets) pass through unchanged.
+async fn authorize_api_key<B>(
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| is_api_key(token))
+        .map(|token| token.to_string());
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(next.run(request).await),
+    };
+
+    let access = resolve_api_key(&token).await?;
+    access.require(route_scope(request.method(), request.uri().path()))?;
+
+    let bearer = HeaderValue::from_str(&format!("Bearer {}", access.wallet_sk.as_str()))?;
+    request.headers_mut().insert(AUTHORIZATION, bearer);
+
+    Ok(next.run(request).await)
+}
@@ -12,9 +12,10 @@ use serde_encrypt::{
     AsSharedKey, EncryptedMessage,
 };
 use thiserror::Error;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 mod assets;
+mod fees;
 mod keys;
 mod payment;
 mod psbt;
@@ -22,20 +23,28 @@ mod wallet;
 
 pub use crate::bitcoin::{
     assets::dust_tx,
-    keys::{new_mnemonic, save_mnemonic, BitcoinKeysError},
+    fees::{
+        confirmation_target, estimate_confirmation, estimate_fee_rate, operation_fee_rate,
+        parse_fee_policy, policy_fee_rate, policy_target, BitcoinFeeError, FeeOperation,
+    },
+    keys::{
+        mnemonic_to_keys, new_mnemonic, response_message, save_mnemonic, sign_response,
+        verify_response, wallet_fingerprint, BitcoinKeysError,
+    },
     payment::{create_payjoin, create_transaction, BitcoinPaymentError},
     psbt::{
         multi_sign_and_publish_psbt, multi_sign_psbt, publish_psbt, sign_and_publish_psbt,
         sign_psbt, BitcoinPsbtError,
     },
     wallet::{
-        get_blockchain, get_wallet, sync_wallet, sync_wallets, BitcoinWalletError, MemoryWallet,
+        get_blockchain, get_wallet, invalidate_wallet, last_synced_height, sync_wallet,
+        sync_wallets, BitcoinWalletError, MemoryWallet,
     },
 };
 
 use crate::{
     bitcoin::keys::get_marketplace_descriptor,
-    constants::{DIBA_DESCRIPTOR, DIBA_DESCRIPTOR_VERSION, DIBA_MAGIC_NO, NETWORK},
+    constants::{current_network, DIBA_DESCRIPTOR, DIBA_DESCRIPTOR_VERSION, DIBA_MAGIC_NO},
     debug, info,
     structs::{
         DecryptedWalletData, EncryptedWalletDataV04, FundVaultDetails, PublishPsbtRequest,
@@ -94,6 +103,9 @@ pub enum BitcoinError {
     /// BitMask Core Bitcoin Wallet error
     #[error(transparent)]
     BitcoinWalletError(#[from] BitcoinWalletError),
+    /// BitMask Core Bitcoin Fee error
+    #[error(transparent)]
+    BitcoinFeeError(#[from] BitcoinFeeError),
     /// hex decode error
     #[error(transparent)]
     HexDecodeError(#[from] hex::FromHexError),
@@ -137,13 +149,50 @@ pub fn hash_password(password: &SecretString) -> SecretString {
     hash
 }
 
+/// Key encrypting the wallet data, decoded from the password hash.
+///
+/// The encryption API takes the key by value, so the key is kept in its
+/// [`SharedKey`], which is wiped when the `EncryptionKey` is dropped.
+pub struct EncryptionKey(SharedKey);
+
+impl EncryptionKey {
+    pub fn from_hash(hash: &SecretString) -> Result<Self, hex::FromHexError> {
+        let decoded = Zeroizing::new(hex::decode(&hash.0)?);
+        let mut key = Zeroizing::new([0u8; 32]);
+        if decoded.len() != key.len() {
+            return Err(hex::FromHexError::InvalidStringLength);
+        }
+        key.copy_from_slice(&decoded);
+
+        Ok(Self(SharedKey::from_array(*key)))
+    }
+
+    pub fn shared_key(&self) -> &SharedKey {
+        &self.0
+    }
+}
+
+impl Zeroize for EncryptionKey {
+    fn zeroize(&mut self) {
+        self.0 = SharedKey::from_array([0; 32]);
+        // The key is not read before being dropped, keep the store anyway
+        std::hint::black_box(&self.0);
+    }
+}
+
+impl Drop for EncryptionKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for EncryptionKey {}
+
 pub fn decrypt_wallet(
     hash: &SecretString,
     encrypted_descriptors: &SecretString,
 ) -> Result<DecryptedWalletData, BitcoinError> {
-    let mut shared_key: [u8; 32] = hex::decode(&hash.0)?
-        .try_into()
-        .expect("hash is of fixed size");
+    let shared_key = EncryptionKey::from_hash(hash)?;
     let encrypted_descriptors: Vec<u8> = hex::decode(&encrypted_descriptors.0)?;
     let (version_prefix, encrypted_descriptors) = encrypted_descriptors.split_at(5);
 
@@ -162,9 +211,7 @@ pub fn decrypt_wallet(
     let encrypted_message = EncryptedMessage::deserialize(encrypted_descriptors.to_owned())?;
 
     let decrypted_wallet_data =
-        DecryptedWalletData::decrypt_owned(&encrypted_message, &SharedKey::from_array(shared_key))?;
-
-    shared_key.zeroize();
+        DecryptedWalletData::decrypt_owned(&encrypted_message, shared_key.shared_key())?;
 
     Ok(decrypted_wallet_data)
 }
@@ -175,21 +222,16 @@ pub async fn upgrade_wallet(
     seed_password: &SecretString,
 ) -> Result<SecretString, BitcoinError> {
     // read hash digest and consume hasher
-    let shared_key: [u8; 32] = hex::decode(&hash.0)?
-        .try_into()
-        .expect("hash is of fixed size");
+    let shared_key = EncryptionKey::from_hash(hash)?;
     let encrypted_descriptors: Vec<u8> = hex::decode(&encrypted_descriptors.0)?;
     let encrypted_message = EncryptedMessage::deserialize(encrypted_descriptors)?;
 
-    match DecryptedWalletData::decrypt_owned(&encrypted_message, &SharedKey::from_array(shared_key))
-    {
+    match DecryptedWalletData::decrypt_owned(&encrypted_message, shared_key.shared_key()) {
         Ok(_data) => Err(BitcoinError::UpgradeUnnecessary),
         Err(_err) => {
             // If there's a deserialization error, attempt to recover just the mnemnonic.
-            let recovered_wallet_data = EncryptedWalletDataV04::decrypt_owned(
-                &encrypted_message,
-                &SharedKey::from_array(shared_key),
-            )?;
+            let recovered_wallet_data =
+                EncryptedWalletDataV04::decrypt_owned(&encrypted_message, shared_key.shared_key())?;
 
             // println!("Recovered wallet data: {recovered_wallet_data:?}"); // Keep commented out for security
             // todo!("Add later version migrations here");
@@ -222,14 +264,11 @@ pub async fn new_wallet(
     hash: &SecretString,
     seed_password: &SecretString,
 ) -> Result<SecretString, BitcoinError> {
-    let mut shared_key: [u8; 32] = hex::decode(&hash.0)?
-        .try_into()
-        .expect("hash is of fixed size");
+    let shared_key = EncryptionKey::from_hash(hash)?;
     let wallet_data = new_mnemonic(seed_password).await?;
-    let encrypted_message = wallet_data.encrypt(&SharedKey::from_array(shared_key))?;
+    let encrypted_message = wallet_data.encrypt(shared_key.shared_key())?;
     let encrypted_descriptors = versioned_descriptor(encrypted_message);
 
-    shared_key.zeroize();
     Ok(encrypted_descriptors)
 }
 
@@ -238,12 +277,10 @@ pub async fn encrypt_wallet(
     hash: &SecretString,
     seed_password: &SecretString,
 ) -> Result<SecretString, BitcoinError> {
-    let shared_key: [u8; 32] = hex::decode(&hash.0)?
-        .try_into()
-        .expect("hash is of fixed size");
+    let shared_key = EncryptionKey::from_hash(hash)?;
 
     let wallet_data = save_mnemonic(mnemonic_phrase, seed_password).await?;
-    let encrypted_message = wallet_data.encrypt(&SharedKey::from_array(shared_key))?;
+    let encrypted_message = wallet_data.encrypt(shared_key.shared_key())?;
     let encrypted_descriptors = versioned_descriptor(encrypted_message);
     Ok(encrypted_descriptors)
 }
@@ -334,7 +371,7 @@ pub async fn get_new_address(
 }
 
 pub async fn validate_address(address: &Address) -> Result<(), BitcoinError> {
-    if address.network != *NETWORK.read().await {
+    if address.network != current_network().await {
         Err(BitcoinError::WrongNetwork)
     } else {
         Ok(())
@@ -351,7 +388,9 @@ pub async fn send_sats(
     use payjoin::UriExt;
 
     let wallet = get_wallet(descriptor, Some(change_descriptor)).await?;
-    let fee_rate = fee_rate.map(FeeRate::from_sat_per_vb);
+    let fee_rate = operation_fee_rate(FeeOperation::Pay, fee_rate)
+        .await
+        .map(FeeRate::from_sat_per_vb);
 
     let details = match payjoin::Uri::try_from(destination) {
         Ok(uri) => {
@@ -397,7 +436,9 @@ pub async fn fund_vault(
     fee_rate: Option<f32>,
 ) -> Result<FundVaultDetails, BitcoinError> {
     let wallet = get_wallet(btc_descriptor_xprv, Some(btc_change_descriptor_xprv)).await?;
-    let fee_rate = fee_rate.map(FeeRate::from_sat_per_vb);
+    let fee_rate = operation_fee_rate(FeeOperation::Issue, fee_rate)
+        .await
+        .map(FeeRate::from_sat_per_vb);
 
     let balance = wallet.lock().await.get_balance()?;
     if balance.confirmed < MIN_FUNDS_SATS {
@@ -528,12 +569,15 @@ pub async fn sign_and_publish_psbt_file(
     let final_psbt = PartiallySignedTransaction::from(original_psbt);
 
     let mut wallets = vec![];
-    for descriptor in descriptors {
-        let wallet = get_wallet(&descriptor, None).await?;
+    for descriptor in descriptors.iter() {
+        let wallet = get_wallet(descriptor, None).await?;
         wallets.push(wallet);
     }
 
     let sign = multi_sign_and_publish_psbt(wallets, final_psbt).await?;
+    for descriptor in descriptors.iter() {
+        invalidate_wallet(descriptor, None).await;
+    }
     let resp = match sign.transaction {
         Some(tx) => PublishedPsbtResponse {
             sign: true,
@@ -561,7 +605,9 @@ pub async fn drain_wallet(
     let wallet = get_wallet(descriptor, change_descriptor).await?;
     sync_wallet(&wallet).await?;
 
-    let fee_rate = fee_rate.map(FeeRate::from_sat_per_vb);
+    let fee_rate = operation_fee_rate(FeeOperation::Drain, fee_rate)
+        .await
+        .map(FeeRate::from_sat_per_vb);
 
     let (mut psbt, details) = {
         let locked_wallet = wallet.lock().await;
@@ -588,6 +634,7 @@ pub async fn drain_wallet(
     let blockchain = get_blockchain().await;
     let tx = psbt.extract_tx();
     blockchain.broadcast(&tx).await?;
+    invalidate_wallet(descriptor, change_descriptor).await;
     let tx = blockchain.get_tx(&details.txid).await?;
 
     if let Some(transaction) = tx.clone() {
@@ -656,6 +703,7 @@ pub async fn bump_fee(
     if broadcast {
         let blockchain = get_blockchain().await;
         blockchain.broadcast(&tx).await?;
+        invalidate_wallet(descriptor, change_descriptor).await;
     }
 
     let sent = tx.output.iter().fold(0, |sum, output| output.value + sum);
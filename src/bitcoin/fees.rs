@@ -0,0 +1,229 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use bitcoin::Txid;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    bitcoin::wallet::get_blockchain,
+    constants::{get_explorer_api, FEE_POLICY, FEE_POLICY_DEFAULT},
+    debug, error,
+    structs::ConfirmationEstimate,
+    util, warn,
+};
+
+// Maximum virtual size of a block (4M weight units)
+const BLOCK_MAX_VSIZE: f32 = 1_000_000.0;
+// Expected interval between blocks (in minutes)
+const BLOCK_INTERVAL_MINUTES: u32 = 10;
+
+#[derive(Error, Debug)]
+pub enum BitcoinFeeError {
+    /// Input is neither a txid nor a fee rate
+    #[error("Invalid input: '{0}' is neither a txid nor a fee rate (sat/vB)")]
+    WrongInput(String),
+    /// Transaction is unknown by the explorer
+    #[error("Transaction {0} was not found in mempool or blockchain")]
+    TxNotFound(String),
+    /// Fee policy cannot be parsed
+    #[error("Invalid fee policy: '{0}' is not an <operation>:<blocks> entry")]
+    WrongPolicy(String),
+    /// Explorer request error
+    #[error("Error retrieving data from explorer: {0}")]
+    ExplorerError(String),
+    /// BDK esplora error
+    #[error(transparent)]
+    BdkEsploraError(#[from] bdk::esplora_client::Error),
+    /// Serde JSON error
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// Operations with their own confirmation target in the fee policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeOperation {
+    /// Funding the UTXOs used to issue assets
+    Issue,
+    /// Payments
+    Pay,
+    /// Sweeping all the funds of a wallet
+    Drain,
+}
+
+impl FeeOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeOperation::Issue => "issue",
+            FeeOperation::Pay => "pay",
+            FeeOperation::Drain => "drain",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct MempoolInfo {
+    /// Pairs of (fee rate, vsize), ordered from the highest fee rate
+    fee_histogram: Vec<(f32, f32)>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct TxInfo {
+    weight: u64,
+    fee: u64,
+}
+
+/// Estimate when a transaction (by txid) or a hypothetical transaction
+/// (by fee rate, in sat/vB) will be confirmed, based on current mempool
+pub async fn estimate_confirmation(
+    txid_or_feerate: &str,
+) -> Result<ConfirmationEstimate, BitcoinFeeError> {
+    let explorer_url = get_explorer_api().await;
+
+    let fee_rate = if let Ok(txid) = Txid::from_str(txid_or_feerate) {
+        let blockchain = get_blockchain().await;
+        let tx_status = blockchain
+            .get_tx_status(&txid)
+            .await?
+            .ok_or_else(|| BitcoinFeeError::TxNotFound(txid.to_string()))?;
+
+        if tx_status.confirmed {
+            let current_height = blockchain.get_height().await?;
+            let block_height = tx_status.block_height.unwrap_or(current_height);
+            return Ok(ConfirmationEstimate {
+                confirmed: true,
+                block_height: Some(block_height),
+                confirmations: current_height.saturating_sub(block_height) + 1,
+                ..Default::default()
+            });
+        }
+
+        let tx_info = util::get(&format!("{explorer_url}/tx/{txid}"), None)
+            .await
+            .map_err(|op| BitcoinFeeError::ExplorerError(op.to_string()))?;
+        let TxInfo { weight, fee } = serde_json::from_str(&tx_info)?;
+        let vsize = (weight as f32 / 4.0).ceil();
+        fee as f32 / vsize
+    } else {
+        match f32::from_str(txid_or_feerate) {
+            Ok(fee_rate) if fee_rate > 0.0 => fee_rate,
+            _ => return Err(BitcoinFeeError::WrongInput(txid_or_feerate.to_string())),
+        }
+    };
+
+    let mempool = util::get(&format!("{explorer_url}/mempool"), None)
+        .await
+        .map_err(|op| BitcoinFeeError::ExplorerError(op.to_string()))?;
+    let MempoolInfo { fee_histogram } = serde_json::from_str(&mempool)?;
+
+    // Everything paying the same or better fee rate will be mined first
+    let vsize_ahead: f32 = fee_histogram
+        .iter()
+        .filter(|(rate, _)| *rate >= fee_rate)
+        .map(|(_, vsize)| vsize)
+        .sum();
+
+    let blocks = (vsize_ahead / BLOCK_MAX_VSIZE).floor() as u32 + 1;
+    debug!(format!(
+        "Estimate confirmation: {fee_rate} sat/vB, {vsize_ahead} vB ahead, {blocks} blocks"
+    ));
+
+    Ok(ConfirmationEstimate {
+        confirmed: false,
+        fee_rate,
+        blocks,
+        minutes: blocks * BLOCK_INTERVAL_MINUTES,
+        ..Default::default()
+    })
+}
+
+/// Parse a fee policy (`<operation>:<blocks>`, comma separated)
+pub fn parse_fee_policy(policy: &str) -> Result<BTreeMap<String, u32>, BitcoinFeeError> {
+    policy
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((operation, blocks)) => match u32::from_str(blocks.trim()) {
+                Ok(blocks) if blocks > 0 => Ok((operation.trim().to_lowercase(), blocks)),
+                _ => Err(BitcoinFeeError::WrongPolicy(entry.to_string())),
+            },
+            None => Err(BitcoinFeeError::WrongPolicy(entry.to_string())),
+        })
+        .collect()
+}
+
+/// Confirmation target (in blocks) of the operation, by the fee policy,
+/// falling back to the default policy
+pub fn policy_target(policy: &str, operation: FeeOperation) -> u32 {
+    let mut targets = parse_fee_policy(FEE_POLICY_DEFAULT).expect("valid default fee policy");
+    match parse_fee_policy(policy) {
+        Ok(configured) => targets.extend(configured),
+        Err(err) => error!(format!("{err}, using the default fee policy")),
+    }
+
+    targets[operation.as_str()]
+}
+
+/// Confirmation target (in blocks) of the operation, by the configured fee
+/// policy (FEE_POLICY)
+pub async fn confirmation_target(operation: FeeOperation) -> u32 {
+    let policy = FEE_POLICY.read().await.to_string();
+    policy_target(&policy, operation)
+}
+
+/// Fee rate (sat/vB) expected to confirm within the target blocks, by the
+/// explorer estimates. Without estimates (eg. regtest), returns none.
+pub async fn estimate_fee_rate(target: u32) -> Result<Option<f32>, BitcoinFeeError> {
+    let explorer_url = get_explorer_api().await;
+    let estimates = util::get(&format!("{explorer_url}/fee-estimates"), None)
+        .await
+        .map_err(|op| BitcoinFeeError::ExplorerError(op.to_string()))?;
+    let estimates: BTreeMap<String, f32> = serde_json::from_str(&estimates)?;
+    let estimates: BTreeMap<u32, f32> = estimates
+        .into_iter()
+        .filter_map(|(blocks, rate)| Some((u32::from_str(&blocks).ok()?, rate)))
+        .collect();
+
+    // The closest target within the requested one, or the fastest known
+    let fee_rate = estimates
+        .range(..=target)
+        .next_back()
+        .or_else(|| estimates.iter().next())
+        .map(|(_, rate)| *rate);
+
+    debug!(format!(
+        "Estimate fee rate: {target} blocks, {fee_rate:?} sat/vB"
+    ));
+    Ok(fee_rate)
+}
+
+/// Fee rate (sat/vB) of the operation: the explicit fee rate when given,
+/// otherwise the estimate for the confirmation target of the fee policy.
+///
+/// Without estimate (eg. regtest, or the explorer failing), returns none: the
+/// operation keeps its default fee rate.
+pub async fn policy_fee_rate(
+    policy: &str,
+    operation: FeeOperation,
+    fee_rate: Option<f32>,
+) -> Option<f32> {
+    if fee_rate.is_some() {
+        return fee_rate;
+    }
+
+    let target = policy_target(policy, operation);
+    match estimate_fee_rate(target).await {
+        Ok(fee_rate) => fee_rate,
+        Err(err) => {
+            warn!(format!("{err}, using the default fee rate"));
+            None
+        }
+    }
+}
+
+/// Fee rate (sat/vB) of the operation, by the configured fee policy
+/// (FEE_POLICY). See [`policy_fee_rate`].
+pub async fn operation_fee_rate(operation: FeeOperation, fee_rate: Option<f32>) -> Option<f32> {
+    let policy = FEE_POLICY.read().await.to_string();
+    policy_fee_rate(&policy, operation, fee_rate).await
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use bdk::{
     bitcoin::{
-        secp256k1::Secp256k1,
+        secp256k1::{schnorr::Signature, Message, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey},
         util::bip32::{ChildNumber, DerivationPath, ExtendedPrivKey, ExtendedPubKey, KeySource},
     },
     keys::{DerivableKey, DescriptorKey, DescriptorKey::Secret as SecretDesc, DescriptorSecretKey},
@@ -10,17 +10,17 @@ use bdk::{
 };
 use bip39::{Language, Mnemonic};
 use bitcoin::{KeyPair, Network};
-use bitcoin_hashes::{sha256, Hash};
+use bitcoin_hashes::{hash160, sha256, Hash};
 use miniscript_crate::{
     descriptor::{DescriptorXKey, Wildcard},
     DescriptorPublicKey,
 };
 use nostr_sdk::prelude::{FromSkStr, ToBech32};
 use thiserror::Error;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
-    constants::{get_marketplace_fee_xpub, get_network, BTC_PATH, NETWORK},
+    constants::{current_network, get_btc_path, get_marketplace_fee_xpub, get_network},
     structs::{DecryptedWalletData, PrivateWalletData, PublicWalletData, SecretString},
 };
 
@@ -59,6 +59,9 @@ pub enum BitcoinKeysError {
     /// Nostr SDK key error
     #[error(transparent)]
     NostrNip19Error(#[from] nostr_sdk::nips::nip19::Error),
+    /// Response signature doesn't match
+    #[error("Response signature doesn't match the body and the server key")]
+    WrongResponseSignature,
 }
 
 fn get_descriptor(
@@ -118,15 +121,79 @@ fn nostr_keypair(xprv: &ExtendedPrivKey) -> Result<(String, String), BitcoinKeys
     let deriv_descriptor = DerivationPath::from_str(NOSTR_PATH)?;
     let secp = Secp256k1::new();
     let nostr_sk = xprv.derive_priv(&secp, &deriv_descriptor)?;
-    let keypair =
-        KeyPair::from_seckey_slice(&secp, nostr_sk.private_key.secret_bytes().as_slice())?;
+    let nostr_sk = Zeroizing::new(nostr_sk.private_key.secret_bytes());
+    let keypair = KeyPair::from_seckey_slice(&secp, nostr_sk.as_slice())?;
 
     Ok((
-        hex::encode(nostr_sk.private_key.secret_bytes()),
+        hex::encode(nostr_sk.as_slice()),
         hex::encode(keypair.x_only_public_key().0.serialize()),
     ))
 }
 
+/// Short and stable identifier of a wallet, safe to display.
+///
+/// Like a BIP-32 fingerprint, these are the first 4 bytes of the HASH160 of
+/// the public key of the wallet nostr key (derived from the seed), so restores
+/// from the same seed always have the same fingerprint.
+pub fn wallet_fingerprint(nostr_hex_sk: &str) -> Result<String, BitcoinKeysError> {
+    let secp = Secp256k1::new();
+    let sk = SecretKey::from_str(nostr_hex_sk)?;
+    let pk = PublicKey::from_secret_key(&secp, &sk);
+    let hash = hash160::Hash::hash(&pk.serialize());
+
+    Ok(hex::encode(&hash[..4]))
+}
+
+/// Sign a response with the server key.
+///
+/// Signatures are BIP-340 Schnorr signatures over the SHA-256 of `body` (the
+/// [`response_message`] of bitmaskd), in hex, so they can be checked against
+/// the server nostr pubkey.
+pub fn sign_response(body: &[u8], server_hex_sk: &str) -> Result<String, BitcoinKeysError> {
+    let secp = Secp256k1::new();
+    let keypair = KeyPair::from_seckey_str(&secp, server_hex_sk)?;
+    let msg = Message::from_slice(&sha256::Hash::hash(body))?;
+
+    Ok(secp.sign_schnorr_no_aux_rand(&msg, &keypair).to_string())
+}
+
+/// Message signed for the responses of bitmaskd.
+///
+/// Binds the body to the request and the response, so a signed body cannot
+/// be replayed for another route, status or time: the lines are the method,
+/// the path (with the query), the status code, the unix timestamp of the
+/// `x-bitmask-timestamp` header and the hex SHA-256 of the body.
+pub fn response_message(
+    method: &str,
+    path: &str,
+    status: u16,
+    timestamp: i64,
+    body: &[u8],
+) -> Vec<u8> {
+    let body_hash = sha256::Hash::hash(body);
+    format!(
+        "{}\n{path}\n{status}\n{timestamp}\n{body_hash}",
+        method.to_uppercase()
+    )
+    .into_bytes()
+}
+
+/// Verify a message signed by [`sign_response`], e.g. the
+/// `x-bitmask-signature` header of bitmaskd over the [`response_message`].
+/// `server_pk` is the hex x-only (or compressed) public key of the server.
+pub fn verify_response(body: &[u8], sig: &str, server_pk: &str) -> Result<(), BitcoinKeysError> {
+    let secp = Secp256k1::verification_only();
+    let server_pk = match XOnlyPublicKey::from_str(server_pk) {
+        Ok(pk) => pk,
+        Err(_) => PublicKey::from_str(server_pk)?.x_only_public_key().0,
+    };
+    let sig = Signature::from_str(sig)?;
+    let msg = Message::from_slice(&sha256::Hash::hash(body))?;
+
+    secp.verify_schnorr(&sig, &msg, &server_pk)
+        .map_err(|_| BitcoinKeysError::WrongResponseSignature)
+}
+
 pub async fn new_mnemonic(
     seed_password: &SecretString,
 ) -> Result<DecryptedWalletData, BitcoinKeysError> {
@@ -141,27 +208,42 @@ pub async fn new_mnemonic(
 pub async fn save_mnemonic(
     mnemonic_phrase: &SecretString,
     seed_password: &SecretString,
+) -> Result<DecryptedWalletData, BitcoinKeysError> {
+    mnemonic_to_keys(mnemonic_phrase, Some(seed_password)).await
+}
+
+/// Derive all the wallet keys from a mnemonic, optionally protected with a
+/// BIP-39 passphrase (the "25th word"). No passphrase is the same as "".
+///
+/// Each passphrase results in an unrelated seed, so the same mnemonic yields
+/// entirely separate wallets: keys, fingerprints and stored state (which is
+/// indexed by the nostr key) are never shared.
+pub async fn mnemonic_to_keys(
+    mnemonic_phrase: &SecretString,
+    passphrase: Option<&SecretString>,
 ) -> Result<DecryptedWalletData, BitcoinKeysError> {
     let mnemonic = Mnemonic::from_str(&mnemonic_phrase.0)?;
+    let no_passphrase = SecretString(String::new());
 
-    get_mnemonic(mnemonic, seed_password).await
+    get_mnemonic(mnemonic, passphrase.unwrap_or(&no_passphrase)).await
 }
 
 pub async fn get_mnemonic(
     mnemonic_phrase: Mnemonic,
     seed_password: &SecretString,
 ) -> Result<DecryptedWalletData, BitcoinKeysError> {
-    let seed = mnemonic_phrase.to_seed_normalized(&seed_password.0);
+    let seed = Zeroizing::new(mnemonic_phrase.to_seed_normalized(&seed_password.0));
 
-    let network = NETWORK.read().await;
-    let xprv = ExtendedPrivKey::new_master(*network, &seed)?;
-    let xprvkh = sha256::Hash::hash(&xprv.to_priv().to_bytes()).to_string();
+    let network = current_network().await;
+    let xprv = ExtendedPrivKey::new_master(network, seed.as_slice())?;
+    let xprv_bytes = Zeroizing::new(xprv.to_priv().to_bytes());
+    let xprvkh = sha256::Hash::hash(&xprv_bytes).to_string();
 
     let secp = Secp256k1::new();
     let xpub = ExtendedPubKey::from_priv(&secp, &xprv);
     let xpubkh = xpub.to_pub().pubkey_hash().to_string();
 
-    let btc_path = BTC_PATH.read().await;
+    let btc_path = get_btc_path().await;
 
     let btc_descriptor_xprv = xprv_desc(&xprv, &btc_path, 0)?;
     let btc_change_descriptor_xprv = xprv_desc(&xprv, &btc_path, 1)?;
@@ -209,7 +291,7 @@ pub async fn get_mnemonic(
 }
 
 pub async fn get_marketplace_descriptor() -> Result<Option<SecretString>, BitcoinKeysError> {
-    let btc_path = BTC_PATH.read().await;
+    let btc_path = get_btc_path().await;
     let marketplace_xpub = get_marketplace_fee_xpub().await;
     let network = get_network().await;
     let network = Network::from_str(&network).expect("wrong network");
@@ -1,6 +1,16 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Weak,
+    },
+};
 
-use bdk::{blockchain::esplora::EsploraBlockchain, database::MemoryDatabase, SyncOptions, Wallet};
+use bdk::{
+    blockchain::{esplora::EsploraBlockchain, GetHeight},
+    database::MemoryDatabase,
+    SyncOptions, Wallet,
+};
 use bitcoin::Network;
 use bitcoin_hashes::{sha256, Hash};
 use futures::Future;
@@ -9,7 +19,10 @@ use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
 
 use crate::{
-    constants::{BITCOIN_EXPLORER_API, NETWORK},
+    cache::LruCache,
+    constants::{
+        current_network, get_explorer_api, get_explorer_batch_size, WALLET_CACHE_CAPACITY,
+    },
     debug,
     structs::SecretString,
 };
@@ -25,10 +38,9 @@ pub enum BitcoinWalletError {
 }
 
 pub type MemoryWallet = Arc<Mutex<Wallet<MemoryDatabase>>>;
-type Wallets = BTreeMap<(String, Option<String>), MemoryWallet>;
+type Wallets = LruCache<(String, Option<String>), MemoryWallet>;
 type NetworkWallet = Arc<RwLock<Wallets>>;
 
-#[derive(Default)]
 struct Networks {
     bitcoin: NetworkWallet,
     testnet: NetworkWallet,
@@ -36,8 +48,55 @@ struct Networks {
     regtest: NetworkWallet,
 }
 
+impl Default for Networks {
+    fn default() -> Self {
+        let wallets = |network: Network| {
+            let name = format!("wallets_{network}");
+            Arc::new(RwLock::new(LruCache::new(&name, *WALLET_CACHE_CAPACITY)))
+        };
+
+        Self {
+            bitcoin: wallets(Network::Bitcoin),
+            testnet: wallets(Network::Testnet),
+            signet: wallets(Network::Signet),
+            regtest: wallets(Network::Regtest),
+        }
+    }
+}
+
 static BDK: Lazy<Networks> = Lazy::new(Networks::default);
 
+// Sync bookkeeping of a single wallet.
+//
+// Every call takes a ticket. A sync covers all tickets issued before it
+// started, so callers that were waiting for it return without syncing again.
+#[derive(Default)]
+struct SyncState {
+    running: Mutex<()>,
+    requested: AtomicU64,
+    completed: AtomicU64,
+    height: AtomicU32,
+}
+
+type WalletHandle = Weak<Mutex<Wallet<MemoryDatabase>>>;
+
+// Sync states by wallet address. Wallets evicted from `BDK` are dropped once
+// unused, so states of dropped wallets are removed before a new wallet could
+// take their address.
+static SYNCS: Lazy<Mutex<BTreeMap<usize, (WalletHandle, Arc<SyncState>)>>> =
+    Lazy::new(Default::default);
+
+async fn sync_state(wallet: &MemoryWallet) -> Arc<SyncState> {
+    let key = Arc::as_ptr(wallet) as usize;
+    let mut syncs = SYNCS.lock().await;
+    syncs.retain(|_, (handle, _)| handle.strong_count() > 0);
+
+    let (_, state) = syncs
+        .entry(key)
+        .or_insert_with(|| (Arc::downgrade(wallet), Default::default()));
+    state.clone()
+}
+
 async fn access_network_wallets<U, F, Fut>(
     network: Network,
     mut f: F,
@@ -65,31 +124,31 @@ where
     Ok(())
 }
 
-pub async fn get_wallet(
-    descriptor: &SecretString,
-    change_descriptor: Option<&SecretString>,
-) -> Result<Arc<Mutex<Wallet<MemoryDatabase>>>, BitcoinWalletError> {
+fn wallet_key(descriptor: &SecretString, change_descriptor: Option<&SecretString>) -> String {
     let descriptor_key = format!("{descriptor:?}{change_descriptor:?}");
-    let key: String = sha256::Hash::hash(descriptor_key.as_bytes()).to_string();
-
-    let network_lock = NETWORK.read().await;
-    let network = network_lock.to_owned();
-    drop(network_lock);
+    sha256::Hash::hash(descriptor_key.as_bytes()).to_string()
+}
 
-    let wallets = match network {
+fn network_wallets(network: Network) -> NetworkWallet {
+    match network {
         Network::Bitcoin => BDK.bitcoin.clone(),
         Network::Testnet => BDK.testnet.clone(),
         Network::Signet => BDK.signet.clone(),
         Network::Regtest => BDK.regtest.clone(),
-    };
+    }
+}
 
-    let wallets = wallets.clone();
-    let wallets_lock = wallets.read().await;
-    let wallets_ref = wallets_lock.get(&(key.clone(), None));
-    if let Some(wallets) = wallets_ref {
-        return Ok(wallets.clone());
+pub async fn get_wallet(
+    descriptor: &SecretString,
+    change_descriptor: Option<&SecretString>,
+) -> Result<Arc<Mutex<Wallet<MemoryDatabase>>>, BitcoinWalletError> {
+    let key = wallet_key(descriptor, change_descriptor);
+    let network = current_network().await;
+
+    let wallets = network_wallets(network);
+    if let Some(wallet) = wallets.read().await.get(&(key.clone(), None)) {
+        return Ok(wallet.clone());
     }
-    drop(wallets_lock);
 
     let new_wallet = Arc::new(Mutex::new(Wallet::new(
         &descriptor.0,
@@ -118,27 +177,70 @@ pub async fn get_wallet(
     Ok(new_wallet)
 }
 
+/// Drop the cached wallet of the descriptors, after a write made its data
+/// stale (e.g. a broadcast spending its UTXOs). The next [`get_wallet`]
+/// builds it again, so callers must sync it before reading its UTXOs.
+pub async fn invalidate_wallet(
+    descriptor: &SecretString,
+    change_descriptor: Option<&SecretString>,
+) {
+    let key = wallet_key(descriptor, change_descriptor);
+    let network = current_network().await;
+
+    network_wallets(network)
+        .write()
+        .await
+        .invalidate(&(key, None));
+}
+
 pub async fn get_blockchain() -> EsploraBlockchain {
     debug!("Getting blockchain");
-    EsploraBlockchain::new(&BITCOIN_EXPLORER_API.read().await, 1)
+    let batch_size = get_explorer_batch_size().min(u8::MAX.into()) as u8;
+    EsploraBlockchain::new(&get_explorer_api().await, 1).with_concurrency(batch_size)
 }
 
+/// Sync the wallet with the explorer.
+///
+/// Overlapping calls are coalesced: a call made while another sync is
+/// running waits for it, and only syncs again when that sync started before
+/// the call was made. Dropping the future mid-sync leaves the last synced
+/// height untouched, which is only updated once a sync succeeds.
 pub async fn sync_wallet(wallet: &MemoryWallet) -> Result<(), BitcoinWalletError> {
+    let state = sync_state(wallet).await;
+    let ticket = state.requested.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let _running = state.running.lock().await;
+    if state.completed.load(Ordering::SeqCst) >= ticket {
+        debug!("Wallet synced (coalesced)");
+        return Ok(());
+    }
+
+    let target = state.requested.load(Ordering::SeqCst);
     let blockchain = get_blockchain().await;
+    let height = blockchain.get_height().await?;
     wallet
         .lock()
         .await
         .sync(&blockchain, SyncOptions::default())
         .await?;
 
+    state.height.fetch_max(height, Ordering::SeqCst);
+    state.completed.store(target, Ordering::SeqCst);
+
     debug!("Wallet synced");
     Ok(())
 }
 
+/// Block height of the last successful sync of the wallet (if any)
+pub async fn last_synced_height(wallet: &MemoryWallet) -> Option<u32> {
+    match sync_state(wallet).await.height.load(Ordering::SeqCst) {
+        0 => None,
+        height => Some(height),
+    }
+}
+
 pub async fn sync_wallets() -> Result<(), BitcoinWalletError> {
-    let network_lock = NETWORK.read().await;
-    let network = network_lock.to_owned();
-    drop(network_lock);
+    let network = current_network().await;
 
     /* // BDK RefCell prevents this from working:
        access_network_wallets(network, move |wallets| async move {
@@ -156,7 +258,7 @@ pub async fn sync_wallets() -> Result<(), BitcoinWalletError> {
     match network {
         Network::Bitcoin => {
             let wallets = BDK.bitcoin.clone();
-            for (_key, wallet) in wallets.write().await.iter_mut() {
+            for wallet in wallets.read().await.values() {
                 let blockchain = get_blockchain().await;
                 let wallet = wallet.lock().await;
                 let wallet_sync_fut = wallet.sync(&blockchain, SyncOptions::default());
@@ -165,7 +267,7 @@ pub async fn sync_wallets() -> Result<(), BitcoinWalletError> {
         }
         Network::Testnet => {
             let wallets = BDK.testnet.clone();
-            for (_key, wallet) in wallets.write().await.iter_mut() {
+            for wallet in wallets.read().await.values() {
                 let blockchain = get_blockchain().await;
                 let wallet = wallet.lock().await;
                 let wallet_sync_fut = wallet.sync(&blockchain, SyncOptions::default());
@@ -174,7 +276,7 @@ pub async fn sync_wallets() -> Result<(), BitcoinWalletError> {
         }
         Network::Signet => {
             let wallets = BDK.signet.clone();
-            for (_key, wallet) in wallets.write().await.iter_mut() {
+            for wallet in wallets.read().await.values() {
                 let blockchain = get_blockchain().await;
                 let wallet = wallet.lock().await;
                 let wallet_sync_fut = wallet.sync(&blockchain, SyncOptions::default());
@@ -183,7 +285,7 @@ pub async fn sync_wallets() -> Result<(), BitcoinWalletError> {
         }
         Network::Regtest => {
             let wallets = BDK.regtest.clone();
-            for (_key, wallet) in wallets.write().await.iter_mut() {
+            for wallet in wallets.read().await.values() {
                 let blockchain = get_blockchain().await;
                 let wallet = wallet.lock().await;
                 let wallet_sync_fut = wallet.sync(&blockchain, SyncOptions::default());
@@ -0,0 +1,198 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Usage of an in-memory cache
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// Lookups answered by the cache
+    pub hits: u64,
+    /// Lookups not found in the cache
+    pub misses: u64,
+    /// Entries dropped to keep the cache within its capacity
+    pub evictions: u64,
+    /// Entries dropped because their data changed
+    pub invalidations: u64,
+    /// Current number of entries
+    pub size: usize,
+    /// Maximum number of entries
+    pub capacity: usize,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+    size: AtomicUsize,
+    capacity: AtomicUsize,
+}
+
+// Counters of every cache, by name
+static CACHES: Lazy<Mutex<BTreeMap<String, Arc<CacheCounters>>>> = Lazy::new(Default::default);
+
+/// Usage of all the in-memory caches, by name
+pub fn cache_stats() -> BTreeMap<String, CacheStats> {
+    let caches = CACHES.lock().expect("cache registry poisoned");
+    caches
+        .iter()
+        .map(|(name, counters)| {
+            let stats = CacheStats {
+                hits: counters.hits.load(Ordering::Relaxed),
+                misses: counters.misses.load(Ordering::Relaxed),
+                evictions: counters.evictions.load(Ordering::Relaxed),
+                invalidations: counters.invalidations.load(Ordering::Relaxed),
+                size: counters.size.load(Ordering::Relaxed),
+                capacity: counters.capacity.load(Ordering::Relaxed),
+            };
+            (name.to_owned(), stats)
+        })
+        .collect()
+}
+
+/// Bounded cache, evicting the least recently used entries first.
+///
+/// Entries holding data that changed must be removed with
+/// [`LruCache::invalidate`]: eviction only ever drops entries, so once
+/// invalidated a key is a miss until it's inserted again. Lookups only take
+/// `&self`, so caches shared behind a `RwLock` are read concurrently.
+pub struct LruCache<K, V> {
+    capacity: usize,
+    clock: AtomicU64,
+    entries: BTreeMap<K, (AtomicU64, V)>,
+    recency: Mutex<BTreeMap<u64, K>>,
+    counters: Arc<CacheCounters>,
+}
+
+impl<K: Ord + Clone, V> LruCache<K, V> {
+    /// New cache of (at least 1) `capacity` entries, reported as `name` by [`cache_stats`]
+    pub fn new(name: &str, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let counters = Arc::new(CacheCounters::default());
+        counters.capacity.store(capacity, Ordering::Relaxed);
+        CACHES
+            .lock()
+            .expect("cache registry poisoned")
+            .insert(name.to_owned(), counters.clone());
+
+        Self {
+            capacity,
+            clock: AtomicU64::new(0),
+            entries: BTreeMap::new(),
+            recency: Mutex::new(BTreeMap::new()),
+            counters,
+        }
+    }
+
+    /// Cached value of `key`, marked as the most recently used
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match self.entries.get(key) {
+            Some((last_used, value)) => {
+                let mut recency = self.recency.lock().expect("cache recency poisoned");
+                let tick = self.tick();
+                recency.remove(&last_used.swap(tick, Ordering::Relaxed));
+                recency.insert(tick, key.clone());
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                Some(value)
+            }
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Cache `value`, evicting the least recently used entry when full.
+    /// Returns the value previously cached for `key` (if any).
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let tick = self.tick();
+        let recency = self.recency.get_mut().expect("cache recency poisoned");
+        let previous = self
+            .entries
+            .insert(key.clone(), (AtomicU64::new(tick), value));
+        if let Some((last_used, _)) = &previous {
+            recency.remove(&last_used.load(Ordering::Relaxed));
+        }
+        recency.insert(tick, key);
+
+        while self.entries.len() > self.capacity {
+            match recency.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                    self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+                None => break,
+            }
+        }
+
+        self.update_size();
+        previous.map(|(_, value)| value)
+    }
+
+    /// Drop the cached value of `key`, because its data changed
+    pub fn invalidate(&mut self, key: &K) -> Option<V> {
+        let (last_used, value) = self.entries.remove(key)?;
+        self.recency
+            .get_mut()
+            .expect("cache recency poisoned")
+            .remove(&last_used.load(Ordering::Relaxed));
+        self.counters.invalidations.fetch_add(1, Ordering::Relaxed);
+        self.update_size();
+        Some(value)
+    }
+
+    /// Drop all the cached values
+    pub fn clear(&mut self) {
+        let dropped = self.entries.len() as u64;
+        self.entries.clear();
+        self.recency
+            .get_mut()
+            .expect("cache recency poisoned")
+            .clear();
+        self.counters
+            .invalidations
+            .fetch_add(dropped, Ordering::Relaxed);
+        self.update_size();
+    }
+
+    /// Cached values (without marking them as used)
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.values().map(|(_, value)| value)
+    }
+
+    /// Cached values, to update in place (without marking them as used)
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.values_mut().map(|(_, value)| value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn update_size(&self) {
+        self.counters
+            .size
+            .store(self.entries.len(), Ordering::Relaxed);
+    }
+}
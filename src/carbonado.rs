@@ -1,7 +1,10 @@
 use amplify::hex::ToHex;
 use bitcoin_30::secp256k1::{PublicKey, SecretKey};
+use zeroize::Zeroizing;
 
-use crate::{carbonado::error::CarbonadoError, constants::NETWORK, info, structs::FileMetadata};
+use crate::{
+    carbonado::error::CarbonadoError, constants::current_network, info, structs::FileMetadata,
+};
 
 pub mod error;
 
@@ -10,8 +13,10 @@ pub mod metrics;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use server::{
-    auctions_retrieve, auctions_store, handle_file, marketplace_retrieve, marketplace_store,
-    retrieve, retrieve_metadata, store,
+    auctions_retrieve, auctions_store, check_path_segment, clear_namespace, delete_namespaced_file,
+    handle_file, handle_namespaced_file, list_namespace, marketplace_retrieve, marketplace_store,
+    retrieve, retrieve_metadata, store, stored_size, CARBONADO_FILE_ROUTE, CARBONADO_FORCE_ROUTE,
+    CARBONADO_METADATA_ROUTE, CARBONADO_NAMESPACED_FILE_ROUTE, CARBONADO_NAMESPACE_ROUTE,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -22,11 +27,12 @@ mod server {
 
     use std::{
         io::{Error, ErrorKind},
-        path::PathBuf,
+        path::{Path, PathBuf},
         str::FromStr,
     };
 
     use bitcoin_30::secp256k1::ecdh::SharedSecret;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use tokio::fs;
 
     pub async fn store(
@@ -37,7 +43,7 @@ mod server {
         metadata: Option<Vec<u8>>,
     ) -> Result<(), CarbonadoError> {
         let level = 15;
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.serialize();
@@ -52,20 +58,40 @@ mod server {
 
         let (body, _encode_info) = carbonado::file::encode(&sk, Some(&pk), input, level, meta)?;
         let filepath = handle_file(&pk_hex, name, body.len()).await?;
-        fs::write(&filepath, body).await?;
+        write_atomic(&filepath, &body).await?;
         // metrics::update(&filepath).await?;
         Ok(())
     }
 
+    // Write to a temporary file and rename it, so an interrupted write never
+    // leaves the file half written. Temporary names are unique (pid and random
+    // suffix), so concurrent writers of the file never share them
+    async fn write_atomic(filepath: &Path, body: &[u8]) -> Result<(), CarbonadoError> {
+        let suffix: u64 = StdRng::from_entropy().gen();
+        let mut tmp_name = filepath.file_name().unwrap_or_default().to_owned();
+        tmp_name.push(format!(".{}.{suffix:016x}.tmp", std::process::id()));
+        let tmp_path = filepath.with_file_name(tmp_name);
+
+        if let Err(err) = fs::write(&tmp_path, body).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(err.into());
+        }
+        if let Err(err) = fs::rename(&tmp_path, filepath).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
     pub async fn marketplace_store(
         name: &str,
         input: &[u8],
         metadata: Option<Vec<u8>>,
     ) -> Result<(PathBuf, Vec<u8>), CarbonadoError> {
-        let marketplace_key: String = get_marketplace_nostr_key().await;
+        let marketplace_key = Zeroizing::new(get_marketplace_nostr_key().await);
 
         let level = 15;
-        let sk = hex::decode(marketplace_key)?;
+        let sk = Zeroizing::new(hex::decode(marketplace_key.as_str())?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.serialize();
@@ -91,18 +117,16 @@ mod server {
         input: &[u8],
         metadata: Option<Vec<u8>>,
     ) -> Result<(PathBuf, Vec<u8>), CarbonadoError> {
-        let coordinator_key: String = get_coordinator_nostr_key().await;
+        let coordinator_key = Zeroizing::new(get_coordinator_nostr_key().await);
 
         let level = 15;
-        let coordinator_sk = hex::decode(coordinator_key)?;
+        let coordinator_sk = Zeroizing::new(hex::decode(coordinator_key.as_str())?);
         let coordinator_secret_key = SecretKey::from_slice(&coordinator_sk)?;
         let bundle_public_key =
             PublicKey::from_str(bundle_id).map_err(|_| CarbonadoError::WrongNostrPublicKey)?;
 
         let share_sk = SharedSecret::new(&bundle_public_key, &coordinator_secret_key);
-        let share_sk = share_sk.display_secret().to_string();
-
-        let sk = hex::decode(share_sk)?;
+        let sk = Zeroizing::new(share_sk.secret_bytes().to_vec());
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
 
@@ -129,13 +153,13 @@ mod server {
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
         use crate::rgb::constants::RGB_STRICT_TYPE_VERSION;
 
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.to_hex();
 
         let mut final_name = name.to_string();
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let networks = ["bitcoin", "testnet", "signet", "regtest"];
         if !networks.into_iter().any(|x| name.contains(x)) {
             final_name = format!("{network}-{name}");
@@ -167,15 +191,15 @@ mod server {
     pub async fn marketplace_retrieve(
         name: &str,
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
-        let marketplace_key: String = get_marketplace_nostr_key().await;
+        let marketplace_key = Zeroizing::new(get_marketplace_nostr_key().await);
 
-        let sk = hex::decode(marketplace_key)?;
+        let sk = Zeroizing::new(hex::decode(marketplace_key.as_str())?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.to_hex();
 
         let mut final_name = name.to_string();
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let networks = ["bitcoin", "testnet", "signet", "regtest"];
         if !networks.into_iter().any(|x| name.contains(x)) {
             final_name = format!("{network}-{name}");
@@ -194,24 +218,22 @@ mod server {
         bundle_id: &str,
         name: &str,
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
-        let coordinator_key: String = get_coordinator_nostr_key().await;
+        let coordinator_key = Zeroizing::new(get_coordinator_nostr_key().await);
 
-        let coordinator_sk = hex::decode(coordinator_key)?;
+        let coordinator_sk = Zeroizing::new(hex::decode(coordinator_key.as_str())?);
         let coordinator_secret_key = SecretKey::from_slice(&coordinator_sk)?;
         let bundle_public_key =
             PublicKey::from_str(bundle_id).map_err(|_| CarbonadoError::WrongNostrPublicKey)?;
 
         let share_sk = SharedSecret::new(&bundle_public_key, &coordinator_secret_key);
-        let share_sk = share_sk.display_secret().to_string();
-
-        let sk = hex::decode(share_sk)?;
+        let sk = Zeroizing::new(share_sk.secret_bytes().to_vec());
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
 
         let pk = public_key.to_hex();
 
         let mut final_name = name.to_string();
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let networks = ["bitcoin", "testnet", "signet", "regtest"];
         if !networks.into_iter().any(|x| name.contains(x)) {
             final_name = format!("{network}-{name}");
@@ -226,22 +248,90 @@ mod server {
         Ok((Vec::new(), None))
     }
 
+    /// Route of the carbonado files (two-segment form)
+    pub const CARBONADO_FILE_ROUTE: &str = "/carbonado/:pk/:name";
+    /// Route overwriting a carbonado file
+    pub const CARBONADO_FORCE_ROUTE: &str = "/carbonado/:pk/:name/force";
+    /// Route of the metadata of a carbonado file
+    pub const CARBONADO_METADATA_ROUTE: &str = "/carbonado/:pk/:name/metadata";
+    /// Route listing (and clearing) a namespace.
+    ///
+    /// Namespaces are routed under their own `ns` prefix: under the pubkey,
+    /// files named `force` or `metadata` would be shadowed by the routes of
+    /// the two-segment form.
+    pub const CARBONADO_NAMESPACE_ROUTE: &str = "/carbonado/ns/:pk/:namespace/";
+    /// Route of the carbonado files of a namespace
+    pub const CARBONADO_NAMESPACED_FILE_ROUTE: &str = "/carbonado/ns/:pk/:namespace/:name";
+
+    // Directory of the namespaces of a pubkey, apart from its unnamespaced files
+    const NAMESPACES_DIR: &str = "namespaces";
+
+    /// Check a pubkey, namespace or file name can be used as a single path
+    /// segment of the carbonado directory: no separators, no parent or
+    /// hidden entries, only `[A-Za-z0-9._-]` characters.
+    pub fn check_path_segment(segment: &str) -> Result<(), CarbonadoError> {
+        let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+        if segment.is_empty()
+            || segment.len() > 255
+            || segment.starts_with('.')
+            || !segment.chars().all(allowed)
+        {
+            return Err(CarbonadoError::UnsafePathSegment(segment.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn carbonado_dir() -> PathBuf {
+        PathBuf::from(
+            std::env::var("CARBONADO_DIR").unwrap_or("/tmp/bitmaskd/carbonado".to_owned()),
+        )
+    }
+
+    fn namespace_dir(pk: &str, namespace: &str) -> Result<PathBuf, CarbonadoError> {
+        check_path_segment(pk)?;
+        check_path_segment(namespace)?;
+        Ok(carbonado_dir()
+            .join(pk)
+            .join(NAMESPACES_DIR)
+            .join(namespace))
+    }
+
+    async fn network_file_name(name: &str) -> String {
+        let network = current_network().await.to_string();
+        let networks = ["bitcoin", "testnet", "signet", "regtest"];
+        if networks.into_iter().any(|x| name.contains(x)) {
+            name.to_string()
+        } else {
+            format!("{network}-{name}")
+        }
+    }
+
     pub async fn handle_file(
         pk: &str,
         name: &str,
         bytes: usize,
     ) -> Result<PathBuf, CarbonadoError> {
-        let mut final_name = name.to_string();
-        let network = NETWORK.read().await.to_string();
-        let networks = ["bitcoin", "testnet", "signet", "regtest"];
-        if !networks.into_iter().any(|x| name.contains(x)) {
-            final_name = format!("{network}-{name}");
-        }
+        handle_namespaced_file(pk, None, name, bytes).await
+    }
 
-        let directory = std::path::Path::new(
-            &std::env::var("CARBONADO_DIR").unwrap_or("/tmp/bitmaskd/carbonado".to_owned()),
-        )
-        .join(pk);
+    /// Path of a carbonado file of the pubkey, in the given namespace (if any)
+    pub async fn handle_namespaced_file(
+        pk: &str,
+        namespace: Option<&str>,
+        name: &str,
+        bytes: usize,
+    ) -> Result<PathBuf, CarbonadoError> {
+        check_path_segment(name)?;
+        let final_name = network_file_name(name).await;
+
+        let directory = match namespace {
+            Some(namespace) => namespace_dir(pk, namespace)?,
+            None => {
+                check_path_segment(pk)?;
+                carbonado_dir().join(pk)
+            }
+        };
 
         let filepath = directory.join(final_name);
         let filedir = filepath.parent().unwrap();
@@ -263,13 +353,84 @@ mod server {
         Ok(filepath)
     }
 
+    /// Names of the files stored by the pubkey in the namespace
+    pub async fn list_namespace(pk: &str, namespace: &str) -> Result<Vec<String>, CarbonadoError> {
+        let directory = namespace_dir(pk, namespace)?;
+        let mut entries = match fs::read_dir(&directory).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut names = vec![];
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Remove a file of the namespace, returning whether it was present
+    pub async fn delete_namespaced_file(
+        pk: &str,
+        namespace: &str,
+        name: &str,
+    ) -> Result<bool, CarbonadoError> {
+        check_path_segment(name)?;
+        let filepath = namespace_dir(pk, namespace)?.join(network_file_name(name).await);
+        match fs::remove_file(&filepath).await {
+            Ok(_) => {
+                info!(format!("delete {}", filepath.to_string_lossy()));
+                Ok(true)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Size (in bytes) of the encoded file, as stored. Missing files are empty.
+    pub async fn stored_size(sk: &str, name: &str) -> Result<u64, CarbonadoError> {
+        let sk = Zeroizing::new(hex::decode(sk)?);
+        let secret_key = SecretKey::from_slice(&sk)?;
+        let public_key = PublicKey::from_secret_key_global(&secret_key);
+        let pk = public_key.to_hex();
+
+        let filepath = handle_file(&pk, name, 0).await?;
+        match fs::metadata(filepath).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove all the files of the namespace, returning how many were present
+    pub async fn clear_namespace(pk: &str, namespace: &str) -> Result<usize, CarbonadoError> {
+        let names = list_namespace(pk, namespace).await?;
+        let directory = namespace_dir(pk, namespace)?;
+        for name in &names {
+            fs::remove_file(directory.join(name)).await?;
+        }
+        if !names.is_empty() {
+            info!(format!(
+                "delete {} files from {}",
+                names.len(),
+                directory.to_string_lossy()
+            ));
+        }
+
+        Ok(names.len())
+    }
+
     pub async fn retrieve_metadata(sk: &str, name: &str) -> Result<FileMetadata, CarbonadoError> {
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.to_hex();
 
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let networks = ["bitcoin", "testnet", "signet", "regtest"];
 
         let mut final_name = name.to_string();
@@ -294,7 +455,7 @@ mod server {
 #[cfg(target_arch = "wasm32")]
 pub use client::{
     auctions_retrieve, auctions_store, marketplace_retrieve, marketplace_store, retrieve,
-    retrieve_metadata, store,
+    retrieve_metadata, store, stored_size,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -306,7 +467,7 @@ mod client {
     use wasm_bindgen::JsValue;
     use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
-    use gloo_net::http::Request;
+    use gloo_net::http::{Method, Request, RequestBuilder};
     use gloo_utils::errors::JsError;
 
     use crate::constants::{CARBONADO_ENDPOINT, RGB_ENDPOINT};
@@ -335,7 +496,7 @@ mod client {
         metadata: Option<Vec<u8>>,
     ) -> Result<(), CarbonadoError> {
         let level = 15;
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.serialize();
@@ -350,7 +511,7 @@ mod client {
 
         let (body, _encode_info) = carbonado::file::encode(&sk, Some(&pk), input, level, meta)?;
         let body = Arc::new(body);
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
 
         let mut force_write = "";
         if force {
@@ -387,7 +548,7 @@ mod client {
         _metadata: Option<Vec<u8>>,
     ) -> Result<(), CarbonadoError> {
         let body = Arc::new(input.to_vec());
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = CARBONADO_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
         let requests = Array::new();
@@ -420,7 +581,7 @@ mod client {
         _metadata: Option<Vec<u8>>,
     ) -> Result<(), CarbonadoError> {
         let body = Arc::new(input.to_vec());
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = RGB_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
         let requests = Array::new();
@@ -447,12 +608,12 @@ mod client {
     }
 
     pub async fn retrieve_metadata(sk: &str, name: &str) -> Result<FileMetadata, CarbonadoError> {
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.to_hex();
 
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = CARBONADO_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
 
@@ -473,6 +634,43 @@ mod client {
         Ok(result)
     }
 
+    /// Size (in bytes) of the encoded file, as stored. Files missing on
+    /// every endpoint are empty.
+    ///
+    /// Endpoints are asked for the size only (`HEAD`), the file is never
+    /// downloaded.
+    pub async fn stored_size(sk: &str, name: &str) -> Result<u64, CarbonadoError> {
+        use carbonado::file::Header;
+
+        let sk = Zeroizing::new(hex::decode(sk)?);
+        let secret_key = SecretKey::from_slice(&sk)?;
+        let public_key = PublicKey::from_secret_key_global(&secret_key);
+        let pk = public_key.to_hex();
+
+        let network = current_network().await.to_string();
+        let endpoints = CARBONADO_ENDPOINT.read().await.to_string();
+        let endpoints: Vec<&str> = endpoints.split(',').collect();
+
+        let requests = Array::new();
+        for endpoint in endpoints.iter() {
+            let url = format!("{endpoint}/{pk}/{network}-{name}");
+            let fetch_fn = future_to_promise(fetch_head_length(url));
+            requests.push(&fetch_fn);
+        }
+
+        let result = match JsFuture::from(Promise::any(&JsValue::from(requests))).await {
+            Ok(result) => result,
+            Err(_) => return Ok(0),
+        };
+
+        let encoded = result.as_f64().unwrap_or_default() as u64;
+        if encoded > Header::len() as u64 {
+            return Ok(encoded);
+        }
+
+        Ok(0)
+    }
+
     pub async fn retrieve(
         sk: &str,
         name: &str,
@@ -480,12 +678,12 @@ mod client {
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
         use carbonado::file::Header;
 
-        let sk = hex::decode(sk)?;
+        let sk = Zeroizing::new(hex::decode(sk)?);
         let secret_key = SecretKey::from_slice(&sk)?;
         let public_key = PublicKey::from_secret_key_global(&secret_key);
         let pk = public_key.to_hex();
 
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = CARBONADO_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
 
@@ -537,7 +735,7 @@ mod client {
     pub async fn marketplace_retrieve(
         name: &str,
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = CARBONADO_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
 
@@ -562,7 +760,7 @@ mod client {
         bundle_id: &str,
         name: &str,
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), CarbonadoError> {
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoints = RGB_ENDPOINT.read().await.to_string();
         let endpoints: Vec<&str> = endpoints.split(',').collect();
 
@@ -641,6 +839,34 @@ mod client {
         }
     }
 
+    // Content length of the file, without its body
+    async fn fetch_head_length(url: String) -> Result<JsValue, JsValue> {
+        let request = RequestBuilder::new(&url)
+            .method(Method::HEAD)
+            .header("Cache-Control", "no-cache")
+            .build();
+
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => return Err(JsValue::from(e.to_string())),
+        };
+
+        match request.send().await {
+            Ok(response) if response.status() == 200 => {
+                let length = response
+                    .headers()
+                    .get("Content-Length")
+                    .and_then(|length| length.parse::<u64>().ok());
+                match length {
+                    Some(length) => Ok(JsValue::from(length as f64)),
+                    None => Err(JsValue::from("no content length")),
+                }
+            }
+            Ok(response) => Err(JsValue::from(response.status())),
+            Err(e) => Err(JsValue::from(e.to_string())),
+        }
+    }
+
     async fn fetch_get_byte_array(url: String) -> Result<JsValue, JsValue> {
         let request = Request::get(&url)
             .header("Content-Type", "application/octet-stream")
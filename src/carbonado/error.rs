@@ -29,6 +29,8 @@ pub enum CarbonadoError {
     WrongNostrPrivateKey,
     /// Wrong Nostr public key
     WrongNostrPublicKey,
+    /// '{0}' is not a safe path segment
+    UnsafePathSegment(String),
     /// Debug: {0}
     Debug(String),
     /// Error: {0}
@@ -1,4 +1,9 @@
-use std::{env, str::FromStr};
+use std::{
+    env,
+    future::Future,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use anyhow::Result;
 use bitcoin::Network;
@@ -23,6 +28,7 @@ fn dot_env(key: &str) -> String {
 }
 
 pub const BMC_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const BMC_API_VERSION: u32 = 1;
 
 pub static ELECTRUM_TIMEOUT: u8 = 4;
 
@@ -48,6 +54,45 @@ static BITCOIN_ELECTRUM_API_REGTEST: Lazy<RwLock<String>> =
 pub static BITCOIN_ELECTRUM_API: Lazy<RwLock<String>> =
     Lazy::new(|| RwLock::new(dot_env("BITCOIN_ELECTRUM_API_REGTEST")));
 
+// Explorer requests sent at once while syncing wallets (read by sync resolvers too)
+pub const EXPLORER_DEFAULT_BATCH_SIZE: usize = 6;
+static EXPLORER_BATCH_SIZE: Lazy<AtomicUsize> = Lazy::new(|| {
+    AtomicUsize::new(
+        dot_env("EXPLORER_BATCH_SIZE")
+            .parse()
+            .unwrap_or(EXPLORER_DEFAULT_BATCH_SIZE),
+    )
+});
+
+/// Size of the batches of concurrent explorer requests.
+///
+/// Scripts are queried in batches of concurrent requests. Every derived
+/// script is still queried, so the results don't depend on the batch size.
+pub fn get_explorer_batch_size() -> usize {
+    EXPLORER_BATCH_SIZE.load(Ordering::Relaxed).max(1)
+}
+
+// Wallets kept in memory (by network), the least recently used are dropped first
+pub const WALLET_CACHE_DEFAULT_CAPACITY: usize = 1_000;
+pub static WALLET_CACHE_CAPACITY: Lazy<usize> = Lazy::new(|| {
+    dot_env("WALLET_CACHE_CAPACITY")
+        .parse()
+        .unwrap_or(WALLET_CACHE_DEFAULT_CAPACITY)
+});
+
+// Confirmation targets (in blocks) by operation, used when no fee rate is given
+pub const FEE_POLICY_DEFAULT: &str = "issue:6,pay:1,drain:6";
+pub static FEE_POLICY: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(dot_env("FEE_POLICY")));
+
+// Allocations received by unconfirmed transfers are held as pending (not spendable) until
+// their witness transaction confirms
+pub static REQUIRE_CONFIRMATION: Lazy<RwLock<String>> =
+    Lazy::new(|| RwLock::new(dot_env("REQUIRE_CONFIRMATION")));
+
+pub async fn get_require_confirmation() -> bool {
+    matches!(REQUIRE_CONFIRMATION.read().await.trim(), "true" | "1")
+}
+
 pub static MARKETPLACE_SEED: Lazy<RwLock<String>> =
     Lazy::new(|| RwLock::new(dot_env("MARKETPLACE_SEED")));
 
@@ -115,40 +160,138 @@ pub static NETWORK: Lazy<RwLock<Network>> = Lazy::new(|| {
     RwLock::new(Network::from_str(&dot_env("BITCOIN_NETWORK")).expect("Parse Bitcoin network"))
 });
 
-pub async fn get_network() -> String {
-    NETWORK.read().await.to_string()
+// Network of a request, overriding the process network (see `with_network`)
+#[derive(Clone, Debug)]
+struct NetworkScope {
+    network: Network,
+    electrum_api: String,
+    btc_path: String,
 }
 
-/// Switch Bitcoin network
-/// For options, see: <https://docs.rs/bitcoin/0.27.1/src/bitcoin/network/constants.rs.html#62-75>
-pub async fn switch_network(network_str: &str) -> Result<()> {
-    let network = Network::from_str(network_str)?;
+tokio::task_local! {
+    static NETWORK_SCOPE: NetworkScope;
+}
 
-    *BTC_PATH.write().await = if network == Network::Bitcoin {
+fn btc_path_of(network: Network) -> String {
+    if network == Network::Bitcoin {
         BTC_MAINNET_PATH.to_owned()
     } else {
         BTC_TESTNET_PATH.to_owned()
-    };
+    }
+}
 
-    *BITCOIN_EXPLORER_API.write().await = match network {
-        Network::Bitcoin => BITCOIN_EXPLORER_API_MAINNET.read().await.to_owned(),
-        Network::Testnet => BITCOIN_EXPLORER_API_TESTNET.read().await.to_owned(),
-        Network::Signet => BITCOIN_EXPLORER_API_SIGNET.read().await.to_owned(),
-        Network::Regtest => BITCOIN_EXPLORER_API_REGTEST.read().await.to_owned(),
-    };
+fn explorer_api_lock(network: Network) -> &'static RwLock<String> {
+    match network {
+        Network::Bitcoin => &BITCOIN_EXPLORER_API_MAINNET,
+        Network::Testnet => &BITCOIN_EXPLORER_API_TESTNET,
+        Network::Signet => &BITCOIN_EXPLORER_API_SIGNET,
+        Network::Regtest => &BITCOIN_EXPLORER_API_REGTEST,
+    }
+}
 
-    *BITCOIN_ELECTRUM_API.write().await = match network {
+async fn explorer_api_of(network: Network) -> String {
+    explorer_api_lock(network).read().await.to_owned()
+}
+
+async fn electrum_api_of(network: Network) -> String {
+    match network {
         Network::Bitcoin => BITCOIN_ELECTRUM_API_MAINNET.read().await.to_owned(),
         Network::Testnet => BITCOIN_ELECTRUM_API_TESTNET.read().await.to_owned(),
         Network::Signet => BITCOIN_ELECTRUM_API_SIGNET.read().await.to_owned(),
         Network::Regtest => BITCOIN_ELECTRUM_API_REGTEST.read().await.to_owned(),
+    }
+}
+
+/// Network of the current request (see [`with_network`]), or the process
+/// network otherwise
+pub async fn current_network() -> Network {
+    match NETWORK_SCOPE.try_with(|scope| scope.network) {
+        Ok(network) => network,
+        Err(_) => *NETWORK.read().await,
+    }
+}
+
+pub async fn get_network() -> String {
+    current_network().await.to_string()
+}
+
+/// Explorer of the current network (see [`current_network`]). The process
+/// network uses `BITCOIN_EXPLORER_API`, the other ones their own explorer
+pub async fn get_explorer_api() -> String {
+    let network = current_network().await;
+    if network == *NETWORK.read().await {
+        BITCOIN_EXPLORER_API.read().await.to_string()
+    } else {
+        explorer_api_of(network).await
+    }
+}
+
+/// Point the explorer of the network to another URL (e.g. a replay server),
+/// returning the one it replaces
+pub async fn replace_explorer_api(network: Network, explorer_api: String) -> String {
+    let explorer = if network == *NETWORK.read().await {
+        &BITCOIN_EXPLORER_API
+    } else {
+        explorer_api_lock(network)
     };
+    std::mem::replace(&mut *explorer.write().await, explorer_api)
+}
+
+/// Electrum server of the current network
+pub async fn get_electrum_api() -> String {
+    match NETWORK_SCOPE.try_with(|scope| scope.electrum_api.clone()) {
+        Ok(electrum_api) => electrum_api,
+        Err(_) => BITCOIN_ELECTRUM_API.read().await.to_string(),
+    }
+}
+
+/// Derivation path of the descriptors of the current network
+pub async fn get_btc_path() -> String {
+    match NETWORK_SCOPE.try_with(|scope| scope.btc_path.clone()) {
+        Ok(btc_path) => btc_path,
+        Err(_) => BTC_PATH.read().await.to_string(),
+    }
+}
 
+/// Switch Bitcoin network
+/// For options, see: <https://docs.rs/bitcoin/0.27.1/src/bitcoin/network/constants.rs.html#62-75>
+pub async fn switch_network(network_str: &str) -> Result<()> {
+    let network = Network::from_str(network_str)?;
+
+    *BTC_PATH.write().await = btc_path_of(network);
+    *BITCOIN_EXPLORER_API.write().await = explorer_api_of(network).await;
+    *BITCOIN_ELECTRUM_API.write().await = electrum_api_of(network).await;
     *NETWORK.write().await = network;
 
     Ok(())
 }
 
+/// Run the future on the given network, without switching the process
+/// network. Storage, wallets and backends of the future follow its network,
+/// so requests of several networks can run at once in the same process.
+///
+/// Backends overridden for the process network (e.g. by a replay server)
+/// are kept when the future runs on the process network.
+pub async fn with_network<F: Future>(network_str: &str, future: F) -> Result<F::Output> {
+    let network = Network::from_str(network_str)?;
+
+    let scope = if network == *NETWORK.read().await {
+        NetworkScope {
+            network,
+            electrum_api: BITCOIN_ELECTRUM_API.read().await.to_string(),
+            btc_path: BTC_PATH.read().await.to_string(),
+        }
+    } else {
+        NetworkScope {
+            network,
+            electrum_api: electrum_api_of(network).await,
+            btc_path: btc_path_of(network),
+        }
+    };
+
+    Ok(NETWORK_SCOPE.scope(scope, future).await)
+}
+
 // lightning
 pub static LNDHUB_ENDPOINT: Lazy<RwLock<String>> =
     Lazy::new(|| RwLock::new(dot_env("LNDHUB_ENDPOINT")));
@@ -160,6 +303,10 @@ pub static RGB_ENDPOINT: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(dot_env
 pub static RGB_PROXY_ENDPOINT: Lazy<RwLock<String>> =
     Lazy::new(|| RwLock::new(dot_env("RGB_PROXY_ENDPOINT")));
 
+// rgb schema registry (serves the schemas missing in the wallets)
+pub static RGB_SCHEMA_REGISTRY: Lazy<RwLock<String>> =
+    Lazy::new(|| RwLock::new(dot_env("RGB_SCHEMA_REGISTRY")));
+
 // carbonado
 pub static CARBONADO_ENDPOINT: Lazy<RwLock<String>> =
     Lazy::new(|| RwLock::new(dot_env("CARBONADO_ENDPOINT")));
@@ -169,6 +316,7 @@ pub async fn get_env(key: &str) -> String {
         "LNDHUB_ENDPOINT" => LNDHUB_ENDPOINT.read().await.to_string(),
         "RGB_ENDPOINT" => RGB_ENDPOINT.read().await.to_string(),
         "CARBONADO_ENDPOINT" => CARBONADO_ENDPOINT.read().await.to_string(),
+        "RGB_SCHEMA_REGISTRY" => RGB_SCHEMA_REGISTRY.read().await.to_string(),
         "BITCOIN_EXPLORER_API_MAINNET" => BITCOIN_EXPLORER_API_MAINNET.read().await.to_string(),
         "BITCOIN_EXPLORER_API_TESTNET" => BITCOIN_EXPLORER_API_TESTNET.read().await.to_string(),
         "BITCOIN_EXPLORER_API_SIGNET" => BITCOIN_EXPLORER_API_SIGNET.read().await.to_string(),
@@ -177,6 +325,9 @@ pub async fn get_env(key: &str) -> String {
         "BITCOIN_ELECTRUM_API_TESTNET" => BITCOIN_ELECTRUM_API_TESTNET.read().await.to_string(),
         "BITCOIN_ELECTRUM_API_SIGNET" => BITCOIN_ELECTRUM_API_SIGNET.read().await.to_string(),
         "BITCOIN_ELECTRUM_API_REGTEST" => BITCOIN_ELECTRUM_API_REGTEST.read().await.to_string(),
+        "EXPLORER_BATCH_SIZE" => get_explorer_batch_size().to_string(),
+        "FEE_POLICY" => FEE_POLICY.read().await.to_string(),
+        "REQUIRE_CONFIRMATION" => REQUIRE_CONFIRMATION.read().await.to_string(),
         _ => {
             error!(format!("get_env called an unknown key, {key}"));
             "".to_owned()
@@ -189,6 +340,7 @@ pub async fn set_env(key: &str, value: &str) {
         "LNDHUB_ENDPOINT" => *LNDHUB_ENDPOINT.write().await = value.to_owned(),
         "RGB_ENDPOINT" => *RGB_ENDPOINT.write().await = value.to_owned(),
         "CARBONADO_ENDPOINT" => *CARBONADO_ENDPOINT.write().await = value.to_owned(),
+        "RGB_SCHEMA_REGISTRY" => *RGB_SCHEMA_REGISTRY.write().await = value.to_owned(),
         "BITCOIN_EXPLORER_API_MAINNET" => {
             *BITCOIN_EXPLORER_API_MAINNET.write().await = value.to_owned()
         }
@@ -213,6 +365,12 @@ pub async fn set_env(key: &str, value: &str) {
         "BITCOIN_ELECTRUM_API_REGTEST" => {
             *BITCOIN_ELECTRUM_API_REGTEST.write().await = value.to_owned()
         }
+        "EXPLORER_BATCH_SIZE" => match value.parse::<usize>() {
+            Ok(size) => EXPLORER_BATCH_SIZE.store(size, Ordering::Relaxed),
+            Err(_) => error!(format!("set_env called an invalid batch size, {value}")),
+        },
+        "FEE_POLICY" => *FEE_POLICY.write().await = value.to_owned(),
+        "REQUIRE_CONFIRMATION" => *REQUIRE_CONFIRMATION.write().await = value.to_owned(),
         _ => {
             error!(format!("set_env called an unknown key, {key}"));
         }
@@ -225,5 +383,13 @@ pub mod storage_keys {
     pub const ASSETS_TRANSFERS: &str = "bitmask_assets_transfers.c15";
     pub const ASSETS_OFFERS: &str = "bitmask-asset_offers.c15";
     pub const ASSETS_BIDS: &str = "bitmask-asset_bids.c15";
+    pub const ASSETS_IGNORED_ALLOCATIONS: &str = "bitmask-asset_ignored_allocations.c15";
+    pub const ASSETS_REVOKED_SEALS: &str = "bitmask-asset_revoked_seals.c15";
+    pub const ASSETS_UNCONFIRMED_TRANSFERS: &str = "bitmask-asset_unconfirmed_transfers.c15";
+    pub const ASSETS_COUNTERPARTIES: &str = "bitmask-asset_counterparties.c15";
+    pub const ASSETS_ACCEPT_JOURNAL: &str = "bitmask-asset_accept_journal.c15";
+    pub const ASSETS_BROADCASTS: &str = "bitmask-asset_broadcasts.c15";
     pub const MARKETPLACE_OFFERS: &str = "bitmask-marketplace_public_offers.c15";
+    pub const API_KEYS: &str = "bitmask-api_keys.c15";
+    pub const API_KEY_GRANT: &str = "bitmask-api_key_grant.c15";
 }
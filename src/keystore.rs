@@ -0,0 +1,178 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_encrypt::{
+    serialize::impls::BincodeSerializer, traits::SerdeEncryptSharedKey, EncryptedMessage,
+};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    bitcoin::{hash_password, versioned_descriptor, EncryptionKey},
+    constants::{DIBA_DESCRIPTOR_VERSION, DIBA_MAGIC_NO},
+    structs::SecretString,
+};
+
+const KEY_SOURCE_VAR: &str = "BITMASK_KEY_SOURCE";
+const KEY_FILE_VAR: &str = "BITMASK_KEY_FILE";
+const KEY_PASSWORD_FILE_VAR: &str = "BITMASK_KEY_PASSWORD_FILE";
+const KEYRING_SERVICE_VAR: &str = "BITMASK_KEYRING_SERVICE";
+const KEYRING_DEFAULT_SERVICE: &str = "bitmaskd";
+
+/// Enables the ECDH `/key` endpoint of bitmaskd, which is not mounted by default
+pub const KEY_ENDPOINT_VAR: &str = "BITMASK_ENABLE_KEY";
+
+#[derive(Error, Debug)]
+pub enum KeySourceError {
+    /// Unknown key source in configuration
+    #[error("Unknown key source '{0}' (expected: env, file or keyring)")]
+    WrongSource(String),
+    /// Required configuration is missing
+    #[error("Key source configuration is missing: {0}")]
+    NoConfig(String),
+    /// Key file is not a versioned encrypted file
+    #[error("Wrong Format: key file is not prefixed with DIBA magic number")]
+    WrongMagicNo,
+    /// Key file was written by an unsupported version
+    #[error("Wrong Version: key file is the wrong version. The version byte was: {0}")]
+    WrongVersion(u8),
+    /// I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// hex decode error
+    #[error(transparent)]
+    HexDecodeError(#[from] hex::FromHexError),
+    /// serde encrypt error
+    #[error(transparent)]
+    SerdeEncryptError(#[from] serde_encrypt::Error),
+    /// OS keyring error
+    #[error(transparent)]
+    KeyringError(#[from] keyring::Error),
+}
+
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct KeyFileData {
+    key: String,
+}
+
+impl SerdeEncryptSharedKey for KeyFileData {
+    type S = BincodeSerializer<Self>;
+}
+
+/// Where the CLI and server obtain their secret keys from
+#[derive(Clone, Debug)]
+pub enum KeySource {
+    /// Plaintext environment variable (legacy behaviour)
+    Env(String),
+    /// Password-protected key file, the password is read from another file
+    File {
+        path: PathBuf,
+        password_file: PathBuf,
+    },
+    /// OS keyring entry
+    Keyring { service: String, user: String },
+}
+
+impl KeySource {
+    /// Select the key source of `name` from configuration (`BITMASK_KEY_SOURCE`).
+    ///
+    /// - `env` (default): read the `name` environment variable.
+    /// - `file`: decrypt `BITMASK_KEY_FILE` using the password stored in
+    ///   `BITMASK_KEY_PASSWORD_FILE`.
+    /// - `keyring`: read the `name` entry of the `BITMASK_KEYRING_SERVICE`
+    ///   service (default: bitmaskd) from the OS keyring.
+    pub fn from_config(name: &str) -> Result<Self, KeySourceError> {
+        let source = env::var(KEY_SOURCE_VAR).unwrap_or_else(|_| "env".to_owned());
+        match source.as_str() {
+            "env" => Ok(KeySource::Env(name.to_owned())),
+            "file" => Ok(KeySource::File {
+                path: config_path(KEY_FILE_VAR)?,
+                password_file: config_path(KEY_PASSWORD_FILE_VAR)?,
+            }),
+            "keyring" => Ok(KeySource::Keyring {
+                service: env::var(KEYRING_SERVICE_VAR)
+                    .unwrap_or_else(|_| KEYRING_DEFAULT_SERVICE.to_owned()),
+                user: name.to_owned(),
+            }),
+            _ => Err(KeySourceError::WrongSource(source)),
+        }
+    }
+
+    pub fn load(&self) -> Result<SecretString, KeySourceError> {
+        match self {
+            KeySource::Env(name) => env::var(name)
+                .map(SecretString)
+                .map_err(|_| KeySourceError::NoConfig(name.to_owned())),
+            KeySource::File {
+                path,
+                password_file,
+            } => {
+                let password = SecretString(fs::read_to_string(password_file)?.trim().to_owned());
+                read_key_file(path, &password)
+            }
+            KeySource::Keyring { service, user } => {
+                let entry = keyring::Entry::new(service, user)?;
+                Ok(SecretString(entry.get_password()?))
+            }
+        }
+    }
+}
+
+/// Encrypt a key with a password (Argon2id) and write it to a file
+pub fn write_key_file(
+    path: &Path,
+    key: &SecretString,
+    password: &SecretString,
+) -> Result<(), KeySourceError> {
+    let shared_key = EncryptionKey::from_hash(&hash_password(password))?;
+
+    let data = KeyFileData {
+        key: key.0.to_owned(),
+    };
+    let encrypted_message = data.encrypt(shared_key.shared_key())?;
+
+    fs::write(path, versioned_descriptor(encrypted_message).0.as_bytes())?;
+    Ok(())
+}
+
+/// Decrypt a key file written by [`write_key_file`]
+pub fn read_key_file(path: &Path, password: &SecretString) -> Result<SecretString, KeySourceError> {
+    let shared_key = EncryptionKey::from_hash(&hash_password(password))?;
+
+    let encrypted = hex::decode(fs::read_to_string(path)?.trim())?;
+    if encrypted.len() < 5 || !encrypted.starts_with(&DIBA_MAGIC_NO) {
+        return Err(KeySourceError::WrongMagicNo);
+    }
+
+    let (version_prefix, encrypted) = encrypted.split_at(5);
+    if version_prefix[4] != DIBA_DESCRIPTOR_VERSION {
+        return Err(KeySourceError::WrongVersion(version_prefix[4]));
+    }
+
+    let encrypted_message = EncryptedMessage::deserialize(encrypted.to_owned())?;
+    let data = KeyFileData::decrypt_owned(&encrypted_message, shared_key.shared_key());
+
+    Ok(SecretString(data?.key.to_owned()))
+}
+
+/// Store a key in the OS keyring, to be loaded by [`KeySource::Keyring`]
+pub fn write_keyring(service: &str, user: &str, key: &SecretString) -> Result<(), KeySourceError> {
+    let entry = keyring::Entry::new(service, user)?;
+    entry.set_password(&key.0)?;
+    Ok(())
+}
+
+/// The ECDH `/key` endpoint hands out shared secrets of the server key, so it
+/// is only mounted when `BITMASK_ENABLE_KEY` is set
+pub fn key_endpoint_enabled() -> bool {
+    matches!(env::var(KEY_ENDPOINT_VAR).as_deref(), Ok("true" | "1"))
+}
+
+fn config_path(var: &str) -> Result<PathBuf, KeySourceError> {
+    env::var(var)
+        .map(PathBuf::from)
+        .map_err(|_| KeySourceError::NoConfig(var.to_owned()))
+}
@@ -1,18 +1,27 @@
 #[macro_use]
 extern crate amplify;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod apikeys;
 pub mod bitcoin;
+pub mod cache;
 pub mod carbonado;
 pub mod constants;
 pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keystore;
 pub mod lightning;
 pub mod nostr;
 pub mod proxy;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod regtest;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
 pub mod rgb;
 pub mod structs;
 pub mod util;
 pub mod validators;
 #[cfg(target_arch = "wasm32")]
 pub mod web;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod webhooks;
@@ -26,7 +26,7 @@ mod server {
     use tokio::fs;
 
     use crate::{
-        constants::{NETWORK, RGB_PROXY_ENDPOINT},
+        constants::{current_network, RGB_PROXY_ENDPOINT},
         info,
         rgb::structs::{
             MediaMetadata, RgbProxyConsigFileReq, RgbProxyConsigReq, RgbProxyConsigRes,
@@ -174,7 +174,7 @@ mod server {
 
     pub async fn handle_file(name: &str, bytes: usize) -> Result<PathBuf, ProxyServerError> {
         let mut final_name = name.to_string();
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let networks = ["bitcoin", "testnet", "signet", "regtest"];
         if !networks.into_iter().any(|x| name.contains(x)) {
             final_name = format!("{network}-{name}");
@@ -343,7 +343,7 @@ pub use client::{
 #[cfg(target_arch = "wasm32")]
 mod client {
     use crate::{
-        constants::{NETWORK, RGB_ENDPOINT},
+        constants::{current_network, RGB_ENDPOINT},
         rgb::structs::{
             MediaMetadata, RgbProxyConsigCarbonadoReq, RgbProxyConsigFileReq, RgbProxyConsigRes,
             RgbProxyConsigUploadRes, RgbProxyMediaRes,
@@ -357,7 +357,7 @@ mod client {
     pub async fn proxy_consig_store(
         request: RgbProxyConsigFileReq,
     ) -> Result<RgbProxyConsigUploadRes, ProxyServerError> {
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let endpoint = RGB_ENDPOINT.read().await.to_string();
 
         let name = request.clone().file_name;
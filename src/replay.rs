@@ -0,0 +1,288 @@
+//! Deterministic record and replay of the backend (explorer) interactions.
+//!
+//! [`RecordReplay`] serves a local explorer endpoint and points the explorer
+//! of the current network to it (see [`replace_explorer_api`]), so every
+//! backend client of the library goes through it. In record mode the requests are forwarded to the
+//! configured explorer and the request/response pairs are saved; in replay
+//! mode they are answered from the recording, without network access.
+//!
+//! Only Esplora (HTTP) explorers are wrapped: Electrum servers (see
+//! [`get_electrum_api`](crate::constants::get_electrum_api)) speak their own
+//! protocol, and recording them is rejected.
+use std::{
+    collections::BTreeMap,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{Method, StatusCode, Uri},
+    Router,
+};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Mutex};
+
+use bitcoin::Network;
+
+use crate::constants::{current_network, get_explorer_api, replace_explorer_api};
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RecordReplayError {
+    /// Recording cannot be read. {0}
+    Read(String),
+    /// Recording cannot be written. {0}
+    Write(String),
+    /// Backend endpoint cannot be served. {0}
+    Serve(String),
+    /// Requests missing in the recording: {0:?}
+    Unmatched(Vec<String>),
+    /// Backend {0} is not an Esplora explorer, only HTTP explorers are recorded
+    Unsupported(String),
+}
+
+/// Response of the backend to a recorded request
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    /// Body (in hexadecimal)
+    pub body: String,
+}
+
+/// Request to the backend, with the responses in the order they were given
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedExchange {
+    pub method: String,
+    pub uri: String,
+    /// Body (in hexadecimal)
+    pub body: String,
+    pub responses: Vec<RecordedResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Record { upstream: String },
+    Replay,
+}
+
+struct ReplayState {
+    mode: Mode,
+    client: reqwest::Client,
+    // Exchanges, by request key
+    exchanges: Mutex<BTreeMap<String, RecordedExchange>>,
+    // Responses already replayed, by request key
+    cursors: Mutex<BTreeMap<String, usize>>,
+    unmatched: Mutex<Vec<String>>,
+}
+
+/// Backend wrapper recording (or replaying) every explorer request
+pub struct RecordReplay {
+    path: PathBuf,
+    addr: SocketAddr,
+    network: Network,
+    previous_explorer: String,
+    state: Arc<ReplayState>,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl RecordReplay {
+    /// Forward the backend requests to the configured explorer, recording them to `path`
+    pub async fn record(path: impl AsRef<Path>) -> Result<Self, RecordReplayError> {
+        let upstream = get_explorer_api().await;
+        let upstream = upstream.trim_end_matches('/').to_string();
+        if !upstream.starts_with("http://") && !upstream.starts_with("https://") {
+            return Err(RecordReplayError::Unsupported(upstream));
+        }
+        Self::start(path.as_ref(), Mode::Record { upstream }, BTreeMap::new()).await
+    }
+
+    /// Answer the backend requests from the recording at `path`.
+    ///
+    /// Requests missing in the recording are answered with an error status,
+    /// and make [`RecordReplay::finish`] fail.
+    pub async fn replay(path: impl AsRef<Path>) -> Result<Self, RecordReplayError> {
+        let recording = tokio::fs::read(path.as_ref())
+            .await
+            .map_err(|op| RecordReplayError::Read(op.to_string()))?;
+        let exchanges: Vec<RecordedExchange> = serde_json::from_slice(&recording)
+            .map_err(|op| RecordReplayError::Read(op.to_string()))?;
+        let exchanges = exchanges
+            .into_iter()
+            .map(|exchange| {
+                (
+                    request_key(&exchange.method, &exchange.uri, &exchange.body),
+                    exchange,
+                )
+            })
+            .collect();
+
+        Self::start(path.as_ref(), Mode::Replay, exchanges).await
+    }
+
+    async fn start(
+        path: &Path,
+        mode: Mode,
+        exchanges: BTreeMap<String, RecordedExchange>,
+    ) -> Result<Self, RecordReplayError> {
+        let state = Arc::new(ReplayState {
+            mode,
+            client: reqwest::Client::new(),
+            exchanges: Mutex::new(exchanges),
+            cursors: Mutex::new(BTreeMap::new()),
+            unmatched: Mutex::new(vec![]),
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|op| RecordReplayError::Serve(op.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|op| RecordReplayError::Serve(op.to_string()))?;
+        let app = Router::new().fallback(exchange).with_state(state.clone());
+        let server = axum::Server::from_tcp(listener)
+            .map_err(|op| RecordReplayError::Serve(op.to_string()))?
+            .serve(app.into_make_service());
+
+        let (shutdown, stop) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            stop.await.ok();
+        }));
+
+        let network = current_network().await;
+        let previous_explorer = replace_explorer_api(network, format!("http://{addr}")).await;
+        debug!("{:?} backend at http://{addr}", state.mode);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            addr,
+            network,
+            previous_explorer,
+            state,
+            shutdown,
+        })
+    }
+
+    /// Explorer URL answered by the wrapper
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop answering requests and restore the explorer. Saves the recording
+    /// in record mode, and fails if some request was missing in replay mode.
+    pub async fn finish(self) -> Result<(), RecordReplayError> {
+        let _ = self.shutdown.send(());
+        replace_explorer_api(self.network, self.previous_explorer).await;
+
+        match self.state.mode {
+            Mode::Record { .. } => {
+                let exchanges: Vec<_> = self
+                    .state
+                    .exchanges
+                    .lock()
+                    .await
+                    .values()
+                    .cloned()
+                    .collect();
+                let recording = serde_json::to_vec_pretty(&exchanges)
+                    .map_err(|op| RecordReplayError::Write(op.to_string()))?;
+                tokio::fs::write(&self.path, recording)
+                    .await
+                    .map_err(|op| RecordReplayError::Write(op.to_string()))?;
+                Ok(())
+            }
+            Mode::Replay => {
+                let unmatched = self.state.unmatched.lock().await.clone();
+                if unmatched.is_empty() {
+                    Ok(())
+                } else {
+                    Err(RecordReplayError::Unmatched(unmatched))
+                }
+            }
+        }
+    }
+}
+
+// Requests are keyed by their content, so the recording doesn't depend on their order
+fn request_key(method: &str, uri: &str, body: &str) -> String {
+    blake3::hash(format!("{method} {uri}\n{body}").as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+async fn exchange(
+    State(state): State<Arc<ReplayState>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> (StatusCode, Vec<u8>) {
+    let body_hex = hex::encode(&body);
+    let uri = uri
+        .path_and_query()
+        .map(|path| path.to_string())
+        .unwrap_or_default();
+    let key = request_key(method.as_str(), &uri, &body_hex);
+
+    match &state.mode {
+        Mode::Record { upstream } => {
+            let request = state
+                .client
+                .request(method.clone(), format!("{upstream}{uri}"))
+                .body(body);
+            let (status, response) = match request.send().await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    match resp.bytes().await {
+                        Ok(bytes) => (status, bytes.to_vec()),
+                        Err(err) => return (StatusCode::BAD_GATEWAY, err.to_string().into_bytes()),
+                    }
+                }
+                Err(err) => return (StatusCode::BAD_GATEWAY, err.to_string().into_bytes()),
+            };
+
+            let mut exchanges = state.exchanges.lock().await;
+            let exchange = exchanges.entry(key).or_insert_with(|| RecordedExchange {
+                method: method.to_string(),
+                uri,
+                body: body_hex,
+                responses: vec![],
+            });
+            exchange.responses.push(RecordedResponse {
+                status: status.as_u16(),
+                body: hex::encode(&response),
+            });
+
+            (status, response)
+        }
+        Mode::Replay => {
+            let exchanges = state.exchanges.lock().await;
+            let responses = exchanges
+                .get(&key)
+                .map(|exchange| &exchange.responses)
+                .filter(|responses| !responses.is_empty());
+            let responses = match responses {
+                Some(responses) => responses,
+                None => {
+                    let request = format!("{method} {uri}");
+                    error!("Request missing in the backend recording: {request}");
+                    state.unmatched.lock().await.push(request.clone());
+                    return (
+                        StatusCode::NOT_IMPLEMENTED,
+                        format!("Request missing in the backend recording: {request}").into_bytes(),
+                    );
+                }
+            };
+
+            // Responses are replayed in order, the last one is kept once all were given
+            let mut cursors = state.cursors.lock().await;
+            let cursor = cursors.entry(key).or_default();
+            let response = &responses[(*cursor).min(responses.len() - 1)];
+            *cursor += 1;
+
+            let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
+            (status, hex::decode(&response.body).unwrap_or_default())
+        }
+    }
+}
@@ -1,33 +1,45 @@
 use ::psbt::{serialize::Serialize, Psbt};
-use amplify::{confinement::U32, hex::ToHex};
-use anyhow::Result;
+use amplify::{
+    confinement::{Confined, U32},
+    hex::ToHex,
+};
+use anyhow::{anyhow, Result};
 use autosurgeon::reconcile;
+use bdk::blockchain::GetHeight;
 use bitcoin::{psbt::PartiallySignedTransaction as PsbtV0, Network, Txid};
 use bitcoin_30::bip32::ExtendedPubKey;
+use bitcoin_hashes::hex::FromHex;
 use bitcoin_scripts::address::AddressNetwork;
-use futures::TryFutureExt;
+use bp::Outpoint;
+use futures::{StreamExt, TryFutureExt};
 use garde::Validate;
 
 use miniscript_crate::DescriptorPublicKey;
-use rgb::RgbDescr;
+use rgb::{RgbDescr, RgbWallet};
+use rgb_schemata::{nia_schema, uda_schema};
 use rgbstd::{
-    containers::BindleContent,
-    contract::ContractId,
-    interface::TypedState,
+    containers::{BindleContent, BuilderSeal, Transfer},
+    contract::{ContractId, SecretSeal},
+    interface::{rgb20, rgb21, IfaceId, IfaceImpl, TypedState},
     persistence::{Inventory, Stash, Stock},
+    schema::{SchemaId, SubSchema},
+    stl::Attachment,
     validation::Validity,
 };
 use rgbwallet::{psbt::DbcPsbtError, RgbInvoice};
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     ops::Sub,
     str::FromStr,
+    sync::Arc,
 };
-use strict_encoding::{tn, StrictSerialize};
+use strict_encoding::{tn, StrictDeserialize, StrictSerialize};
 use thiserror::Error;
 
 pub mod accept;
+pub mod accounting;
+pub mod backup;
 pub mod cambria;
 pub mod carbonado;
 pub mod consignment;
@@ -36,67 +48,112 @@ pub mod contract;
 pub mod crdt;
 pub mod fs;
 pub mod import;
+pub mod index;
 pub mod issue;
+pub mod merkle;
+pub mod ownership;
 pub mod prebuild;
 pub mod prefetch;
 pub mod proxy;
 pub mod psbt;
 pub mod resolvers;
+pub mod seal;
 pub mod structs;
 pub mod swap;
 pub mod transfer;
 pub mod wallet;
 
 use crate::{
-    bitcoin::{publish_psbt_file, sign_psbt_file},
-    constants::{get_network, BITCOIN_EXPLORER_API, NETWORK},
+    bitcoin::{
+        get_blockchain, invalidate_wallet, operation_fee_rate, publish_psbt_file, sign_psbt_file,
+        FeeOperation,
+    },
+    constants::{
+        current_network, get_explorer_api, get_network, get_require_confirmation, BMC_API_VERSION,
+        BMC_VERSION, LNDHUB_ENDPOINT, RGB_SCHEMA_REGISTRY,
+    },
     rgb::{
-        issue::{issue_contract as create_contract, IssueContractError},
-        psbt::{create_psbt as create_rgb_psbt, extract_output_commit},
+        issue::{
+            issue_contract as create_contract, terms_attachment, IssueContractError,
+            TERMS_MEDIA_TYPE,
+        },
+        psbt::{
+            combine_psbts as combine_rgb_psbts, create_psbt as create_rgb_psbt,
+            extract_output_commit, join_psbts as join_rgb_psbts,
+        },
         resolvers::ExplorerResolver,
         transfer::{
-            accept_transfer as accept_rgb_transfer, create_invoice as create_rgb_invoice,
-            pay_invoice,
+            accept_transfer as accept_rgb_transfer, check_invoice_chain, check_revoked_seals,
+            check_transfer, check_wallet_invoice, consignment_id as rgb_consignment_id,
+            create_invoice as create_rgb_invoice, invoice_paid_amount, outpoints_owned_state,
+            pay_invoice, provenance_links, rebind_invoice as rebind_rgb_invoice, reused_seals,
+            seal_usage, seals_paid_amount, witness_outpoints, witness_txids,
         },
         wallet::list_allocations,
     },
     structs::{
-        AcceptRequest, AcceptResponse, AssetType, BatchRgbTransferItem, BatchRgbTransferResponse,
-        ContractHiddenResponse, ContractResponse, ContractsResponse, FullRgbTransferRequest,
-        ImportRequest, InterfaceDetail, InterfacesResponse, InvoiceRequest, InvoiceResponse,
-        IssueMediaRequest, IssueRequest, IssueResponse, MediaEncode, MediaRequest, MediaResponse,
-        MediaView, NextAddressResponse, NextUtxoResponse, NextUtxosResponse, PsbtFeeRequest,
-        PsbtRequest, PsbtResponse, PublicRgbBidResponse, PublicRgbOfferResponse,
-        PublicRgbOffersResponse, PublishPsbtRequest, ReIssueRequest, ReIssueResponse,
-        RgbAuctionBidRequest, RgbAuctionBidResponse, RgbAuctionFinishResponse,
-        RgbAuctionOfferRequest, RgbAuctionOfferResponse, RgbBidDetail, RgbBidRequest,
-        RgbBidResponse, RgbBidsResponse, RgbInternalSaveTransferRequest,
-        RgbInternalTransferResponse, RgbInvoiceResponse, RgbOfferBidsResponse, RgbOfferDetail,
-        RgbOfferRequest, RgbOfferResponse, RgbOfferUpdateRequest, RgbOfferUpdateResponse,
-        RgbOffersResponse, RgbRemoveTransferRequest, RgbReplaceResponse, RgbSaveTransferRequest,
-        RgbSwapItem, RgbSwapRequest, RgbSwapResponse, RgbTransferDetail, RgbTransferRequest,
+        AcceptRecoveryResponse, AcceptRequest, AcceptResponse, AllocationProofResponse,
+        AllocationProvenanceResponse, AllocationValue, AllocationsExportResponse, AssetType,
+        BalanceChange, BalanceDelta, BalanceProofRequest, BalanceProofResponse,
+        BatchRgbTransferItem, BatchRgbTransferResponse, CapabilitiesResponse,
+        ContractHiddenResponse, ContractResponse, ContractSnapshot, ContractTermsResponse,
+        ContractsResponse, CounterpartiesRequest, CounterpartiesResponse, FullRgbTransferRequest,
+        IgnoredAllocationsResponse, ImportRequest, InterfaceDetail, InterfacesResponse,
+        InvoicePaymentStatus, InvoiceRequest, InvoiceResponse, IssueMediaRequest, IssueRequest,
+        IssueResponse, IssueTermsRequest, MediaEncode, MediaRequest, MediaResponse, MediaView,
+        MergePsbtRequest, MergePsbtResponse, NextAddressResponse, NextUtxoResponse,
+        NextUtxosResponse, ProvenanceStep, PsbtFeeRequest, PsbtRequest, PsbtResponse,
+        PsbtTimelockRequest, PublicRgbBidResponse, PublicRgbOfferResponse, PublicRgbOffersResponse,
+        PublishPsbtRequest, PublishedPsbtResponse, ReIssueRequest, ReIssueResponse,
+        RebroadcastResponse, ReindexResponse, ReusedResponse, RgbAuctionBidRequest,
+        RgbAuctionBidResponse, RgbAuctionFinishResponse, RgbAuctionOfferRequest,
+        RgbAuctionOfferResponse, RgbBidDetail, RgbBidRequest, RgbBidResponse, RgbBidsResponse,
+        RgbInternalSaveTransferRequest, RgbInternalTransferResponse, RgbInvoiceResponse,
+        RgbJoinTransferRequest, RgbOfferBidsResponse, RgbOfferDetail, RgbOfferRequest,
+        RgbOfferResponse, RgbOfferUpdateRequest, RgbOfferUpdateResponse, RgbOffersResponse,
+        RgbRemoveTransferRequest, RgbReplaceResponse, RgbSaveTransferRequest, RgbSwapItem,
+        RgbSwapRequest, RgbSwapResponse, RgbTransferDetail, RgbTransferReceipt, RgbTransferRequest,
         RgbTransferResponse, RgbTransferStatusResponse, RgbTransfersResponse, SchemaDetail,
-        SchemasResponse, SignPsbtRequest, SignedPsbtResponse, SimpleContractResponse, TransferType,
-        TxStatus, UtxoResponse, WatcherDetailResponse, WatcherRequest, WatcherResponse,
-        WatcherUtxoResponse,
+        SchemasResponse, SignPsbtRequest, SignedPsbtResponse, SimpleContractResponse,
+        SimulateAcceptResponse, StockDiffResponse, StockSnapshot, SuspiciousAllocation,
+        SuspiciousAllocationsResponse, TimelockOutputResponse, TransferHistoryResponse,
+        TransferType, TxStatus, UtxoResponse, VerifyAllocationProofResponse,
+        VerifyBalanceProofResponse, WalletBackup, WalletBackupRestoreResponse,
+        WatcherDetailResponse, WatcherRequest, WatcherResponse, WatcherUtxoResponse,
     },
+    util,
     validators::RGBContext,
 };
 
 use self::{
+    accounting::{contract_csv, AccountingEntry, ExplorerOutspend, ExplorerTxInfo},
+    backup::{
+        merge_transfers, merge_wallets, read_backup, restore_stock, restore_wallets, wallet_backup,
+    },
+    carbonado::{retrieve_terms, store_terms},
     consignment::NewTransferOptions,
-    constants::{RGB_DEFAULT_FETCH_LIMIT, RGB_DEFAULT_NAME},
-    contract::{export_boilerplate, export_contract, extract_metadata, ExportContractError},
+    constants::{RGB_DEFAULT_FETCH_LIMIT, RGB_DEFAULT_NAME, RGB_IFACE_VERSION_PARAM},
+    contract::{
+        contract_terms, export_boilerplate, export_contract, export_raw_contract, extract_metadata,
+        unavailable_schema, ExportContractError,
+    },
     crdt::{LocalRgbAccount, RawRgbAccount, RgbMerge},
     fs::{
-        retrieve_account, retrieve_bids, retrieve_local_account, retrieve_offers,
-        retrieve_public_offers, retrieve_stock as retrieve_rgb_stock, retrieve_stock_account,
+        retrieve_accept_journal, retrieve_account, retrieve_bids, retrieve_broadcasts,
+        retrieve_counterparties, retrieve_ignored_allocations, retrieve_local_account,
+        retrieve_offers, retrieve_public_offers, retrieve_revoked_seals,
+        retrieve_stock as retrieve_rgb_stock, retrieve_stock_account,
         retrieve_stock_account_transfers, retrieve_stock_transfers, retrieve_transfers,
-        store_account, store_bids, store_local_account, store_offers,
-        store_stock as store_rgb_stock, store_stock_account, store_stock_account_transfers,
-        store_stock_transfers, store_transfers, RgbPersistenceError,
+        retrieve_unconfirmed_transfers, store_accept_journal, store_account, store_bids,
+        store_broadcasts, store_counterparties, store_ignored_allocations, store_local_account,
+        store_offers, store_revoked_seals, store_stock as store_rgb_stock, store_stock_account,
+        store_stock_account_transfers, store_stock_transfers, store_transfers,
+        store_unconfirmed_transfers, RgbPersistenceError,
     },
     import::{import_contract, ImportContractError},
+    index::{reindex as start_reindex, transfer_index},
+    merkle::{allocation_leaf, merkle_fold, merkle_path, merkle_root},
+    ownership::{ownership_message, sign_ownership, verify_ownership},
     prebuild::{
         prebuild_buyer_swap, prebuild_extract_transfer, prebuild_seller_swap,
         prebuild_transfer_asset,
@@ -111,11 +168,14 @@ use self::{
         post_consignments, post_media_metadata, post_media_metadata_list, ProxyError,
     },
     psbt::{
-        save_rgb_commit_str, set_tapret_output, CreatePsbtError, EstimateFeeError, NewPsbtOptions,
+        save_rgb_commit_str, set_tapret_output, timelock_script, CreatePsbtError, EstimateFeeError,
+        MergePsbtError, NewPsbtOptions,
     },
+    seal::SealDefinition,
     structs::{
-        ContractAmount, ContractBoilerplate, MediaMetadata, RgbAccountV1, RgbExtractTransfer,
-        RgbTransferV1, RgbTransfersV1,
+        ContractAmount, ContractBoilerplate, MediaMetadata, RgbAccountV1, RgbCounterparties,
+        RgbExtractTransfer, RgbIgnoredAllocations, RgbQuarantinedAccept, RgbRevokedSeals,
+        RgbTransferV1, RgbTransfersV1, RgbUnconfirmedTransfers, SchemaRegistryEntry,
     },
     swap::{
         complete_bid, complete_offer, get_auction, get_auction_fifo_bids, get_auction_highest_bids,
@@ -126,10 +186,14 @@ use self::{
         RgbAuctionStrategy, RgbAuctionSwaps, RgbBid, RgbBidSwap, RgbOffer, RgbOfferErrors,
         RgbOfferOptions, RgbOfferSwap, RgbSwapStrategy,
     },
-    transfer::{extract_transfer, AcceptTransferError, NewInvoiceError, NewPaymentError},
+    transfer::{
+        extract_transfer, iface_version_number, invoice_origin, normalize_origin,
+        sign_invoice_origin, verify_psbt_matches_consignment, wallet_seals, AcceptTransferError,
+        CounterpartyPolicy, NewInvoiceError, NewPaymentError, PsbtConsignmentError,
+    },
     wallet::{
         create_wallet, next_address, next_utxo, next_utxos, register_address, register_utxo,
-        sync_wallet,
+        reused_addresses, sync_explorer_wallet_with_gap, sync_wallet,
     },
 };
 
@@ -148,6 +212,66 @@ pub enum IssueError {
     Issue(IssueContractError),
     /// Occurs an error in export step. {0}
     Export(ExportContractError),
+    /// Contract terms are invalid. {0}
+    WrongTerms(String),
+}
+
+// Store the terms document (if informed) and return the attachment committing
+// to it. Documents are informed by the issuer, never fetched on its behalf.
+async fn prepare_terms(terms: IssueTermsRequest) -> Result<Attachment, IssueError> {
+    let IssueTermsRequest {
+        text,
+        data,
+        media_type,
+        hash,
+    } = terms;
+    let document =
+        match (text, data) {
+            (Some(text), None) => Some(text.into_bytes()),
+            (None, Some(data)) => Some(hex::decode(&data).map_err(|_| {
+                IssueError::WrongTerms("terms document is an invalid hex".to_string())
+            })?),
+            (None, None) => None,
+            (Some(_), Some(_)) => {
+                return Err(IssueError::WrongTerms(
+                    "terms text and document cannot be informed together".to_string(),
+                ))
+            }
+        };
+
+    let digest = match (&document, hash) {
+        (Some(document), hash) => {
+            let digest = *blake3::hash(document).as_bytes();
+            if matches!(hash, Some(hash) if hash.to_lowercase() != hex::encode(digest)) {
+                return Err(IssueError::WrongTerms(
+                    "terms document does not match the informed hash".to_string(),
+                ));
+            }
+            digest
+        }
+        (None, Some(hash)) => hex::decode(&hash)
+            .ok()
+            .and_then(|digest| digest.try_into().ok())
+            .ok_or_else(|| IssueError::WrongTerms(format!("'{hash}' is an invalid digest")))?,
+        (None, None) => {
+            return Err(IssueError::WrongTerms(
+                "terms text, document or hash is required".to_string(),
+            ))
+        }
+    };
+
+    let media_type = media_type.unwrap_or_else(|| TERMS_MEDIA_TYPE.to_string());
+    let attachment = terms_attachment(&media_type, digest).ok_or_else(|| {
+        IssueError::WrongTerms(format!("'{media_type}' is an invalid media type"))
+    })?;
+
+    if let Some(document) = document {
+        store_terms(&hex::encode(digest), &document)
+            .await
+            .map_err(|err| IssueError::WrongTerms(err.to_string()))?;
+    }
+
+    Ok(attachment)
 }
 
 /// RGB Operations
@@ -169,10 +293,16 @@ pub async fn issue_contract(sk: &str, request: IssueRequest) -> Result<IssueResp
         iface,
         seal,
         meta,
+        terms,
     } = request;
 
+    let terms = match terms {
+        Some(terms) => Some(prepare_terms(terms).await?),
+        None => None,
+    };
+
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -216,6 +346,7 @@ pub async fn issue_contract(sk: &str, request: IssueRequest) -> Result<IssueResp
         &seal,
         &network,
         meta,
+        terms,
         &mut resolver,
         &mut stock,
     )
@@ -293,7 +424,7 @@ pub async fn reissue_contract(
     }
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -302,6 +433,7 @@ pub async fn reissue_contract(
     let mut reissue_resp = vec![];
     for contract in request.contracts {
         let ContractResponse {
+            contract_id,
             ticker,
             name,
             description,
@@ -313,6 +445,12 @@ pub async fn reissue_contract(
             ..
         } = contract;
 
+        // Keep the terms committed by the original contract
+        let terms = ContractId::from_str(&contract_id)
+            .ok()
+            .and_then(|contract_id| contract_terms(contract_id, &mut stock).ok())
+            .flatten();
+
         let seals: Vec<String> = allocations
             .into_iter()
             .map(|alloc| format!("tapret1st:{}", alloc.utxo))
@@ -351,6 +489,7 @@ pub async fn reissue_contract(
             &seal,
             &network,
             meta,
+            terms,
             &mut resolver,
             &mut stock,
         )
@@ -433,6 +572,12 @@ pub enum InvoiceError {
     IO(RgbPersistenceError),
     /// Occurs an error in invoice step. {0}
     Invoice(NewInvoiceError),
+    /// Rgb Invoice cannot be decoded. {0}
+    WrongInvoice(String),
+    /// There is no unspent UTXO to bind the invoice to.
+    NoUtxo,
+    /// Occurs an error in watcher step. {0}
+    Watcher(WatcherError),
 }
 
 pub async fn create_invoice(
@@ -442,19 +587,88 @@ pub async fn create_invoice(
     let (mut stock, mut rgb_account) =
         retrieve_stock_account(sk).await.map_err(InvoiceError::IO)?;
 
-    let invoice = internal_create_invoice(request, &mut stock).await?;
+    let seal = request.seal.clone();
+    let invoice = internal_create_invoice(sk, request, &mut stock).await?;
     rgb_account.invoices.push(invoice.to_string());
 
+    // The new invoice is already counted, so any other usage means reuse
+    let reused = seal_usage(&seal, &mut stock).map_err(InvoiceError::Invoice)? > 1;
+
     store_stock_account(sk, stock, rgb_account)
         .await
         .map_err(InvoiceError::IO)?;
 
     Ok(InvoiceResponse {
         invoice: invoice.to_string(),
+        reused,
+    })
+}
+
+/// Bind the invoice to a fresh blinded seal (on the next unspent UTXO of the
+/// watcher), keeping its contract, amount and expiry.
+///
+/// The previous seal is revoked, so transfers paying to it are rejected.
+pub async fn rebind_invoice(sk: &str, invoice: &str) -> Result<InvoiceResponse, InvoiceError> {
+    let prev_invoice =
+        RgbInvoice::from_str(invoice).map_err(|err| InvoiceError::WrongInvoice(err.to_string()))?;
+    let iface = prev_invoice
+        .iface
+        .clone()
+        .map(|iface| iface.to_string())
+        .unwrap_or_default();
+
+    let NextUtxoResponse { utxo } = watcher_next_utxo(sk, RGB_DEFAULT_NAME, &iface)
+        .await
+        .map_err(InvoiceError::Watcher)?;
+    let seal = match utxo {
+        Some(utxo) => format!("tapret1st:{}", utxo.outpoint),
+        _ => return Err(InvoiceError::NoUtxo),
+    };
+
+    let (mut stock, mut rgb_account) =
+        retrieve_stock_account(sk).await.map_err(InvoiceError::IO)?;
+    let mut revoked = retrieve_revoked_seals(sk).await.map_err(InvoiceError::IO)?;
+
+    let (mut new_invoice, prev_seal) =
+        rebind_rgb_invoice(&prev_invoice, &seal, sk.as_bytes(), &mut stock)
+            .map_err(InvoiceError::Invoice)?;
+    // The origin signs the beneficiary, so it is signed again for the new seal
+    if invoice_origin(&prev_invoice).is_some() {
+        sign_invoice_origin(&mut new_invoice, sk).map_err(InvoiceError::Invoice)?;
+    }
+    rgb_account.invoices.retain(|x| x != invoice);
+    rgb_account.invoices.push(new_invoice.to_string());
+    revoked.seals.insert(prev_seal.to_string());
+
+    let reused = seal_usage(&seal, &mut stock).map_err(InvoiceError::Invoice)? > 1;
+
+    store_revoked_seals(sk, revoked)
+        .await
+        .map_err(InvoiceError::IO)?;
+    store_stock_account(sk, stock, rgb_account)
+        .await
+        .map_err(InvoiceError::IO)?;
+
+    Ok(InvoiceResponse {
+        invoice: new_invoice.to_string(),
+        reused,
     })
 }
 
+pub async fn find_reused(sk: &str) -> Result<ReusedResponse> {
+    let (mut stock, rgb_account) = retrieve_stock_account(sk).await?;
+
+    let seals = reused_seals(&mut stock)?;
+    let addresses = match rgb_account.wallets.get(RGB_DEFAULT_NAME) {
+        Some(rgb_wallet) => reused_addresses(rgb_wallet),
+        _ => BTreeMap::new(),
+    };
+
+    Ok(ReusedResponse { seals, addresses })
+}
+
 async fn internal_create_invoice(
+    sk: &str,
     request: InvoiceRequest,
     stock: &mut Stock,
 ) -> Result<RgbInvoice, InvoiceError> {
@@ -471,10 +685,16 @@ async fn internal_create_invoice(
         iface,
         seal,
         amount,
-        params,
+        mut params,
+        iface_version,
+        origin,
     } = request;
 
-    let network = NETWORK.read().await.to_string();
+    if let Some(version) = iface_version {
+        params.insert(RGB_IFACE_VERSION_PARAM.to_string(), version.to_string());
+    }
+
+    let network = current_network().await.to_string();
 
     let contr_id = ContractId::from_str(&contract_id).map_err(|_| InvoiceError::NoContract)?;
     let boilerplate = export_boilerplate(contr_id, stock).map_err(|_| InvoiceError::NoContract)?;
@@ -487,16 +707,20 @@ async fn internal_create_invoice(
     }
 
     let invoice_amount = invoice_amount.to_value();
-    let invoice = create_rgb_invoice(
+    let mut invoice = create_rgb_invoice(
         &contract_id,
         &iface,
         invoice_amount,
         &seal,
+        sk.as_bytes(),
         &network,
         params,
         stock,
     )
     .map_err(InvoiceError::Invoice)?;
+    if origin {
+        sign_invoice_origin(&mut invoice, sk).map_err(InvoiceError::Invoice)?;
+    }
 
     Ok(invoice)
 }
@@ -530,11 +754,19 @@ pub enum PsbtError {
     WrongNetwork(String),
     /// Occurs an error in export step. {0}
     Export(ExportContractError),
+    /// PSBT file cannot be decoded. {0}
+    WrongPsbt(String),
+    /// Occurs an error in merge step. {0}
+    Merge(MergePsbtError),
+    /// Data output cannot be decoded. {0}
+    WrongDataOutput(String),
+    /// Beneficiary key of the time-locked output is invalid. {0}
+    WrongLockBeneficiary(String),
 }
 
 pub async fn create_psbt(sk: &str, request: PsbtRequest) -> Result<PsbtResponse, PsbtError> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -570,6 +802,10 @@ async fn internal_create_psbt(
         bitcoin_inputs,
         bitcoin_changes,
         fee,
+        max_fee,
+        max_outputs,
+        data_output,
+        lock,
         ..
     } = request;
 
@@ -585,7 +821,28 @@ async fn internal_create_psbt(
         PsbtFeeRequest::FeeRate(_) => return Err(PsbtError::NoFeeRate),
     };
 
-    let options = options.unwrap_or_default();
+    let mut options = options.unwrap_or_default();
+    if max_fee.is_some() {
+        options.max_fee = max_fee;
+    }
+    if max_outputs.is_some() {
+        options.max_outputs = max_outputs;
+    }
+    if let Some(data_output) = data_output {
+        let data = Vec::<u8>::from_hex(&data_output)
+            .map_err(|err| PsbtError::WrongDataOutput(err.to_string()))?;
+        options.data = Some(data);
+    }
+    if let Some(PsbtTimelockRequest { lock, beneficiary }) = lock {
+        let beneficiary = bitcoin::PublicKey::from_str(&beneficiary)
+            .map_err(|err| PsbtError::WrongLockBeneficiary(err.to_string()))?;
+        options.timelock = Some((lock, beneficiary));
+    }
+    // Anti-fee-sniping nLockTime, skipped when the explorer is unreachable
+    if options.lock.is_none() && options.tip_height.is_none() {
+        options.tip_height = get_blockchain().await.get_height().await.ok();
+    }
+
     let wallet = rgb_account.wallets.get(RGB_DEFAULT_NAME);
     let (mut psbt_file, change_terminal) = create_rgb_psbt(
         all_inputs,
@@ -611,6 +868,90 @@ async fn internal_create_psbt(
     Ok(psbt)
 }
 
+/// Address and script of the time-locked output of the beneficiary.
+///
+/// Invoices paying to the address bind the assets to the output, so they can
+/// only be spent once the lock expires.
+pub async fn timelock_output(
+    request: PsbtTimelockRequest,
+) -> Result<TimelockOutputResponse, PsbtError> {
+    let PsbtTimelockRequest { lock, beneficiary } = request;
+    if !lock.is_valid() {
+        return Err(PsbtError::Create(CreatePsbtError::WrongLockTime(lock)));
+    }
+    let beneficiary = bitcoin::PublicKey::from_str(&beneficiary)
+        .map_err(|err| PsbtError::WrongLockBeneficiary(err.to_string()))?;
+
+    let network = get_network().await;
+    let network =
+        Network::from_str(&network).map_err(|err| PsbtError::WrongNetwork(err.to_string()))?;
+    let script = timelock_script(lock, &beneficiary);
+
+    Ok(TimelockOutputResponse {
+        address: bitcoin::Address::p2wsh(&script, network).to_string(),
+        script: script.as_bytes().to_hex(),
+    })
+}
+
+/// Join PSBTs of different contributors into one transaction (disjoint inputs and outputs)
+pub fn join_psbts(request: MergePsbtRequest) -> Result<MergePsbtResponse, PsbtError> {
+    let psbts = decode_psbts(request.psbts)?;
+    let joined = join_rgb_psbts(psbts).map_err(PsbtError::Merge)?;
+
+    Ok(MergePsbtResponse {
+        psbt: Serialize::serialize(&Psbt::from(joined)).to_hex(),
+    })
+}
+
+/// Join PSBTs of different contributors and pay the invoice with the joined
+/// transaction.
+///
+/// The RGB transitions of the contributors were bound to their own
+/// transactions, so the transfer is rebuilt from the stock over the joined
+/// PSBT (see [`transfer_asset`]).
+pub async fn join_transfer_psbts(
+    sk: &str,
+    request: RgbJoinTransferRequest,
+) -> Result<RgbTransferResponse, TransferError> {
+    let RgbJoinTransferRequest {
+        psbts,
+        rgb_invoice,
+        terminal,
+    } = request;
+    let joined = join_psbts(MergePsbtRequest { psbts }).map_err(TransferError::Create)?;
+
+    let request = RgbTransferRequest {
+        rgb_invoice,
+        psbt: joined.psbt,
+        terminal,
+        from_seals: vec![],
+        receipt: false,
+        change_split: vec![],
+    };
+    transfer_asset(sk, request).await
+}
+
+/// Combine signatures of PSBTs of the same transaction
+pub fn combine_psbts(request: MergePsbtRequest) -> Result<MergePsbtResponse, PsbtError> {
+    let psbts = decode_psbts(request.psbts)?;
+    let combined = combine_rgb_psbts(psbts).map_err(PsbtError::Merge)?;
+
+    Ok(MergePsbtResponse {
+        psbt: Serialize::serialize(&Psbt::from(combined)).to_hex(),
+    })
+}
+
+fn decode_psbts(psbts: Vec<String>) -> Result<Vec<PsbtV0>, PsbtError> {
+    psbts
+        .into_iter()
+        .map(|psbt| {
+            Psbt::from_str(&psbt)
+                .map(PsbtV0::from)
+                .map_err(|op| PsbtError::WrongPsbt(op.to_string()))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Display, From, Error)]
 #[display(doc_comments)]
 pub enum TransferError {
@@ -634,6 +975,17 @@ pub enum TransferError {
         /// Amount sent: sum of output value + transaction fee
         output: u64,
     },
+    /// Insufficient funds to create the change output (required: {required} sats / available: {available} sats / shortfall: {shortfall} sats)
+    NoChangeFunds {
+        /// Amount required: transaction fee + bitcoin outputs + change output
+        required: u64,
+
+        /// Amount available in the wallet
+        available: u64,
+
+        /// Amount missing
+        shortfall: u64,
+    },
     /// Occurs an error in create step. {0}
     Create(PsbtError),
     /// Occurs an error in estimate fee step. {0}
@@ -662,11 +1014,33 @@ pub enum TransferError {
     Save(SaveTransferError),
     /// Occurs an error in retrieve proxy step. {0}
     Proxy(ProxyError),
+    /// Operation deadline exceeded, no changes were stored.
+    Expired,
+    /// Operation was interrupted, the changes are recovered on the next accept.
+    Interrupted,
+    /// Witness transaction cannot be decoded. {0}
+    WrongTx(String),
+    /// Witness transaction cannot be published. {0}
+    Publish(String),
+    /// Witness transaction does not match the consignment. {0}
+    PsbtMismatch(PsbtConsignmentError),
+}
+
+// Requests without fee (the default zero fee value) follow the fee policy of
+// the operation, as the bitcoin transactions
+async fn policy_psbt_fee(operation: FeeOperation, fee: PsbtFeeRequest) -> PsbtFeeRequest {
+    match fee {
+        PsbtFeeRequest::Value(0) => match operation_fee_rate(operation, None).await {
+            Some(fee_rate) => PsbtFeeRequest::FeeRate(fee_rate),
+            None => fee,
+        },
+        fee => fee,
+    }
 }
 
 pub async fn full_transfer_asset(
     sk: &str,
-    request: FullRgbTransferRequest,
+    mut request: FullRgbTransferRequest,
 ) -> Result<RgbTransferResponse, TransferError> {
     if let Err(err) = request.validate(&RGBContext::default()) {
         let errors = err
@@ -675,6 +1049,7 @@ pub async fn full_transfer_asset(
             .collect();
         return Err(TransferError::Validation(errors));
     }
+    request.fee = policy_psbt_fee(FeeOperation::Pay, request.fee).await;
 
     let (mut stock, mut rgb_transfers) = retrieve_stock_transfers(sk)
         .await
@@ -693,7 +1068,7 @@ pub async fn full_transfer_asset(
     let mut rgb_account_changes = RawRgbAccount::from(rgb_account.clone());
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -702,13 +1077,28 @@ pub async fn full_transfer_asset(
         _ => return Err(TransferError::NoWatcher),
     };
 
-    let (asset_inputs, bitcoin_inputs, bitcoin_changes, fee_value) =
-        prebuild_transfer_asset(request.clone(), &mut stock, &mut rgb_wallet, &mut resolver)
-            .await?;
+    let ignored = retrieve_ignored_allocations(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    let unconfirmed = retrieve_unconfirmed_transfers(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    let (asset_inputs, bitcoin_inputs, bitcoin_changes, fee_value) = prebuild_transfer_asset(
+        request.clone(),
+        &mut stock,
+        &mut rgb_wallet,
+        &ignored,
+        &unconfirmed,
+        &mut resolver,
+    )
+    .await?;
 
     let FullRgbTransferRequest {
         rgb_invoice,
         change_terminal,
+        from_seals,
+        max_fee,
+        receipt,
         ..
     } = request;
 
@@ -720,6 +1110,10 @@ pub async fn full_transfer_asset(
         asset_descriptor_change: None,
         asset_terminal_change: Some(change_terminal),
         rbf: true,
+        lock: None,
+        max_fee,
+        max_outputs: None,
+        data_output: None,
     };
 
     let psbt_response = internal_create_psbt(psbt_req, &mut rgb_account, &mut resolver, None)
@@ -730,17 +1124,25 @@ pub async fn full_transfer_asset(
         rgb_invoice,
         psbt: psbt_response.psbt,
         terminal: psbt_response.terminal.clone(),
+        from_seals,
+        receipt,
+        change_split: vec![],
     };
 
-    let options = NewTransferOptions::default();
+    let options = NewTransferOptions {
+        counterparty_policy: counterparty_policy(sk).await?,
+        ..default!()
+    };
     let RgbInternalTransferResponse {
         consig_id,
+        consignment_id,
         consig,
         psbt,
         commit,
         outpoint,
         amount,
         txid,
+        receipt,
         ..
     } = internal_transfer_asset(
         transfer_req,
@@ -769,10 +1171,12 @@ pub async fn full_transfer_asset(
 
     let resp = RgbTransferResponse {
         consig_id,
+        consignment_id,
         consig,
         psbt,
         commit,
         txid,
+        receipt,
     };
 
     rgb_account.clone().update(&mut rgb_account_changes);
@@ -793,20 +1197,36 @@ pub async fn full_transfer_asset(
 pub async fn transfer_asset(
     sk: &str,
     request: RgbTransferRequest,
+) -> Result<RgbTransferResponse, TransferError> {
+    transfer_asset_until(sk, request, || true).await
+}
+
+/// Same as [`transfer_asset`], but `can_commit` is checked right before
+/// storing the changes. When it returns `false` (e.g. the caller deadline
+/// has passed), the transfer is discarded and the stock is left untouched.
+pub async fn transfer_asset_until(
+    sk: &str,
+    request: RgbTransferRequest,
+    can_commit: impl FnOnce() -> bool,
 ) -> Result<RgbTransferResponse, TransferError> {
     let (mut stock, mut rgb_account, mut rgb_transfers) = retrieve_stock_account_transfers(sk)
         .await
         .map_err(TransferError::IO)?;
 
-    let options = NewTransferOptions::default();
+    let options = NewTransferOptions {
+        counterparty_policy: counterparty_policy(sk).await?,
+        ..default!()
+    };
     let RgbInternalTransferResponse {
         consig_id,
+        consignment_id,
         consig,
         psbt,
         commit,
         outpoint,
         amount,
         txid,
+        receipt,
         ..
     } = internal_transfer_asset(
         request.clone(),
@@ -835,12 +1255,18 @@ pub async fn transfer_asset(
 
     let resp = RgbTransferResponse {
         consig_id,
+        consignment_id,
         consig,
         psbt,
         commit,
         txid,
+        receipt,
     };
 
+    if !can_commit() {
+        return Err(TransferError::Expired);
+    }
+
     store_stock_account_transfers(sk, stock, rgb_account, rgb_transfers)
         .await
         .map_err(TransferError::IO)?;
@@ -895,6 +1321,8 @@ pub enum RgbSwapError {
     Swap(RgbOfferErrors),
     /// Occurs an error in transfer step. {0}
     Transfer(TransferError),
+    /// Occurs an error in broadcast check step. {0}
+    PsbtMismatch(PsbtConsignmentError),
     /// Offer {0} is valid. Reason {1}
     WrongOffer(String, String),
     /// Swap fee cannot be decoded. {0}
@@ -934,7 +1362,7 @@ pub async fn create_seller_offer(
     }
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -1011,7 +1439,7 @@ pub async fn create_auction_offers(
     }
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -1178,7 +1606,7 @@ pub async fn internal_create_seller_offer(
         return Err(RgbSwapError::Validation(errors));
     }
 
-    let network = NETWORK.read().await.to_string();
+    let network = current_network().await.to_string();
     let network =
         Network::from_str(&network).map_err(|op| RgbSwapError::WrongNetwork(op.to_string()))?;
 
@@ -1212,8 +1640,19 @@ pub async fn internal_create_seller_offer(
         fee_airdrop,
         ..
     } = options.clone();
+    let unconfirmed = retrieve_unconfirmed_transfers(sk)
+        .await
+        .map_err(RgbSwapError::IO)?;
     let (allocations, asset_inputs, bitcoin_inputs, mut bitcoin_changes, change_value) =
-        prebuild_seller_swap(request, options, rgb_stock, &mut rgb_wallet, rgb_resolver).await?;
+        prebuild_seller_swap(
+            request,
+            options,
+            rgb_stock,
+            &mut rgb_wallet,
+            &unconfirmed,
+            rgb_resolver,
+        )
+        .await?;
 
     rgb_account
         .wallets
@@ -1237,6 +1676,10 @@ pub async fn internal_create_seller_offer(
         asset_descriptor_change: None,
         asset_terminal_change: Some(change_terminal.clone()),
         rbf: true,
+        lock: None,
+        max_fee: None,
+        max_outputs: None,
+        data_output: None,
     };
 
     let seller_psbt = internal_create_psbt(psbt_req, rgb_account, rgb_resolver, Some(psbt_options))
@@ -1272,7 +1715,7 @@ pub async fn create_buyer_bid(
         retrieve_stock_account(sk).await.map_err(RgbSwapError::IO)?;
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -1328,7 +1771,7 @@ pub async fn create_auction_bid(
         .map_err(RgbSwapError::IO)?;
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -1379,11 +1822,17 @@ pub async fn create_auction_bid(
                 psbt: swap_psbt.clone(),
                 rgb_invoice: buyer_invoice.to_string(),
                 terminal: change_terminal.to_string(),
+                from_seals: vec![],
+                receipt: false,
+                change_split: vec![],
             };
 
             let params = NewTransferOptions {
                 offer_id: Some(offer_id.clone()),
                 bid_id: Some(bid_id.clone()),
+                counterparty_policy: counterparty_policy(sk)
+                    .await
+                    .map_err(RgbSwapError::Transfer)?,
                 ..default!()
             };
 
@@ -1480,7 +1929,7 @@ pub async fn create_auction_bid(
 
 async fn internal_create_buyer_bid(
     sk: &str,
-    request: RgbBidRequest,
+    mut request: RgbBidRequest,
     rgb_account: &mut RgbAccountV1,
     rgb_stock: &mut Stock,
     resolver: &mut ExplorerResolver,
@@ -1492,6 +1941,7 @@ async fn internal_create_buyer_bid(
             .collect();
         return Err(RgbSwapError::Validation(errors));
     }
+    request.fee = policy_psbt_fee(FeeOperation::Pay, request.fee).await;
 
     let RgbBidRequest {
         offer_id,
@@ -1598,6 +2048,10 @@ async fn internal_create_buyer_bid(
                 asset_descriptor_change: None,
                 asset_terminal_change: Some(change_terminal.clone()),
                 rbf: true,
+                lock: None,
+                max_fee: None,
+                max_outputs: None,
+                data_output: None,
             };
 
             let options = NewPsbtOptions {
@@ -1647,8 +2101,11 @@ async fn internal_create_buyer_bid(
         amount: invoice_amount.to_string(),
         seal: format!("tapret1st:{buyer_outpoint}"),
         params: HashMap::new(),
+        iface_version: None,
+        // Sellers with a counterparty policy identify the bidders by origin
+        origin: true,
     };
-    let invoice = internal_create_invoice(invoice_req, rgb_stock)
+    let invoice = internal_create_invoice(sk, invoice_req, rgb_stock)
         .await
         .map_err(RgbSwapError::Invoice)?;
 
@@ -1726,11 +2183,17 @@ pub async fn create_swap_transfer(
         psbt: swap_psbt.clone(),
         rgb_invoice: buyer_invoice.to_string(),
         terminal: change_terminal.to_string(),
+        from_seals: vec![],
+        receipt: false,
+        change_split: vec![],
     };
 
     let params = NewTransferOptions {
         offer_id: Some(offer_id.clone()),
         bid_id: Some(bid_id.clone()),
+        counterparty_policy: counterparty_policy(sk)
+            .await
+            .map_err(RgbSwapError::Transfer)?,
         ..default!()
     };
 
@@ -2002,13 +2465,21 @@ pub async fn finish_auction_offers(
             _ => "/10/1",
         };
 
-        let options = NewTransferOptions::with(true, all_invoices);
+        let options = NewTransferOptions {
+            counterparty_policy: counterparty_policy(sk)
+                .await
+                .map_err(RgbSwapError::Transfer)?,
+            ..NewTransferOptions::with(true, all_invoices)
+        };
         let final_psbt = Psbt::from(final_psbt.unwrap());
         let final_psbt = Serialize::serialize(&final_psbt).to_hex();
         let request = RgbTransferRequest {
             psbt: final_psbt,
             rgb_invoice: buyer_invoice.clone(),
             terminal: change_terminal.to_string(),
+            from_seals: vec![],
+            receipt: false,
+            change_split: vec![],
         };
 
         let RgbInternalTransferResponse {
@@ -2029,6 +2500,13 @@ pub async fn finish_auction_offers(
         .await
         .map_err(RgbSwapError::Transfer)?;
 
+        verify_psbt_matches_consignment(&final_psbt, &final_consig)
+            .map_err(RgbSwapError::PsbtMismatch)?;
+        let witness = Psbt::from_str(&final_psbt)
+            .map_err(|op| RgbSwapError::WrongPsbtFinal(op.to_string()))?;
+        store_broadcast(sk, &PsbtV0::from(witness))
+            .await
+            .map_err(RgbSwapError::IO)?;
         publish_psbt_file(PublishPsbtRequest {
             psbt: final_psbt.clone(),
         })
@@ -2152,12 +2630,12 @@ pub async fn finish_auction_offers(
 
 async fn internal_transfer_asset(
     request: RgbTransferRequest,
-    options: NewTransferOptions,
+    mut options: NewTransferOptions,
     stock: &mut Stock,
     rgb_account: &mut RgbAccountV1,
     rgb_transfers: &mut RgbTransfersV1,
 ) -> Result<RgbInternalTransferResponse, TransferError> {
-    let network = NETWORK.read().await.to_string();
+    let network = current_network().await.to_string();
     let context = RGBContext::with(&network);
 
     if let Err(err) = request.validate(&context) {
@@ -2175,8 +2653,25 @@ async fn internal_transfer_asset(
     let RgbTransferRequest {
         rgb_invoice: invoice,
         psbt,
+        from_seals,
+        receipt: with_receipt,
+        change_split,
         ..
     } = request;
+    options.change_split = change_split;
+
+    for seal in from_seals {
+        let outpoint = Outpoint::from_str(&seal).map_err(|_| {
+            let mut errors = BTreeMap::new();
+            errors.insert("from_seals".to_string(), format!("invalid outpoint {seal}"));
+            TransferError::Validation(errors)
+        })?;
+        options.from_seals.push(outpoint);
+    }
+
+    let rgb_invoice = RgbInvoice::from_str(&invoice)
+        .map_err(|err| TransferError::WrongInvoice(err.to_string()))?;
+    check_invoice_chain(&rgb_invoice, &network).map_err(TransferError::Pay)?;
 
     let (psbt, mut transfers) =
         pay_invoice(invoice.clone(), psbt, options.clone(), stock).map_err(TransferError::Pay)?;
@@ -2185,16 +2680,21 @@ async fn internal_transfer_asset(
 
     let transfer = transfers.remove(0);
     let consig_id = transfer.bindle_id().to_string();
+    let consignment_id =
+        rgb_consignment_id(&transfer).map_err(|err| TransferError::WrongConsig(err.to_string()))?;
     let consig = transfer
         .to_strict_serialized::<{ U32 }>()
         .map_err(|err| TransferError::WrongConsig(err.to_string()))?;
 
-    let rgb_invoice = RgbInvoice::from_str(&invoice)
-        .map_err(|err| TransferError::WrongInvoice(err.to_string()))?;
-
     let consig = consig.to_hex();
     let commit = commit.to_hex();
     let psbt_hex = psbt.to_string();
+    let psbt_fee = psbt
+        .inputs
+        .iter()
+        .filter_map(|input| input.witness_utxo.as_ref().map(|utxo| utxo.value))
+        .sum::<u64>()
+        .saturating_sub(psbt.outputs.iter().map(|output| output.amount).sum());
 
     let iface = rgb_invoice.clone().iface.unwrap().to_string();
     let mut consigs = BTreeMap::default();
@@ -2216,6 +2716,20 @@ async fn internal_transfer_asset(
         }
     }
 
+    // The receipt is built before the transfer is saved: when it fails,
+    // nothing is stored
+    let receipt = if with_receipt {
+        Some(transfer_receipt(
+            &rgb_invoice,
+            psbt_fee,
+            &psbt.to_txid().to_string(),
+            &consignment_id,
+            stock,
+        )?)
+    } else {
+        None
+    };
+
     let internal_request = RgbInternalSaveTransferRequest::with(
         consig_id.clone(),
         consig.clone(),
@@ -2232,6 +2746,7 @@ async fn internal_transfer_asset(
 
     let resp = RgbInternalTransferResponse {
         consig_id,
+        consignment_id,
         consig,
         amount,
         psbt: psbt_hex,
@@ -2239,11 +2754,48 @@ async fn internal_transfer_asset(
         outpoint: outpoint.to_string(),
         consigs,
         txid: txid.to_hex(),
+        receipt,
     };
 
     Ok(resp)
 }
 
+// Display fields of a transfer, so clients don't have to re-derive them
+fn transfer_receipt(
+    invoice: &RgbInvoice,
+    fee: u64,
+    txid: &str,
+    consignment_id: &str,
+    stock: &mut Stock,
+) -> Result<RgbTransferReceipt, TransferError> {
+    let contract_id = invoice.contract.ok_or(TransferError::NoContract)?;
+    let ContractBoilerplate {
+        contract_id,
+        ticker,
+        name,
+        precision,
+        ..
+    } = export_boilerplate(contract_id, stock).map_err(|_| TransferError::NoContract)?;
+
+    let amount = match invoice.owned_state {
+        TypedState::Amount(amount) => ContractAmount::new(amount, precision).to_string(),
+        _ => "1".to_string(),
+    };
+
+    Ok(RgbTransferReceipt {
+        contract_id,
+        iface: invoice.iface.clone().unwrap_or_default().to_string(),
+        ticker,
+        name,
+        amount,
+        beneficiary: invoice.beneficiary.to_string(),
+        fee,
+        txid: txid.to_string(),
+        consignment_id: consignment_id.to_string(),
+        created_at: chrono::Local::now().naive_utc().timestamp(),
+    })
+}
+
 pub async fn list_auctions() -> Result<Vec<RgbAuctionOfferResponse>, RgbSwapError> {
     let utc = chrono::Local::now().naive_utc().timestamp();
     let auction_offers: Vec<_> = get_public_offers()
@@ -2265,6 +2817,35 @@ pub async fn accept_transfer(
     sk: &str,
     request: AcceptRequest,
 ) -> Result<AcceptResponse, TransferError> {
+    accept_transfer_until(sk, request, |_| true).await
+}
+
+/// Checkpoints of [`accept_transfer_until`], before storing each change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AcceptCheckpoint {
+    /// The consignment is validated, nothing is stored yet
+    Validated,
+    /// The stock is stored, the pending transfers of the wallet are not
+    StockStored,
+}
+
+/// Same as [`accept_transfer`], but `can_continue` is checked at every
+/// [`AcceptCheckpoint`]. When it returns `false` once the consignment is
+/// validated, the transfer is discarded and the stock is left untouched.
+///
+/// The changes are staged in memory and stored after writing a journal of the
+/// transfer, so a transfer interrupted while storing is accepted again on the
+/// next call (see [`recover_accept_transfers`]).
+pub async fn accept_transfer_until(
+    sk: &str,
+    request: AcceptRequest,
+    mut can_continue: impl FnMut(AcceptCheckpoint) -> bool,
+) -> Result<AcceptResponse, TransferError> {
+    // Failed entries are quarantined, they never block new transfers
+    if let Err(err) = recover_accept_transfers(sk).await {
+        warn!(format!("Cannot recover the interrupted transfers: {err}"));
+    }
+
     if let Err(err) = request.validate(&RGBContext::default()) {
         let errors = err
             .iter()
@@ -2274,29 +2855,190 @@ pub async fn accept_transfer(
     }
     let mut stock = retrieve_rgb_stock(sk).await.map_err(TransferError::IO)?;
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
-    let AcceptRequest { consignment, .. } = request;
+    let AcceptRequest {
+        consignment,
+        invoice,
+        ..
+    } = request;
+
+    let invoice = match invoice {
+        Some(invoice) => Some(
+            RgbInvoice::from_str(&invoice)
+                .map_err(|err| TransferError::WrongInvoice(err.to_string()))?,
+        ),
+        None => None,
+    };
+    // The payment status is only reported for the invoices of the wallet
+    if let Some(invoice) = &invoice {
+        check_wallet_invoice(invoice, &stock)
+            .map_err(|err| TransferError::WrongInvoice(err.to_string()))?;
+    }
+
+    let revoked = retrieve_revoked_seals(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    check_revoked_seals(&consignment, &revoked.seals).map_err(TransferError::Accept)?;
+
     prefetch_resolver_rgb(&consignment, &mut resolver, None).await;
 
-    let transfer = accept_rgb_transfer(consignment, false, &mut resolver, &mut stock)
+    let transfer = accept_rgb_transfer(consignment.clone(), false, &mut resolver, &mut stock)
         .map_err(TransferError::Accept)?;
 
+    let mut paid_amount = None;
+    let mut payment_status = None;
+    if let Some(invoice) = invoice {
+        let invoiced = match invoice.owned_state {
+            TypedState::Amount(amount) => amount,
+            TypedState::Data(_) => 1,
+            _ => 0,
+        };
+
+        if let Some(paid) = invoice_paid_amount(&transfer, &invoice) {
+            paid_amount = Some(paid);
+            payment_status = Some(InvoicePaymentStatus::with(invoiced, paid));
+        }
+    }
+
     let resp = AcceptResponse {
         contract_id: transfer.contract_id().to_string(),
         transfer_id: transfer.transfer_id().to_string(),
         valid: true,
+        paid_amount,
+        payment_status,
     };
 
-    store_rgb_stock(sk, stock)
+    if !can_continue(AcceptCheckpoint::Validated) {
+        return Err(TransferError::Expired);
+    }
+
+    commit_accept_transfer(
+        sk,
+        &consignment,
+        stock,
+        &mut resolver,
+        &transfer,
+        can_continue,
+    )
+    .await?;
+
+    Ok(resp)
+}
+
+/// Accept again the transfers of interrupted [`accept_transfer`] calls.
+/// Accepting a transfer twice is harmless, so every entry of the journal is
+/// replayed whole. Entries which cannot be accepted again are logged and
+/// moved to the quarantine of the journal, so they are not retried forever.
+///
+/// Wallets should call it once when they are opened, since the interrupted
+/// transfers are otherwise recovered on the next accept.
+pub async fn recover_accept_transfers(sk: &str) -> Result<AcceptRecoveryResponse, TransferError> {
+    let journal = retrieve_accept_journal(sk)
         .await
         .map_err(TransferError::IO)?;
 
+    let mut resp = AcceptRecoveryResponse::default();
+    for (consig_id, consignment) in journal.entries {
+        match recover_accept_entry(sk, &consignment).await {
+            Ok(()) => resp.recovered.push(consig_id),
+            Err(err) => {
+                warn!(format!(
+                    "Cannot recover the interrupted transfer {consig_id}: {err}"
+                ));
+                let mut journal = retrieve_accept_journal(sk)
+                    .await
+                    .map_err(TransferError::IO)?;
+                journal.entries.remove(&consig_id);
+                journal.quarantine.insert(
+                    consig_id.clone(),
+                    RgbQuarantinedAccept {
+                        consignment,
+                        error: err.to_string(),
+                    },
+                );
+                store_accept_journal(sk, journal)
+                    .await
+                    .map_err(TransferError::IO)?;
+                resp.quarantined.push(consig_id);
+            }
+        }
+    }
+
     Ok(resp)
 }
 
+async fn recover_accept_entry(sk: &str, consignment: &str) -> Result<(), TransferError> {
+    let mut stock = retrieve_rgb_stock(sk).await.map_err(TransferError::IO)?;
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+
+    prefetch_resolver_rgb(consignment, &mut resolver, None).await;
+
+    let transfer = accept_rgb_transfer(consignment.to_owned(), false, &mut resolver, &mut stock)
+        .map_err(TransferError::Accept)?;
+    commit_accept_transfer(sk, consignment, stock, &mut resolver, &transfer, |_| true).await
+}
+
+// Store the changes of an accepted transfer, between writing and clearing
+// its journal entry. The witness transactions are kept until they confirm, so
+// the received allocations can be held by the confirmation policy.
+async fn commit_accept_transfer(
+    sk: &str,
+    consignment: &str,
+    stock: Stock,
+    resolver: &mut ExplorerResolver,
+    transfer: &Transfer,
+    mut can_continue: impl FnMut(AcceptCheckpoint) -> bool,
+) -> Result<(), TransferError> {
+    let consig_id = transfer.transfer_id().to_string();
+    let mut unconfirmed = retrieve_unconfirmed_transfers(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    release_confirmed_transfers(&mut unconfirmed, resolver).await?;
+    unconfirmed
+        .transfers
+        .entry(transfer.contract_id().to_string())
+        .or_default()
+        .extend(
+            witness_txids(transfer)
+                .into_iter()
+                .map(|txid| txid.to_hex()),
+        );
+
+    let mut journal = retrieve_accept_journal(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    journal
+        .entries
+        .insert(consig_id.clone(), consignment.to_owned());
+    store_accept_journal(sk, journal)
+        .await
+        .map_err(TransferError::IO)?;
+
+    store_rgb_stock(sk, stock)
+        .await
+        .map_err(TransferError::IO)?;
+    if !can_continue(AcceptCheckpoint::StockStored) {
+        return Err(TransferError::Interrupted);
+    }
+    store_unconfirmed_transfers(sk, unconfirmed)
+        .await
+        .map_err(TransferError::IO)?;
+
+    let mut journal = retrieve_accept_journal(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    journal.entries.remove(&consig_id);
+    store_accept_journal(sk, journal)
+        .await
+        .map_err(TransferError::IO)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Display, From, Error)]
 #[display(doc_comments)]
 pub enum SaveTransferError {
@@ -2320,17 +3062,73 @@ pub enum SaveTransferError {
     Write(String, String),
 }
 
-pub async fn save_transfer(
+/// Preview the balance changes of a transfer, without accepting it.
+///
+/// The consignment goes through the same validation of [`accept_transfer`],
+/// but the stock is never stored.
+pub async fn simulate_accept(
     sk: &str,
-    request: RgbSaveTransferRequest,
-) -> Result<RgbTransferStatusResponse, SaveTransferError> {
-    if let Err(err) = request.validate(&RGBContext::default()) {
-        let errors = err
-            .iter()
-            .map(|(f, e)| (f.to_string(), e.to_string()))
-            .collect();
-        return Err(SaveTransferError::Validation(errors));
-    }
+    consignment: String,
+) -> Result<SimulateAcceptResponse, TransferError> {
+    let mut stock = retrieve_rgb_stock(sk).await.map_err(TransferError::IO)?;
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+
+    prefetch_resolver_rgb(&consignment, &mut resolver, None).await;
+
+    let (transfer, warnings) =
+        check_transfer(consignment, &mut resolver).map_err(TransferError::Accept)?;
+
+    let seals: Vec<SecretSeal> = wallet_seals(&stock)
+        .map_err(|err| TransferError::IO(RgbPersistenceError::RetrieveStock(err.to_string())))?
+        .iter()
+        .map(SealDefinition::to_concealed_seal)
+        .collect();
+
+    let contract_id = transfer.contract_id();
+    let before = if stock
+        .contract_ids()
+        .unwrap_or_default()
+        .contains(&contract_id)
+    {
+        get_contract(sk, &contract_id.to_string())
+            .await
+            .map(|contract| contract.balance)
+            .map_err(|err| TransferError::WrongConsig(err.to_string()))?
+    } else {
+        0
+    };
+
+    let received = seals_paid_amount(&transfer, &seals);
+    let balances = vec![BalanceDelta {
+        contract_id: contract_id.to_string(),
+        before,
+        received,
+        after: before + received,
+    }];
+
+    Ok(SimulateAcceptResponse {
+        transfer_id: transfer.transfer_id().to_string(),
+        contract_id: contract_id.to_string(),
+        valid: true,
+        warnings,
+        balances,
+    })
+}
+
+pub async fn save_transfer(
+    sk: &str,
+    request: RgbSaveTransferRequest,
+) -> Result<RgbTransferStatusResponse, SaveTransferError> {
+    if let Err(err) = request.validate(&RGBContext::default()) {
+        let errors = err
+            .iter()
+            .map(|(f, e)| (f.to_string(), e.to_string()))
+            .collect();
+        return Err(SaveTransferError::Validation(errors));
+    }
 
     let RgbSaveTransferRequest { iface, consignment } = request;
 
@@ -2493,7 +3291,11 @@ pub async fn verify_transfers(sk: &str) -> Result<BatchRgbTransferResponse, Tran
         .await
         .map_err(TransferError::Save)?;
 
-    let (rgb_pending, transfers) = internal_verify_transfers(&mut stock, rgb_transfers).await?;
+    let revoked = retrieve_revoked_seals(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    let (rgb_pending, transfers) =
+        internal_verify_transfers(&mut stock, rgb_transfers, &revoked).await?;
 
     let mut my_public_offers = vec![];
     let check_offers: Vec<_> = transfers
@@ -2548,6 +3350,185 @@ pub async fn verify_transfers(sk: &str) -> Result<BatchRgbTransferResponse, Tran
     Ok(BatchRgbTransferResponse { transfers })
 }
 
+/// Publish the witness transaction of a transfer.
+///
+/// The signed transaction is stored before it gets broadcasted, so
+/// [`rebroadcast_pending`] can send the same copy again once the explorer
+/// drops it from its mempool.
+pub async fn publish_transfer_psbt(
+    sk: &str,
+    request: PublishPsbtRequest,
+) -> Result<SignedPsbtResponse, TransferError> {
+    let psbt =
+        Psbt::from_str(&request.psbt).map_err(|err| TransferError::WrongTx(err.to_string()))?;
+    let psbt = PsbtV0::from(psbt);
+
+    // The witness transaction must anchor the consignments sent with it. The
+    // other transactions of the wallet are not checked, nor rebroadcasted.
+    let txid = psbt.unsigned_tx.txid();
+    let rgb_transfers = retrieve_transfers(sk).await.map_err(TransferError::IO)?;
+    let transfers: Vec<_> = rgb_transfers
+        .transfers
+        .values()
+        .flatten()
+        .filter(|transfer| matches!(Txid::from_str(&transfer.tx_id.to_hex()), Ok(id) if id == txid))
+        .collect();
+    for transfer in transfers.iter() {
+        verify_psbt_matches_consignment(&request.psbt, &transfer.consig)
+            .map_err(TransferError::PsbtMismatch)?;
+    }
+    if !transfers.is_empty() {
+        store_broadcast(sk, &psbt)
+            .await
+            .map_err(TransferError::IO)?;
+    }
+
+    publish_psbt_file(request)
+        .await
+        .map_err(|err| TransferError::Publish(err.to_string()))
+}
+
+/// Sign the witness transaction of a transfer with the descriptors, and
+/// publish it (see [`publish_transfer_psbt`]).
+pub async fn sign_and_publish_transfer_psbt(
+    sk: &str,
+    request: SignPsbtRequest,
+) -> Result<PublishedPsbtResponse, TransferError> {
+    let descriptors = request.descriptors.clone();
+    let SignedPsbtResponse { psbt, .. } = sign_psbt_file(request)
+        .await
+        .map_err(|err| TransferError::Publish(err.to_string()))?;
+
+    let final_psbt =
+        Psbt::from_str(&psbt).map_err(|err| TransferError::WrongTx(err.to_string()))?;
+    let final_psbt = PsbtV0::from(final_psbt);
+    if !is_finalized(&final_psbt) {
+        return Ok(PublishedPsbtResponse {
+            sign: false,
+            txid: String::new(),
+        });
+    }
+
+    publish_transfer_psbt(sk, PublishPsbtRequest { psbt }).await?;
+    for descriptor in descriptors.iter() {
+        invalidate_wallet(descriptor, None).await;
+    }
+
+    Ok(PublishedPsbtResponse {
+        sign: true,
+        txid: final_psbt.extract_tx().txid().to_string(),
+    })
+}
+
+// Keep a copy of the signed witness transaction for `rebroadcast_pending`.
+// Unsigned transactions are rejected by the explorer, so they're not stored.
+async fn store_broadcast(sk: &str, psbt: &PsbtV0) -> Result<(), RgbPersistenceError> {
+    if !is_finalized(psbt) {
+        return Ok(());
+    }
+
+    let tx = psbt.clone().extract_tx();
+    let mut broadcasts = retrieve_broadcasts(sk).await?;
+    broadcasts.txs.insert(
+        tx.txid().to_hex(),
+        bitcoin::consensus::serialize(&tx).to_hex(),
+    );
+    store_broadcasts(sk, broadcasts).await
+}
+
+fn is_finalized(psbt: &PsbtV0) -> bool {
+    psbt.inputs
+        .iter()
+        .all(|input| input.final_script_witness.is_some() || input.final_script_sig.is_some())
+}
+
+/// Re-broadcast the unconfirmed transactions of the known transfers.
+///
+/// Transactions are sent again from the copies stored when they were
+/// published. The ones published before those copies existed are fetched back
+/// from the explorer, and reported as missing when it no longer knows them.
+/// A failed broadcast of a transaction the explorer already knows (e.g. still
+/// in its mempool) is reported as accepted, so it's safe to call again.
+pub async fn rebroadcast_pending(sk: &str) -> Result<RebroadcastResponse, TransferError> {
+    let rgb_transfers = retrieve_transfers(sk).await.map_err(TransferError::IO)?;
+    let mut broadcasts = retrieve_broadcasts(sk).await.map_err(TransferError::IO)?;
+
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+
+    let mut txids = BTreeSet::new();
+    for transfer in rgb_transfers.transfers.into_values().flatten() {
+        let txid = Txid::from_str(&transfer.tx_id.to_hex())
+            .map_err(|err| TransferError::WrongTx(err.to_string()))?;
+        txids.insert(txid);
+    }
+    prefetch_resolver_txs_status(txids.iter().cloned().collect(), &mut resolver).await;
+
+    let blockchain = crate::bitcoin::get_blockchain().await;
+    let mut resp = RebroadcastResponse::default();
+    let mut failed = BTreeMap::new();
+    for txid in txids {
+        if let Some(TxStatus::Block(_)) = resolver.txs_status.get(&txid) {
+            broadcasts.txs.remove(&txid.to_hex());
+            continue;
+        }
+
+        let tx = match broadcasts.txs.get(&txid.to_hex()) {
+            Some(raw) => {
+                let tx = Vec::<u8>::from_hex(raw)
+                    .map_err(|err| err.to_string())
+                    .and_then(|bytes| {
+                        bitcoin::consensus::deserialize::<bitcoin::Transaction>(&bytes)
+                            .map_err(|err| err.to_string())
+                    });
+                match tx {
+                    Ok(tx) => tx,
+                    Err(err) => {
+                        resp.rejected.insert(txid.to_hex(), err);
+                        continue;
+                    }
+                }
+            }
+            None => match blockchain.get_tx(&txid).await {
+                Ok(Some(tx)) => tx,
+                Ok(None) => {
+                    resp.missing.push(txid.to_hex());
+                    continue;
+                }
+                Err(err) => {
+                    resp.rejected.insert(txid.to_hex(), err.to_string());
+                    continue;
+                }
+            },
+        };
+
+        match blockchain.broadcast(&tx).await {
+            Ok(_) => resp.accepted.push(txid.to_hex()),
+            Err(err) => {
+                failed.insert(txid, err.to_string());
+            }
+        }
+    }
+
+    prefetch_resolver_txs_status(failed.keys().cloned().collect(), &mut resolver).await;
+    for (txid, err) in failed {
+        match resolver.txs_status.get(&txid) {
+            Some(TxStatus::Mempool) | Some(TxStatus::Block(_)) => resp.accepted.push(txid.to_hex()),
+            _ => {
+                resp.rejected.insert(txid.to_hex(), err);
+            }
+        }
+    }
+
+    store_broadcasts(sk, broadcasts)
+        .await
+        .map_err(TransferError::IO)?;
+
+    Ok(resp)
+}
+
 pub async fn internal_extract_transfers_swaps(
     sk: &str,
     rgb_accounts: &mut RgbAccountV1,
@@ -2730,9 +3711,10 @@ pub async fn internal_extract_transfers_seals(
 pub async fn internal_verify_transfers(
     stock: &mut Stock,
     rgb_transfers: RgbTransfersV1,
+    revoked: &RgbRevokedSeals,
 ) -> Result<(RgbTransfersV1, Vec<BatchRgbTransferItem>), TransferError> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -2756,6 +3738,20 @@ pub async fn internal_verify_transfers(
                 .unwrap_or(&TxStatus::NotFound)
                 .to_owned();
 
+            // Transfers paying to a revoked seal are dropped, never accepted
+            if check_revoked_seals(&activity.consig, &revoked.seals).is_err() {
+                transfers.push(BatchRgbTransferItem {
+                    iface,
+                    status,
+                    is_accept: false,
+                    contract_id: contract_id.clone(),
+                    consig_id: activity.consig_id.to_string(),
+                    is_mine: activity.sender,
+                    txid: txid.to_hex(),
+                });
+                continue;
+            }
+
             let accept_status = match status.clone() {
                 TxStatus::Block(_) => {
                     prefetch_resolver_rgb(&activity.consig, &mut resolver, None).await;
@@ -2814,7 +3810,7 @@ pub async fn internal_verify_transfers(
 
 pub async fn get_contract(sk: &str, contract_id: &str) -> Result<ContractResponse> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -2851,7 +3847,18 @@ pub async fn get_contract(sk: &str, contract_id: &str) -> Result<ContractRespons
         _ => None,
     };
 
+    if unavailable_schema(contract_id, &stock).is_some() {
+        let mut contract =
+            export_raw_contract(contract_id, &mut stock, &mut resolver, &mut wallet)?;
+        hide_ignored_allocations(&mut contract, &retrieve_ignored_allocations(sk).await?);
+        return Ok(contract);
+    }
+
     let mut contract = export_contract(contract_id, &mut stock, &mut resolver, &mut wallet)?;
+    hide_ignored_allocations(&mut contract, &retrieve_ignored_allocations(sk).await?);
+
+    let unconfirmed = retrieve_unconfirmed_transfers(sk).await?;
+    hold_unconfirmed_allocations(&mut contract, &unconfirmed, &mut resolver, &mut stock).await?;
     contract.meta = if let Some(meta) = contract.meta {
         Some(
             extract_metadata(meta)
@@ -2872,6 +3879,37 @@ pub async fn get_contract(sk: &str, contract_id: &str) -> Result<ContractRespons
     Ok(contract)
 }
 
+/// Terms document committed in the contract, checked against its digest
+pub async fn get_contract_terms(sk: &str, contract_id: &str) -> Result<ContractTermsResponse> {
+    let mut stock = retrieve_rgb_stock(sk).await?;
+    let contract_id = ContractId::from_str(contract_id)?;
+
+    let terms = contract_terms(contract_id, &mut stock)?;
+    let mut text = None;
+    let mut data = None;
+    if let Some(terms) = &terms {
+        if let Some(stored) = retrieve_terms(&hex::encode(terms.digest)).await? {
+            if blake3::hash(&stored).as_bytes() != &terms.digest {
+                return Err(ExportContractError::TermsMismatch(contract_id.to_string()).into());
+            }
+            if terms.ty.to_string().starts_with("text/") {
+                text = String::from_utf8(stored.clone()).ok();
+            }
+            if text.is_none() {
+                data = Some(hex::encode(stored));
+            }
+        }
+    }
+
+    Ok(ContractTermsResponse {
+        contract_id: contract_id.to_string(),
+        hash: terms.as_ref().map(|terms| hex::encode(terms.digest)),
+        media_type: terms.map(|terms| terms.ty.to_string()),
+        text,
+        data,
+    })
+}
+
 pub async fn get_simple_contract(sk: &str, contract_id: &str) -> Result<SimpleContractResponse> {
     let mut stock = retrieve_rgb_stock(sk).await?;
     let contract_id = ContractId::from_str(contract_id)?;
@@ -2881,6 +3919,7 @@ pub async fn get_simple_contract(sk: &str, contract_id: &str) -> Result<SimpleCo
         contract_id,
         iface_id,
         precision,
+        ..
     } = contract;
 
     Ok(SimpleContractResponse {
@@ -2890,6 +3929,325 @@ pub async fn get_simple_contract(sk: &str, contract_id: &str) -> Result<SimpleCo
     })
 }
 
+/// Hide an incoming allocation from balances and coin selection
+pub async fn ignore_allocation(sk: &str, outpoint: &str) -> Result<IgnoredAllocationsResponse> {
+    let outpoint = Outpoint::from_str(outpoint)?.to_string();
+    let mut ignored = retrieve_ignored_allocations(sk).await?;
+    if ignored.outpoints.insert(outpoint) {
+        store_ignored_allocations(sk, ignored.clone()).await?;
+    }
+
+    Ok(IgnoredAllocationsResponse {
+        outpoints: ignored.outpoints.into_iter().collect(),
+    })
+}
+
+/// Show an ignored allocation again
+pub async fn unignore_allocation(sk: &str, outpoint: &str) -> Result<IgnoredAllocationsResponse> {
+    let outpoint = Outpoint::from_str(outpoint)?.to_string();
+    let mut ignored = retrieve_ignored_allocations(sk).await?;
+    if ignored.outpoints.remove(&outpoint) {
+        store_ignored_allocations(sk, ignored.clone()).await?;
+    }
+
+    Ok(IgnoredAllocationsResponse {
+        outpoints: ignored.outpoints.into_iter().collect(),
+    })
+}
+
+pub async fn list_ignored_allocations(sk: &str) -> Result<IgnoredAllocationsResponse> {
+    let ignored = retrieve_ignored_allocations(sk).await?;
+    Ok(IgnoredAllocationsResponse {
+        outpoints: ignored.outpoints.into_iter().collect(),
+    })
+}
+
+/// Replace the acceptable counterparties of a contract, checked before paying
+/// an invoice. Counterparties are identified by the origin pubkey of their
+/// invoices; without allowed or denied origins, the policy is off
+pub async fn set_counterparties(
+    sk: &str,
+    request: CounterpartiesRequest,
+) -> Result<CounterpartiesResponse> {
+    let contract_id = ContractId::from_str(&request.contract_id)?.to_string();
+    let mut counterparties = retrieve_counterparties(sk).await?;
+
+    let origins = |pubkeys: Vec<String>| -> Result<BTreeSet<String>> {
+        pubkeys
+            .into_iter()
+            .map(|pubkey| {
+                normalize_origin(&pubkey)
+                    .ok_or_else(|| anyhow!("'{pubkey}' is an invalid origin pubkey"))
+            })
+            .collect()
+    };
+    let allow = origins(request.allow)?;
+    let deny = origins(request.deny)?;
+    if allow.is_empty() {
+        counterparties.allow.remove(&contract_id);
+    } else {
+        counterparties.allow.insert(contract_id.clone(), allow);
+    }
+    if deny.is_empty() {
+        counterparties.deny.remove(&contract_id);
+    } else {
+        counterparties.deny.insert(contract_id.clone(), deny);
+    }
+    store_counterparties(sk, counterparties.clone()).await?;
+
+    Ok(counterparties_response(&counterparties, contract_id))
+}
+
+pub async fn list_counterparties(sk: &str, contract_id: &str) -> Result<CounterpartiesResponse> {
+    let contract_id = ContractId::from_str(contract_id)?.to_string();
+    let counterparties = retrieve_counterparties(sk).await?;
+
+    Ok(counterparties_response(&counterparties, contract_id))
+}
+
+fn counterparties_response(
+    counterparties: &RgbCounterparties,
+    contract_id: String,
+) -> CounterpartiesResponse {
+    let list = |lists: &BTreeMap<String, BTreeSet<String>>| -> Vec<String> {
+        lists
+            .get(&contract_id)
+            .map(|list| list.iter().cloned().collect())
+            .unwrap_or_default()
+    };
+
+    CounterpartiesResponse {
+        allow: list(&counterparties.allow),
+        deny: list(&counterparties.deny),
+        contract_id,
+    }
+}
+
+// Counterparty policy of the wallet, if any beneficiary is allowed or denied
+async fn counterparty_policy(
+    sk: &str,
+) -> Result<Option<Arc<dyn CounterpartyPolicy>>, TransferError> {
+    let counterparties = retrieve_counterparties(sk)
+        .await
+        .map_err(TransferError::IO)?;
+    if counterparties.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Arc::new(counterparties)))
+}
+
+/// List the incoming allocations below the threshold (in atomic units) that
+/// don't pay any of our invoices, and aren't the change of our transfers
+pub async fn list_suspicious_allocations(
+    sk: &str,
+    threshold: u64,
+) -> Result<SuspiciousAllocationsResponse> {
+    let ContractsResponse { contracts } = list_contracts(sk, false).await?;
+    let (stock, rgb_transfers) = retrieve_stock_transfers(sk).await?;
+
+    let invoice_seals: BTreeSet<String> = wallet_seals(&stock)?
+        .into_iter()
+        .map(|seal| seal.outpoint.to_string())
+        .collect();
+    let sent_txids: BTreeSet<String> = rgb_transfers
+        .transfers
+        .into_values()
+        .flatten()
+        .filter(|transfer| transfer.sender)
+        .map(|transfer| transfer.tx_id.to_string())
+        .collect();
+
+    let mut allocations = vec![];
+    for contract in contracts {
+        for allocation in contract.allocations {
+            let value = match allocation.value {
+                AllocationValue::Value(value) => value,
+                AllocationValue::UDA(_) => continue,
+            };
+            let txid = allocation.utxo.split(':').next().unwrap_or_default();
+            if !allocation.is_mine
+                || allocation.is_spent
+                || value >= threshold
+                || invoice_seals.contains(&allocation.utxo)
+                || sent_txids.contains(txid)
+            {
+                continue;
+            }
+
+            allocations.push(SuspiciousAllocation {
+                contract_id: contract.contract_id.clone(),
+                utxo: allocation.utxo,
+                value,
+            });
+        }
+    }
+
+    Ok(SuspiciousAllocationsResponse { allocations })
+}
+
+// Ignored allocations don't count in the contract balance
+fn hide_ignored_allocations(contract: &mut ContractResponse, ignored: &RgbIgnoredAllocations) {
+    if ignored.outpoints.is_empty() {
+        return;
+    }
+
+    contract
+        .allocations
+        .retain(|allocation| !ignored.outpoints.contains(&allocation.utxo));
+    contract.balance = contract
+        .allocations
+        .iter()
+        .filter(|allocation| allocation.is_mine && !allocation.is_spent)
+        .map(|allocation| match allocation.value {
+            AllocationValue::Value(value) => value,
+            AllocationValue::UDA(_) => 1,
+        })
+        .sum();
+    contract.balance_normalized = ContractAmount::new(contract.balance, contract.precision)
+        .to_string()
+        .parse()
+        .unwrap_or_default();
+}
+
+/// Hold the allocations received by unconfirmed transfers as pending,
+/// outside of the spendable balance, when the deployment requires
+/// confirmations (`REQUIRE_CONFIRMATION`).
+async fn hold_unconfirmed_allocations(
+    contract: &mut ContractResponse,
+    unconfirmed: &RgbUnconfirmedTransfers,
+    resolver: &mut ExplorerResolver,
+    stock: &mut Stock,
+) -> Result<(), TransferError> {
+    if !get_require_confirmation().await {
+        return Ok(());
+    }
+
+    let contract_id =
+        ContractId::from_str(&contract.contract_id).map_err(|_| TransferError::NoContract)?;
+    let outpoints = unconfirmed_outpoints(contract_id, unconfirmed, resolver, stock).await?;
+    if outpoints.is_empty() {
+        return Ok(());
+    }
+
+    let mut balance = 0;
+    let mut pending_balance = 0;
+    for allocation in contract.allocations.iter_mut() {
+        allocation.is_pending = outpoints.contains(&allocation.utxo);
+        if !allocation.is_mine || allocation.is_spent {
+            continue;
+        }
+
+        let value = match allocation.value {
+            AllocationValue::Value(value) => value,
+            AllocationValue::UDA(_) => 1,
+        };
+        if allocation.is_pending {
+            pending_balance += value;
+        } else {
+            balance += value;
+        }
+    }
+
+    contract.balance = balance;
+    contract.pending_balance = pending_balance;
+    contract.balance_normalized = ContractAmount::new(balance, contract.precision)
+        .to_string()
+        .parse()
+        .unwrap_or_default();
+
+    Ok(())
+}
+
+/// Outpoints excluded from the coin selection of the contract by the
+/// confirmation policy (see [`hold_unconfirmed_allocations`]).
+pub(crate) async fn held_outpoints(
+    contract_id: ContractId,
+    unconfirmed: &RgbUnconfirmedTransfers,
+    resolver: &mut ExplorerResolver,
+    stock: &mut Stock,
+) -> Result<BTreeSet<String>, TransferError> {
+    if !get_require_confirmation().await {
+        return Ok(bset![]);
+    }
+
+    unconfirmed_outpoints(contract_id, unconfirmed, resolver, stock).await
+}
+
+// Outpoints assigned by the witness transactions of the contract which are
+// not confirmed yet
+async fn unconfirmed_outpoints(
+    contract_id: ContractId,
+    unconfirmed: &RgbUnconfirmedTransfers,
+    resolver: &mut ExplorerResolver,
+    stock: &mut Stock,
+) -> Result<BTreeSet<String>, TransferError> {
+    let txids = match unconfirmed.transfers.get(&contract_id.to_string()) {
+        Some(txids) if !txids.is_empty() => txids
+            .iter()
+            .map(|txid| Txid::from_str(txid))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| TransferError::WrongTx(err.to_string()))?,
+        _ => return Ok(bset![]),
+    };
+
+    prefetch_resolver_txs_status(txids.clone(), resolver).await;
+    let pending = txids
+        .into_iter()
+        .filter(|txid| !matches!(resolver.txs_status.get(txid), Some(TxStatus::Block(_))))
+        .map(|txid| bp::Txid::from_str(&txid.to_hex()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| TransferError::WrongTx(err.to_string()))?;
+    if pending.is_empty() {
+        return Ok(bset![]);
+    }
+
+    let history = stock
+        .export_contract(contract_id)
+        .map_err(|err| {
+            TransferError::Export(ExportContractError::StashInconsistency(
+                contract_id.to_string(),
+                err.to_string(),
+            ))
+        })?
+        .unbindle();
+    Ok(witness_outpoints(&history, &pending)
+        .into_iter()
+        .map(|outpoint| outpoint.to_string())
+        .collect())
+}
+
+// Forget the witness transactions confirmed since their transfers were
+// accepted
+async fn release_confirmed_transfers(
+    unconfirmed: &mut RgbUnconfirmedTransfers,
+    resolver: &mut ExplorerResolver,
+) -> Result<(), TransferError> {
+    let txids = unconfirmed
+        .transfers
+        .values()
+        .flatten()
+        .map(|txid| Txid::from_str(txid))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| TransferError::WrongTx(err.to_string()))?;
+    if txids.is_empty() {
+        return Ok(());
+    }
+
+    prefetch_resolver_txs_status(txids, resolver).await;
+    for txids in unconfirmed.transfers.values_mut() {
+        txids.retain(|txid| {
+            let status = Txid::from_str(txid)
+                .ok()
+                .and_then(|txid| resolver.txs_status.get(&txid));
+            !matches!(status, Some(TxStatus::Block(_)))
+        });
+    }
+    unconfirmed.transfers.retain(|_, txids| !txids.is_empty());
+
+    Ok(())
+}
+
 pub async fn hidden_contract(sk: &str, contract_id: &str) -> Result<ContractHiddenResponse> {
     let mut rgb_account = retrieve_account(sk).await?;
     if !rgb_account
@@ -2907,83 +4265,278 @@ pub async fn hidden_contract(sk: &str, contract_id: &str) -> Result<ContractHidd
 }
 
 pub async fn list_contracts(sk: &str, hidden_contracts: bool) -> Result<ContractsResponse> {
-    let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
-        ..default!()
-    };
+    let mut listing = ContractsListing::new(sk, hidden_contracts).await?;
 
-    let (mut stock, mut rgb_account) = retrieve_stock_account(sk).await?;
+    let mut contracts = vec![];
+    while let Some(contract) = listing.next_contract().await {
+        contracts.push(contract?);
+    }
 
-    let wallet = rgb_account.wallets.get(RGB_DEFAULT_NAME);
-    let mut wallet = match wallet {
-        Some(wallet) => {
-            let mut fetch_wallet = wallet.to_owned();
-            for contract_type in [AssetType::RGB20, AssetType::RGB21] {
-                let contract_index = contract_type as u32;
-                sync_wallet(contract_index, &mut fetch_wallet, &mut resolver);
-                prefetch_resolver_utxos(
-                    contract_index,
-                    &mut fetch_wallet,
-                    &mut resolver,
-                    Some(RGB_DEFAULT_FETCH_LIMIT),
-                )
-                .await;
-            }
-            Some(fetch_wallet)
-        }
-        _ => None,
-    };
+    Ok(ContractsResponse { contracts })
+}
 
-    let mut contracts = vec![];
-    for contract_type in [AssetType::RGB20, AssetType::RGB21] {
-        let iface_name = contract_type.to_string().to_uppercase().clone();
-        let iface_name = tn!(iface_name);
-        let iface = stock
-            .iface_by_name(&iface_name)
-            .expect("Iface name not found")
-            .clone();
+/// Lines of [`list_contracts_ndjson`]
+#[cfg(not(target_arch = "wasm32"))]
+pub type ContractLines = futures::stream::BoxStream<'static, Result<String>>;
+/// Lines of [`list_contracts_ndjson`]
+#[cfg(target_arch = "wasm32")]
+pub type ContractLines = futures::stream::LocalBoxStream<'static, Result<String>>;
+
+/// Contracts as newline-delimited JSON (NDJSON), one contract per line.
+///
+/// Each contract is exported from the stock as its line is consumed, so the
+/// first lines are sent before the last contracts are exported.
+pub async fn list_contracts_ndjson(sk: &str, hidden_contracts: bool) -> Result<ContractLines> {
+    let listing = ContractsListing::new(sk, hidden_contracts).await?;
+    let lines = futures::stream::unfold(listing, |mut listing| async move {
+        let line = listing.next_contract().await?.and_then(|contract| {
+            let line = serde_json::to_string(&contract)?;
+            Ok(line + "\n")
+        });
+        Some((line, listing))
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return Ok(lines.boxed());
+    #[cfg(target_arch = "wasm32")]
+    return Ok(lines.boxed_local());
+}
+
+// Contracts of the wallet (listed by interface, then the ones without
+// schema), exported one at a time
+struct ContractsListing {
+    stock: Stock,
+    wallet: Option<RgbWallet>,
+    resolver: ExplorerResolver,
+    ignored: RgbIgnoredAllocations,
+    unconfirmed: RgbUnconfirmedTransfers,
+    pending: VecDeque<(ContractId, Option<IfaceId>)>,
+}
 
-        let contract_ids = stock
-            .contract_ids_by_iface(&iface_name)
-            .expect("contract not found");
+impl ContractsListing {
+    async fn new(sk: &str, hidden_contracts: bool) -> Result<Self> {
+        let mut resolver = ExplorerResolver {
+            explorer_url: get_explorer_api().await,
+            ..default!()
+        };
+
+        let (stock, rgb_account) = retrieve_stock_account(sk).await?;
+        let ignored = retrieve_ignored_allocations(sk).await?;
+        let unconfirmed = retrieve_unconfirmed_transfers(sk).await?;
+
+        let wallet = rgb_account.wallets.get(RGB_DEFAULT_NAME);
+        let wallet = match wallet {
+            Some(wallet) => {
+                let mut fetch_wallet = wallet.to_owned();
+                for contract_type in [AssetType::RGB20, AssetType::RGB21] {
+                    let contract_index = contract_type as u32;
+                    sync_wallet(contract_index, &mut fetch_wallet, &mut resolver);
+                    prefetch_resolver_utxos(
+                        contract_index,
+                        &mut fetch_wallet,
+                        &mut resolver,
+                        Some(RGB_DEFAULT_FETCH_LIMIT),
+                    )
+                    .await;
+                }
+                Some(fetch_wallet)
+            }
+            _ => None,
+        };
 
-        for contract_id in contract_ids {
-            if hidden_contracts
+        let is_hidden = |contract_id: &ContractId| {
+            hidden_contracts
                 && rgb_account
                     .hidden_contracts
                     .contains(&contract_id.to_string())
+        };
+
+        let mut pending = VecDeque::new();
+        let mut listed = bset![];
+        for contract_type in [AssetType::RGB20, AssetType::RGB21] {
+            let iface_name = contract_type.to_string().to_uppercase().clone();
+            let iface_name = tn!(iface_name);
+            let iface_id = stock
+                .iface_by_name(&iface_name)
+                .expect("Iface name not found")
+                .iface_id();
+
+            let contract_ids = stock
+                .contract_ids_by_iface(&iface_name)
+                .expect("contract not found");
+            for contract_id in contract_ids {
+                if is_hidden(&contract_id)
+                    || unavailable_schema(contract_id, &stock).is_some()
+                    || !listed.insert(contract_id)
+                {
+                    continue;
+                }
+                pending.push_back((contract_id, Some(iface_id)));
+            }
+        }
+
+        // Contracts without schema are listed with their raw state
+        for contract_id in stock.contract_ids()? {
+            if listed.contains(&contract_id)
+                || is_hidden(&contract_id)
+                || unavailable_schema(contract_id, &stock).is_none()
             {
                 continue;
             }
+            pending.push_back((contract_id, None));
+        }
+
+        Ok(Self {
+            stock,
+            wallet,
+            resolver,
+            ignored,
+            unconfirmed,
+            pending,
+        })
+    }
+
+    async fn next_contract(&mut self) -> Option<Result<ContractResponse>> {
+        let (contract_id, iface_id) = self.pending.pop_front()?;
+        Some(self.export(contract_id, iface_id).await)
+    }
+
+    async fn export(
+        &mut self,
+        contract_id: ContractId,
+        iface_id: Option<IfaceId>,
+    ) -> Result<ContractResponse> {
+        let iface_id = match iface_id {
+            Some(iface_id) => iface_id,
+            None => {
+                let mut resp = export_raw_contract(
+                    contract_id,
+                    &mut self.stock,
+                    &mut self.resolver,
+                    &mut self.wallet,
+                )?;
+                hide_ignored_allocations(&mut resp, &self.ignored);
+                return Ok(resp);
+            }
+        };
+
+        let contract_iface = self
+            .stock
+            .clone()
+            .contract_iface(contract_id, iface_id)
+            .expect("Iface not found");
+
+        prefetch_resolver_allocations(contract_iface, &mut self.resolver, true).await;
+        let mut resp = export_contract(
+            contract_id,
+            &mut self.stock,
+            &mut self.resolver,
+            &mut self.wallet,
+        )?;
+        hide_ignored_allocations(&mut resp, &self.ignored);
+        hold_unconfirmed_allocations(
+            &mut resp,
+            &self.unconfirmed,
+            &mut self.resolver,
+            &mut self.stock,
+        )
+        .await?;
+        resp.meta = if let Some(meta) = resp.meta {
+            Some(
+                extract_metadata(meta)
+                    .await
+                    .expect("Error to retrieve metadata"),
+            )
+        } else {
+            None
+        };
+
+        Ok(resp)
+    }
+}
+
+/// Balances and allocations of every contract in the wallet, to be compared
+/// later with [`diff_stock`]
+pub async fn snapshot_stock(sk: &str) -> Result<StockSnapshot> {
+    let ContractsResponse { contracts } = list_contracts(sk, false).await?;
+    let contracts = contracts
+        .into_iter()
+        .map(|contract| {
+            (
+                contract.contract_id,
+                ContractSnapshot {
+                    balance: contract.balance,
+                    allocations: contract
+                        .allocations
+                        .into_iter()
+                        .filter(|allocation| !allocation.is_spent)
+                        .collect(),
+                },
+            )
+        })
+        .collect();
+
+    Ok(StockSnapshot {
+        created_at: chrono::Local::now().naive_utc().timestamp(),
+        contracts,
+    })
+}
+
+/// Changes between two snapshots taken with [`snapshot_stock`]
+pub fn diff_stock(before: StockSnapshot, after: StockSnapshot) -> StockDiffResponse {
+    let mut diff = StockDiffResponse::default();
+    let empty = ContractSnapshot::default();
+
+    let contract_ids: BTreeSet<&String> = before
+        .contracts
+        .keys()
+        .chain(after.contracts.keys())
+        .collect();
+    for contract_id in contract_ids {
+        let (old, new) = match (
+            before.contracts.get(contract_id),
+            after.contracts.get(contract_id),
+        ) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(old), None) => {
+                diff.removed_contracts.push(contract_id.clone());
+                (old, &empty)
+            }
+            (None, Some(new)) => {
+                diff.added_contracts.push(contract_id.clone());
+                (&empty, new)
+            }
+            (None, None) => continue,
+        };
 
-            let contract_iface = stock
-                .clone()
-                .contract_iface(contract_id, iface.iface_id())
-                .expect("Iface not found");
+        if old.balance != new.balance {
+            diff.balances.push(BalanceChange {
+                contract_id: contract_id.clone(),
+                before: old.balance,
+                after: new.balance,
+            });
+        }
 
-            prefetch_resolver_allocations(contract_iface, &mut resolver, true).await;
-            let mut resp = export_contract(contract_id, &mut stock, &mut resolver, &mut wallet)?;
-            resp.meta = if let Some(meta) = resp.meta {
-                Some(
-                    extract_metadata(meta)
-                        .await
-                        .expect("Error to retrieve metadata"),
-                )
-            } else {
-                None
-            };
-            contracts.push(resp);
+        let added: Vec<_> = new
+            .allocations
+            .difference(&old.allocations)
+            .cloned()
+            .collect();
+        if !added.is_empty() {
+            diff.added_allocations.insert(contract_id.clone(), added);
+        }
+        let removed: Vec<_> = old
+            .allocations
+            .difference(&new.allocations)
+            .cloned()
+            .collect();
+        if !removed.is_empty() {
+            diff.removed_allocations
+                .insert(contract_id.clone(), removed);
         }
     }
 
-    if let Some(wallet) = wallet {
-        rgb_account
-            .wallets
-            .insert(RGB_DEFAULT_NAME.to_string(), wallet);
-        store_account(sk, rgb_account).await?;
-    };
-
-    Ok(ContractsResponse { contracts })
+    diff
 }
 
 pub async fn list_interfaces(sk: &str) -> Result<InterfacesResponse> {
@@ -2999,6 +4552,7 @@ pub async fn list_interfaces(sk: &str) -> Result<InterfacesResponse> {
                 name: face.name.to_string(),
                 iface: iface_id.to_string(),
                 iimpl: iimpl.impl_id().to_string(),
+                versions: vec![iface_version_number(iimpl.version)],
             };
             interfaces.push(item)
         }
@@ -3027,11 +4581,140 @@ pub async fn list_schemas(sk: &str) -> Result<SchemasResponse> {
     Ok(SchemasResponse { schemas })
 }
 
+/// Fetch a schema missing in the wallet (and the implementations of its
+/// interfaces) from the schema registry, see `RGB_SCHEMA_REGISTRY`. The
+/// contracts of the schema are fully available afterwards.
+pub async fn fetch_schema(sk: &str, schema_id: &str) -> Result<SchemaDetail> {
+    let schema_id = SchemaId::from_str(schema_id)?;
+    let registry = RGB_SCHEMA_REGISTRY.read().await.to_string();
+    if registry.is_empty() {
+        return Err(anyhow!("There is no schema registry (RGB_SCHEMA_REGISTRY)"));
+    }
+
+    let url = format!("{}/{schema_id}", registry.trim_end_matches('/'));
+    let entry: SchemaRegistryEntry = serde_json::from_str(&util::get(&url, None).await?)?;
+
+    let data = Vec::<u8>::from_hex(&entry.schema)?;
+    let schema = SubSchema::from_strict_serialized::<{ U32 }>(Confined::try_from_iter(data)?)?;
+    if schema.schema_id() != schema_id {
+        return Err(anyhow!(
+            "Registry returned the schema {} instead of {schema_id}",
+            schema.schema_id()
+        ));
+    }
+
+    let mut iimpls = vec![];
+    for iimpl in entry.iimpls {
+        let data = Vec::<u8>::from_hex(&iimpl)?;
+        iimpls.push(IfaceImpl::from_strict_serialized::<{ U32 }>(
+            Confined::try_from_iter(data)?,
+        )?);
+    }
+
+    let mut stock = retrieve_rgb_stock(sk).await?;
+    stock.import_schema(schema)?;
+    for iimpl in iimpls {
+        stock.import_iface_impl(iimpl)?;
+    }
+
+    let mut ifaces = vec![];
+    for (iface_id, _) in stock.schema(schema_id)?.clone().iimpls.into_iter() {
+        let face = stock.iface_by_id(iface_id)?;
+        ifaces.push(face.name.to_string());
+    }
+    store_rgb_stock(sk, stock).await?;
+
+    Ok(SchemaDetail {
+        schema: schema_id.to_string(),
+        ifaces,
+    })
+}
+
+/// Bytes stored by the wallet, by category: the stock, the consignments (the
+/// saved transfers) and the other wallet state.
+pub async fn storage_stats(sk: &str) -> Result<crate::structs::StorageStatsResponse> {
+    use crate::{
+        carbonado::stored_size,
+        constants::storage_keys::{
+            ASSETS_ACCEPT_JOURNAL, ASSETS_BIDS, ASSETS_BROADCASTS, ASSETS_COUNTERPARTIES,
+            ASSETS_IGNORED_ALLOCATIONS, ASSETS_OFFERS, ASSETS_REVOKED_SEALS, ASSETS_STOCK,
+            ASSETS_TRANSFERS, ASSETS_UNCONFIRMED_TRANSFERS, ASSETS_WALLETS,
+        },
+        rgb::carbonado::storage_file_prefix,
+        structs::StorageStatsResponse,
+    };
+
+    let files = [
+        (ASSETS_STOCK, "stock"),
+        (ASSETS_TRANSFERS, "consignments"),
+        (ASSETS_WALLETS, "other"),
+        (ASSETS_OFFERS, "other"),
+        (ASSETS_BIDS, "other"),
+        (ASSETS_IGNORED_ALLOCATIONS, "other"),
+        (ASSETS_REVOKED_SEALS, "other"),
+        (ASSETS_UNCONFIRMED_TRANSFERS, "other"),
+        (ASSETS_COUNTERPARTIES, "other"),
+        (ASSETS_ACCEPT_JOURNAL, "other"),
+        (ASSETS_BROADCASTS, "other"),
+    ];
+
+    let mut categories: BTreeMap<String, u64> = BTreeMap::new();
+    for (name, category) in files {
+        let file_name = format!("{}.c15", storage_file_prefix(name));
+        let bytes = stored_size(sk, &file_name).await?;
+        *categories.entry(category.to_string()).or_default() += bytes;
+    }
+
+    Ok(StorageStatsResponse {
+        total: categories.values().sum(),
+        categories,
+    })
+}
+
+/// Version, optional features, interfaces and schemas supported by the library
+pub async fn capabilities() -> CapabilitiesResponse {
+    let features = BTreeMap::from([
+        (
+            "lightning".to_string(),
+            !LNDHUB_ENDPOINT.read().await.is_empty(),
+        ),
+        ("nostr_transport".to_string(), false),
+        ("witness_transfers".to_string(), false),
+        (
+            "schema_registry".to_string(),
+            !RGB_SCHEMA_REGISTRY.read().await.is_empty(),
+        ),
+    ]);
+
+    let schemas = vec![
+        SchemaDetail {
+            schema: nia_schema().schema_id().to_string(),
+            ifaces: vec![rgb20().name.to_string()],
+        },
+        SchemaDetail {
+            schema: uda_schema().schema_id().to_string(),
+            ifaces: vec![rgb21().name.to_string()],
+        },
+    ];
+    let interfaces = schemas
+        .iter()
+        .flat_map(|schema| schema.ifaces.clone())
+        .collect();
+
+    CapabilitiesResponse {
+        version: BMC_VERSION.to_string(),
+        api_version: BMC_API_VERSION,
+        features,
+        interfaces,
+        schemas,
+    }
+}
+
 pub async fn list_transfers(sk: &str, contract_id: String) -> Result<RgbTransfersResponse> {
     let rgb_transfers = retrieve_transfers(sk).await?;
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3071,6 +4754,476 @@ pub async fn list_transfers(sk: &str, contract_id: String) -> Result<RgbTransfer
     Ok(RgbTransfersResponse { transfers })
 }
 
+/// Transfer history of the contract, read from the transfer index.
+///
+/// The index is derived from the stock and periodically rebuilt; call
+/// [`reindex`] to rebuild it on demand.
+pub async fn transfer_history(sk: &str, contract_id: &str) -> Result<TransferHistoryResponse> {
+    let contract_id = ContractId::from_str(contract_id)?.to_string();
+    let index = transfer_index(sk).await?;
+    let transfers = index
+        .contracts
+        .get(&contract_id)
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(TransferHistoryResponse {
+        contract_id,
+        transfers,
+        indexed_at: index.built_at,
+    })
+}
+
+/// Rebuild the transfer index from the stock in the background.
+///
+/// Reads keep using the current index until the rebuild is done.
+pub async fn reindex(sk: &str) -> Result<ReindexResponse> {
+    let started = start_reindex(sk).await;
+    Ok(ReindexResponse { started })
+}
+
+pub async fn export_contract_csv(sk: &str, contract_id: &str) -> Result<String> {
+    let contract = get_contract(sk, contract_id).await?;
+    let rgb_transfers = retrieve_transfers(sk).await?;
+    let explorer_url = get_explorer_api().await;
+
+    let mut entries = vec![];
+    if let Some(transfer_activities) = rgb_transfers.transfers.get(contract_id) {
+        for activity in transfer_activities.to_owned() {
+            let seals: Vec<SecretSeal> = activity
+                .beneficiaries
+                .iter()
+                .filter_map(|seal| SecretSeal::from_str(seal).ok())
+                .collect();
+            let (_, transfer) = extract_transfer(activity.consig)?;
+
+            let txid = activity.tx_id.to_hex();
+            let ExplorerTxInfo { fee, status } =
+                util::get(&format!("{explorer_url}/tx/{txid}"), None)
+                    .await
+                    .ok()
+                    .and_then(|tx_info| serde_json::from_str(&tx_info).ok())
+                    .unwrap_or_default();
+
+            let (direction, counterparty, fee) = if activity.sender {
+                (TransferType::Sended, activity.beneficiaries, fee)
+            } else {
+                (TransferType::Received, vec![], None)
+            };
+
+            entries.push(AccountingEntry {
+                date: status.block_time,
+                direction,
+                amount: seals_paid_amount(&transfer, &seals),
+                counterparty,
+                txid,
+                fee,
+            });
+        }
+    }
+
+    Ok(contract_csv(
+        contract.precision,
+        &entries,
+        &contract.allocations,
+    ))
+}
+
+/// Export the consignment proving the current balance of the contract.
+///
+/// Only the history of the wallet unspent allocations is included, so the
+/// balance can be checked with [`verify_balance_proof`] without the stock.
+/// Every outpoint is signed over the verifier challenge with the wallet key
+/// (see [`ownership_message`]), proving the wallet controls it.
+pub async fn export_balance_proof(
+    sk: &str,
+    request: BalanceProofRequest,
+) -> Result<BalanceProofResponse> {
+    let BalanceProofRequest {
+        contract_id,
+        challenge,
+        descriptor,
+    } = request;
+    let (contract_id, balance, utxos, consignment) = balance_consignment(sk, &contract_id).await?;
+
+    let rgb_account = retrieve_account(sk).await?;
+    let rgb_wallet = rgb_account
+        .wallets
+        .get(RGB_DEFAULT_NAME)
+        .ok_or(TransferError::NoWatcher)?;
+
+    let mut signatures = BTreeMap::new();
+    for utxo in utxos.iter() {
+        let outpoint = Outpoint::from_str(utxo)?;
+        let derivation = rgb_wallet
+            .utxo(outpoint)
+            .map(|utxo| utxo.derivation.clone())
+            .ok_or_else(|| anyhow!("outpoint {utxo} is not one of the wallet UTXOs"))?;
+        let message = ownership_message(&challenge, &contract_id, utxo);
+        signatures.insert(
+            utxo.to_owned(),
+            sign_ownership(&descriptor, &derivation, &message)?,
+        );
+    }
+
+    Ok(BalanceProofResponse {
+        contract_id,
+        balance,
+        utxos,
+        consignment,
+        signatures,
+    })
+}
+
+// Consignment with the history of the wallet unspent allocations of the contract
+async fn balance_consignment(
+    sk: &str,
+    contract_id: &str,
+) -> Result<(String, u64, Vec<String>, String)> {
+    let contract = get_contract(sk, contract_id).await?;
+    let mut utxos: Vec<String> = contract
+        .allocations
+        .into_iter()
+        .filter(|allocation| allocation.is_mine && !allocation.is_spent)
+        .map(|allocation| allocation.utxo)
+        .collect();
+    utxos.sort();
+    utxos.dedup();
+
+    let outpoints = utxos
+        .iter()
+        .map(|utxo| Outpoint::from_str(utxo))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stock = retrieve_rgb_stock(sk).await?;
+    let contract_id = ContractId::from_str(contract_id)?;
+    let history = stock.export_contract(contract_id)?.unbindle();
+    let (balance, seals) = outpoints_owned_state(&history, &outpoints);
+
+    let transfer = stock.transfer(contract_id, seals.into_iter().map(BuilderSeal::Revealed))?;
+    let consignment = transfer.to_strict_serialized::<{ U32 }>()?.to_hex();
+
+    Ok((contract_id.to_string(), balance, utxos, consignment))
+}
+
+/// Verify a balance proof exported by [`export_balance_proof`] for the
+/// `challenge` of the verifier.
+///
+/// The consignment is fully validated, and only the outpoints still unspent
+/// at the current chain tip, and signed over the challenge by the key of
+/// their taproot output, count for the balance.
+pub async fn verify_balance_proof(
+    proof: BalanceProofResponse,
+    challenge: &str,
+) -> Result<VerifyBalanceProofResponse> {
+    let BalanceProofResponse {
+        contract_id,
+        balance,
+        utxos,
+        consignment,
+        signatures,
+    } = proof;
+
+    let explorer_url = get_explorer_api().await;
+    let mut resolver = ExplorerResolver {
+        explorer_url: explorer_url.clone(),
+        ..default!()
+    };
+
+    prefetch_resolver_rgb(&consignment, &mut resolver, None).await;
+    let (transfer, warnings) = check_transfer(consignment, &mut resolver)?;
+    if transfer.contract_id().to_string() != contract_id {
+        return Err(AcceptTransferError::WrongContract(contract_id).into());
+    }
+
+    let mut unspent = vec![];
+    let mut spent = vec![];
+    let mut unproven = vec![];
+    for utxo in utxos {
+        let outpoint = Outpoint::from_str(&utxo)?;
+        let outspend_url = format!(
+            "{explorer_url}/tx/{}/outspend/{}",
+            outpoint.txid, outpoint.vout
+        );
+        let outspend: ExplorerOutspend =
+            serde_json::from_str(&util::get(&outspend_url, None).await?)?;
+        if outspend.spent {
+            spent.push(utxo);
+            continue;
+        }
+
+        let tx_url = format!("{explorer_url}/tx/{}/hex", outpoint.txid);
+        let tx: bitcoin::Transaction = bitcoin::consensus::deserialize(&Vec::<u8>::from_hex(
+            util::get(&tx_url, None).await?.trim(),
+        )?)?;
+        let proven = match (
+            tx.output.get(outpoint.vout.into_u32() as usize),
+            signatures.get(&utxo),
+        ) {
+            (Some(txout), Some(sig)) => {
+                let message = ownership_message(challenge, &contract_id, &utxo);
+                verify_ownership(txout.script_pubkey.as_bytes(), sig, &message).is_ok()
+            }
+            _ => false,
+        };
+
+        if proven {
+            unspent.push(outpoint);
+        } else {
+            unproven.push(utxo);
+        }
+    }
+
+    let (verified, _) = outpoints_owned_state(&transfer, &unspent);
+    Ok(VerifyBalanceProofResponse {
+        contract_id,
+        valid: spent.is_empty() && unproven.is_empty() && verified == balance,
+        balance: verified,
+        spent,
+        unproven,
+        warnings,
+    })
+}
+
+/// Commitment to the unspent allocations of the contract, as known by the
+/// wallet stock (see [`allocation_proof`]).
+///
+/// The commitment is self-attested: nothing anchors it to the consignment or
+/// the witness transactions, so proofs against it only show an allocation is
+/// part of the state claimed by the wallet. Verifiers must get it from a
+/// source they trust, and check the allocation with [`allocation_provenance`]
+pub async fn allocations_commitment(sk: &str, contract_id: &str) -> Result<String> {
+    let contract = get_contract(sk, contract_id).await?;
+    let leaves = allocation_leaves(&contract);
+
+    Ok(hex::encode(merkle_root(&leaves)))
+}
+
+/// Prove an unspent allocation of the contract (anchored to the outpoint) is
+/// part of the commitment to the contract state
+pub async fn allocation_proof(
+    sk: &str,
+    contract_id: &str,
+    outpoint: &str,
+) -> Result<AllocationProofResponse> {
+    let utxo = Outpoint::from_str(outpoint)?.to_string();
+    let contract = get_contract(sk, contract_id).await?;
+    let leaves = allocation_leaves(&contract);
+
+    let allocation = contract
+        .allocations
+        .iter()
+        .find(|allocation| !allocation.is_spent && allocation.utxo == utxo)
+        .ok_or_else(|| anyhow!("Outpoint {utxo} has no allocation of contract {contract_id}"))?;
+    let leaf = allocation_leaf(&contract.contract_id, &utxo, &allocation.value);
+    let path = merkle_path(&leaves, &leaf)
+        .ok_or_else(|| anyhow!("Allocation of {utxo} is not part of the commitment"))?;
+
+    Ok(AllocationProofResponse {
+        contract_id: contract.contract_id.clone(),
+        commitment: hex::encode(merkle_root(&leaves)),
+        utxo,
+        value: allocation.value.clone(),
+        path,
+    })
+}
+
+/// Verify an allocation proof exported by [`allocation_proof`], without the
+/// stock. The proof is valid when it belongs to the contract and its path
+/// leads from the allocation to the trusted commitment, obtained by the
+/// verifier apart from the proof (see [`allocations_commitment`]). The
+/// commitment carried by the proof must match it, but is never trusted
+pub fn verify_allocation_proof(
+    proof: AllocationProofResponse,
+    contract_id: &str,
+    commitment: &str,
+) -> VerifyAllocationProofResponse {
+    let AllocationProofResponse {
+        contract_id: proof_contract_id,
+        commitment: proof_commitment,
+        utxo,
+        value,
+        path,
+    } = proof;
+
+    let commitment = commitment.to_lowercase();
+    let leaf = allocation_leaf(contract_id, &utxo, &value);
+    let valid = proof_contract_id == contract_id
+        && proof_commitment.to_lowercase() == commitment
+        && merkle_fold(leaf, &path).map(hex::encode) == Some(commitment);
+
+    VerifyAllocationProofResponse {
+        contract_id: contract_id.to_string(),
+        utxo,
+        valid,
+    }
+}
+
+/// Bitcoin transactions anchoring the operations that lead to an allocation
+/// of the contract, from the genesis to the transition assigning it.
+///
+/// Operations the stock doesn't know are returned as gaps, and the
+/// provenance is complete only without gaps.
+pub async fn allocation_provenance(
+    sk: &str,
+    contract_id: &str,
+    outpoint: &str,
+) -> Result<AllocationProvenanceResponse> {
+    let outpoint = Outpoint::from_str(outpoint)?;
+    let utxo = outpoint.to_string();
+
+    let mut stock = retrieve_rgb_stock(sk).await?;
+    let contract_id = ContractId::from_str(contract_id)?;
+    let history = stock.export_contract(contract_id)?.unbindle();
+    let links = provenance_links(&history, outpoint)
+        .ok_or_else(|| anyhow!("Outpoint {utxo} has no allocation of contract {contract_id}"))?;
+
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+    let mut anchor_txids = BTreeMap::new();
+    for txid in links.iter().filter_map(|link| link.txid) {
+        anchor_txids.insert(txid, Txid::from_str(&txid.to_hex())?);
+    }
+    prefetch_resolver_txs_status(anchor_txids.values().copied().collect(), &mut resolver).await;
+
+    let steps: Vec<ProvenanceStep> = links
+        .into_iter()
+        .map(|link| {
+            let status = link
+                .txid
+                .and_then(|txid| anchor_txids.get(&txid))
+                .and_then(|txid| resolver.txs_status.get(txid))
+                .unwrap_or(&TxStatus::NotFound)
+                .to_owned();
+            ProvenanceStep {
+                opid: link.opid.to_string(),
+                txid: link.txid.map(|txid| txid.to_string()),
+                status,
+                is_genesis: link.is_genesis,
+                is_gap: link.is_gap,
+            }
+        })
+        .collect();
+    let complete =
+        steps.first().is_some_and(|step| step.is_genesis) && steps.iter().all(|step| !step.is_gap);
+
+    Ok(AllocationProvenanceResponse {
+        contract_id: contract_id.to_string(),
+        utxo,
+        steps,
+        complete,
+    })
+}
+
+fn allocation_leaves(contract: &ContractResponse) -> Vec<[u8; 32]> {
+    contract
+        .allocations
+        .iter()
+        .filter(|allocation| !allocation.is_spent)
+        .map(|allocation| {
+            allocation_leaf(&contract.contract_id, &allocation.utxo, &allocation.value)
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Export the allocations of a single contract owned by the wallet.
+///
+/// The consignment covers only the unspent owned state of the contract, and
+/// can be applied to another stock with [`import_allocations`].
+pub async fn export_allocations(sk: &str, contract_id: &str) -> Result<AllocationsExportResponse> {
+    let (contract_id, _, utxos, consignment) = balance_consignment(sk, contract_id).await?;
+
+    Ok(AllocationsExportResponse {
+        contract_id,
+        utxos,
+        consignment,
+    })
+}
+
+/// Import the allocations exported by [`export_allocations`].
+///
+/// The consignment carries the contract genesis, so contracts missing in the
+/// stock are imported with their allocations. Consignments which don't
+/// validate, or which belong to another contract, are rejected. The exported
+/// outpoints are checked against the allocations of the imported contract:
+/// each one must hold an unspent allocation the wallet controls, so imported
+/// allocations are always in the wallet balance.
+pub async fn import_allocations(
+    sk: &str,
+    request: AllocationsExportResponse,
+) -> Result<ContractResponse> {
+    let AllocationsExportResponse {
+        contract_id,
+        utxos,
+        consignment,
+    } = request;
+
+    let (mut stock, rgb_account) = retrieve_stock_account(sk).await?;
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+
+    prefetch_resolver_rgb(&consignment, &mut resolver, None).await;
+    let transfer = accept_rgb_transfer(consignment, false, &mut resolver, &mut stock)?;
+    let imported_id = transfer.contract_id();
+    if imported_id.to_string() != contract_id {
+        return Err(AcceptTransferError::WrongContract(contract_id).into());
+    }
+
+    let mut wallet = rgb_account
+        .wallets
+        .get(RGB_DEFAULT_NAME)
+        .cloned()
+        .ok_or(TransferError::NoWatcher)?;
+    for contract_type in [AssetType::RGB20, AssetType::RGB21] {
+        let contract_index = contract_type.clone() as u32;
+        let iface_name = contract_type.to_string().to_uppercase();
+        let iface = stock
+            .iface_by_name(&tn!(iface_name))
+            .map_err(|_| TransferError::NoIface)?;
+        if let Ok(contract_iface) = stock.contract_iface(imported_id, iface.iface_id()) {
+            prefetch_resolver_allocations(contract_iface, &mut resolver, true).await;
+        }
+        prefetch_resolver_utxos(
+            contract_index,
+            &mut wallet,
+            &mut resolver,
+            Some(RGB_DEFAULT_FETCH_LIMIT),
+        )
+        .await;
+        sync_wallet(contract_index, &mut wallet, &mut resolver);
+    }
+
+    // Ownership and spending are checked on the allocations of the imported
+    // contract, never on the exported outpoints alone
+    let imported = export_contract(imported_id, &mut stock, &mut resolver, &mut Some(wallet))?;
+    for utxo in utxos.iter() {
+        let utxo = Outpoint::from_str(utxo)?.to_string();
+        let allocations: Vec<_> = imported
+            .allocations
+            .iter()
+            .filter(|allocation| allocation.utxo == utxo)
+            .collect();
+        if allocations.is_empty() {
+            return Err(AcceptTransferError::UnknownAllocation(utxo).into());
+        }
+        if allocations.iter().any(|allocation| !allocation.is_mine) {
+            return Err(AcceptTransferError::ForeignAllocation(utxo).into());
+        }
+        if allocations.iter().any(|allocation| allocation.is_spent) {
+            return Err(AcceptTransferError::SpentAllocation(utxo).into());
+        }
+    }
+
+    store_rgb_stock(sk, stock).await?;
+    get_contract(sk, &contract_id).await
+}
+
 pub async fn list_my_orders(sk: &str) -> Result<RgbOfferBidsResponse> {
     let rgb_offers = retrieve_offers(sk).await?;
     let rgb_bids = retrieve_bids(sk).await?;
@@ -3156,7 +5309,7 @@ pub enum ImportError {
 
 pub async fn import(sk: &str, request: ImportRequest) -> Result<ContractResponse, ImportError> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3296,7 +5449,7 @@ pub async fn watcher_details(sk: &str, name: &str) -> Result<WatcherDetailRespon
     };
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3340,7 +5493,7 @@ pub async fn watcher_address(
     if let Some(wallet) = rgb_account.wallets.get(name) {
         // Prefetch
         let mut resolver = ExplorerResolver {
-            explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+            explorer_url: get_explorer_api().await,
             ..default!()
         };
 
@@ -3375,12 +5528,12 @@ pub async fn watcher_utxo(
 
     let mut resp = WatcherUtxoResponse::default();
     if let Some(wallet) = rgb_account.wallets.get(name) {
-        let network = NETWORK.read().await.to_string();
+        let network = current_network().await.to_string();
         let network =
             Network::from_str(&network).map_err(|op| WatcherError::Validation(op.to_string()))?;
 
         let mut resolver = ExplorerResolver {
-            explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+            explorer_url: get_explorer_api().await,
             ..default!()
         };
 
@@ -3413,7 +5566,7 @@ pub async fn watcher_next_address(
 ) -> Result<NextAddressResponse, WatcherError> {
     let rgb_account = retrieve_account(sk).await.map_err(WatcherError::IO)?;
 
-    let network = NETWORK.read().await.to_string();
+    let network = current_network().await.to_string();
     let network =
         Network::from_str(&network).map_err(|op| WatcherError::Validation(op.to_string()))?;
     let network = AddressNetwork::from(network);
@@ -3457,7 +5610,7 @@ pub async fn watcher_next_utxo(
     };
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3511,7 +5664,7 @@ pub async fn watcher_unspent_utxos(
     };
 
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3544,6 +5697,102 @@ pub async fn watcher_unspent_utxos(
     })
 }
 
+/// Recover the utxos of the watcher, deriving addresses until `gap_limit`
+/// consecutive ones are unused (the default sync uses a gap of 20).
+pub async fn recover_watcher(
+    sk: &str,
+    name: &str,
+    iface: &str,
+    gap_limit: u32,
+) -> Result<NextUtxosResponse, WatcherError> {
+    let mut rgb_account = retrieve_account(sk).await.map_err(WatcherError::IO)?;
+    let mut wallet = match rgb_account.wallets.get(name) {
+        Some(wallet) => wallet.to_owned(),
+        _ => return Err(WatcherError::NoWatcher),
+    };
+
+    let iface_index = match iface {
+        "RGB20" => 20,
+        "RGB21" => 21,
+        _ => 10,
+    };
+
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..default!()
+    };
+
+    sync_explorer_wallet_with_gap(iface_index, &mut wallet, &mut resolver, gap_limit).await;
+
+    let utxos = wallet
+        .utxos
+        .iter()
+        .filter(|utxo| utxo.derivation.terminal.app == iface_index)
+        .map(|utxo| UtxoResponse::with(utxo.outpoint, utxo.amount, utxo.status))
+        .collect();
+
+    rgb_account.wallets.insert(name.to_string(), wallet);
+    store_account(sk, rgb_account)
+        .await
+        .map_err(WatcherError::IO)?;
+
+    Ok(NextUtxosResponse { utxos })
+}
+
+/// Export the wallet as a backup bundle: the standard Bitcoin descriptors
+/// (with checksums and the key origins of `key_origins`, if any), and the RGB
+/// addendum needed to re-derive the seals, with the stock and transfers.
+pub async fn export_backup(sk: &str, key_origins: Option<&[String]>) -> Result<WalletBackup> {
+    let (stock, rgb_account, rgb_transfers) = retrieve_stock_account_transfers(sk).await?;
+    let backup = wallet_backup(
+        &rgb_account,
+        &stock,
+        &rgb_transfers,
+        key_origins,
+        current_network().await,
+    )?;
+    Ok(backup)
+}
+
+/// Rebuild the wallets of a backup bundle exported by [`export_backup`].
+///
+/// Existing wallets with the same xpub keep their UTXOs and gain the tapret
+/// tweaks of the backup, and the missing transfers are added. Wallets with
+/// the same name but another xpub, and a stock already holding contracts, are
+/// only replaced with `overwrite`.
+pub async fn restore_backup(
+    sk: &str,
+    backup: &[u8],
+    overwrite: bool,
+) -> Result<WalletBackupRestoreResponse> {
+    let network = current_network().await;
+    let backup = read_backup(backup)?;
+    let wallets = restore_wallets(&backup, network)?;
+    let restored_stock = restore_stock(&backup)?;
+
+    let (mut stock, mut rgb_account, mut rgb_transfers) =
+        retrieve_stock_account_transfers(sk).await?;
+    merge_wallets(&mut rgb_account.wallets, wallets.clone(), overwrite)?;
+    merge_transfers(&mut rgb_transfers, &backup.rgb.transfers);
+
+    let replace_stock = overwrite || stock.contract_ids()?.is_empty();
+    if replace_stock {
+        stock = restored_stock;
+    }
+    store_stock_account_transfers(sk, stock, rgb_account, rgb_transfers).await?;
+
+    Ok(WalletBackupRestoreResponse {
+        network: network.to_string(),
+        wallets: wallets.into_keys().collect(),
+        descriptors: backup
+            .descriptors
+            .into_iter()
+            .map(|descriptor| descriptor.desc)
+            .collect(),
+        stock: replace_stock,
+    })
+}
+
 pub async fn clear_stock(sk: &str) {
     store_rgb_stock(sk, Stock::default())
         .await
@@ -3589,6 +5838,8 @@ pub async fn import_uda_data(request: MediaRequest) -> Result<MediaResponse> {
 
 pub async fn decode_invoice(invoice: String) -> Result<RgbInvoiceResponse> {
     let rgb_invoice = RgbInvoice::from_str(&invoice)?;
+    let network = current_network().await.to_string();
+    check_invoice_chain(&rgb_invoice, &network)?;
 
     let contract_id = rgb_invoice
         .contract
@@ -3607,13 +5858,21 @@ pub async fn decode_invoice(invoice: String) -> Result<RgbInvoiceResponse> {
     })
 }
 
+pub async fn consignment_id(consignment: String) -> Result<String> {
+    let serialized = Vec::<u8>::from_hex(&consignment)?;
+    let confined = Confined::try_from_iter(serialized.iter().copied())?;
+    let transfer = Transfer::from_strict_serialized::<{ U32 }>(confined)?;
+
+    Ok(rgb_consignment_id(&transfer)?)
+}
+
 pub async fn inspect_contract(
     stock: &mut Stock,
     rgb_account: RgbAccountV1,
     contract_id: &str,
 ) -> Result<ContractResponse> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -3654,7 +5913,7 @@ pub async fn inspect_contract(
 
 pub async fn read_contract(sk: &str, contract_id: &str) -> Result<ContractResponse> {
     let mut resolver = ExplorerResolver {
-        explorer_url: BITCOIN_EXPLORER_API.read().await.to_string(),
+        explorer_url: get_explorer_api().await,
         ..default!()
     };
 
@@ -0,0 +1,111 @@
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::{
+    rgb::structs::ContractAmount,
+    structs::{AllocationDetail, AllocationValue, TransferType},
+};
+
+const TRANSFERS_HEADER: &str = "Date,Direction,Amount,Counterparty Seal,Txid,Fee";
+const HOLDINGS_HEADER: &str = "Utxo,Amount";
+
+/// Witness transaction details, as returned by the explorer
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ExplorerTxInfo {
+    pub fee: Option<u64>,
+    #[serde(default)]
+    pub status: ExplorerTxStatus,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ExplorerTxStatus {
+    pub block_time: Option<i64>,
+}
+
+/// Spending status of an output, as returned by the explorer
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ExplorerOutspend {
+    pub spent: bool,
+}
+
+/// A single contract movement, as exported for bookkeeping
+#[derive(Clone, Debug)]
+pub struct AccountingEntry {
+    /// Block timestamp (unconfirmed transfers have none)
+    pub date: Option<i64>,
+    /// Transfer direction
+    pub direction: TransferType,
+    /// Amount moved (in the contract raw units)
+    pub amount: u64,
+    /// Beneficiary seals of the transfer
+    pub counterparty: Vec<String>,
+    /// Witness transaction id
+    pub txid: String,
+    /// Bitcoin fee paid by us (in sats)
+    pub fee: Option<u64>,
+}
+
+/// Render contract movements and current holdings as CSV.
+///
+/// Fields with separators, quotes or line breaks are quoted (RFC 4180).
+///
+/// The first section has one row per transfer, the second section
+/// (separated by an empty line) is a snapshot of the unspent allocations
+/// owned by the wallet. Amounts are in human units, using the contract precision.
+pub fn contract_csv(
+    precision: u8,
+    entries: &[AccountingEntry],
+    allocations: &[AllocationDetail],
+) -> String {
+    let mut lines = vec![TRANSFERS_HEADER.to_owned()];
+    for entry in entries {
+        let date = entry
+            .date
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+        let direction = match entry.direction {
+            TransferType::Sended => "sent",
+            TransferType::Received => "received",
+            TransferType::Unknown => "unknown",
+        };
+        let fee = entry.fee.map(|fee| fee.to_string()).unwrap_or_default();
+
+        lines.push(
+            [
+                date,
+                direction.to_owned(),
+                ContractAmount::load(entry.amount, precision).to_string(),
+                entry.counterparty.join(" "),
+                entry.txid.clone(),
+                fee,
+            ]
+            .map(|field| csv_field(&field))
+            .join(","),
+        );
+    }
+
+    lines.push("".to_owned());
+    lines.push(HOLDINGS_HEADER.to_owned());
+    for alloc in allocations.iter().filter(|x| x.is_mine && !x.is_spent) {
+        let amount = match alloc.value {
+            AllocationValue::Value(value) => ContractAmount::load(value, precision).to_string(),
+            AllocationValue::UDA(_) => "1".to_owned(),
+        };
+        lines.push(
+            [alloc.utxo.clone(), amount]
+                .map(|field| csv_field(&field))
+                .join(","),
+        );
+    }
+
+    lines.join("\n")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
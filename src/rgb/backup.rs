@@ -0,0 +1,336 @@
+use amplify::confinement::{Confined, U32};
+use bdk::descriptor::checksum::calc_checksum;
+use bitcoin::Network;
+use bitcoin_30::bip32::{DerivationPath, ExtendedPubKey, Fingerprint};
+use bitcoin_hashes::{sha256, Hash};
+use bp::dbc::tapret::TapretCommitment;
+use rgb::{RgbDescr, RgbWallet, Tapret, TerminalPath};
+use rgbstd::persistence::Stock;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{
+    rgb::structs::{RgbAccountV1, RgbTransfersV1},
+    structs::{BackupDescriptor, RgbBackupAddendum, RgbBackupWallet, WalletBackup},
+};
+
+pub const BACKUP_VERSION: u8 = 1;
+
+// Terminals of the RGB allocations (contracts, RGB20 and RGB21 assets)
+const RGB_BACKUP_TERMINALS: [u32; 4] = [9, 10, 20, 21];
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum BackupError {
+    /// Backup is not a valid JSON container. {0}
+    Format(String),
+    /// Backup version {0} is not supported
+    UnsupportedVersion(u8),
+    /// Backup belongs to the {0} network, but the wallet runs on {1}
+    WrongNetwork(String, String),
+    /// Descriptor {0} is invalid. {1}
+    WrongDescriptor(String, String),
+    /// Descriptor {0} has a missing or wrong checksum
+    WrongDescriptorChecksum(String),
+    /// Checksum of the RGB addendum doesn't match its wallets
+    WrongAddendumChecksum,
+    /// Wallet {0} has an invalid xpub. {1}
+    WrongXpub(String, String),
+    /// Wallet {0} has an invalid tapret tweak. {1}
+    WrongTapret(String, String),
+    /// Key origin of {0} is invalid, expected [fingerprint/path]xpub. {1}
+    WrongKeyOrigin(String, String),
+    /// Wallet {0} already exists with another xpub, restore the backup with overwrite
+    WalletConflict(String),
+    /// Stock of the backup cannot be encoded. {0}
+    WrongStock(String),
+}
+
+/// Build the backup bundle of the wallets of the RGB account.
+///
+/// Each watcher xpub exports its receive (`/0/*`) and change (`/1/*`)
+/// Bitcoin descriptors, and the inactive descriptors of the RGB terminals
+/// (`/9/*`, `/10/*`, `/20/*` and `/21/*`) to rescan their UTXOs. Spending
+/// these outside of RGB burns their allocations. Descriptors carry the key
+/// origin found in `key_origins` (keys or descriptors like
+/// `tr([fingerprint/path]xpub/0/*)`), which signers need to find the master
+/// key, and are exported as bare xpubs without it.
+/// The addendum also carries the stock and the transfers of the wallet.
+pub fn wallet_backup(
+    account: &RgbAccountV1,
+    stock: &Stock,
+    transfers: &RgbTransfersV1,
+    key_origins: Option<&[String]>,
+    network: Network,
+) -> Result<WalletBackup, BackupError> {
+    let mut origins = BTreeMap::new();
+    for key in key_origins.unwrap_or_default() {
+        let (xpub, origin) = parse_key_origin(key)?;
+        origins.insert(xpub, origin);
+    }
+
+    let mut wallets = BTreeMap::new();
+    for (name, wallet) in account.wallets.iter() {
+        let RgbDescr::Tapret(tapret) = wallet.descr.clone();
+        let taprets = tapret
+            .taprets
+            .into_iter()
+            .map(|(TerminalPath { app, index }, tweaks)| {
+                (
+                    format!("{app}:{index}"),
+                    tweaks.into_iter().map(|tap| tap.to_string()).collect(),
+                )
+            })
+            .collect();
+
+        wallets.insert(
+            name.to_owned(),
+            RgbBackupWallet {
+                xpub: tapret.xpub.to_string(),
+                taprets,
+            },
+        );
+    }
+
+    let mut descriptors = BTreeSet::new();
+    for RgbBackupWallet { xpub, .. } in wallets.values() {
+        let key = match origins.get(xpub) {
+            Some(origin) => format!("[{origin}]{xpub}"),
+            None => xpub.to_owned(),
+        };
+        let terminals = [(0, true, false), (1, true, true)]
+            .into_iter()
+            .chain(RGB_BACKUP_TERMINALS.map(|app| (app, false, false)));
+        for (app, active, internal) in terminals {
+            let desc = format!("tr({key}/{app}/*)");
+            let checksum = calc_checksum(&desc)
+                .map_err(|err| BackupError::WrongDescriptor(desc.clone(), err.to_string()))?;
+            descriptors.insert(BackupDescriptor {
+                desc: format!("{desc}#{checksum}"),
+                active,
+                internal,
+                timestamp: 0,
+            });
+        }
+    }
+
+    let stock = stock
+        .to_strict_serialized::<U32>()
+        .map_err(|err| BackupError::WrongStock(err.to_string()))?;
+    let mut rgb = RgbBackupAddendum {
+        wallets,
+        stock: hex::encode(stock.as_slice()),
+        transfers: transfers.clone(),
+        checksum: String::new(),
+    };
+    rgb.checksum = addendum_checksum(&rgb);
+
+    Ok(WalletBackup {
+        version: BACKUP_VERSION,
+        network: network.to_string(),
+        descriptors: descriptors.into_iter().collect(),
+        rgb,
+    })
+}
+
+/// Parse the JSON container built by [`wallet_backup`].
+pub fn read_backup(backup: &[u8]) -> Result<WalletBackup, BackupError> {
+    serde_json::from_slice(backup).map_err(|err| BackupError::Format(err.to_string()))
+}
+
+/// Rebuild the RGB wallets of the backup, after validating its version,
+/// network and checksums.
+///
+/// Restored wallets have no UTXOs: they are found again by the next sync,
+/// with the tapret tweaks re-deriving the seals.
+pub fn restore_wallets(
+    backup: &WalletBackup,
+    network: Network,
+) -> Result<BTreeMap<String, RgbWallet>, BackupError> {
+    if backup.version != BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(backup.version));
+    }
+
+    if backup.network != network.to_string() {
+        return Err(BackupError::WrongNetwork(
+            backup.network.clone(),
+            network.to_string(),
+        ));
+    }
+
+    for BackupDescriptor { desc, .. } in backup.descriptors.iter() {
+        let valid = match desc.split_once('#') {
+            Some((body, checksum)) => calc_checksum(body)
+                .map(|expected| expected == checksum)
+                .unwrap_or_default(),
+            None => false,
+        };
+        if !valid {
+            return Err(BackupError::WrongDescriptorChecksum(desc.to_owned()));
+        }
+    }
+
+    if addendum_checksum(&backup.rgb) != backup.rgb.checksum {
+        return Err(BackupError::WrongAddendumChecksum);
+    }
+
+    let wallets = &backup.rgb.wallets;
+    let mut restored = BTreeMap::new();
+    for (name, RgbBackupWallet { xpub, taprets }) in wallets.iter() {
+        let xpub = ExtendedPubKey::from_str(xpub)
+            .map_err(|err| BackupError::WrongXpub(name.to_owned(), err.to_string()))?;
+        // Extended keys only tell mainnet apart from the test networks
+        if (xpub.network == bitcoin_30::Network::Bitcoin) != (network == Network::Bitcoin) {
+            return Err(BackupError::WrongNetwork(
+                xpub.network.to_string(),
+                network.to_string(),
+            ));
+        }
+
+        let mut tapret = Tapret {
+            xpub,
+            taprets: BTreeMap::new(),
+        };
+        for (terminal, tweaks) in taprets.iter() {
+            let wrong_terminal = || BackupError::WrongTapret(name.to_owned(), terminal.to_owned());
+            let (app, index) = terminal.split_once(':').ok_or_else(wrong_terminal)?;
+            let terminal = TerminalPath {
+                app: app.parse().map_err(|_| wrong_terminal())?,
+                index: index.parse().map_err(|_| wrong_terminal())?,
+            };
+
+            let mut commitments = BTreeSet::new();
+            for tweak in tweaks {
+                let commitment = TapretCommitment::from_str(tweak)
+                    .map_err(|err| BackupError::WrongTapret(name.to_owned(), err.to_string()))?;
+                commitments.insert(commitment);
+            }
+            tapret.taprets.insert(terminal, commitments);
+        }
+
+        restored.insert(
+            name.to_owned(),
+            RgbWallet {
+                descr: RgbDescr::Tapret(tapret),
+                utxos: empty!(),
+            },
+        );
+    }
+
+    Ok(restored)
+}
+
+/// Decode the stock carried by the backup (checked by [`restore_wallets`]).
+pub fn restore_stock(backup: &WalletBackup) -> Result<Stock, BackupError> {
+    let stock =
+        hex::decode(&backup.rgb.stock).map_err(|err| BackupError::WrongStock(err.to_string()))?;
+    let confined =
+        Confined::try_from_iter(stock).map_err(|err| BackupError::WrongStock(err.to_string()))?;
+    Stock::from_strict_serialized::<U32>(confined)
+        .map_err(|err| BackupError::WrongStock(err.to_string()))
+}
+
+/// Add the restored wallets to the wallets of the account.
+///
+/// Wallets with the same name and xpub gain the tapret tweaks of the backup.
+/// A wallet with the same name but another xpub is only replaced when
+/// `overwrite` is set, otherwise nothing is restored.
+pub fn merge_wallets(
+    wallets: &mut HashMap<String, RgbWallet>,
+    restored: BTreeMap<String, RgbWallet>,
+    overwrite: bool,
+) -> Result<(), BackupError> {
+    if !overwrite {
+        for (name, wallet) in restored.iter() {
+            if let Some(current) = wallets.get(name) {
+                let RgbDescr::Tapret(tapret) = &current.descr;
+                let RgbDescr::Tapret(restored) = &wallet.descr;
+                if tapret.xpub != restored.xpub {
+                    return Err(BackupError::WalletConflict(name.to_owned()));
+                }
+            }
+        }
+    }
+
+    for (name, wallet) in restored {
+        let RgbDescr::Tapret(restored) = wallet.descr.clone();
+        match wallets.get_mut(&name) {
+            Some(current) => {
+                let RgbDescr::Tapret(tapret) = &mut current.descr;
+                if tapret.xpub == restored.xpub {
+                    for (terminal, tweaks) in restored.taprets {
+                        tapret.taprets.entry(terminal).or_default().extend(tweaks);
+                    }
+                } else {
+                    *current = wallet;
+                }
+            }
+            None => {
+                wallets.insert(name, wallet);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the transfers of the backup missing from the wallet transfers.
+pub fn merge_transfers(transfers: &mut RgbTransfersV1, restored: &RgbTransfersV1) {
+    for (contract_id, restored) in restored.transfers.iter() {
+        let current = transfers
+            .transfers
+            .entry(contract_id.to_owned())
+            .or_default();
+        for transfer in restored {
+            if !current
+                .iter()
+                .any(|item| item.consig_id == transfer.consig_id)
+            {
+                current.push(transfer.clone());
+            }
+        }
+    }
+}
+
+// Keys like `[fingerprint/path]xpub`, alone or in a descriptor
+fn parse_key_origin(key: &str) -> Result<(String, String), BackupError> {
+    let wrong_origin = |err: String| BackupError::WrongKeyOrigin(key.to_owned(), err);
+    let inner = key.trim().trim_start_matches("tr(").trim_end_matches(')');
+    let (origin, xpub) = inner
+        .strip_prefix('[')
+        .and_then(|inner| inner.split_once(']'))
+        .ok_or_else(|| wrong_origin("missing key origin".to_string()))?;
+    let xpub = xpub.split('/').next().unwrap_or_default();
+    let xpub = ExtendedPubKey::from_str(xpub).map_err(|err| wrong_origin(err.to_string()))?;
+
+    let (fingerprint, path) = origin.split_once('/').unwrap_or((origin, ""));
+    Fingerprint::from_str(fingerprint).map_err(|err| wrong_origin(err.to_string()))?;
+    let path = match path {
+        "" => DerivationPath::default(),
+        path => DerivationPath::from_str(&format!("m/{path}"))
+            .map_err(|err| wrong_origin(err.to_string()))?,
+    };
+    if path.len() != xpub.depth as usize {
+        return Err(wrong_origin(format!(
+            "path has {} steps, but the xpub depth is {}",
+            path.len(),
+            xpub.depth
+        )));
+    }
+
+    Ok((xpub.to_string(), origin.to_owned()))
+}
+
+fn addendum_checksum(addendum: &RgbBackupAddendum) -> String {
+    let RgbBackupAddendum {
+        wallets,
+        stock,
+        transfers,
+        ..
+    } = addendum;
+    let data = serde_json::to_vec(&(wallets, stock, transfers)).expect("backup serialization");
+    sha256::Hash::hash(&data).to_string()
+}
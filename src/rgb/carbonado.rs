@@ -15,7 +15,10 @@ use crate::{
         crdt::{
             LocalRgbAccount, LocalRgbAuctions, LocalRgbOfferBid, LocalRgbOffers, RawRgbAccount,
         },
-        structs::{RgbAccountV1, RgbTransfersV1},
+        structs::{
+            RgbAcceptJournal, RgbAccountV1, RgbBroadcasts, RgbCounterparties,
+            RgbIgnoredAllocations, RgbRevokedSeals, RgbTransfersV1, RgbUnconfirmedTransfers,
+        },
         swap::{RgbAuctionSwaps, RgbBidSwap, RgbBids, RgbOffers, RgbPublicSwaps},
     },
 };
@@ -56,6 +59,13 @@ pub enum StorageError {
     Reconcile(String, String),
 }
 
+/// Prefix of the carbonado files holding the storage key
+pub fn storage_file_prefix(name: &str) -> String {
+    blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase()
+}
+
 // User Carbonado Operations
 pub async fn store_stock(sk: &str, name: &str, stock: &Stock) -> Result<(), StorageError> {
     let data = stock
@@ -165,6 +175,172 @@ pub async fn store_bids(sk: &str, name: &str, rgb_bids: &RgbBids) -> Result<(),
     .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
 }
 
+pub async fn store_ignored_allocations(
+    sk: &str,
+    name: &str,
+    ignored: &RgbIgnoredAllocations,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(ignored)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+pub async fn store_revoked_seals(
+    sk: &str,
+    name: &str,
+    revoked: &RgbRevokedSeals,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(revoked)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+pub async fn store_unconfirmed_transfers(
+    sk: &str,
+    name: &str,
+    unconfirmed: &RgbUnconfirmedTransfers,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(unconfirmed)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+pub async fn store_broadcasts(
+    sk: &str,
+    name: &str,
+    broadcasts: &RgbBroadcasts,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(broadcasts)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+pub async fn store_counterparties(
+    sk: &str,
+    name: &str,
+    counterparties: &RgbCounterparties,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(counterparties)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+pub async fn store_accept_journal(
+    sk: &str,
+    name: &str,
+    journal: &RgbAcceptJournal,
+) -> Result<(), StorageError> {
+    let data = to_allocvec(journal)
+        .map_err(|op| StorageError::StrictWrite(name.to_string(), op.to_string()))?;
+
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    store(
+        sk,
+        &format!("{hashed_name}.c15"),
+        &data,
+        true,
+        Some(RGB_STRICT_TYPE_VERSION.to_vec()),
+    )
+    .await
+    .map_err(|op| StorageError::CarbonadoWrite(name.to_string(), op.to_string()))
+}
+
+/// Store the document of contract terms, keyed by its digest
+pub async fn store_terms(digest: &str, document: &[u8]) -> Result<(), StorageError> {
+    let name = format!("terms-{digest}");
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    marketplace_store(&format!("{hashed_name}.c15"), document, None)
+        .await
+        .map_err(|op| StorageError::CarbonadoWrite(name, op.to_string()))
+}
+
+pub async fn retrieve_terms(digest: &str) -> Result<Option<Vec<u8>>, StorageError> {
+    let name = format!("terms-{digest}");
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = marketplace_retrieve(&format!("{hashed_name}.c15"))
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name, op.to_string()))?;
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(data))
+}
+
 pub async fn retrieve_stock(sk: &str, name: &str) -> Result<Stock, StorageError> {
     let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
         .to_hex()
@@ -250,6 +426,126 @@ pub async fn retrieve_offers(sk: &str, name: &str) -> Result<RgbOffers, StorageE
     }
 }
 
+pub async fn retrieve_ignored_allocations(
+    sk: &str,
+    name: &str,
+) -> Result<RgbIgnoredAllocations, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbIgnoredAllocations::default())
+    } else {
+        let ignored = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(ignored)
+    }
+}
+
+pub async fn retrieve_revoked_seals(sk: &str, name: &str) -> Result<RgbRevokedSeals, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbRevokedSeals::default())
+    } else {
+        let revoked = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(revoked)
+    }
+}
+
+pub async fn retrieve_unconfirmed_transfers(
+    sk: &str,
+    name: &str,
+) -> Result<RgbUnconfirmedTransfers, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbUnconfirmedTransfers::default())
+    } else {
+        let unconfirmed = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(unconfirmed)
+    }
+}
+
+pub async fn retrieve_broadcasts(sk: &str, name: &str) -> Result<RgbBroadcasts, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbBroadcasts::default())
+    } else {
+        let broadcasts = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(broadcasts)
+    }
+}
+
+pub async fn retrieve_counterparties(
+    sk: &str,
+    name: &str,
+) -> Result<RgbCounterparties, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbCounterparties::default())
+    } else {
+        let counterparties = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(counterparties)
+    }
+}
+
+pub async fn retrieve_accept_journal(
+    sk: &str,
+    name: &str,
+) -> Result<RgbAcceptJournal, StorageError> {
+    let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
+        .to_hex()
+        .to_lowercase();
+
+    let (data, _) = retrieve(sk, &format!("{hashed_name}.c15"), vec![])
+        .await
+        .map_err(|op| StorageError::CarbonadoRetrieve(name.to_string(), op.to_string()))?;
+
+    if data.is_empty() {
+        Ok(RgbAcceptJournal::default())
+    } else {
+        let journal = from_bytes(&data)
+            .map_err(|op| StorageError::StrictRetrieve(name.to_string(), op.to_string()))?;
+        Ok(journal)
+    }
+}
+
 pub async fn retrieve_bids(sk: &str, name: &str) -> Result<RgbBids, StorageError> {
     let hashed_name = blake3::hash(format!("{LIB_ID_RGB}-{name}").as_bytes())
         .to_hex()
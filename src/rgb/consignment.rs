@@ -2,6 +2,7 @@ use std::{
     cmp::Ordering,
     collections::{BTreeMap, HashMap},
     iter,
+    sync::Arc,
 };
 
 use amplify::ByteArray;
@@ -22,6 +23,8 @@ use rgbwallet::{
 use seals::txout::CloseMethod;
 use strict_encoding::TypeName;
 
+use crate::rgb::transfer::CounterpartyPolicy;
+
 #[derive(Clone, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub struct TransitionContainer {
@@ -70,6 +73,28 @@ pub struct NewTransferOptions {
     pub other_invoices: Vec<RgbInvoice>,
     pub offer_id: Option<String>,
     pub bid_id: Option<String>,
+    pub from_seals: Vec<Outpoint>,
+    pub counterparty_policy: Option<Arc<dyn CounterpartyPolicy>>,
+    /// Amounts of the change allocations of the invoice contract (each on its
+    /// own seal), instead of a single change allocation
+    pub change_split: Vec<u64>,
+}
+
+/// Payment errors of [`ConsignmentEx::pay_all`]
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PayAllError<E1: std::error::Error, E2: std::error::Error> {
+    /// {0}
+    #[from]
+    Pay(PayError<E1, E2>),
+    /// Change split sums up to {actual}, but the change of the payment is {expected}
+    ChangeSplitMismatch {
+        /// Change of the payment
+        expected: u64,
+
+        /// Sum of the change split
+        actual: u64,
+    },
 }
 
 impl NewTransferOptions {
@@ -94,6 +119,73 @@ pub trait ConsignmentEx: Inventory {
         psbt: &mut Psbt,
         method: CloseMethod,
         options: NewTransferOptions,
+    ) -> Result<Vec<Bindle<Transfer>>, PayAllError<Self::Error, <Self::Stash as Stash>::Error>>
+    where
+        Self::Error: From<<Self::Stash as Stash>::Error>,
+    {
+        // Unknown contract states are reported by the payment itself
+        if !options.change_split.is_empty() {
+            if let Some(expected) = self.payment_change(&invoice, psbt, &options.other_invoices) {
+                let actual = options.change_split.iter().sum();
+                if actual != expected {
+                    return Err(PayAllError::ChangeSplitMismatch { expected, actual });
+                }
+            }
+        }
+
+        Ok(self.pay_all_states(invoice, psbt, method, options)?)
+    }
+
+    /// Change of the invoice contract left by the PSBT inputs, once paid the
+    /// invoice and the other invoices of the contract (`None` when the
+    /// contract state is unknown)
+    fn payment_change(
+        &mut self,
+        invoice: &RgbInvoice,
+        psbt: &Psbt,
+        other_invoices: &[RgbInvoice],
+    ) -> Option<u64> {
+        let contract_id = invoice.contract?;
+        let iface = invoice.iface.clone()?;
+        let builder = self
+            .transition_builder(contract_id, iface, None::<TypeName>)
+            .ok()?;
+        let assignment_id = invoice
+            .assignment
+            .as_ref()
+            .or_else(|| builder.default_assignment().ok())
+            .and_then(|name| builder.assignments_type(name))?;
+
+        let prev_outputs = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .map(|outpoint| Outpoint::new(outpoint.txid.to_byte_array().into(), outpoint.vout));
+        let inputs: u64 = self
+            .state_for_outpoints(contract_id, prev_outputs)
+            .ok()?
+            .into_iter()
+            .filter(|(opout, _)| opout.ty == assignment_id)
+            .map(|(_, state)| owned_value(&state))
+            .sum();
+
+        let previous_state_value: u64 = other_invoices
+            .iter()
+            .filter(|other| other.contract == Some(contract_id))
+            .map(|other| owned_value(&other.owned_state))
+            .sum();
+
+        Some(inputs.saturating_sub(owned_value(&invoice.owned_state) + previous_state_value))
+    }
+
+    #[allow(clippy::result_large_err, clippy::type_complexity)]
+    fn pay_all_states(
+        &mut self,
+        invoice: RgbInvoice,
+        psbt: &mut Psbt,
+        method: CloseMethod,
+        options: NewTransferOptions,
     ) -> Result<Vec<Bindle<Transfer>>, PayError<Self::Error, <Self::Stash as Stash>::Error>>
     where
         Self::Error: From<<Self::Stash as Stash>::Error>,
@@ -164,6 +256,10 @@ pub trait ConsignmentEx: Inventory {
                 out_classes.entry(class).or_default().push(no as u32);
             }
         }
+        // Each allocation of the change split goes to its own output
+        let mut split_outputs: Vec<_> = out_classes.values().flatten().copied().collect();
+        split_outputs.sort();
+
         let mut out_classes = out_classes
             .into_iter()
             .map(|(class, indexes)| (class, indexes.into_iter().cycle()))
@@ -338,14 +434,39 @@ pub trait ConsignmentEx: Inventory {
 
                     match sum_inputs.cmp(&amt) {
                         Ordering::Greater => {
-                            if default_change_seal.is_none() {
-                                default_change_seal =
-                                    output_for_assignment(suppl.as_ref(), assignment_id);
-                            }
-
-                            if let Some(seal) = default_change_seal {
-                                let change = amt + previous_state_value;
-                                let change = TypedState::Amount(sum_inputs - change);
+                            let change = sum_inputs - amt - previous_state_value;
+                            if contract_id == current_contract_id
+                                && !options.change_split.is_empty()
+                            {
+                                // Sum checked against the change by `pay_all`
+                                if split_outputs.len() < options.change_split.len() {
+                                    return Err(PayError::NoBlankOrChange(
+                                        VelocityHint::default(),
+                                        assignment_id,
+                                    ));
+                                }
+
+                                for (value, vout) in
+                                    options.change_split.iter().zip(split_outputs.iter())
+                                {
+                                    let seal = GraphSeal::new_vout(method, *vout);
+                                    builder = builder.add_raw_state(
+                                        assignment_id,
+                                        BuilderSeal::Revealed(seal),
+                                        TypedState::Amount(*value),
+                                    )?;
+                                }
+                            } else if change > 0 {
+                                if default_change_seal.is_none() {
+                                    default_change_seal =
+                                        output_for_assignment(suppl.as_ref(), assignment_id);
+                                }
+
+                                let seal = default_change_seal.ok_or(PayError::NoBlankOrChange(
+                                    VelocityHint::default(),
+                                    assignment_id,
+                                ))?;
+                                let change = TypedState::Amount(change);
                                 builder = builder.add_raw_state(assignment_id, seal, change)?;
                             }
                             amt
@@ -801,3 +922,12 @@ pub trait ConsignmentEx: Inventory {
 }
 
 impl<I> ConsignmentEx for I where I: Inventory {}
+
+// Value of the owned state, counting each data allocation as 1
+fn owned_value(state: &TypedState) -> u64 {
+    match state {
+        TypedState::Amount(value) => *value,
+        TypedState::Data(_) => 1,
+        _ => 0,
+    }
+}
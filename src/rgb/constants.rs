@@ -2,14 +2,61 @@
 pub const LIB_NAME_BITMASK: &str = "bitmask";
 pub const RGB_CHANGE_INDEX: &str = "0";
 pub const RGB_PSBT_TAPRET: &str = "TAPRET";
+pub const RGB_PSBT_TIMELOCK: &str = "TIMELOCK";
 pub const RGB_DEFAULT_NAME: &str = "default";
 pub const RGB_OLDEST_VERSION: [u8; 8] = [0; 8];
 pub const RGB_STRICT_TYPE_VERSION: [u8; 8] = *b"rgbst161";
 pub const RGB_DEFAULT_FETCH_LIMIT: u32 = 10;
+pub const RGB_DEFAULT_GAP_LIMIT: u32 = 20;
 pub const BITCOIN_DEFAULT_FETCH_LIMIT: u32 = 20;
 pub const RGB20_DERIVATION_INDEX: u32 = 20;
 pub const RGB21_DERIVATION_INDEX: u32 = 21;
 
+// Fee Safety Cap (max. percent of the input value, never below the floor in sats)
+pub const RGB_MAX_FEE_PERC: u64 = 10;
+pub const RGB_MAX_FEE_FLOOR: u64 = 10_000;
+
+// Interface Version (of the interface implementation of the contract), invoices target
+// one with the query param
+pub const RGB_IFACE_VERSION_PARAM: &str = "iface_version";
+
+// Invoice Origin (wallet pubkey, and its signature of the beneficiary), identifying the
+// recipient wallet across its invoices
+pub const RGB_ORIGIN_PARAM: &str = "origin";
+pub const RGB_ORIGIN_SIG_PARAM: &str = "origin_sig";
+
+// Version of the transfer transactions (raised when a time-lock requires it)
+pub const RGB_TX_VERSION: u32 = 2;
+
+// Max. number of outputs of a transfer transaction
+pub const RGB_MAX_OUTPUTS: usize = 100;
+
+// Max. size (in bytes) of the data carried by an OP_RETURN output (standard relay limit)
+pub const RGB_MAX_DATA_OUTPUT: usize = 80;
+
+// Anti-fee-sniping nLockTime: one in ten transactions picks a random height
+// up to this many blocks behind the tip (as Bitcoin Core does)
+pub const RGB_FEE_SNIPING_JITTER_PERC: u32 = 10;
+pub const RGB_FEE_SNIPING_MAX_JITTER: u32 = 100;
+
+// Amount (in sats) of the time-locked output of a transfer
+pub const RGB_TIMELOCK_OUTPUT_AMOUNT: u64 = 1_000;
+
+// Transfer index rebuilds: min. seconds between two rebuilds of a wallet, and
+// seconds between the periodic rebuilds of all the wallets
+pub const RGB_REINDEX_MIN_INTERVAL: i64 = 60;
+pub const RGB_REINDEX_INTERVAL: u64 = 3600;
+
+// Seconds between the checks of the transfers waiting for confirmation
+pub const RGB_CONFIRMATION_INTERVAL: u64 = 60;
+
+// Max. rebuilds (of all the wallets) started within a window of seconds
+pub const RGB_REINDEX_MAX_STARTS: usize = 10;
+pub const RGB_REINDEX_RATE_WINDOW: i64 = 60;
+
+// Max. number of wallets with a transfer index kept in memory
+pub const RGB_INDEX_CACHE_CAPACITY: usize = 100;
+
 // General Errors
 #[cfg(target_arch = "wasm32")]
 pub const CARBONADO_UNAVAILABLE: &str = "carbonado filesystem";
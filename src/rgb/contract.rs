@@ -3,19 +3,20 @@ use bech32::{encode, ToBase32};
 use rgb::{Resolver, RgbWallet};
 use rgbstd::{
     contract::ContractId,
-    interface::{rgb21::TokenData, IfaceId, IfacePair},
-    persistence::{Inventory, InventoryInconsistency, StashInconsistency, Stock},
-    stl::{ContractData, DivisibleAssetSpec, RicardianContract, Timestamp},
+    interface::{rgb21::TokenData, IfaceId, IfacePair, TypedState},
+    persistence::{Inventory, InventoryInconsistency, Stash, StashInconsistency, Stock},
+    schema::SchemaId,
+    stl::{Attachment, ContractData, DivisibleAssetSpec, RicardianContract, Timestamp},
 };
-use std::str::FromStr;
+use std::{collections::BTreeSet, str::FromStr};
 use strict_encoding::{FieldName, StrictDeserialize, StrictSerialize};
 
 use crate::rgb::{
     resolvers::ResolveSpent, structs::ContractBoilerplate, wallet::contract_allocations,
 };
 use crate::structs::{
-    AllocationValue, ContractFormats, ContractMediaDetail, ContractResponse, GenesisFormats,
-    MediaInfo,
+    AllocationDetail, AllocationValue, ContractFormats, ContractMediaDetail, ContractResponse,
+    GenesisFormats, MediaInfo,
 };
 
 use super::{
@@ -40,6 +41,49 @@ pub enum ExportContractError {
     WrongValue(String, String),
     /// The the contract {0} cannot have {1} global data
     GlobalNotFound(String, String),
+    /// The terms of the contract {0} do not match the committed digest
+    TermsMismatch(String),
+    /// The schema {1} of the contract {0} is unavailable
+    SchemaUnavailable(String, String),
+}
+
+/// Schema of the contract, when the stock knows the contract but not its
+/// schema (see `fetch_schema`)
+pub fn unavailable_schema(contract_id: ContractId, stock: &Stock) -> Option<SchemaId> {
+    let schema_id = stock.genesis(contract_id).ok()?.schema_id;
+    match stock.schema(schema_id) {
+        Ok(_) => None,
+        Err(_) => Some(schema_id),
+    }
+}
+
+/// Terms document (media type and digest) committed in the contract data
+/// (only RGB20)
+pub fn contract_terms(
+    contract_id: ContractId,
+    stock: &mut Stock,
+) -> Result<Option<Attachment>, ExportContractError> {
+    let contr_id = contract_id.to_string();
+    let contract_bindle = stock
+        .export_contract(contract_id)
+        .or(Err(ExportContractError::NoContrat(contr_id.clone())))?;
+
+    let iface_id = match contract_bindle.ifaces.keys().next() {
+        Some(iface_id) => iface_id.to_owned(),
+        None => return Ok(None),
+    };
+
+    let contract_iface = stock
+        .contract_iface(contract_id, iface_id)
+        .map_err(|err| ExportContractError::StashInconsistency(contr_id, err.to_string()))?;
+
+    let ty: FieldName = FieldName::from("data");
+    let media = match contract_iface.global(ty) {
+        Ok(values) => ContractData::from_strict_val_unchecked(&values[0]).media,
+        Err(_) => None,
+    };
+
+    Ok(media)
 }
 
 pub fn export_boilerplate(
@@ -75,6 +119,8 @@ pub fn export_boilerplate(
     Ok(ContractBoilerplate {
         contract_id: contract_id.to_string(),
         iface_id: iface_id.to_string(),
+        ticker: specs.ticker().to_string(),
+        name: specs.name().to_string(),
         precision: specs.precision.into(),
     })
 }
@@ -337,6 +383,7 @@ where
         supply,
         balance,
         balance_normalized,
+        pending_balance: 0,
         allocations,
         created: created.into(),
         contract: ContractFormats {
@@ -350,6 +397,150 @@ where
             armored: "".to_string(),
         },
         meta,
+        schema_unavailable: false,
+    };
+
+    Ok(resp)
+}
+
+/// Export a contract whose schema is unavailable.
+///
+/// Without the schema the state cannot be interpreted, so the contract shows
+/// only its raw state in the stock (the value of fungible assignments, one
+/// unit per structured assignment) for the outpoints of the genesis and of
+/// the wallet, and the balance of the unspent outpoints of the wallet. The
+/// supply is the raw state assigned by the genesis.
+pub fn export_raw_contract<T>(
+    contract_id: ContractId,
+    stock: &mut Stock,
+    resolver: &mut T,
+    wallet: &mut Option<RgbWallet>,
+) -> Result<ContractResponse, ExportContractError>
+where
+    T: ResolveSpent + Resolver,
+{
+    let contr_id = contract_id.to_string();
+    let genesis = stock
+        .genesis(contract_id)
+        .or(Err(ExportContractError::NoContrat(contr_id.clone())))?
+        .clone();
+
+    let genesis_serialized = match genesis.to_strict_serialized::<U32>() {
+        Ok(serialized) => serialized,
+        _ => {
+            return Err(ExportContractError::ContractFormat(
+                contr_id,
+                "bench32".to_string(),
+            ))
+        }
+    };
+    let genesis_strict = genesis_serialized.to_hex();
+    let genesis_legacy = match encode(
+        "rgb",
+        genesis_serialized.to_base32(),
+        bech32::Variant::Bech32m,
+    ) {
+        Ok(legacy) => legacy,
+        _ => {
+            return Err(ExportContractError::ContractFormat(
+                contr_id,
+                "bench32".to_string(),
+            ))
+        }
+    };
+
+    let mut supply = 0;
+    let mut outpoints = BTreeSet::new();
+    for (_, assigns) in genesis.assignments.iter() {
+        for assign in assigns.as_fungible() {
+            let value = assign.as_revealed_state().map(|state| state.value.as_u64());
+            supply += value.unwrap_or_default();
+            outpoints.extend(assign.revealed_seal().and_then(|seal| seal.outpoint()));
+        }
+        for assign in assigns.as_structured() {
+            supply += 1;
+            outpoints.extend(assign.revealed_seal().and_then(|seal| seal.outpoint()));
+        }
+    }
+    if let Some(wallet) = wallet.as_ref() {
+        outpoints.extend(wallet.utxos.iter().map(|utxo| utxo.outpoint));
+    }
+
+    let mut allocations = vec![];
+    for outpoint in outpoints {
+        let state = stock
+            .state_for_outpoints(contract_id, [outpoint])
+            .map_err(|err| {
+                ExportContractError::StashInconsistency(contr_id.clone(), err.to_string())
+            })?;
+        if state.is_empty() {
+            continue;
+        }
+        let value = state
+            .values()
+            .map(|state| match state {
+                TypedState::Amount(value) => *value,
+                TypedState::Void => 0,
+                _ => 1,
+            })
+            .sum();
+
+        let txid = bitcoin::Txid::from_str(&outpoint.txid.to_hex()).map_err(|err| {
+            ExportContractError::StashInconsistency(contr_id.clone(), err.to_string())
+        })?;
+        let is_spent = resolver
+            .resolve_spent_status(txid, outpoint.vout.into_u32().into(), false)
+            .map(|status| status.is_spent)
+            .unwrap_or_default();
+        let utxo = wallet.as_ref().and_then(|wallet| wallet.utxo(outpoint));
+        allocations.push(AllocationDetail {
+            utxo: outpoint.to_string(),
+            value: AllocationValue::Value(value),
+            derivation: utxo
+                .map(|utxo| {
+                    format!(
+                        "/{}/{}",
+                        utxo.derivation.terminal.app, utxo.derivation.terminal.index
+                    )
+                })
+                .unwrap_or_default(),
+            is_mine: utxo.is_some(),
+            is_spent,
+            is_pending: false,
+        });
+    }
+
+    let balance = allocations
+        .iter()
+        .filter(|a| a.is_mine && !a.is_spent)
+        .map(|a| match a.value {
+            AllocationValue::Value(value) => value,
+            AllocationValue::UDA(_) => 1,
+        })
+        .sum();
+
+    let resp = ContractResponse {
+        contract_id: contr_id,
+        iimpl_id: String::new(),
+        iface: String::new(),
+        ticker: String::new(),
+        name: String::new(),
+        description: String::new(),
+        precision: 0,
+        supply,
+        balance,
+        balance_normalized: balance as f64,
+        pending_balance: 0,
+        allocations,
+        created: 0,
+        contract: ContractFormats::default(),
+        genesis: GenesisFormats {
+            legacy: genesis_legacy,
+            strict: genesis_strict,
+            armored: "".to_string(),
+        },
+        meta: None,
+        schema_unavailable: true,
     };
 
     Ok(resp)
@@ -1,24 +1,40 @@
 use rgbstd::persistence::Stock;
 
 use crate::constants::storage_keys::{
-    ASSETS_BIDS, ASSETS_OFFERS, ASSETS_STOCK, ASSETS_TRANSFERS, ASSETS_WALLETS, MARKETPLACE_OFFERS,
+    ASSETS_ACCEPT_JOURNAL, ASSETS_BIDS, ASSETS_BROADCASTS, ASSETS_COUNTERPARTIES,
+    ASSETS_IGNORED_ALLOCATIONS, ASSETS_OFFERS, ASSETS_REVOKED_SEALS, ASSETS_STOCK,
+    ASSETS_TRANSFERS, ASSETS_UNCONFIRMED_TRANSFERS, ASSETS_WALLETS, MARKETPLACE_OFFERS,
 };
 use crate::rgb::{
     carbonado::{
         cdrt_retrieve_wallets, cdrt_store_wallets,
+        retrieve_accept_journal as retrieve_rgb_accept_journal,
         retrieve_auctions_offers as retrieve_rgb_auctions_offers,
-        retrieve_bids as retrieve_rgb_bids, retrieve_offers as retrieve_rgb_offers,
-        retrieve_public_offers as retrieve_rgb_public_offers, retrieve_stock as retrieve_rgb_stock,
+        retrieve_bids as retrieve_rgb_bids, retrieve_broadcasts as retrieve_rgb_broadcasts,
+        retrieve_counterparties as retrieve_rgb_counterparties,
+        retrieve_ignored_allocations as retrieve_rgb_ignored_allocations,
+        retrieve_offers as retrieve_rgb_offers,
+        retrieve_public_offers as retrieve_rgb_public_offers,
+        retrieve_revoked_seals as retrieve_rgb_revoked_seals, retrieve_stock as retrieve_rgb_stock,
         retrieve_swap_offer_bid as retrieve_rgb_swap_offer_bid,
-        retrieve_transfers as retrieve_rgb_transfers, retrieve_wallets,
+        retrieve_transfers as retrieve_rgb_transfers,
+        retrieve_unconfirmed_transfers as retrieve_rgb_unconfirmed_transfers, retrieve_wallets,
+        store_accept_journal as store_rgb_accept_journal,
         store_auction_offers as store_rgb_auction_offers, store_bids as store_rgb_bids,
+        store_broadcasts as store_rgb_broadcasts, store_counterparties as store_rgb_counterparties,
+        store_ignored_allocations as store_rgb_ignored_allocations,
         store_offers as store_rgb_offers, store_public_offers as store_rgb_public_offers,
-        store_stock as store_rgb_stock, store_swap_offer_bid,
-        store_transfers as store_rgb_transfer, store_wallets,
+        store_revoked_seals as store_rgb_revoked_seals, store_stock as store_rgb_stock,
+        store_swap_offer_bid, store_transfers as store_rgb_transfer,
+        store_unconfirmed_transfers as store_rgb_unconfirmed_transfers, store_wallets,
     },
     crdt::LocalRgbAccount,
     crdt::{LocalRgbOfferBid, LocalRgbOffers},
-    structs::{RgbAccountV1, RgbTransfersV1},
+    index::queue_reindex,
+    structs::{
+        RgbAcceptJournal, RgbAccountV1, RgbBroadcasts, RgbCounterparties, RgbIgnoredAllocations,
+        RgbRevokedSeals, RgbTransfersV1, RgbUnconfirmedTransfers,
+    },
     swap::{RgbBids, RgbOffers},
 };
 
@@ -41,6 +57,10 @@ pub enum RgbPersistenceError {
     RetrieveRgbBids(String),
     // Retrieve Swap Bid Error. {0}
     RetrieveSwapBids(String),
+    // Retrieve Ignored Allocations Error. {0}
+    RetrieveIgnoredAllocations(String),
+    // Retrieve Revoked Seals Error. {0}
+    RetrieveRevokedSeals(String),
     // Retrieve Public Offers Error. {0}
     RetrievePublicOffers(String),
     // Retrieve Auction Offers Error. {0}
@@ -63,6 +83,26 @@ pub enum RgbPersistenceError {
     WriteRgbAuctionOffers(String),
     // Store Swap Bid Error. {0}
     WriteSwapBids(String),
+    // Store Ignored Allocations Error. {0}
+    WriteIgnoredAllocations(String),
+    // Store Revoked Seals Error. {0}
+    WriteRevokedSeals(String),
+    // Retrieve Unconfirmed Transfers Error. {0}
+    RetrieveUnconfirmedTransfers(String),
+    // Store Unconfirmed Transfers Error. {0}
+    WriteUnconfirmedTransfers(String),
+    // Retrieve Counterparties Error. {0}
+    RetrieveCounterparties(String),
+    // Store Counterparties Error. {0}
+    WriteCounterparties(String),
+    // Retrieve Accept Journal Error. {0}
+    RetrieveAcceptJournal(String),
+    // Store Accept Journal Error. {0}
+    WriteAcceptJournal(String),
+    // Retrieve Broadcasts Error. {0}
+    RetrieveBroadcasts(String),
+    // Store Broadcasts Error. {0}
+    WriteBroadcasts(String),
 }
 
 pub async fn retrieve_stock(sk: &str) -> Result<Stock, RgbPersistenceError> {
@@ -144,6 +184,58 @@ pub async fn retrieve_bids(sk: &str) -> Result<RgbBids, RgbPersistenceError> {
     Ok(bids)
 }
 
+pub async fn retrieve_ignored_allocations(
+    sk: &str,
+) -> Result<RgbIgnoredAllocations, RgbPersistenceError> {
+    let ignored = retrieve_rgb_ignored_allocations(sk, ASSETS_IGNORED_ALLOCATIONS)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveIgnoredAllocations(op.to_string()))?;
+
+    Ok(ignored)
+}
+
+pub async fn retrieve_revoked_seals(sk: &str) -> Result<RgbRevokedSeals, RgbPersistenceError> {
+    let revoked = retrieve_rgb_revoked_seals(sk, ASSETS_REVOKED_SEALS)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveRevokedSeals(op.to_string()))?;
+
+    Ok(revoked)
+}
+
+pub async fn retrieve_unconfirmed_transfers(
+    sk: &str,
+) -> Result<RgbUnconfirmedTransfers, RgbPersistenceError> {
+    let unconfirmed = retrieve_rgb_unconfirmed_transfers(sk, ASSETS_UNCONFIRMED_TRANSFERS)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveUnconfirmedTransfers(op.to_string()))?;
+
+    Ok(unconfirmed)
+}
+
+pub async fn retrieve_broadcasts(sk: &str) -> Result<RgbBroadcasts, RgbPersistenceError> {
+    let broadcasts = retrieve_rgb_broadcasts(sk, ASSETS_BROADCASTS)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveBroadcasts(op.to_string()))?;
+
+    Ok(broadcasts)
+}
+
+pub async fn retrieve_counterparties(sk: &str) -> Result<RgbCounterparties, RgbPersistenceError> {
+    let counterparties = retrieve_rgb_counterparties(sk, ASSETS_COUNTERPARTIES)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveCounterparties(op.to_string()))?;
+
+    Ok(counterparties)
+}
+
+pub async fn retrieve_accept_journal(sk: &str) -> Result<RgbAcceptJournal, RgbPersistenceError> {
+    let journal = retrieve_rgb_accept_journal(sk, ASSETS_ACCEPT_JOURNAL)
+        .await
+        .map_err(|op| RgbPersistenceError::RetrieveAcceptJournal(op.to_string()))?;
+
+    Ok(journal)
+}
+
 pub async fn retrieve_stock_account(
     sk: &str,
 ) -> Result<(Stock, RgbAccountV1), RgbPersistenceError> {
@@ -169,7 +261,10 @@ pub async fn retrieve_stock_account_transfers(
 pub async fn store_stock(sk: &str, stock: Stock) -> Result<(), RgbPersistenceError> {
     store_rgb_stock(sk, ASSETS_STOCK, &stock)
         .await
-        .map_err(|op| RgbPersistenceError::WriteStock(op.to_string()))
+        .map_err(|op| RgbPersistenceError::WriteStock(op.to_string()))?;
+    // Transfers of the stock are indexed in the background
+    queue_reindex(sk).await;
+    Ok(())
 }
 
 pub async fn store_transfers(
@@ -205,6 +300,60 @@ pub async fn store_bids(sk: &str, rgb_bids: RgbBids) -> Result<(), RgbPersistenc
         .map_err(|op| RgbPersistenceError::WriteRgbBids(op.to_string()))
 }
 
+pub async fn store_ignored_allocations(
+    sk: &str,
+    ignored: RgbIgnoredAllocations,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_ignored_allocations(sk, ASSETS_IGNORED_ALLOCATIONS, &ignored)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteIgnoredAllocations(op.to_string()))
+}
+
+pub async fn store_revoked_seals(
+    sk: &str,
+    revoked: RgbRevokedSeals,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_revoked_seals(sk, ASSETS_REVOKED_SEALS, &revoked)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteRevokedSeals(op.to_string()))
+}
+
+pub async fn store_unconfirmed_transfers(
+    sk: &str,
+    unconfirmed: RgbUnconfirmedTransfers,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_unconfirmed_transfers(sk, ASSETS_UNCONFIRMED_TRANSFERS, &unconfirmed)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteUnconfirmedTransfers(op.to_string()))
+}
+
+pub async fn store_broadcasts(
+    sk: &str,
+    broadcasts: RgbBroadcasts,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_broadcasts(sk, ASSETS_BROADCASTS, &broadcasts)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteBroadcasts(op.to_string()))
+}
+
+pub async fn store_counterparties(
+    sk: &str,
+    counterparties: RgbCounterparties,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_counterparties(sk, ASSETS_COUNTERPARTIES, &counterparties)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteCounterparties(op.to_string()))
+}
+
+pub async fn store_accept_journal(
+    sk: &str,
+    journal: RgbAcceptJournal,
+) -> Result<(), RgbPersistenceError> {
+    store_rgb_accept_journal(sk, ASSETS_ACCEPT_JOURNAL, &journal)
+        .await
+        .map_err(|op| RgbPersistenceError::WriteAcceptJournal(op.to_string()))
+}
+
 pub async fn store_swap_bids(
     sk: &str,
     name: &str,
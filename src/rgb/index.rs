@@ -0,0 +1,304 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rgbstd::{
+    contract::Operation,
+    persistence::{Inventory, Stock},
+    validation::{AnchoredBundle, ConsignmentApi},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use zeroize::Zeroizing;
+
+use crate::{
+    cache::LruCache,
+    constants::{get_network, with_network},
+    rgb::{
+        constants::{
+            RGB_INDEX_CACHE_CAPACITY, RGB_REINDEX_MAX_STARTS, RGB_REINDEX_MIN_INTERVAL,
+            RGB_REINDEX_RATE_WINDOW,
+        },
+        fs::{retrieve_stock, RgbPersistenceError},
+    },
+    warn,
+};
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum IndexError {
+    /// I/O or connectivity error. {0}
+    IO(RgbPersistenceError),
+    /// The stock cannot be indexed. {0}
+    Stock(String),
+}
+
+/// Operation of the transfer history of a contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransfer {
+    /// Operation ID of the state transition
+    pub opid: String,
+    /// Witness transaction anchoring the transition
+    pub txid: String,
+}
+
+/// Transfer history of the contracts of a stock.
+///
+/// The index is derived: the stock stays authoritative, and the index is
+/// rebuilt from it by [`reindex`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransferIndex {
+    /// Transfers by contract ID
+    pub contracts: BTreeMap<String, Vec<IndexedTransfer>>,
+    /// Timestamp of the stock snapshot the index was built from
+    pub built_at: i64,
+}
+
+// Wallet keys are never kept: rebuilds get them from the request which
+// starts them
+#[derive(Clone, Default)]
+struct IndexSlot {
+    index: Option<Arc<TransferIndex>>,
+    rebuilding: bool,
+    // Rebuild requested while the index was rebuilt, or by `reindex_all`
+    pending: bool,
+    last_started: i64,
+}
+
+// (network, blake3 hash of the nostr sk)
+type IndexKey = (String, String);
+
+fn index_key(network: &str, sk: &str) -> IndexKey {
+    let hash = blake3::hash(sk.as_bytes()).to_hex().to_string();
+    (network.to_owned(), hash)
+}
+
+// Indexes of the most recently used wallets, swapped in whole once rebuilt
+static INDEXES: Lazy<RwLock<LruCache<IndexKey, IndexSlot>>> =
+    Lazy::new(|| RwLock::new(LruCache::new("transfer_indexes", RGB_INDEX_CACHE_CAPACITY)));
+
+// Start times of the rebuilds of the last rate window, of all the wallets
+static REINDEX_STARTS: Lazy<Mutex<VecDeque<i64>>> = Lazy::new(Default::default);
+
+// Count a rebuild start, unless the rate limit is reached
+fn allow_reindex(now: i64) -> bool {
+    let mut starts = REINDEX_STARTS.lock().expect("reindex rate poisoned");
+    while starts
+        .front()
+        .map_or(false, |started| now - started >= RGB_REINDEX_RATE_WINDOW)
+    {
+        starts.pop_front();
+    }
+    if starts.len() >= RGB_REINDEX_MAX_STARTS {
+        return false;
+    }
+    starts.push_back(now);
+    true
+}
+
+/// Build the transfer index of the stock.
+///
+/// Yields between contracts, so indexing a large stock doesn't starve the
+/// requests sharing the executor.
+pub async fn build_index(stock: &mut Stock) -> Result<TransferIndex, IndexError> {
+    let built_at = Utc::now().timestamp();
+    let contract_ids = stock
+        .contract_ids()
+        .map_err(|err| IndexError::Stock(err.to_string()))?;
+
+    let mut contracts = BTreeMap::new();
+    for contract_id in contract_ids {
+        let history = stock
+            .export_contract(contract_id)
+            .map_err(|err| IndexError::Stock(err.to_string()))?
+            .unbindle();
+
+        let mut transfers = vec![];
+        for AnchoredBundle { anchor, bundle } in history.bundles.iter() {
+            let transitions = history
+                .known_transitions_by_bundle_id(bundle.bundle_id())
+                .unwrap_or_default();
+            for transition in transitions {
+                transfers.push(IndexedTransfer {
+                    opid: transition.id().to_string(),
+                    txid: anchor.txid.to_string(),
+                });
+            }
+        }
+        contracts.insert(contract_id.to_string(), transfers);
+
+        tokio::task::yield_now().await;
+    }
+
+    Ok(TransferIndex {
+        contracts,
+        built_at,
+    })
+}
+
+/// Current transfer index of the wallet.
+///
+/// Reads never wait for a running rebuild: they get the previous index until
+/// the fresh one is swapped in. Only the first read builds the index, and
+/// reads start the rebuilds requested by [`reindex_all`].
+pub async fn transfer_index(sk: &str) -> Result<Arc<TransferIndex>, IndexError> {
+    let network = get_network().await;
+    let key = index_key(&network, sk);
+    let cached = INDEXES
+        .read()
+        .await
+        .get(&key)
+        .and_then(|slot| Some((slot.index.clone()?, slot.pending && !slot.rebuilding)));
+    if let Some((index, pending)) = cached {
+        if pending {
+            start_rebuild(&network, key, sk).await;
+        }
+        return Ok(index);
+    }
+
+    let mut stock = retrieve_stock(sk).await?;
+    let index = Arc::new(build_index(&mut stock).await?);
+
+    let mut indexes = INDEXES.write().await;
+    let mut slot = indexes.get(&key).cloned().unwrap_or_default();
+    let index = slot.index.get_or_insert(index).clone();
+    indexes.insert(key, slot);
+    Ok(index)
+}
+
+/// Queue the rebuild of the transfer index of the wallet, once its stock is
+/// stored.
+///
+/// Only wallets with an index in memory are rebuilt (the others are indexed
+/// on their next read). Stocks stored during a rebuild are indexed by a single
+/// rebuild once it's done, and reads get the previous index meanwhile.
+pub async fn queue_reindex(sk: &str) {
+    let network = get_network().await;
+    let key = index_key(&network, sk);
+    {
+        let mut indexes = INDEXES.write().await;
+        let mut slot = match indexes.get(&key) {
+            Some(slot) if slot.index.is_some() => slot.clone(),
+            _ => return,
+        };
+        if slot.rebuilding {
+            slot.pending = true;
+            indexes.insert(key, slot);
+            return;
+        }
+    }
+    start_rebuild(&network, key, sk).await;
+}
+
+/// Rebuild the transfer index of the wallet in the background.
+///
+/// Rebuilds are throttled: returns `false` (without rebuilding) while another
+/// rebuild is running, when the last one started less than
+/// [`RGB_REINDEX_MIN_INTERVAL`] seconds ago, or when
+/// [`RGB_REINDEX_MAX_STARTS`] rebuilds (of any wallet) started in the last
+/// [`RGB_REINDEX_RATE_WINDOW`] seconds.
+pub async fn reindex(sk: &str) -> bool {
+    let network = get_network().await;
+    let key = index_key(&network, sk);
+    let now = Utc::now().timestamp();
+    {
+        let mut indexes = INDEXES.write().await;
+        let mut slot = indexes.get(&key).cloned().unwrap_or_default();
+        if slot.rebuilding || now - slot.last_started < RGB_REINDEX_MIN_INTERVAL {
+            return false;
+        }
+        if !allow_reindex(now) {
+            return false;
+        }
+        slot.last_started = now;
+        indexes.insert(key.clone(), slot);
+    }
+
+    start_rebuild(&network, key, sk).await
+}
+
+/// Request the rebuild of the transfer index of every wallet indexed by this
+/// process. Each index is rebuilt on the next read of its wallet (see
+/// [`transfer_index`]), which knows the wallet key.
+pub async fn reindex_all() -> usize {
+    let mut indexes = INDEXES.write().await;
+    let mut requested = 0;
+    for slot in indexes.values_mut() {
+        if !slot.pending {
+            slot.pending = true;
+            requested += 1;
+        }
+    }
+    requested
+}
+
+// Rebuild the index in the background, unless already rebuilding, and again
+// while rebuilds are requested meanwhile. Evicted slots stop the rebuilds:
+// the next read builds the index again
+async fn start_rebuild(network: &str, key: IndexKey, sk: &str) -> bool {
+    {
+        let mut indexes = INDEXES.write().await;
+        let mut slot = match indexes.get(&key) {
+            Some(slot) if !slot.rebuilding => slot.clone(),
+            _ => return false,
+        };
+        slot.rebuilding = true;
+        slot.pending = false;
+        indexes.insert(key.clone(), slot);
+    }
+
+    let network = network.to_owned();
+    let sk = Zeroizing::new(sk.to_owned());
+    spawn_reindex(async move {
+        loop {
+            let rebuild = async {
+                let mut stock = retrieve_stock(&sk).await?;
+                build_index(&mut stock).await
+            };
+            let index = match with_network(&network, rebuild).await {
+                Ok(Ok(index)) => Some(Arc::new(index)),
+                Ok(Err(err)) => {
+                    warn!(format!("Cannot rebuild the transfer index: {err}"));
+                    None
+                }
+                Err(err) => {
+                    warn!(format!("Cannot rebuild the transfer index: {err}"));
+                    None
+                }
+            };
+
+            let mut indexes = INDEXES.write().await;
+            let mut slot = match indexes.get(&key).cloned() {
+                Some(slot) => slot,
+                None => return,
+            };
+            if let Some(index) = index {
+                slot.index = Some(index);
+            }
+            let again = slot.pending;
+            slot.pending = false;
+            slot.rebuilding = again;
+            indexes.insert(key.clone(), slot);
+            if !again {
+                return;
+            }
+        }
+    });
+
+    true
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_reindex(task: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(task);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn_reindex(task: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(task);
+}
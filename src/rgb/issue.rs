@@ -1,4 +1,5 @@
 use amplify::{
+    ascii::AsciiString,
     confinement::{Confined, SmallBlob},
     Wrapper,
 };
@@ -15,7 +16,7 @@ use rgbstd::{
     persistence::{Inventory, Stash, Stock},
     resolvers::ResolveHeight,
     stl::{
-        Amount, Attachment, ContractData, DivisibleAssetSpec, MediaType, Precision,
+        Amount, Attachment, ContractData, DivisibleAssetSpec, MediaRegName, MediaType, Precision,
         RicardianContract, Timestamp,
     },
     validation::{Failure, ResolveTx},
@@ -25,6 +26,9 @@ use strict_types::encoding::TypeName;
 
 use crate::structs::IssueMediaRequest;
 
+/// Media type of the terms informed as text
+pub const TERMS_MEDIA_TYPE: &str = "text/plain";
+
 #[derive(Clone, PartialEq, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum IssueContractError {
@@ -36,6 +40,8 @@ pub enum IssueContractError {
     ContractInvalid(String, Vec<Failure>),
     /// The contract {0} cannot be imported (reason: {1})
     NoImport(String, String),
+    /// The contract interface {0} does not support terms
+    NoTermsSupport(String),
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -49,6 +55,7 @@ pub fn issue_contract<T>(
     seal: &str,
     network: &str,
     meta: Option<IssueMediaRequest>,
+    terms: Option<Attachment>,
     resolver: &mut T,
     stock: &mut Stock,
 ) -> Result<Contract, IssueContractError>
@@ -64,8 +71,18 @@ where
         .map_err(|_| IssueContractError::Forge(BuilderError::InterfaceMismatch))?;
 
     let contract_issued = match iface.name.as_str() {
-        "RGB20" => {
-            issue_fungible_asset(ticker, name, description, precision, supply, seal, network)
+        "RGB20" => issue_fungible_asset(
+            ticker,
+            name,
+            description,
+            precision,
+            supply,
+            seal,
+            network,
+            terms,
+        ),
+        "RGB21" if terms.is_some() => {
+            return Err(IssueContractError::NoTermsSupport(iface.name.to_string()))
         }
         "RGB21" => issue_uda_asset(
             ticker,
@@ -100,7 +117,39 @@ where
     Ok(resp)
 }
 
+/// Attachment committing to the terms document, if the media type is valid
+/// (`type/subtype`, with the characters of RFC 6838 names).
+pub fn terms_attachment(media_type: &str, digest: [u8; 32]) -> Option<Attachment> {
+    let valid_name = |name: &str| {
+        !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    // Names are owned by the media type, instead of `MediaType::with` static
+    // strings, so issuing never leaks the request
+    let reg_name = |name: &str| {
+        AsciiString::from_ascii(name.to_owned())
+            .ok()
+            .and_then(|name| Confined::try_from(name).ok())
+            .map(MediaRegName::from_inner)
+    };
+    match media_type.split_once('/') {
+        Some((ty, subtype)) if valid_name(ty) && valid_name(subtype) => Some(Attachment {
+            ty: MediaType {
+                ty: reg_name(ty)?,
+                subtype: Some(reg_name(subtype)?),
+                charset: None,
+            },
+            digest,
+        }),
+        _ => None,
+    }
+}
+
 /// RGB20 interface
+#[allow(clippy::too_many_arguments)]
 fn issue_fungible_asset(
     ticker: &str,
     name: &str,
@@ -109,6 +158,7 @@ fn issue_fungible_asset(
     supply: u64,
     seal: &str,
     network: &str,
+    terms_media: Option<Attachment>,
 ) -> Result<Contract, IssueError> {
     let iface = rgb20();
     let schema = nia_schema();
@@ -122,7 +172,11 @@ fn issue_fungible_asset(
     let precision = Precision::try_from(precision).expect("invalid precision");
     let spec = DivisibleAssetSpec::new(ticker, name, precision);
     let terms = RicardianContract::from_str(description).expect("invalid contract text");
-    let contract_data = ContractData { terms, media: None };
+    // Only the terms digest goes on-chain, the document lives in storage
+    let contract_data = ContractData {
+        terms,
+        media: terms_media,
+    };
 
     // Issuer State
     let seal = ExplicitSeal::<Txid>::from_str(seal).expect("invalid seal definition");
@@ -0,0 +1,86 @@
+use crate::structs::{AllocationValue, MerkleStep};
+
+const LEAF_TAG: &[u8] = b"bitmask:allocation:leaf";
+const NODE_TAG: &[u8] = b"bitmask:allocation:node";
+
+/// Leaf committing to an allocation (contract id, anchored outpoint and value)
+pub fn allocation_leaf(contract_id: &str, utxo: &str, value: &AllocationValue) -> [u8; 32] {
+    let value = serde_json::to_vec(value).unwrap_or_default();
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(LEAF_TAG);
+    for data in [contract_id.as_bytes(), utxo.as_bytes(), &value] {
+        hasher.update(&(data.len() as u64).to_le_bytes());
+        hasher.update(data);
+    }
+    *hasher.finalize().as_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(NODE_TAG);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Root of the leaves. Leaves are sorted, so the root only depends on the set
+/// of allocations, and levels with an odd number of nodes promote the last
+/// one. Without leaves, the root is all zeros
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    level.sort();
+    if level.is_empty() {
+        return [0; 32];
+    }
+
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Inclusion path of the leaf, if it is one of the leaves
+pub fn merkle_path(leaves: &[[u8; 32]], leaf: &[u8; 32]) -> Option<Vec<MerkleStep>> {
+    let mut level = leaves.to_vec();
+    level.sort();
+    let mut index = level.iter().position(|current| current == leaf)?;
+
+    let mut path = vec![];
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            path.push(MerkleStep {
+                hash: hex::encode(level[sibling]),
+                left: sibling < index,
+            });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(path)
+}
+
+/// Root obtained folding the leaf with its inclusion path, if the path is
+/// well formed
+pub fn merkle_fold(leaf: [u8; 32], path: &[MerkleStep]) -> Option<[u8; 32]> {
+    path.iter().try_fold(leaf, |current, step| {
+        let sibling: [u8; 32] = hex::decode(&step.hash).ok()?.try_into().ok()?;
+        if step.left {
+            Some(node(&sibling, &current))
+        } else {
+            Some(node(&current, &sibling))
+        }
+    })
+}
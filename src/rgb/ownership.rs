@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use bitcoin_30::{
+    bip32::{DerivationPath, ExtendedPrivKey},
+    hashes::{sha256, Hash},
+    key::TapTweak,
+    secp256k1::{schnorr::Signature, KeyPair, Message, Secp256k1, XOnlyPublicKey},
+    taproot::TaprootBuilder,
+    ScriptBuf,
+};
+use bp::TapScript;
+use commit_verify::CommitVerify;
+use rgb::{DeriveInfo, TerminalPath};
+
+use crate::structs::SecretString;
+
+const OWNERSHIP_TAG: &str = "bitmask:ownership";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OwnershipError {
+    /// Descriptor has no extended private key
+    WrongDescriptor,
+    /// Key of the terminal {0} cannot be derived. {1}
+    WrongDerivation(String, String),
+    /// Tapret commitment of the terminal {0} is invalid
+    WrongTapret(String),
+    /// Outpoint is not locked by a taproot output (key-path spendable)
+    NoTaproot,
+    /// Ownership signature is invalid
+    WrongSignature,
+}
+
+/// Message signed to prove the control of an outpoint.
+///
+/// The challenge is chosen by the verifier, so signatures made for a
+/// previous proof (or by another party) cannot be replayed.
+pub fn ownership_message(challenge: &str, contract_id: &str, utxo: &str) -> Message {
+    let tag = sha256::Hash::hash(OWNERSHIP_TAG.as_bytes());
+    let mut data = [tag.as_ref(), tag.as_ref()].concat();
+    for field in [challenge, contract_id, utxo] {
+        data.extend((field.len() as u64).to_le_bytes());
+        data.extend(field.as_bytes());
+    }
+
+    Message::from_slice(sha256::Hash::hash(&data).as_ref())
+        .expect("sha256 hashes are valid messages")
+}
+
+/// Sign the message with the key of the wallet output.
+///
+/// The key is derived at the output terminal from the xprv descriptor (e.g.
+/// `rgb_assets_descriptor_xprv`) and, for outputs hosting a tapret
+/// commitment, tweaked with it: the signature checks against the taproot
+/// output key, the key spending the outpoint.
+pub fn sign_ownership(
+    descriptor: &SecretString,
+    derivation: &DeriveInfo,
+    message: &Message,
+) -> Result<String, OwnershipError> {
+    let secp = Secp256k1::new();
+    let TerminalPath { app, index } = derivation.terminal;
+    let terminal = format!("/{app}/{index}");
+
+    let xprv = descriptor_xprv(descriptor)?;
+    let path = DerivationPath::from_str(&format!("m{terminal}"))
+        .map_err(|err| OwnershipError::WrongDerivation(terminal.clone(), err.to_string()))?;
+    let xprv = xprv
+        .derive_priv(&secp, &path)
+        .map_err(|err| OwnershipError::WrongDerivation(terminal.clone(), err.to_string()))?;
+
+    let keypair = KeyPair::from_secret_key(&secp, &xprv.private_key);
+    let merkle_root = match &derivation.tweak {
+        Some(tapret) => {
+            let internal_key = keypair.x_only_public_key().0;
+            let leaf = ScriptBuf::from_bytes(TapScript::commit(tapret).to_vec());
+            let merkle_root = TaprootBuilder::with_capacity(1)
+                .add_leaf(0, leaf)
+                .ok()
+                .and_then(|builder| builder.finalize(&secp, internal_key).ok())
+                .and_then(|spend_info| spend_info.merkle_root())
+                .ok_or(OwnershipError::WrongTapret(terminal))?;
+            Some(merkle_root)
+        }
+        None => None,
+    };
+
+    let output_keypair = keypair.tap_tweak(&secp, merkle_root).to_inner();
+    Ok(secp
+        .sign_schnorr_no_aux_rand(message, &output_keypair)
+        .to_string())
+}
+
+/// Verify the signature of the message against the taproot output key of the
+/// script pubkey locking the outpoint.
+pub fn verify_ownership(
+    script_pubkey: &[u8],
+    sig: &str,
+    message: &Message,
+) -> Result<(), OwnershipError> {
+    let script_pubkey = ScriptBuf::from_bytes(script_pubkey.to_vec());
+    if !script_pubkey.is_v1_p2tr() {
+        return Err(OwnershipError::NoTaproot);
+    }
+    let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..34])
+        .map_err(|_| OwnershipError::NoTaproot)?;
+    let sig = Signature::from_str(sig).map_err(|_| OwnershipError::WrongSignature)?;
+
+    Secp256k1::verification_only()
+        .verify_schnorr(&sig, message, &output_key)
+        .map_err(|_| OwnershipError::WrongSignature)
+}
+
+// Account xprv of descriptors like `tr([fingerprint/path]xprv/20/*)`
+fn descriptor_xprv(descriptor: &SecretString) -> Result<ExtendedPrivKey, OwnershipError> {
+    let key = descriptor
+        .0
+        .trim()
+        .trim_start_matches("tr(")
+        .trim_end_matches(')');
+    let key = key.rsplit(']').next().unwrap_or(key);
+    let xprv = key.split('/').next().unwrap_or_default();
+
+    ExtendedPrivKey::from_str(xprv).map_err(|_| OwnershipError::WrongDescriptor)
+}
@@ -17,7 +17,7 @@ use strict_encoding::tn;
 
 use crate::{
     bitcoin::get_swap_new_address,
-    constants::{get_marketplace_fee_percentage, NETWORK},
+    constants::{current_network, get_marketplace_fee_percentage},
     structs::{
         AllocationDetail, AllocationValue, AssetType, FullRgbTransferRequest, PsbtFeeRequest,
         PsbtInputRequest, PsbtSigHashRequest, RgbBidRequest, RgbOfferRequest, SecretString,
@@ -29,13 +29,17 @@ use crate::rgb::{
     constants::{BITCOIN_DEFAULT_FETCH_LIMIT, RGB_DEFAULT_FETCH_LIMIT},
     contract::{export_boilerplate, export_contract},
     fs::RgbPersistenceError,
+    held_outpoints,
     prefetch::prefetch_resolver_txs,
     prefetch::{
         prefetch_resolver_allocations, prefetch_resolver_user_utxo_status, prefetch_resolver_utxos,
     },
-    psbt::estimate_fee,
+    psbt::{estimate_fee, EstimateFeeError},
     resolvers::ExplorerResolver,
-    structs::{AddressAmount, ContractAmount, RgbExtractTransfer},
+    structs::{
+        AddressAmount, ContractAmount, RgbExtractTransfer, RgbIgnoredAllocations,
+        RgbUnconfirmedTransfers,
+    },
     swap::RgbSwapStrategy,
     swap::{get_public_offer, RgbBid, RgbOfferSwap},
     transfer::extract_transfer,
@@ -52,6 +56,8 @@ pub async fn prebuild_transfer_asset(
     request: FullRgbTransferRequest,
     stock: &mut Stock,
     rgb_wallet: &mut RgbWallet,
+    ignored: &RgbIgnoredAllocations,
+    unconfirmed: &RgbUnconfirmedTransfers,
     resolver: &mut ExplorerResolver,
 ) -> Result<
     (
@@ -105,6 +111,8 @@ pub async fn prebuild_transfer_asset(
         change_terminal,
         fee,
         mut bitcoin_changes,
+        from_seals,
+        ..
     } = request;
 
     let wildcard_terminal = "/*/*";
@@ -154,11 +162,17 @@ pub async fn prebuild_transfer_asset(
 
     let contract = export_contract(contract_id, stock, resolver, &mut Some(rgb_wallet.clone()))
         .map_err(TransferError::Export)?;
+    let held = held_outpoints(contract_id, unconfirmed, resolver, stock).await?;
 
     let allocations: Vec<AllocationDetail> = contract
         .allocations
         .into_iter()
         .filter(|x| x.is_mine && !x.is_spent)
+        // Pending allocations are not spendable by the confirmation policy
+        .filter(|x| !held.contains(&x.utxo))
+        .filter(|x| from_seals.is_empty() || from_seals.contains(&x.utxo))
+        // Ignored allocations are only spent when selected explicitly
+        .filter(|x| from_seals.contains(&x.utxo) || !ignored.outpoints.contains(&x.utxo))
         .collect();
 
     let asset_total: u64 = allocations
@@ -173,7 +187,14 @@ pub async fn prebuild_transfer_asset(
 
     if asset_total < target_amount {
         let mut errors = BTreeMap::new();
-        errors.insert("rgb_invoice".to_string(), "insufficient state".to_string());
+        if from_seals.is_empty() {
+            errors.insert("rgb_invoice".to_string(), "insufficient state".to_string());
+        } else {
+            errors.insert(
+                "from_seals".to_string(),
+                "insufficient state in selected seals".to_string(),
+            );
+        }
         return Err(TransferError::Validation(errors));
     }
 
@@ -307,7 +328,7 @@ pub async fn prebuild_transfer_asset(
                 }
             }
 
-            let change_value = bitcoin_total - total_spendable;
+            let change_value = bitcoin_total.saturating_sub(total_spendable);
             (change_value, fee_value)
         }
         PsbtFeeRequest::FeeRate(fee_rate) => {
@@ -346,30 +367,61 @@ pub async fn prebuild_transfer_asset(
                 .collect();
             prefetch_resolver_txs(txids, resolver).await;
 
-            let (change_value, fee) = estimate_fee(
+            let mut estimate = estimate_fee(
                 assets_inputs.clone(),
                 bitcoin_inputs.clone(),
                 bitcoin_changes.clone(),
                 fee_rate,
                 rgb_wallet,
                 Some(rnd_amount),
-                Some(change_terminal),
+                Some(change_terminal.clone()),
                 resolver,
-            )
-            .map_err(TransferError::Estimate)?;
+            );
+            if let Err(EstimateFeeError::Inflation { .. }) = estimate {
+                estimate = estimate_fee(
+                    assets_inputs.clone(),
+                    bitcoin_inputs.clone(),
+                    bitcoin_changes.clone(),
+                    fee_rate,
+                    rgb_wallet,
+                    Some(DUST_LIMIT_SATOSHI),
+                    Some(change_terminal),
+                    resolver,
+                );
+            }
+
+            let (change_value, fee) = estimate.map_err(|err| match err {
+                EstimateFeeError::Inflation { input, output } => TransferError::NoChangeFunds {
+                    required: output,
+                    available: input,
+                    shortfall: output - input,
+                },
+                _ => TransferError::Estimate(err),
+            })?;
 
             (change_value, fee)
         }
     };
 
-    let total_spendable = fee_value + rnd_amount + total_bitcoin_spend;
+    // The change seal needs its own output. When the wallet cannot afford the
+    // randomized change amount, the smallest (non-dust) output is created
+    // instead, and it takes all the remaining bitcoin (as it does any dust
+    // bitcoin change).
+    let change_amount = if bitcoin_total >= fee_value + rnd_amount + total_bitcoin_spend {
+        rnd_amount
+    } else {
+        DUST_LIMIT_SATOSHI
+    };
+
+    let total_spendable = fee_value + change_amount + total_bitcoin_spend;
     if bitcoin_total < total_spendable {
-        return Err(TransferError::Inflation {
-            input: bitcoin_total,
-            output: total_spendable,
+        return Err(TransferError::NoChangeFunds {
+            required: total_spendable,
+            available: bitcoin_total,
+            shortfall: total_spendable - bitcoin_total,
         });
-    } else if change_value > 0 {
-        let network = NETWORK.read().await.to_string();
+    } else if change_amount == rnd_amount && change_value >= DUST_LIMIT_SATOSHI {
+        let network = current_network().await.to_string();
         let network = Network::from_str(&network)
             .map_err(|err| TransferError::WrongNetwork(err.to_string()))?;
 
@@ -391,6 +443,7 @@ pub async fn prebuild_seller_swap(
     options: RgbOfferOptions,
     stock: &mut Stock,
     rgb_wallet: &mut RgbWallet,
+    unconfirmed: &RgbUnconfirmedTransfers,
     resolver: &mut ExplorerResolver,
 ) -> Result<
     (
@@ -484,11 +537,16 @@ pub async fn prebuild_seller_swap(
 
     let contract = export_contract(contract_id, stock, resolver, &mut Some(rgb_wallet.clone()))
         .map_err(RgbSwapError::Export)?;
+    let held = held_outpoints(contract_id, unconfirmed, resolver, stock)
+        .await
+        .map_err(RgbSwapError::Transfer)?;
 
     let allocations: Vec<AllocationDetail> = contract
         .allocations
         .into_iter()
         .filter(|x| x.is_mine && !x.is_spent)
+        // Pending allocations are not spendable by the confirmation policy
+        .filter(|x| !held.contains(&x.utxo))
         .collect();
 
     let asset_total: u64 = allocations
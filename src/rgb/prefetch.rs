@@ -21,16 +21,18 @@ use bitcoin_scripts::{
     PubkeyScript,
 };
 use bp::{LockTime, Outpoint, SeqNo, Tx, TxIn, TxOut, TxVer, Txid as BpTxid, VarIntArray, Witness};
+use futures::future::join_all;
 use reqwest::StatusCode;
 use rgb::{DeriveInfo, MiningStatus, RgbWallet, SpkDescriptor, Utxo};
 use rgbstd::containers::Contract;
 use rgbstd::interface::ContractIface;
 use std::collections::HashMap;
 use std::f32::consts::E;
-use std::{collections::BTreeMap, str::FromStr};
+use std::{collections::BTreeMap, ops::Range, str::FromStr};
 use strict_encoding::StrictDeserialize;
 use wallet::onchain::ResolveTx;
 
+use crate::constants::get_explorer_batch_size;
 use crate::rgb::resolvers::ExploreClientExtError;
 use crate::rgb::structs::MediaMetadata;
 
@@ -79,6 +81,15 @@ pub async fn prefetch_resolver_utxos(
 ) {
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn prefetch_resolver_utxos_range(
+    iface_index: u32,
+    wallet: &mut RgbWallet,
+    explorer: &mut ExplorerResolver,
+    range: Range<u32>,
+) {
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub async fn prefetch_resolver_txs(txids: Vec<Txid>, explorer: &mut ExplorerResolver) {}
 
@@ -332,67 +343,65 @@ pub async fn prefetch_resolver_utxos(
     explorer: &mut ExplorerResolver,
     limit: Option<u32>,
 ) {
-    let esplora_client: EsploraBlockchain =
-        EsploraBlockchain::new(&explorer.explorer_url, 1).with_concurrency(6);
-
     let index = 0;
     let mut step = 100;
     if let Some(limit) = limit {
         step = limit;
     }
 
-    let scripts = wallet.descr.derive(iface_index, index..step);
+    prefetch_resolver_utxos_range(iface_index, wallet, explorer, index..step).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn prefetch_resolver_utxos_range(
+    iface_index: u32,
+    wallet: &mut RgbWallet,
+    explorer: &mut ExplorerResolver,
+    range: Range<u32>,
+) {
+    let batch_size = get_explorer_batch_size();
+    let esplora_client: EsploraBlockchain = EsploraBlockchain::new(&explorer.explorer_url, 1)
+        .with_concurrency(batch_size.min(u8::MAX.into()) as u8);
+
+    let scripts: Vec<_> = wallet
+        .descr
+        .derive(iface_index, range)
+        .into_iter()
+        .collect();
     let mut new_utxos = bset![];
-    for (derive, script) in scripts {
-        // TODO: Remove that after bitcoin v.30 full compatibility
-        let script_compatible =
-            Script::from_str(&script.as_script().to_hex_string()).expect("invalid script");
+    for batch in scripts.chunks(batch_size) {
+        let histories = join_all(
+            batch
+                .iter()
+                .map(|(_, script)| scripthash_history(&esplora_client, script)),
+        )
+        .await;
+
+        for ((derive, _), (script_compatible, related_txs)) in batch.iter().zip(histories) {
+            related_txs.into_iter().for_each(|tx| {
+                for (index, vout) in tx.vout.iter().enumerate() {
+                    if vout.scriptpubkey != script_compatible {
+                        continue;
+                    }
 
-        let mut related_txs = esplora_client
-            .scripthash_txs(&script_compatible, None)
-            .await
-            .expect("Service unavailable");
-        let n_confirmed = related_txs.iter().filter(|tx| tx.status.confirmed).count();
-        // esplora pages on 25 confirmed transactions. If there are 25 or more we
-        // keep requesting to see if there's more.
-        if n_confirmed >= 25 {
-            loop {
-                let new_related_txs = esplora_client
-                    .scripthash_txs(&script_compatible, Some(related_txs.last().unwrap().txid))
-                    .await
-                    .expect("Service unavailable");
-                let n = new_related_txs.len();
-                related_txs.extend(new_related_txs);
-                // we've reached the end
-                if n < 25 {
-                    break;
+                    let status = match tx.status.block_height {
+                        Some(height) => MiningStatus::Blockchain(height),
+                        _ => MiningStatus::Mempool,
+                    };
+                    let outpoint = Outpoint::new(
+                        bp::Txid::from_str(&tx.txid.to_hex()).expect("invalid outpoint parse"),
+                        index as u32,
+                    );
+                    let new_utxo = Utxo {
+                        outpoint,
+                        status,
+                        amount: vout.value,
+                        derivation: derive.clone(),
+                    };
+                    new_utxos.insert(new_utxo);
                 }
-            }
+            });
         }
-
-        related_txs.into_iter().for_each(|tx| {
-            for (index, vout) in tx.vout.iter().enumerate() {
-                if vout.scriptpubkey != script_compatible {
-                    continue;
-                }
-
-                let status = match tx.status.block_height {
-                    Some(height) => MiningStatus::Blockchain(height),
-                    _ => MiningStatus::Mempool,
-                };
-                let outpoint = Outpoint::new(
-                    bp::Txid::from_str(&tx.txid.to_hex()).expect("invalid outpoint parse"),
-                    index as u32,
-                );
-                let new_utxo = Utxo {
-                    outpoint,
-                    status,
-                    amount: vout.value,
-                    derivation: derive.clone(),
-                };
-                new_utxos.insert(new_utxo);
-            }
-        });
     }
 
     for mut new_utxo in new_utxos {
@@ -417,6 +426,41 @@ pub async fn prefetch_resolver_utxos(
     }
 }
 
+// Full transaction history of a script (following esplora pagination)
+#[cfg(target_arch = "wasm32")]
+async fn scripthash_history(
+    esplora_client: &EsploraBlockchain,
+    script: &ScriptBuf,
+) -> (Script, Vec<ExplorerTX>) {
+    // TODO: Remove that after bitcoin v.30 full compatibility
+    let script_compatible =
+        Script::from_str(&script.as_script().to_hex_string()).expect("invalid script");
+
+    let mut related_txs = esplora_client
+        .scripthash_txs(&script_compatible, None)
+        .await
+        .expect("Service unavailable");
+    let n_confirmed = related_txs.iter().filter(|tx| tx.status.confirmed).count();
+    // esplora pages on 25 confirmed transactions. If there are 25 or more we
+    // keep requesting to see if there's more.
+    if n_confirmed >= 25 {
+        loop {
+            let new_related_txs = esplora_client
+                .scripthash_txs(&script_compatible, Some(related_txs.last().unwrap().txid))
+                .await
+                .expect("Service unavailable");
+            let n = new_related_txs.len();
+            related_txs.extend(new_related_txs);
+            // we've reached the end
+            if n < 25 {
+                break;
+            }
+        }
+    }
+
+    (script_compatible, related_txs)
+}
+
 #[cfg(target_arch = "wasm32")]
 pub async fn prefetch_resolver_txs(txids: Vec<Txid>, explorer: &mut ExplorerResolver) {
     let esplora_client = EsploraBlockchain::new(&explorer.explorer_url, 1).with_concurrency(6);
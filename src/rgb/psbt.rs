@@ -1,11 +1,11 @@
-use std::{collections::BTreeSet, str::FromStr};
+use std::{cmp, collections::BTreeSet, str::FromStr};
 
 use amplify::hex::{FromHex, ToHex};
 use bdk::FeeRate;
 use bitcoin::{
-    blockdata::opcodes,
+    blockdata::{opcodes, script::Builder},
     hashes::{sha256, Hash},
-    psbt::TapTree,
+    psbt::{PartiallySignedTransaction as PsbtV0, TapTree},
     schnorr::TapTweak,
     secp256k1::SECP256K1,
     util::{
@@ -14,15 +14,16 @@ use bitcoin::{
     },
     TxIn, TxOut, Txid,
 };
-use bitcoin::{EcdsaSighashType, OutPoint, Script, XOnlyPublicKey};
+use bitcoin::{EcdsaSighashType, OutPoint, PublicKey, Script, XOnlyPublicKey};
 // TODO: Incompatible versions between RGB and Descriptor Wallet
 use bitcoin_30::{secp256k1::SECP256K1 as SECP256K1_30, ScriptBuf};
-use bitcoin_blockchain::locks::SeqNo;
+use bitcoin_blockchain::locks::{LockTime, SeqNo};
 use bitcoin_scripts::PubkeyScript;
 use bp::{dbc::tapret::TapretCommitment, Outpoint, TapScript, Vout};
 use commit_verify::{mpc::Commitment, CommitVerify};
 use miniscript_crate::{Descriptor, ForEachKey, ToPublicKey};
 use psbt::{ProprietaryKey, ProprietaryKeyType, PsbtVersion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rgb::{
     psbt::{
         DbcPsbtError, TapretKeyError, PSBT_OUT_TAPRET_COMMITMENT, PSBT_OUT_TAPRET_HOST,
@@ -39,8 +40,15 @@ use wallet::{
 
 use crate::{
     debug, info,
-    rgb::{constants::RGB_PSBT_TAPRET, structs::AddressAmount},
-    structs::{AssetType, PsbtInputRequest, PsbtSigHashRequest},
+    rgb::{
+        constants::{
+            RGB_FEE_SNIPING_JITTER_PERC, RGB_FEE_SNIPING_MAX_JITTER, RGB_MAX_DATA_OUTPUT,
+            RGB_MAX_FEE_FLOOR, RGB_MAX_FEE_PERC, RGB_MAX_OUTPUTS, RGB_PSBT_TAPRET,
+            RGB_PSBT_TIMELOCK, RGB_TIMELOCK_OUTPUT_AMOUNT, RGB_TX_VERSION,
+        },
+        structs::AddressAmount,
+    },
+    structs::{AssetType, PsbtInputRequest, PsbtLockRequest, PsbtSigHashRequest},
 };
 
 use crate::rgb::structs::AddressFormatParseError;
@@ -68,6 +76,42 @@ pub enum CreatePsbtError {
     Incomplete(String),
     /// Invalid PSBT proprietary key. '{0}'
     WrongMetadata(ProprietaryKeyError),
+    /// Invalid time-lock. '{0:?}'
+    WrongLockTime(PsbtLockRequest),
+    /// Time-lock {lock:?} is not enforced by transactions of version {version}
+    WrongTxVersion {
+        /// Requested time-lock
+        lock: PsbtLockRequest,
+
+        /// Version of the transaction
+        version: u32,
+    },
+    /// Fee is above the safety cap (fee: {fee} sats / max: {max} sats). Set max fee to proceed.
+    FeeTooHigh {
+        /// Transaction fee
+        fee: u64,
+
+        /// Maximum fee allowed
+        max: u64,
+    },
+    /// Data output is too large ({size} bytes / max: {max} bytes).
+    DataTooLarge {
+        /// Size of the data
+        size: usize,
+
+        /// Maximum size allowed
+        max: usize,
+    },
+    /// Data output requires another output to host the RGB commitment.
+    NoDataHost,
+    /// Transaction has too many outputs ({count} / max: {max}). Split the transfer into multiple transactions.
+    TooManyOutputs {
+        /// Number of outputs
+        count: usize,
+
+        /// Maximum number of outputs allowed
+        max: usize,
+    },
     /// The PSBT is invalid (Unexpected behavior).
     Inconclusive,
 }
@@ -103,6 +147,16 @@ pub fn create_psbt(
         return Err(CreatePsbtError::EmptyInputs);
     }
 
+    for lock in options
+        .lock
+        .iter()
+        .chain(options.timelock.iter().map(|(lock, _)| lock))
+    {
+        if !lock.is_valid() {
+            return Err(CreatePsbtError::WrongLockTime(*lock));
+        }
+    }
+
     let mut inputs = vec![];
 
     // Define "Universal" Descriptor
@@ -150,10 +204,29 @@ pub fn create_psbt(
         })
         .collect();
 
-    let outputs: Vec<(PubkeyScript, u64)> = bitcoin_addresses
-        .into_iter()
-        .map(|AddressAmount { address, amount }| (address.script_pubkey().into(), amount))
-        .collect();
+    // Data output goes first, so it never becomes the tapret host (the last output)
+    let mut outputs: Vec<(PubkeyScript, u64)> = vec![];
+    if let Some(data) = &options.data {
+        if data.len() > RGB_MAX_DATA_OUTPUT {
+            return Err(CreatePsbtError::DataTooLarge {
+                size: data.len(),
+                max: RGB_MAX_DATA_OUTPUT,
+            });
+        }
+        outputs.push((Script::new_op_return(data).into(), 0));
+    }
+    // Time-locked output is never the tapret host either (P2WSH)
+    let timelock_script = options.timelock.as_ref().map(|(lock, beneficiary)| {
+        Script::new_v0_p2wsh(&timelock_script(*lock, beneficiary).wscript_hash())
+    });
+    if let Some(script_pubkey) = &timelock_script {
+        outputs.push((script_pubkey.clone().into(), RGB_TIMELOCK_OUTPUT_AMOUNT));
+    }
+    outputs.extend(
+        bitcoin_addresses
+            .into_iter()
+            .map(|AddressAmount { address, amount }| (address.script_pubkey().into(), amount)),
+    );
 
     // Change Terminal Derivation
     let mut change_index = DerivationSubpath::new();
@@ -163,7 +236,10 @@ pub fn create_psbt(
             .map_err(CreatePsbtError::WrongTerminal)?;
     }
 
-    let psbt = Psbt::new(
+    let max_outputs = options.max_outputs();
+    let has_data = options.data.is_some();
+    let lock = options.lock;
+    let mut psbt = Psbt::new(
         global_descriptor,
         &inputs,
         &outputs,
@@ -172,11 +248,75 @@ pub fn create_psbt(
         tx_resolver,
         options,
     )
-    .map_err(|op| CreatePsbtError::Incomplete(op.to_string()))?;
+    .map_err(|op| match op {
+        PsbtConstructError::FeeTooHigh { fee, max } => CreatePsbtError::FeeTooHigh { fee, max },
+        _ => CreatePsbtError::Incomplete(op.to_string()),
+    })?;
+
+    if has_data && psbt.outputs.len() < 2 {
+        return Err(CreatePsbtError::NoDataHost);
+    }
+
+    // Marked, so payments check the beneficiary seal is bound to it
+    if let Some(script_pubkey) = timelock_script {
+        let script_pubkey = PubkeyScript::from(script_pubkey);
+        if let Some(output) = psbt
+            .outputs
+            .iter_mut()
+            .find(|output| output.script == script_pubkey)
+        {
+            let key = ProprietaryKey {
+                prefix: RGB_PSBT_TIMELOCK.as_bytes().to_vec(),
+                subtype: 0,
+                key: vec![],
+            };
+            output.proprietary.insert(key, vec![]);
+        }
+    }
+
+    if let Some(lock) = lock {
+        if psbt.tx_version < lock.min_tx_version() {
+            return Err(CreatePsbtError::WrongTxVersion {
+                lock,
+                version: psbt.tx_version,
+            });
+        }
+    }
+
+    if psbt.outputs.len() > max_outputs {
+        return Err(CreatePsbtError::TooManyOutputs {
+            count: psbt.outputs.len(),
+            max: max_outputs,
+        });
+    }
 
     Ok((psbt, change_index.to_string()))
 }
 
+/// Script of the time-locked output: only the beneficiary spends it, once the
+/// lock expires (BIP-65 / BIP-112).
+pub fn timelock_script(lock: PsbtLockRequest, beneficiary: &PublicKey) -> Script {
+    // Relative time-locks in units of 512 seconds (BIP-68)
+    const SEQUENCE_LOCKTIME_TYPE_FLAG: i64 = 1 << 22;
+
+    let builder = match lock {
+        PsbtLockRequest::Height(value) | PsbtLockRequest::Time(value) => Builder::new()
+            .push_int(value as i64)
+            .push_opcode(opcodes::all::OP_CLTV),
+        PsbtLockRequest::RelativeHeight(blocks) => Builder::new()
+            .push_int(blocks as i64)
+            .push_opcode(opcodes::all::OP_CSV),
+        PsbtLockRequest::RelativeTime(intervals) => Builder::new()
+            .push_int(SEQUENCE_LOCKTIME_TYPE_FLAG | intervals as i64)
+            .push_opcode(opcodes::all::OP_CSV),
+    };
+    builder
+        .push_opcode(opcodes::all::OP_DROP)
+        .push_key(beneficiary)
+        .push_opcode(opcodes::all::OP_CHECKSIG)
+        .into_script()
+}
+
 pub fn set_tapret_output(psbt: Psbt, pos: u16) -> Result<Psbt, CreatePsbtError> {
     let mut psbt = psbt;
 
@@ -319,6 +459,142 @@ pub fn save_rgb_commit(
     wallet.descr = RgbDescr::Tapret(tapret);
 }
 
+// Proprietary key prefix of the RGB transitions (global) and their consumers (inputs)
+const PSBT_RGB_PREFIX: &[u8] = b"RGB";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MergePsbtError {
+    /// At least 2 PSBTs are required to be merged
+    NotEnoughPsbts,
+    /// Input {0} is added by more than one PSBT
+    DuplicatedInput(String),
+    /// PSBTs don't spend the same transaction and can't be combined. {0}
+    WrongCombine(String),
+    /// Input {0} has a relative time-lock, which is not enforced by transactions of version {1}
+    WrongTxVersion(String, i32),
+}
+
+/// Join PSBTs made by different parties into one transaction, as the rpc
+/// method `joinpsbts` (merge disjoint inputs and outputs).
+///
+/// Inputs and outputs of the first PSBT keep their positions, the others are
+/// appended in order. Only the first RGB commitment host is kept. Because the
+/// RGB transitions are bound to the layout of the transaction, the ones
+/// already present are removed: the transfer is rebuilt from the stock over
+/// the joined PSBT by [`join_transfer_psbts`](crate::rgb::join_transfer_psbts).
+pub fn join_psbts(psbts: Vec<PsbtV0>) -> Result<PsbtV0, MergePsbtError> {
+    if psbts.len() < 2 {
+        return Err(MergePsbtError::NotEnoughPsbts);
+    }
+
+    let mut psbts = psbts.into_iter();
+    let mut joined = psbts.next().expect("at least 2 PSBTs");
+    let mut spent: BTreeSet<OutPoint> = bset![];
+    for txin in &joined.unsigned_tx.input {
+        if !spent.insert(txin.previous_output) {
+            return Err(MergePsbtError::DuplicatedInput(
+                txin.previous_output.to_string(),
+            ));
+        }
+    }
+    let mut has_host = joined.outputs.iter().any(is_tapret_host);
+
+    for other in psbts {
+        joined.version = cmp::max(joined.version, other.version);
+        joined.unsigned_tx.version =
+            cmp::max(joined.unsigned_tx.version, other.unsigned_tx.version);
+        joined.unsigned_tx.lock_time =
+            cmp::max(joined.unsigned_tx.lock_time, other.unsigned_tx.lock_time);
+
+        for (xpub, source) in other.xpub {
+            joined.xpub.entry(xpub).or_insert(source);
+        }
+        joined.proprietary.extend(other.proprietary);
+        joined.unknown.extend(other.unknown);
+
+        for (txin, input) in other.unsigned_tx.input.into_iter().zip(other.inputs) {
+            if !spent.insert(txin.previous_output) {
+                return Err(MergePsbtError::DuplicatedInput(
+                    txin.previous_output.to_string(),
+                ));
+            }
+            joined.unsigned_tx.input.push(txin);
+            joined.inputs.push(input);
+        }
+
+        for (txout, mut output) in other.unsigned_tx.output.into_iter().zip(other.outputs) {
+            if is_tapret_host(&output) {
+                if has_host {
+                    output
+                        .proprietary
+                        .retain(|key, _| key.prefix != PSBT_TAPRET_PREFIX.to_vec());
+                }
+                has_host = true;
+            }
+            joined.unsigned_tx.output.push(txout);
+            joined.outputs.push(output);
+        }
+    }
+
+    // Relative time-locks of the inputs are only enforced from version 2 (BIP-68)
+    if joined.unsigned_tx.version < PsbtLockRequest::RELATIVE_LOCK_TX_VERSION as i32 {
+        if let Some(txin) = joined
+            .unsigned_tx
+            .input
+            .iter()
+            .find(|txin| txin.sequence.is_relative_lock_time())
+        {
+            return Err(MergePsbtError::WrongTxVersion(
+                txin.previous_output.to_string(),
+                joined.unsigned_tx.version,
+            ));
+        }
+    }
+
+    // Drop the RGB transitions made for the former transactions
+    joined
+        .proprietary
+        .retain(|key, _| key.prefix != PSBT_RGB_PREFIX.to_vec());
+    for input in joined.inputs.iter_mut() {
+        input
+            .proprietary
+            .retain(|key, _| key.prefix != PSBT_RGB_PREFIX.to_vec());
+    }
+    for output in joined.outputs.iter_mut() {
+        output.proprietary.retain(|key, _| {
+            key.prefix != PSBT_TAPRET_PREFIX.to_vec() || key.subtype == PSBT_OUT_TAPRET_HOST
+        });
+    }
+
+    Ok(joined)
+}
+
+/// Combine PSBTs of the same transaction signed by different parties
+/// (merge signatures), as described by BIP 174.
+pub fn combine_psbts(psbts: Vec<PsbtV0>) -> Result<PsbtV0, MergePsbtError> {
+    if psbts.len() < 2 {
+        return Err(MergePsbtError::NotEnoughPsbts);
+    }
+
+    let mut psbts = psbts.into_iter();
+    let mut combined = psbts.next().expect("at least 2 PSBTs");
+    for other in psbts {
+        combined
+            .combine(other)
+            .map_err(|op| MergePsbtError::WrongCombine(op.to_string()))?;
+    }
+
+    Ok(combined)
+}
+
+fn is_tapret_host(output: &bitcoin::psbt::Output) -> bool {
+    output
+        .proprietary
+        .keys()
+        .any(|key| key.prefix == PSBT_TAPRET_PREFIX.to_vec() && key.subtype == PSBT_OUT_TAPRET_HOST)
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum EstimateFeeError {
@@ -513,10 +789,17 @@ impl PsbtInputEx<InputDescriptor> for InputDescriptor {
             .parse::<DerivationSubpath<UnhardenedIndex>>()
             .map_err(|_| PsbtInputError::WrongTerminal)?;
 
-        let seq_no = if opt.rbf {
-            SeqNo::rbf()
-        } else {
-            SeqNo::default()
+        let seq_no = match opt.lock {
+            Some(PsbtLockRequest::RelativeHeight(blocks)) => SeqNo::from_height(blocks),
+            Some(PsbtLockRequest::RelativeTime(intervals)) => SeqNo::from_intervals(intervals),
+            // nLockTime is only enforced when at least one input is non-final
+            Some(PsbtLockRequest::Height(_) | PsbtLockRequest::Time(_)) if !opt.rbf => {
+                SeqNo::from_consensus(0xFFFFFFFE)
+            }
+            _ if opt.rbf => SeqNo::rbf(),
+            // Keep the anti-fee-sniping nLockTime enforced without signaling RBF
+            None if opt.tip_height.is_some() => SeqNo::from_consensus(0xFFFFFFFE),
+            _ => SeqNo::default(),
         };
 
         let mut input = InputDescriptor {
@@ -711,6 +994,15 @@ pub enum PsbtConstructError {
         /// Amount sent: sum of output value + transaction fee
         output: u64,
     },
+
+    /// PSBT fee ({fee} sats) is above the safety cap ({max} sats)
+    FeeTooHigh {
+        /// Transaction fee
+        fee: u64,
+
+        /// Maximum fee allowed
+        max: u64,
+    },
 }
 
 #[derive(Clone, Debug, Display, Error, From)]
@@ -719,6 +1011,15 @@ pub struct NewPsbtOptions {
     pub set_tapret: bool,
     pub force_inflation: u64,
     pub rbf: bool,
+    /// Time-lock of the transaction itself (nLockTime / nSequence), as
+    /// required to spend outputs guarded by [`timelock_script`]
+    pub lock: Option<PsbtLockRequest>,
+    /// Time-locked output of the beneficiary key, added to the transaction
+    pub timelock: Option<(PsbtLockRequest, PublicKey)>,
+    pub max_fee: Option<u64>,
+    pub max_outputs: Option<usize>,
+    pub tip_height: Option<u32>,
+    pub data: Option<Vec<u8>>,
 }
 
 impl Default for NewPsbtOptions {
@@ -727,6 +1028,12 @@ impl Default for NewPsbtOptions {
             rbf: true,
             set_tapret: true,
             force_inflation: 0,
+            lock: None,
+            timelock: None,
+            max_fee: None,
+            max_outputs: None,
+            tip_height: None,
+            data: None,
         }
     }
 }
@@ -737,6 +1044,26 @@ impl NewPsbtOptions {
             rbf,
             set_tapret: true,
             force_inflation: 0,
+            lock: None,
+            timelock: None,
+            max_fee: None,
+            max_outputs: None,
+            tip_height: None,
+            data: None,
+        }
+    }
+
+    pub fn with_lock(rbf: bool, lock: Option<PsbtLockRequest>) -> Self {
+        Self {
+            rbf,
+            set_tapret: true,
+            force_inflation: 0,
+            lock,
+            timelock: None,
+            max_fee: None,
+            max_outputs: None,
+            tip_height: None,
+            data: None,
         }
     }
 
@@ -745,12 +1072,84 @@ impl NewPsbtOptions {
             rbf: true,
             set_tapret: true,
             force_inflation: inflaction,
+            lock: None,
+            timelock: None,
+            max_fee: None,
+            max_outputs: None,
+            tip_height: None,
+            data: None,
         }
     }
 
     pub fn check_inflaction(self) -> bool {
         self.force_inflation == 0
     }
+
+    /// Maximum fee allowed for the amount spent. Without an explicit
+    /// `max_fee`, the fee is limited to a percent of the input value
+    /// (but never below a fixed floor).
+    pub fn max_fee(&self, total_spent: u64) -> u64 {
+        self.max_fee
+            .unwrap_or_else(|| (total_spent * RGB_MAX_FEE_PERC / 100).max(RGB_MAX_FEE_FLOOR))
+    }
+
+    /// Maximum number of outputs of the transaction. Without an explicit
+    /// `max_outputs`, the default limit applies.
+    pub fn max_outputs(&self) -> usize {
+        self.max_outputs.unwrap_or(RGB_MAX_OUTPUTS)
+    }
+
+    /// Version of the transaction, high enough to enforce the requested
+    /// time-lock.
+    pub fn tx_version(&self) -> u32 {
+        let min_version = self.lock.map(|lock| lock.min_tx_version()).unwrap_or(1);
+        cmp::max(RGB_TX_VERSION, min_version)
+    }
+
+    /// nLockTime of the transaction. Without a requested time-lock, the
+    /// chain tip (when known) is used to discourage fee sniping, sometimes
+    /// jittered to a random recent block.
+    pub fn fallback_locktime(&self) -> Option<LockTime> {
+        match (self.lock, self.tip_height) {
+            (Some(PsbtLockRequest::Height(height)), _) => LockTime::from_height(height),
+            (Some(PsbtLockRequest::Time(timestamp)), _) => LockTime::from_unix_timestamp(timestamp),
+            (None, Some(tip_height)) => {
+                let mut rng = StdRng::from_entropy();
+                let mut height = tip_height;
+                if rng.gen_range(0..100) < RGB_FEE_SNIPING_JITTER_PERC {
+                    height = height.saturating_sub(rng.gen_range(0..RGB_FEE_SNIPING_MAX_JITTER));
+                }
+                LockTime::from_height(height.max(1))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PsbtLockRequest {
+    // Values below this threshold are block heights, above are unix timestamps (BIP-65)
+    const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+    // Relative time-locks (nSequence) are only enforced from this version (BIP-68)
+    pub const RELATIVE_LOCK_TX_VERSION: u32 = 2;
+
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            PsbtLockRequest::Height(height) => height > 0 && height < Self::LOCKTIME_THRESHOLD,
+            PsbtLockRequest::Time(timestamp) => timestamp >= Self::LOCKTIME_THRESHOLD,
+            PsbtLockRequest::RelativeHeight(blocks) => blocks > 0,
+            PsbtLockRequest::RelativeTime(intervals) => intervals > 0,
+        }
+    }
+
+    /// Minimum version of the transaction enforcing the time-lock.
+    pub fn min_tx_version(&self) -> u32 {
+        match self {
+            PsbtLockRequest::RelativeHeight(_) | PsbtLockRequest::RelativeTime(_) => {
+                Self::RELATIVE_LOCK_TX_VERSION
+            }
+            PsbtLockRequest::Height(_) | PsbtLockRequest::Time(_) => 1,
+        }
+    }
 }
 
 impl PsbtEx<Psbt> for Psbt {
@@ -987,6 +1386,11 @@ impl PsbtEx<Psbt> for Psbt {
         let change = if !options.clone().check_inflaction() {
             options.force_inflation
         } else {
+            let max_fee = options.max_fee(total_spent);
+            if fee > max_fee {
+                return Err(PsbtConstructError::FeeTooHigh { fee, max: max_fee });
+            }
+
             match total_spent.checked_sub(total_sent + fee) {
                 Some(change) => change,
                 None => {
@@ -1068,11 +1472,11 @@ impl PsbtEx<Psbt> for Psbt {
 
         Ok(Psbt {
             psbt_version: PsbtVersion::V0,
-            tx_version: 2,
+            tx_version: options.tx_version(),
             xpub,
             inputs: psbt_inputs,
             outputs: psbt_outputs,
-            fallback_locktime: None,
+            fallback_locktime: options.fallback_locktime(),
             proprietary: none!(),
             unknown: none!(),
         })
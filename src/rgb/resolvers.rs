@@ -20,7 +20,10 @@ use rgbstd::{
 };
 use wallet::onchain::{ResolveTx, TxResolverError};
 
-use crate::structs::{TxStatus, UtxoSpentStatus};
+use crate::{
+    constants::get_explorer_batch_size,
+    structs::{TxStatus, UtxoSpentStatus},
+};
 
 #[derive(Default)]
 pub struct ExplorerResolver {
@@ -40,65 +43,50 @@ impl rgb::Resolver for ExplorerResolver {
         &mut self,
         scripts: BTreeMap<DeriveInfo, bitcoin_30::ScriptBuf>,
     ) -> Result<BTreeSet<rgb::prelude::Utxo>, String> {
-        use bitcoin::Script;
-        use bitcoin_scripts::address::{AddressCompat, AddressNetwork};
-        use bp::ScriptPubkey;
-        use esplora_block::FromHex;
-        use std::collections::HashSet;
-
         let mut utxos = bset![];
         let explorer_client = esplora_block::Builder::new(&self.explorer_url)
             .build_blocking()
             .expect("service unavailable");
 
-        for (derive, script) in scripts {
-            // TODO: Remove that after bitcoin v.30 full compatibility
-            let script_compatible =
-                Script::from_hex(&script.as_script().to_hex_string()).expect("invalid script");
+        let scripts: Vec<_> = scripts.into_iter().collect();
+        for batch in scripts.chunks(get_explorer_batch_size()) {
+            let histories: Vec<_> = std::thread::scope(|scope| {
+                let client = &explorer_client;
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|(_, script)| scope.spawn(move || scripthash_history(client, script)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("Service unavailable"))
+                    .collect()
+            });
 
-            let mut related_txs = explorer_client
-                .scripthash_txs(&script_compatible, None)
-                .expect("Service unavailable");
-            let n_confirmed = related_txs.iter().filter(|tx| tx.status.confirmed).count();
-            // esplora pages on 25 confirmed transactions. If there are 25 or more we
-            // keep requesting to see if there's more.
-            if n_confirmed >= 25 {
-                loop {
-                    let new_related_txs = explorer_client
-                        .scripthash_txs(&script_compatible, Some(related_txs.last().unwrap().txid))
-                        .expect("Service unavailable");
-                    let n = new_related_txs.len();
-                    related_txs.extend(new_related_txs);
-                    // we've reached the end
-                    if n < 25 {
-                        break;
-                    }
-                }
-            }
+            for ((derive, _), (script_compatible, related_txs)) in batch.iter().zip(histories) {
+                related_txs.into_iter().for_each(|tx| {
+                    for (index, vout) in tx.vout.iter().enumerate() {
+                        if vout.scriptpubkey != script_compatible {
+                            continue;
+                        }
 
-            related_txs.into_iter().for_each(|tx| {
-                for (index, vout) in tx.vout.iter().enumerate() {
-                    if vout.scriptpubkey != script_compatible {
-                        continue;
+                        let status = match tx.status.block_height {
+                            Some(height) => MiningStatus::Blockchain(height),
+                            _ => MiningStatus::Mempool,
+                        };
+                        let outpoint = Outpoint::new(
+                            Txid::from_str(&tx.txid.to_hex()).expect("invalid outpoint parse"),
+                            index as u32,
+                        );
+                        let new_utxo = Utxo {
+                            outpoint,
+                            status,
+                            amount: vout.value,
+                            derivation: derive.clone(),
+                        };
+                        utxos.insert(new_utxo);
                     }
-
-                    let status = match tx.status.block_height {
-                        Some(height) => MiningStatus::Blockchain(height),
-                        _ => MiningStatus::Mempool,
-                    };
-                    let outpoint = Outpoint::new(
-                        Txid::from_str(&tx.txid.to_hex()).expect("invalid outpoint parse"),
-                        index as u32,
-                    );
-                    let new_utxo = Utxo {
-                        outpoint,
-                        status,
-                        amount: vout.value,
-                        derivation: derive.clone(),
-                    };
-                    utxos.insert(new_utxo);
-                }
-            });
+                });
+            }
         }
         Ok(utxos)
     }
@@ -112,6 +100,41 @@ impl rgb::Resolver for ExplorerResolver {
     }
 }
 
+// Full transaction history of a script (following esplora pagination)
+#[cfg(not(target_arch = "wasm32"))]
+fn scripthash_history(
+    explorer_client: &BlockingClient,
+    script: &bitcoin_30::ScriptBuf,
+) -> (bitcoin::Script, Vec<ExplorerTX>) {
+    use esplora_block::FromHex;
+
+    // TODO: Remove that after bitcoin v.30 full compatibility
+    let script_compatible =
+        bitcoin::Script::from_hex(&script.as_script().to_hex_string()).expect("invalid script");
+
+    let mut related_txs = explorer_client
+        .scripthash_txs(&script_compatible, None)
+        .expect("Service unavailable");
+    let n_confirmed = related_txs.iter().filter(|tx| tx.status.confirmed).count();
+    // esplora pages on 25 confirmed transactions. If there are 25 or more we
+    // keep requesting to see if there's more.
+    if n_confirmed >= 25 {
+        loop {
+            let new_related_txs = explorer_client
+                .scripthash_txs(&script_compatible, Some(related_txs.last().unwrap().txid))
+                .expect("Service unavailable");
+            let n = new_related_txs.len();
+            related_txs.extend(new_related_txs);
+            // we've reached the end
+            if n < 25 {
+                break;
+            }
+        }
+    }
+
+    (script_compatible, related_txs)
+}
+
 impl ResolveTx for ExplorerResolver {
     #[cfg(not(target_arch = "wasm32"))]
     fn resolve_tx(
@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use bitcoin_30::hashes::{sha256, Hash};
+use bp::{
+    seals::txout::{CloseMethod, ExplicitSeal},
+    Outpoint, Txid,
+};
+use rgbstd::contract::{GraphSeal, SecretSeal};
+use seals::txout::TxoSeal;
+
+const BLINDING_TAG: &str = "bitmask:seal-blinding";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealDefinitionError {
+    /// '{0}' is an invalid outpoint (expected: <txid>:<vout>)
+    WrongOutpoint(String),
+    /// '{0}' is an invalid blinding factor (expected: 64-bit unsigned integer)
+    WrongBlinding(String),
+    /// '{0}' is an invalid close method (expected: tapret1st or opret1st)
+    WrongMethod(String),
+    /// '{0}' is an invalid invoice seal (expected: <method>:<txid>:<vout>)
+    WrongSeal(String),
+    /// '{0}' is a concealed seal, the outpoint cannot be recovered from it
+    ConcealedSeal(String),
+    /// '{0}' is closed over the witness transaction, its outpoint is unknown
+    WitnessSeal(String),
+}
+
+/// Seal of an owned state: the outpoint, the blinding factor concealing it
+/// in the invoice, and how the seal is closed.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct SealDefinition {
+    pub outpoint: Outpoint,
+    pub blinding: u64,
+    pub method: CloseMethod,
+}
+
+impl SealDefinition {
+    /// Seal from the stored outpoint, blinding factor and close method
+    pub fn from_parts(
+        outpoint: &str,
+        blinding: &str,
+        method: &str,
+    ) -> Result<Self, SealDefinitionError> {
+        let outpoint = Outpoint::from_str(outpoint)
+            .map_err(|_| SealDefinitionError::WrongOutpoint(outpoint.to_string()))?;
+        let blinding = u64::from_str(blinding)
+            .map_err(|_| SealDefinitionError::WrongBlinding(blinding.to_string()))?;
+        let method = CloseMethod::from_str(method)
+            .map_err(|_| SealDefinitionError::WrongMethod(method.to_string()))?;
+
+        Ok(Self {
+            outpoint,
+            blinding,
+            method,
+        })
+    }
+
+    /// Seal from the invoice format (`<method>:<txid>:<vout>`).
+    ///
+    /// The blinding factor is derived from the wallet secret and the nonce
+    /// (the number of seals already defined on the outpoint), so the same
+    /// seal is defined again from the same inputs, every invoice on an
+    /// outpoint is blinded differently, and the outpoint stays concealed to
+    /// anyone without the secret.
+    pub fn from_invoice_seal(
+        seal: &str,
+        secret: &[u8],
+        nonce: u32,
+    ) -> Result<Self, SealDefinitionError> {
+        let (method, outpoint) = parse_invoice_seal(seal)?;
+
+        let tag = sha256::Hash::hash(BLINDING_TAG.as_bytes());
+        let mut data = [tag.as_ref(), tag.as_ref()].concat();
+        data.extend((secret.len() as u64).to_le_bytes());
+        data.extend(secret);
+        data.extend(seal_id(method, outpoint).as_bytes());
+        data.extend(nonce.to_le_bytes());
+
+        let digest = sha256::Hash::hash(&data);
+        let mut blinding = [0u8; 8];
+        blinding.copy_from_slice(&digest[..8]);
+
+        Ok(Self {
+            outpoint,
+            blinding: u64::from_le_bytes(blinding),
+            method,
+        })
+    }
+
+    /// Seal in the invoice format (`<method>:<txid>:<vout>`)
+    pub fn to_invoice_seal(&self) -> String {
+        seal_id(self.method, self.outpoint)
+    }
+
+    /// Seal stored in the stock and used by the transfers
+    pub fn to_graph_seal(&self) -> GraphSeal {
+        GraphSeal::with_blinding(
+            self.method,
+            self.outpoint.txid,
+            self.outpoint.vout,
+            self.blinding,
+        )
+    }
+
+    /// Seal as the invoice beneficiary
+    pub fn to_concealed_seal(&self) -> SecretSeal {
+        self.to_graph_seal().to_concealed_seal()
+    }
+}
+
+impl TryFrom<GraphSeal> for SealDefinition {
+    type Error = SealDefinitionError;
+
+    /// Seal of the stock (e.g. the seal secret of an invoice)
+    fn try_from(seal: GraphSeal) -> Result<Self, Self::Error> {
+        let outpoint = seal
+            .outpoint()
+            .ok_or(SealDefinitionError::WitnessSeal(format!(
+                "{}:{}:{}",
+                seal.method, seal.txid, seal.vout
+            )))?;
+
+        Ok(Self {
+            outpoint,
+            blinding: seal.blinding,
+            method: seal.method,
+        })
+    }
+}
+
+/// Close method and outpoint of a seal in the invoice format
+/// (`<method>:<txid>:<vout>`)
+pub fn parse_invoice_seal(seal: &str) -> Result<(CloseMethod, Outpoint), SealDefinitionError> {
+    if SecretSeal::from_str(seal).is_ok() {
+        return Err(SealDefinitionError::ConcealedSeal(seal.to_string()));
+    }
+
+    let explicit = ExplicitSeal::<Txid>::from_str(seal)
+        .map_err(|_| SealDefinitionError::WrongSeal(seal.to_string()))?;
+    Ok((explicit.method, Outpoint::new(explicit.txid, explicit.vout)))
+}
+
+fn seal_id(method: CloseMethod, outpoint: Outpoint) -> String {
+    format!("{method}:{outpoint}")
+}
@@ -6,7 +6,7 @@ use core::fmt::Display;
 use rgb::{RgbWallet, TerminalPath};
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     str::FromStr,
 };
 
@@ -273,6 +273,8 @@ impl Display for ContractAmount {
 pub struct ContractBoilerplate {
     pub contract_id: String,
     pub iface_id: String,
+    pub ticker: String,
+    pub name: String,
     pub precision: u8,
 }
 
@@ -307,6 +309,67 @@ pub struct RgbAccountV1 {
     pub invoices: Vec<String>,
 }
 
+/// Incoming allocations hidden from balances and coin selection (by outpoint)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbIgnoredAllocations {
+    pub outpoints: BTreeSet<String>,
+}
+
+/// Seals replaced by a rebound invoice, transfers paying to them are rejected
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbRevokedSeals {
+    pub seals: BTreeSet<String>,
+}
+
+/// Accepted transfers whose witness transaction was not confirmed yet
+/// (witness txids, by contract id)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbUnconfirmedTransfers {
+    pub transfers: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Signed witness transactions of the published transfers, kept until they
+/// confirm to rebroadcast them (raw transactions in hex, by txid)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbBroadcasts {
+    pub txs: BTreeMap<String, String>,
+}
+
+/// Acceptable counterparties of the outgoing transfers (beneficiaries, by
+/// contract id)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbCounterparties {
+    pub allow: BTreeMap<String, BTreeSet<String>>,
+    pub deny: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Accepted transfers whose changes are being stored, by consignment id.
+/// The entries left behind were interrupted and are accepted again
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbAcceptJournal {
+    pub entries: BTreeMap<String, String>,
+    /// Entries which cannot be accepted again, kept aside for inspection
+    pub quarantine: BTreeMap<String, RgbQuarantinedAccept>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default)]
+pub struct RgbQuarantinedAccept {
+    pub consignment: String,
+    pub error: String,
+}
+
+impl RgbAcceptJournal {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl RgbCounterparties {
+    pub fn is_empty(&self) -> bool {
+        self.allow.values().all(BTreeSet::is_empty) && self.deny.values().all(BTreeSet::is_empty)
+    }
+}
+
 #[derive(
     Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize, Default, Display,
 )]
@@ -542,3 +605,13 @@ pub struct RgbProxyConsig {
     pub consignment: String,
     pub txid: String,
 }
+
+/// Schema served by a schema registry, with the implementations of its
+/// interfaces (strict encoded, in hexadecimal)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaRegistryEntry {
+    pub schema: String,
+    #[serde(default)]
+    pub iimpls: Vec<String>,
+}
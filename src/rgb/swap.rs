@@ -27,6 +27,7 @@ use std::{
 use strict_encoding::{
     StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize, StrictType,
 };
+use zeroize::Zeroizing;
 
 use crate::{
     rgb::{
@@ -110,7 +111,10 @@ pub struct RgbOfferOptions {
 impl RgbOfferOptions {
     pub fn new(secret: String) -> Self {
         let secp = Secp256k1::new();
-        let secret = hex::decode(secret).expect("cannot decode hex sk in new RgbOffer");
+        let secret = Zeroizing::new(secret);
+        let secret = Zeroizing::new(
+            hex::decode(secret.as_str()).expect("cannot decode hex sk in new RgbOffer"),
+        );
         let secret_key = SecretKey::from_slice(&secret).expect("error parsing sk in new RgbOffer");
         let public_key = PublicKey::from_secret_key(&secp, &secret_key);
         let bundle_id = Some(public_key.to_hex());
@@ -123,7 +127,10 @@ impl RgbOfferOptions {
 
     pub fn new_airdrop(secret: String, fee: PsbtFeeRequest, max: u64) -> Self {
         let secp = Secp256k1::new();
-        let secret = hex::decode(secret).expect("cannot decode hex sk in new RgbOffer");
+        let secret = Zeroizing::new(secret);
+        let secret = Zeroizing::new(
+            hex::decode(secret.as_str()).expect("cannot decode hex sk in new RgbOffer"),
+        );
         let secret_key = SecretKey::from_slice(&secret).expect("error parsing sk in new RgbOffer");
         let public_key = PublicKey::from_secret_key(&secp, &secret_key);
         let bundle_id = Some(public_key.to_hex());
@@ -197,7 +204,10 @@ impl RgbOffer {
         max_claim: Option<u64>,
     ) -> Self {
         let secp = Secp256k1::new();
-        let secret = hex::decode(secret).expect("cannot decode hex sk in new RgbOffer");
+        let secret = Zeroizing::new(secret);
+        let secret = Zeroizing::new(
+            hex::decode(secret.as_str()).expect("cannot decode hex sk in new RgbOffer"),
+        );
         let secret_key = SecretKey::from_slice(&secret).expect("error parsing sk in new RgbOffer");
         let pub_key = PublicKey::from_secret_key(&secp, &secret_key).to_hex();
 
@@ -362,7 +372,10 @@ impl RgbBid {
         bitcoin_utxos: Vec<String>,
     ) -> Self {
         let secp = Secp256k1::new();
-        let secret = hex::decode(secret).expect("cannot decode hex sk in new RgbBid");
+        let secret = Zeroizing::new(secret);
+        let secret = Zeroizing::new(
+            hex::decode(secret.as_str()).expect("cannot decode hex sk in new RgbBid"),
+        );
         let secret_key = SecretKey::from_slice(&secret).expect("error parsing sk in new RgbBid");
         let pub_key = PublicKey::from_secret_key(&secp, &secret_key).to_hex();
 
@@ -812,14 +825,15 @@ pub async fn get_swap_bids_by_offer(
             pub_key: public,
             ..
         } = bid.clone();
-        let secret = hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
+        let secret =
+            Zeroizing::new(hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?);
         let secret_key =
             SecretKey::from_slice(&secret).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
         let public_key =
             PublicKey::from_str(&public).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
 
         let share_sk = SharedSecret::new(&public_key, &secret_key);
-        let share_sk = share_sk.display_secret().to_string();
+        let share_sk = Zeroizing::new(share_sk.display_secret().to_string());
 
         let file_name = format!("{offer_id}-{bid_id}");
         match retrieve_swap_offer_bid(&share_sk, &file_name, expire_at).await {
@@ -839,14 +853,15 @@ pub async fn get_swap_bid_by_seller(
 ) -> Result<RgbBidSwap, RgbOfferErrors> {
     let bid = get_public_bid(offer_id.clone(), bid_id.clone()).await?;
 
-    let secret = hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
+    let secret =
+        Zeroizing::new(hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?);
     let secret_key =
         SecretKey::from_slice(&secret).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
     let public_key =
         PublicKey::from_str(&bid.pub_key).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
 
     let share_sk = SharedSecret::new(&public_key, &secret_key);
-    let share_sk = share_sk.display_secret().to_string();
+    let share_sk = Zeroizing::new(share_sk.display_secret().to_string());
 
     let file_name = format!("{offer_id}-{bid_id}");
     let LocalRgbOfferBid { rgb_bid, .. } =
@@ -868,14 +883,15 @@ pub async fn get_swap_bid_by_buyer(
         ..
     } = get_public_offer(offer_id.clone()).await?;
 
-    let secret = hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
+    let secret =
+        Zeroizing::new(hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?);
     let secret_key =
         SecretKey::from_slice(&secret).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
     let public_key =
         PublicKey::from_str(&public).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
 
     let share_sk = SharedSecret::new(&public_key, &secret_key);
-    let share_sk = share_sk.display_secret().to_string();
+    let share_sk = Zeroizing::new(share_sk.display_secret().to_string());
 
     let file_name = format!("{offer_id}-{bid_id}");
     let LocalRgbOfferBid { rgb_bid, .. } =
@@ -1030,14 +1046,15 @@ pub async fn publish_swap_bid(
         bid_id, offer_id, ..
     } = new_bid.clone();
 
-    let secret = hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
+    let secret =
+        Zeroizing::new(hex::decode(sk).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?);
     let secret_key =
         SecretKey::from_slice(&secret).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
     let public_key =
         PublicKey::from_str(offer_pub).map_err(|op| RgbOfferErrors::Keys(op.to_string()))?;
 
     let share_sk = SharedSecret::new(&public_key, &secret_key);
-    let share_sk = share_sk.display_secret().to_string();
+    let share_sk = Zeroizing::new(share_sk.display_secret().to_string());
 
     let file_name = format!("{offer_id}-{bid_id}");
 
@@ -1,29 +1,50 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    iter,
+    str::FromStr,
+};
 
 use amplify::{
     confinement::{Confined, U32},
     hex::ToHex,
+    ByteArray,
+};
+use bitcoin_30::{
+    bip32::{ChildNumber, KeySource},
+    consensus::serialize,
+    hashes::Hash,
+    psbt::{Output as PsbtOutput, Psbt as PSBT},
+    secp256k1::{KeyPair, PublicKey, Secp256k1, XOnlyPublicKey},
+    ScriptBuf, TxOut,
 };
-use bitcoin_30::psbt::Psbt as PSBT;
 use bitcoin_hashes::hex::FromHex;
-use bp::{seals::txout::CloseMethod, Chain, Txid};
+use bp::{seals::txout::CloseMethod, Chain, Outpoint, Txid};
 use indexmap::IndexMap;
-use psbt::{serialize::Serialize, Psbt};
+use psbt::{serialize::Serialize, ProprietaryKey, Psbt};
+use rgb::psbt::{PSBT_OUT_TAPRET_COMMITMENT, PSBT_OUT_TAPRET_HOST, PSBT_TAPRET_PREFIX};
 use rgbstd::{
-    containers::{Bindle, Transfer},
-    contract::{ContractId, GraphSeal},
-    interface::TypedState,
+    containers::{Bindle, Consignment, Transfer},
+    contract::{ContractId, GenesisSeal, GraphSeal, OpId, Operation, SecretSeal},
+    interface::{TypedState, VerNo},
     persistence::{Inventory, Stash, Stock},
     resolvers::ResolveHeight,
     validation::{AnchoredBundle, ConsignmentApi, ResolveTx, Status},
 };
-use rgbwallet::{InvoiceParseError, RgbInvoice, RgbTransport};
-use seals::txout::ExplicitSeal;
-use strict_encoding::{StrictDeserialize, TypeName};
+use rgbwallet::{Beneficiary, InvoiceParseError, RgbInvoice, RgbTransport};
+use seals::txout::TxoSeal;
+use strict_encoding::{SerializeError, StrictDeserialize, StrictSerialize, TypeName};
 
+use crate::bitcoin::{sign_response, verify_response};
 use crate::rgb::{
-    consignment::{ConsignmentEx, NewTransferOptions},
-    prebuild::prebuild_extract_transfer,
+    consignment::{ConsignmentEx, NewTransferOptions, PayAllError},
+    constants::{
+        RGB_IFACE_VERSION_PARAM, RGB_ORIGIN_PARAM, RGB_ORIGIN_SIG_PARAM, RGB_PSBT_TIMELOCK,
+    },
+    contract::unavailable_schema,
+    prebuild::{prebuild_extract_transfer, DUST_LIMIT_SATOSHI},
+    seal::{parse_invoice_seal, SealDefinition, SealDefinitionError},
+    structs::RgbCounterparties,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -35,6 +56,8 @@ pub enum NewInvoiceError {
     WrongContract(String),
     /// '{0}' is an invalid seal definition
     WrongSeal(String),
+    /// Seal cannot be decoded. {0}
+    InvalidSeal(SealDefinitionError),
     /// Network cannot be decoded. {0}
     WrongNetwork(String),
     /// {0} is unspecified or wrong contract id
@@ -43,6 +66,20 @@ pub enum NewInvoiceError {
     EmptyContracts,
     /// Error saving secret seal: {0}
     StoreSeal(String),
+    /// Error reading secret seals: {0}
+    RetrieveSeals(String),
+    /// Only invoices to a blinded seal can be rebound
+    NotBlinded,
+    /// Seal {0} of the invoice is not one of the wallet
+    ForeignSeal(String),
+    /// Contract {0} does not implement the interface {1}
+    UnsupportedIface(String, String),
+    /// Version {1} of the interface {0} is not supported
+    UnsupportedIfaceVersion(String, String),
+    /// Schema {1} of the contract {0} is unavailable, fetch it before creating invoices
+    SchemaUnavailable(String, String),
+    /// Origin of the invoice cannot be signed. {0}
+    WrongOrigin(String),
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -58,6 +95,50 @@ pub enum NewPaymentError {
     WrongPSBT(String),
     /// Consignmnet has not been completed. {0}
     NoPay(String),
+    /// Outpoint {0} holds contract state but it is not one of the selected seals
+    UnselectedSeal(String),
+    /// Selected seals cannot cover the invoice amount (expected: {expected} / available: {available})
+    InsufficientSeals {
+        /// Amount requested by the invoice
+        expected: u64,
+
+        /// Amount allocated in the selected seals
+        available: u64,
+    },
+    /// Network cannot be decoded. {0}
+    WrongNetwork(String),
+    /// Invoice is payable on {invoice}, but the wallet uses {wallet}
+    ChainMismatch {
+        /// Chain of the invoice
+        invoice: String,
+
+        /// Chain of the wallet network
+        wallet: String,
+    },
+    /// Counterparty '{0}' is not allowed to receive the contract assets
+    CounterpartyDenied(String),
+    /// Contract {0} does not implement the interface {1}
+    UnsupportedIface(String, String),
+    /// Version {1} of the interface {0} is not supported
+    UnsupportedIfaceVersion(String, String),
+    /// Schema {1} of the contract {0} is unavailable, fetch it before paying
+    SchemaUnavailable(String, String),
+    /// Change split sums up to {actual}, but the change of the payment is {expected}
+    ChangeSplitMismatch {
+        /// Change of the payment
+        expected: u64,
+
+        /// Sum of the change split
+        actual: u64,
+    },
+    /// Change split cannot have empty allocations
+    EmptyChangeSplit,
+    /// PSBT has no change output of the wallet able to host {0} change allocations
+    NoChangeSplitOutput(usize),
+    /// PSBT has no account key to derive the outputs of the change split
+    NoChangeSplitKey,
+    /// Invoice does not pay to the time-locked output of the PSBT
+    UnboundTimelock,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -76,6 +157,49 @@ pub enum AcceptTransferError {
     InvalidConsig(Vec<String>),
     /// The Consignment is invalid (Unexpected behavior on validation).
     Inconclusive,
+    /// The Consignment pays to a seal replaced by a rebound invoice ({0})
+    RevokedSeal(String),
+    /// Allocation on {0} is not controlled by the wallet
+    ForeignAllocation(String),
+    /// Outpoint {0} has no allocation of the contract
+    UnknownAllocation(String),
+    /// Allocation on {0} is already spent
+    SpentAllocation(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PsbtConsignmentError {
+    /// PSBT file cannot be decoded. {0}
+    WrongPsbt(String),
+    /// Consignment cannot be decoded. {0}
+    WrongConsig(String),
+    /// Consignment has no terminal bundles
+    NoTerminals,
+    /// Bundle {0} of the consignment is not anchored
+    NoAnchor(String),
+    /// PSBT transaction does not match the consignment anchor (psbt: {psbt} / anchor: {anchor})
+    TxidMismatch {
+        /// Id of the PSBT transaction
+        psbt: String,
+
+        /// Id of the transaction anchoring the consignment
+        anchor: String,
+    },
+    /// None of the PSBT outputs holds the tapret commitment
+    NoCommitment,
+    /// Anchor of the bundle {0} has an invalid commitment proof
+    WrongAnchor(String),
+    /// Tapret commitment of the PSBT is not the anchor of the bundle {0}
+    CommitmentMismatch(String),
+    /// Seal is closed over output {vout}, but the PSBT transaction has {outputs} outputs
+    SealOutOfRange {
+        /// Output of the witness transaction defined by the seal
+        vout: u32,
+
+        /// Number of outputs of the PSBT transaction
+        outputs: usize,
+    },
 }
 
 pub fn create_invoice(
@@ -83,6 +207,7 @@ pub fn create_invoice(
     iface: &str,
     amount: u64,
     seal: &str,
+    secret: &[u8],
     network: &str,
     params: HashMap<String, String>,
     stock: &mut Stock,
@@ -96,6 +221,13 @@ pub fn create_invoice(
     let contract_id = ContractId::from_str(contract_id)
         .map_err(|_| NewInvoiceError::NoContract(contract_id.to_string()))?;
 
+    if let Some(schema_id) = unavailable_schema(contract_id, stock) {
+        return Err(NewInvoiceError::SchemaUnavailable(
+            contract_id.to_string(),
+            schema_id.to_string(),
+        ));
+    }
+
     // Temporary removal
     // if !stock
     //     .contract_ids()
@@ -108,9 +240,26 @@ pub fn create_invoice(
     let chain =
         Chain::from_str(network).map_err(|op| NewInvoiceError::WrongNetwork(op.to_string()))?;
 
-    let seal = ExplicitSeal::<Txid>::from_str(seal)
-        .map_err(|_| NewInvoiceError::WrongIface(seal.to_string()))?;
-    let seal = GraphSeal::new(seal.method, seal.txid, seal.vout);
+    let iface_name = iface.name.to_string();
+    let iface_version = match contract_iface_version(stock, contract_id, &iface_name) {
+        Some(iface_version) => iface_version,
+        None => {
+            return Err(NewInvoiceError::UnsupportedIface(
+                contract_id.to_string(),
+                iface_name,
+            ))
+        }
+    };
+    if let Some(version) = params.get(RGB_IFACE_VERSION_PARAM) {
+        if u8::from_str(version).ok() != Some(iface_version) {
+            return Err(NewInvoiceError::UnsupportedIfaceVersion(
+                iface_name,
+                version.to_string(),
+            ));
+        }
+    }
+
+    let seal = new_seal_definition(seal, secret, stock)?.to_graph_seal();
     // Query Params
     let mut query = IndexMap::default();
     for (k, v) in params {
@@ -138,6 +287,292 @@ pub fn create_invoice(
     Ok(invoice)
 }
 
+/// Interface versions are numbered from 1 (`VerNo::V1`)
+pub fn iface_version_number(version: VerNo) -> u8 {
+    version as u8 + 1
+}
+
+/// Version of the interface implemented by the schema of the contract, if
+/// the stock has an implementation of the interface for it
+pub fn contract_iface_version(stock: &Stock, contract_id: ContractId, iface: &str) -> Option<u8> {
+    let schema_id = stock.genesis(contract_id).ok()?.schema_id;
+    let schema = stock.schema(schema_id).ok()?;
+    schema
+        .iimpls
+        .iter()
+        .find(|(iface_id, _)| {
+            stock
+                .iface_by_id(**iface_id)
+                .is_ok_and(|face| face.name.to_string() == iface)
+        })
+        .map(|(_, iimpl)| iface_version_number(iimpl.version))
+}
+
+/// Ensure the contract implements the interface of the invoice, in the
+/// version targeted by the invoice (if any). The transition is built with
+/// this implementation, so it is compatible with the payee
+pub fn check_iface_version(invoice: &RgbInvoice, stock: &Stock) -> Result<(), NewPaymentError> {
+    let contract_id = match invoice.contract {
+        Some(contract_id) => contract_id,
+        None => return Ok(()),
+    };
+    let iface = invoice
+        .iface
+        .as_ref()
+        .map(|iface| iface.to_string())
+        .unwrap_or_default();
+
+    let iface_version = match contract_iface_version(stock, contract_id, &iface) {
+        Some(iface_version) => iface_version,
+        None => {
+            return Err(NewPaymentError::UnsupportedIface(
+                contract_id.to_string(),
+                iface,
+            ))
+        }
+    };
+    if let Some(version) = invoice.unknown_query.get(RGB_IFACE_VERSION_PARAM) {
+        if u8::from_str(version).ok() != Some(iface_version) {
+            return Err(NewPaymentError::UnsupportedIfaceVersion(
+                iface,
+                version.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the beneficiary of the invoice by a new blinded seal, keeping the
+/// rest of the invoice (contract, amount, expiry and params).
+///
+/// Returns the new invoice and the replaced seal.
+pub fn rebind_invoice(
+    invoice: &RgbInvoice,
+    seal: &str,
+    secret: &[u8],
+    stock: &mut Stock,
+) -> Result<(RgbInvoice, SecretSeal), NewInvoiceError> {
+    let prev_seal = match invoice.beneficiary {
+        Beneficiary::BlindedSeal(seal) => seal,
+        _ => return Err(NewInvoiceError::NotBlinded),
+    };
+    // Only the invoices of the wallet can be rebound
+    check_wallet_invoice(invoice, stock)?;
+
+    let seal = new_seal_definition(seal, secret, stock)?.to_graph_seal();
+
+    let mut invoice = invoice.clone();
+    invoice.beneficiary = seal.to_concealed_seal().into();
+
+    stock
+        .store_seal_secret(seal)
+        .map_err(|op| NewInvoiceError::StoreSeal(op.to_string()))?;
+
+    Ok((invoice, prev_seal))
+}
+
+/// Check the blinded seal of the invoice is one of the wallet seals, so the
+/// invoice was created by the wallet. Invoices to an address are not checked.
+pub fn check_wallet_invoice(invoice: &RgbInvoice, stock: &Stock) -> Result<(), NewInvoiceError> {
+    let invoice_seal = match invoice.beneficiary {
+        Beneficiary::BlindedSeal(seal) => seal,
+        _ => return Ok(()),
+    };
+    if !wallet_seals(stock)?
+        .iter()
+        .any(|seal| seal.to_concealed_seal() == invoice_seal)
+    {
+        return Err(NewInvoiceError::ForeignSeal(invoice_seal.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Check the transfer doesn't pay to any of the revoked seals
+pub fn check_revoked_seals(
+    transfer: &str,
+    revoked: &BTreeSet<String>,
+) -> Result<(), AcceptTransferError> {
+    let revoked: Vec<SecretSeal> = revoked
+        .iter()
+        .filter_map(|seal| SecretSeal::from_str(seal).ok())
+        .collect();
+    if revoked.is_empty() {
+        return Ok(());
+    }
+
+    let transfer_extracted = prebuild_extract_transfer(transfer)
+        .map_err(|op| AcceptTransferError::WrongConsig(op.to_string()))?;
+    let transfer = transfer_extracted.transfer.unbindle();
+    if let Some(seal) = revoked
+        .iter()
+        .find(|seal| seals_paid_amount(&transfer, &[**seal]) > 0)
+    {
+        return Err(AcceptTransferError::RevokedSeal(seal.to_string()));
+    }
+
+    Ok(())
+}
+
+// Seal of a new invoice, blinded with the number of invoices already created
+// for its outpoint
+fn new_seal_definition(
+    seal: &str,
+    secret: &[u8],
+    stock: &mut Stock,
+) -> Result<SealDefinition, NewInvoiceError> {
+    let nonce = seal_usage(seal, stock)? as u32;
+    SealDefinition::from_invoice_seal(seal, secret, nonce).map_err(NewInvoiceError::InvalidSeal)
+}
+
+/// Count how many invoices were created for the outpoint of the seal
+pub fn seal_usage(seal: &str, stock: &mut Stock) -> Result<usize, NewInvoiceError> {
+    let (_, outpoint) =
+        parse_invoice_seal(seal).map_err(|_| NewInvoiceError::WrongSeal(seal.to_string()))?;
+
+    Ok(seals_usage(stock)?
+        .get(&outpoint.to_string())
+        .copied()
+        .unwrap_or_default())
+}
+
+/// Seals of the invoices created by the wallet
+pub fn wallet_seals(stock: &Stock) -> Result<Vec<SealDefinition>, NewInvoiceError> {
+    let seals = stock
+        .seal_secrets()
+        .map_err(|err| NewInvoiceError::RetrieveSeals(err.to_string()))?;
+
+    Ok(seals
+        .into_iter()
+        .filter_map(|seal| SealDefinition::try_from(seal).ok())
+        .collect())
+}
+
+/// List the outpoints used by more than one invoice
+pub fn reused_seals(stock: &mut Stock) -> Result<BTreeMap<String, usize>, NewInvoiceError> {
+    let mut usage = seals_usage(stock)?;
+    usage.retain(|_, count| *count > 1);
+    Ok(usage)
+}
+
+fn seals_usage(stock: &mut Stock) -> Result<BTreeMap<String, usize>, NewInvoiceError> {
+    let mut usage = BTreeMap::<String, usize>::new();
+    for seal in wallet_seals(stock)? {
+        *usage.entry(seal.outpoint.to_string()).or_default() += 1;
+    }
+    Ok(usage)
+}
+
+/// Check the invoice can be paid from a wallet of the network.
+///
+/// Invoices without chain are payable on the bitcoin mainnet.
+pub fn check_invoice_chain(invoice: &RgbInvoice, network: &str) -> Result<(), NewPaymentError> {
+    let wallet =
+        Chain::from_str(network).map_err(|op| NewPaymentError::WrongNetwork(op.to_string()))?;
+    let invoice = invoice.chain.unwrap_or(Chain::Bitcoin);
+    if invoice != wallet {
+        return Err(NewPaymentError::ChainMismatch {
+            invoice: invoice.to_string(),
+            wallet: wallet.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+const INVOICE_ORIGIN_TAG: &str = "bitmask:invoice-origin";
+
+// Message signed by the origin of the invoice: the beneficiary, so the
+// signature cannot be copied to the invoices of another wallet
+fn origin_message(invoice: &RgbInvoice) -> Vec<u8> {
+    format!("{INVOICE_ORIGIN_TAG}\n{}", invoice.beneficiary).into_bytes()
+}
+
+/// Sign the beneficiary of the invoice with the wallet key.
+///
+/// The x-only pubkey of the key and the signature are added in the `origin`
+/// and `origin_sig` params: the pubkey identifies the wallet across its
+/// invoices (the blinded seals change on every invoice), e.g. to the
+/// counterparty policy of the payer.
+pub fn sign_invoice_origin(invoice: &mut RgbInvoice, sk: &str) -> Result<(), NewInvoiceError> {
+    let secp = Secp256k1::new();
+    let keypair = KeyPair::from_seckey_str(&secp, sk)
+        .map_err(|err| NewInvoiceError::WrongOrigin(err.to_string()))?;
+    let origin = keypair.x_only_public_key().0.to_string();
+    let sig = sign_response(&origin_message(invoice), sk)
+        .map_err(|err| NewInvoiceError::WrongOrigin(err.to_string()))?;
+
+    invoice
+        .unknown_query
+        .insert(RGB_ORIGIN_PARAM.to_string(), origin);
+    invoice
+        .unknown_query
+        .insert(RGB_ORIGIN_SIG_PARAM.to_string(), sig);
+    Ok(())
+}
+
+/// Pubkey of the wallet which created the invoice, if the invoice carries
+/// a valid origin signature (see [`sign_invoice_origin`])
+pub fn invoice_origin(invoice: &RgbInvoice) -> Option<String> {
+    let origin = invoice.unknown_query.get(RGB_ORIGIN_PARAM)?;
+    let sig = invoice.unknown_query.get(RGB_ORIGIN_SIG_PARAM)?;
+    verify_response(&origin_message(invoice), sig, origin).ok()?;
+
+    Some(origin.to_owned())
+}
+
+/// Origin pubkey (x-only or compressed) in the x-only form of the invoices
+pub fn normalize_origin(pubkey: &str) -> Option<String> {
+    match XOnlyPublicKey::from_str(pubkey) {
+        Ok(pubkey) => Some(pubkey.to_string()),
+        Err(_) => PublicKey::from_str(pubkey)
+            .ok()
+            .map(|pubkey| pubkey.x_only_public_key().0.to_string()),
+    }
+}
+
+/// Policy deciding which counterparties can receive the assets of a contract
+pub trait CounterpartyPolicy: Debug + Send + Sync {
+    /// Whether the wallet with the `origin` pubkey (see [`invoice_origin`])
+    /// can receive the assets of the contract. Invoices without a valid
+    /// origin are unidentified.
+    fn allows(&self, contract_id: &ContractId, origin: Option<&str>) -> bool;
+}
+
+impl CounterpartyPolicy for RgbCounterparties {
+    fn allows(&self, contract_id: &ContractId, origin: Option<&str>) -> bool {
+        let contract_id = contract_id.to_string();
+        if let (Some(deny), Some(origin)) = (self.deny.get(&contract_id), origin) {
+            if deny.contains(origin) {
+                return false;
+            }
+        }
+
+        // Unidentified counterparties are not in any allowlist
+        match self.allow.get(&contract_id) {
+            Some(allow) if !allow.is_empty() => origin
+                .map(|origin| allow.contains(origin))
+                .unwrap_or_default(),
+            _ => true,
+        }
+    }
+}
+
+pub fn check_counterparty(
+    invoice: &RgbInvoice,
+    policy: &dyn CounterpartyPolicy,
+) -> Result<(), NewPaymentError> {
+    let contract_id = invoice.contract.ok_or(NewPaymentError::Invalid)?;
+    let origin = invoice_origin(invoice);
+    if !policy.allows(&contract_id, origin.as_deref()) {
+        let counterparty = origin.unwrap_or_else(|| invoice.beneficiary.to_string());
+        return Err(NewPaymentError::CounterpartyDenied(counterparty));
+    }
+
+    Ok(())
+}
+
 pub fn pay_invoice(
     invoice: String,
     psbt: String,
@@ -153,15 +588,319 @@ pub fn pay_invoice(
     let mut psbt_final =
         PSBT::deserialize(&psbt).map_err(|err| NewPaymentError::WrongPSBT(err.to_string()))?;
 
+    for invoice in [&invoice].into_iter().chain(&options.other_invoices) {
+        let contract_id = match invoice.contract {
+            Some(contract_id) => contract_id,
+            None => continue,
+        };
+        if let Some(schema_id) = unavailable_schema(contract_id, stock) {
+            return Err(NewPaymentError::SchemaUnavailable(
+                contract_id.to_string(),
+                schema_id.to_string(),
+            ));
+        }
+        check_iface_version(invoice, stock)?;
+    }
+
+    if let Some(policy) = &options.counterparty_policy {
+        for invoice in [&invoice].into_iter().chain(&options.other_invoices) {
+            check_counterparty(invoice, policy.as_ref())?;
+        }
+    }
+
+    check_timelock_beneficiary(&invoice, &psbt_final)?;
+
+    if !options.from_seals.is_empty() {
+        verify_from_seals(&invoice, &psbt_final, &options.from_seals, stock)?;
+    }
+
+    if !options.change_split.is_empty() {
+        verify_change_split(&invoice, &psbt_final, &options, stock)?;
+        add_change_split_outputs(&mut psbt_final, options.change_split.len())?;
+    }
+
     let transfers = stock
         .pay_all(invoice, &mut psbt_final, CloseMethod::TapretFirst, options)
-        .map_err(|err| NewPaymentError::NoPay(err.to_string()))?;
+        .map_err(|err| match err {
+            PayAllError::ChangeSplitMismatch { expected, actual } => {
+                NewPaymentError::ChangeSplitMismatch { expected, actual }
+            }
+            PayAllError::Pay(err) => NewPaymentError::NoPay(err.to_string()),
+        })?;
 
     let psbt_file = Psbt::from_str(&PSBT::serialize(&psbt_final).to_hex())
         .map_err(|err| NewPaymentError::WrongPSBT(err.to_string()))?;
     Ok((psbt_file, transfers))
 }
 
+// Ensure the change split sums up to the change left by the PSBT inputs, once
+// paid the invoice and the other invoices of the contract
+fn verify_change_split(
+    invoice: &RgbInvoice,
+    psbt: &PSBT,
+    options: &NewTransferOptions,
+    stock: &mut Stock,
+) -> Result<(), NewPaymentError> {
+    if options.change_split.contains(&0) {
+        return Err(NewPaymentError::EmptyChangeSplit);
+    }
+
+    let expected = stock
+        .payment_change(invoice, psbt, &options.other_invoices)
+        .ok_or(NewPaymentError::Invalid)?;
+    let actual = options.change_split.iter().sum();
+    if actual != expected {
+        return Err(NewPaymentError::ChangeSplitMismatch { expected, actual });
+    }
+
+    Ok(())
+}
+
+// Give each allocation of the change split its own output, paying to the next
+// addresses of the account of the largest change output of the wallet. The
+// outputs share its value, less the fee of the outputs added
+fn add_change_split_outputs(psbt: &mut PSBT, allocations: usize) -> Result<(), NewPaymentError> {
+    let is_change = |output: &PsbtOutput| {
+        !output.bip32_derivation.is_empty() || !output.tap_key_origins.is_empty()
+    };
+    let change_outputs = psbt
+        .outputs
+        .iter()
+        .filter(|output| is_change(output))
+        .count();
+    if change_outputs >= allocations {
+        return Ok(());
+    }
+
+    let missing = allocations - change_outputs;
+    let vout = psbt
+        .outputs
+        .iter()
+        .zip(psbt.unsigned_tx.output.iter())
+        .enumerate()
+        .filter(|(_, (output, _))| is_change(output))
+        .max_by_key(|(_, (_, txout))| txout.value)
+        .map(|(vout, _)| vout)
+        .ok_or(NewPaymentError::NoChangeSplitOutput(allocations))?;
+    let added =
+        derive_change_outputs(psbt, vout, missing).ok_or(NewPaymentError::NoChangeSplitKey)?;
+
+    let added_weight = added
+        .iter()
+        .map(|(_, txout)| serialize(txout).len() as u64 * 4)
+        .sum();
+    let value = psbt.unsigned_tx.output[vout]
+        .value
+        .checked_sub(fee_of_weight(psbt, added_weight))
+        .ok_or(NewPaymentError::NoChangeSplitOutput(allocations))?;
+    let share = value / (missing as u64 + 1);
+    if share < DUST_LIMIT_SATOSHI {
+        return Err(NewPaymentError::NoChangeSplitOutput(allocations));
+    }
+
+    psbt.unsigned_tx.output[vout].value = value - share * missing as u64;
+    for (output, mut txout) in added {
+        txout.value = share;
+        psbt.outputs.push(output);
+        psbt.unsigned_tx.output.push(txout);
+    }
+
+    Ok(())
+}
+
+fn output_key_source(output: &PsbtOutput) -> Option<&KeySource> {
+    output.bip32_derivation.values().next().or_else(|| {
+        output
+            .tap_key_origins
+            .values()
+            .next()
+            .map(|(_, source)| source)
+    })
+}
+
+// Outputs like the change output `vout`, each paying to the next unused index
+// (in the PSBT) of its derivation branch, as derived from the account xpub
+fn derive_change_outputs(
+    psbt: &PSBT,
+    vout: usize,
+    count: usize,
+) -> Option<Vec<(PsbtOutput, TxOut)>> {
+    let (fingerprint, path) = output_key_source(&psbt.outputs[vout])?;
+    let (xpub, account_path) = psbt
+        .xpub
+        .iter()
+        .find(|(_, (account_fp, account_path))| {
+            account_fp == fingerprint && path.as_ref().starts_with(account_path.as_ref())
+        })
+        .map(|(xpub, (_, account_path))| (xpub, account_path))?;
+    let (_, branch) = path.as_ref()[account_path.as_ref().len()..].split_last()?;
+
+    let prefix = &path.as_ref()[..path.as_ref().len() - 1];
+    let last_index = psbt
+        .outputs
+        .iter()
+        .filter_map(output_key_source)
+        .filter(|(fp, path)| fp == fingerprint && path.as_ref().starts_with(prefix))
+        .filter_map(|(_, path)| match path.as_ref().get(prefix.len()) {
+            Some(ChildNumber::Normal { index }) => Some(*index),
+            _ => None,
+        })
+        .max()?;
+
+    // Copies do not host the tapret commitment of the transfer
+    let mut template = psbt.outputs[vout].clone();
+    template
+        .proprietary
+        .retain(|key, _| key.prefix != PSBT_TAPRET_PREFIX.to_vec());
+    let template_txout = &psbt.unsigned_tx.output[vout];
+
+    let secp = Secp256k1::new();
+    (1..=count as u32)
+        .map(|offset| {
+            let index = ChildNumber::from_normal_idx(last_index.checked_add(offset)?).ok()?;
+            let relative: Vec<_> = branch.iter().copied().chain(iter::once(index)).collect();
+            let key = xpub.derive_pub(&secp, &relative).ok()?.public_key;
+            let source = (*fingerprint, account_path.extend(&relative));
+
+            let mut output = template.clone();
+            let mut txout = template_txout.clone();
+            output.bip32_derivation.clear();
+            output.tap_key_origins.clear();
+            if !template.bip32_derivation.is_empty() {
+                output.bip32_derivation.insert(key, source.clone());
+            }
+            if template_txout.script_pubkey.is_v1_p2tr() {
+                let (internal_key, _) = key.x_only_public_key();
+                output.tap_internal_key = Some(internal_key);
+                if !template.tap_key_origins.is_empty() {
+                    output
+                        .tap_key_origins
+                        .insert(internal_key, (vec![], source));
+                }
+                txout.script_pubkey = ScriptBuf::new_v1_p2tr(&secp, internal_key, None);
+            } else {
+                let pubkey_hash = bitcoin_30::PublicKey::new(key).wpubkey_hash()?;
+                txout.script_pubkey = ScriptBuf::new_v0_p2wpkh(&pubkey_hash);
+            }
+            Some((output, txout))
+        })
+        .collect()
+}
+
+// Fee of `weight` more weight units, at the fee rate paid by the PSBT (once
+// its inputs are signed, as taproot key spends). Zero when the value of an
+// input is unknown
+fn fee_of_weight(psbt: &PSBT, weight: u64) -> u64 {
+    const KEY_SPEND_WITNESS_WEIGHT: u64 = 66;
+    const SEGWIT_MARKER_WEIGHT: u64 = 2;
+
+    let inputs: Option<u64> = psbt
+        .inputs
+        .iter()
+        .zip(psbt.unsigned_tx.input.iter())
+        .map(|(input, txin)| {
+            input
+                .witness_utxo
+                .as_ref()
+                .map(|txout| txout.value)
+                .or_else(|| {
+                    let prev_tx = input.non_witness_utxo.as_ref()?;
+                    let prev_out = prev_tx.output.get(txin.previous_output.vout as usize)?;
+                    Some(prev_out.value)
+                })
+        })
+        .sum();
+    let outputs: u64 = psbt
+        .unsigned_tx
+        .output
+        .iter()
+        .map(|txout| txout.value)
+        .sum();
+    let fee = match inputs.and_then(|inputs| inputs.checked_sub(outputs)) {
+        Some(fee) => fee,
+        None => return 0,
+    };
+
+    let tx_weight = psbt.unsigned_tx.weight().to_wu()
+        + SEGWIT_MARKER_WEIGHT
+        + KEY_SPEND_WITNESS_WEIGHT * psbt.inputs.len() as u64;
+    (fee * weight + tx_weight - 1) / tx_weight
+}
+
+// Time-locked outputs of the PSBT (see `timelock_script`) only guard the
+// assets of invoices paying to them
+fn check_timelock_beneficiary(invoice: &RgbInvoice, psbt: &PSBT) -> Result<(), NewPaymentError> {
+    let locked: Vec<_> = psbt
+        .outputs
+        .iter()
+        .zip(psbt.unsigned_tx.output.iter())
+        .filter(|(output, _)| {
+            output
+                .proprietary
+                .keys()
+                .any(|key| key.prefix == RGB_PSBT_TIMELOCK.as_bytes())
+        })
+        .map(|(_, txout)| txout.script_pubkey.clone())
+        .collect();
+    if locked.is_empty() {
+        return Ok(());
+    }
+
+    match &invoice.beneficiary {
+        Beneficiary::WitnessUtxo(addr) if locked.contains(&addr.script_pubkey()) => Ok(()),
+        _ => Err(NewPaymentError::UnboundTimelock),
+    }
+}
+
+// Ensure the contract state spent by PSBT comes only from the selected seals
+fn verify_from_seals(
+    invoice: &RgbInvoice,
+    psbt: &PSBT,
+    from_seals: &[Outpoint],
+    stock: &mut Stock,
+) -> Result<(), NewPaymentError> {
+    let contract_id = invoice.contract.ok_or(NewPaymentError::Invalid)?;
+    let prev_outputs = psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .map(|txin| txin.previous_output)
+        .map(|outpoint| Outpoint::new(outpoint.txid.to_byte_array().into(), outpoint.vout));
+
+    let mut available = 0;
+    for outpoint in prev_outputs {
+        let state = stock
+            .state_for_outpoints(contract_id, [outpoint])
+            .map_err(|err| NewPaymentError::NoPay(err.to_string()))?;
+        if state.is_empty() {
+            continue;
+        }
+
+        if !from_seals.contains(&outpoint) {
+            return Err(NewPaymentError::UnselectedSeal(outpoint.to_string()));
+        }
+
+        available += state
+            .values()
+            .map(|state| match state {
+                TypedState::Amount(value) => *value,
+                _ => 0,
+            })
+            .sum::<u64>();
+    }
+
+    if let TypedState::Amount(expected) = invoice.owned_state {
+        if available < expected {
+            return Err(NewPaymentError::InsufficientSeals {
+                expected,
+                available,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 pub fn validate_transfer<R: ResolveTx>(
     transfer: String,
     resolver: &mut R,
@@ -193,6 +932,26 @@ pub fn accept_transfer<T>(
     resolver: &mut T,
     stock: &mut Stock,
 ) -> Result<Bindle<Transfer>, AcceptTransferError>
+where
+    T: ResolveHeight + ResolveTx,
+    T::Error: 'static,
+{
+    let (consig, _) = check_transfer(transfer, resolver)?;
+
+    let bindle = Bindle::new(consig.clone());
+    match stock.accept_transfer(consig, resolver, force) {
+        Ok(_) => Ok(bindle),
+        Err(err) => Err(AcceptTransferError::InvalidConsig(vec![err.to_string()])),
+    }
+}
+
+/// Validate a transfer consignment, without touching the stock.
+///
+/// Returns the validated transfer and the validation warnings.
+pub fn check_transfer<T>(
+    transfer: String,
+    resolver: &mut T,
+) -> Result<(Transfer, Vec<String>), AcceptTransferError>
 where
     T: ResolveHeight + ResolveTx,
     T::Error: 'static,
@@ -212,11 +971,481 @@ where
         }
     })?;
 
-    let bindle = Bindle::new(consig.clone());
-    match stock.accept_transfer(consig, resolver, force) {
-        Ok(_) => Ok(bindle),
-        Err(err) => Err(AcceptTransferError::InvalidConsig(vec![err.to_string()])),
+    let warnings = consig
+        .validation_status()
+        .map(|status| status.warnings.iter().map(|x| x.to_string()).collect())
+        .unwrap_or_default();
+    Ok((consig, warnings))
+}
+
+/// Check the structure of a (strict serialized) transfer consignment, without
+/// resolving its witness transactions.
+///
+/// Returns the contract id of the consignment.
+pub fn check_consignment_structure(consignment: &[u8]) -> Result<ContractId, AcceptTransferError> {
+    let confined = Confined::try_from_iter(consignment.iter().copied())
+        .map_err(|err| AcceptTransferError::WrongConsig(err.to_string()))?;
+    let transfer = Transfer::from_strict_serialized::<{ U32 }>(confined)
+        .map_err(|err| AcceptTransferError::WrongConsig(err.to_string()))?;
+
+    if transfer.terminals.is_empty() {
+        return Err(AcceptTransferError::InvalidConsig(vec![
+            "consignment has no terminals".to_string(),
+        ]));
+    }
+
+    let bundle_ids: Vec<_> = transfer
+        .bundles
+        .iter()
+        .map(|anchored| anchored.bundle.bundle_id())
+        .collect();
+    let missing: Vec<String> = transfer
+        .terminals
+        .keys()
+        .filter(|bundle_id| !bundle_ids.contains(bundle_id))
+        .map(|bundle_id| format!("terminal bundle {bundle_id} is not in the consignment"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AcceptTransferError::InvalidConsig(missing));
+    }
+
+    Ok(transfer.contract_id())
+}
+
+/// Sum the amount assigned by the transfer to the invoice beneficiary.
+///
+/// Only blinded seals can be matched against the consignment, so witness
+/// (address) beneficiaries return `None`.
+pub fn invoice_paid_amount(transfer: &Transfer, invoice: &RgbInvoice) -> Option<u64> {
+    let beneficiary = match invoice.beneficiary {
+        Beneficiary::BlindedSeal(seal) => seal,
+        _ => return None,
+    };
+
+    if invoice.contract != Some(transfer.contract_id()) {
+        return Some(0);
+    }
+
+    Some(seals_paid_amount(transfer, &[beneficiary]))
+}
+
+/// Sum the amount assigned by the transfer to any of the given seals.
+///
+/// Each non-fungible assignment counts as one unit.
+pub fn seals_paid_amount(transfer: &Transfer, seals: &[SecretSeal]) -> u64 {
+    let mut paid = 0;
+    for (bundle_id, _) in transfer.terminals() {
+        let transitions = transfer
+            .known_transitions_by_bundle_id(bundle_id)
+            .unwrap_or_default();
+        for transition in transitions {
+            for (_, assigns) in transition.assignments.iter() {
+                paid += assigns
+                    .as_fungible()
+                    .iter()
+                    .filter(|assign| seals.contains(&assign.to_confidential_seal()))
+                    .filter_map(|assign| assign.as_revealed_state())
+                    .map(|state| state.value.as_u64())
+                    .sum::<u64>();
+                paid += assigns
+                    .as_structured()
+                    .iter()
+                    .filter(|assign| seals.contains(&assign.to_confidential_seal()))
+                    .count() as u64;
+            }
+        }
+    }
+
+    paid
+}
+
+/// Sum the amount the consignment assigns to any of the given outpoints,
+/// from the genesis or from any of its (revealed) transitions.
+///
+/// Returns the amount and the (revealed) transition seals holding it. Each
+/// non-fungible assignment counts as one unit.
+pub fn outpoints_owned_state<const TYPE: bool>(
+    consignment: &Consignment<TYPE>,
+    outpoints: &[Outpoint],
+) -> (u64, Vec<GraphSeal>) {
+    let mut owned = 0;
+    let is_owned = |seal: Option<GenesisSeal>| {
+        seal.and_then(|seal| seal.outpoint())
+            .is_some_and(|outpoint| outpoints.contains(&outpoint))
+    };
+    for (_, assigns) in consignment.genesis.assignments.iter() {
+        owned += assigns
+            .as_fungible()
+            .iter()
+            .filter(|assign| is_owned(assign.revealed_seal()))
+            .filter_map(|assign| assign.as_revealed_state())
+            .map(|state| state.value.as_u64())
+            .sum::<u64>();
+        owned += assigns
+            .as_structured()
+            .iter()
+            .filter(|assign| is_owned(assign.revealed_seal()))
+            .count() as u64;
+    }
+
+    let mut seals = vec![];
+    for AnchoredBundle { anchor, bundle } in consignment.bundles.iter() {
+        let transitions = consignment
+            .known_transitions_by_bundle_id(bundle.bundle_id())
+            .unwrap_or_default();
+        // Seals without txid are closed over outputs of the witness transaction
+        let owned_seal = |seal: Option<GraphSeal>| {
+            seal.filter(|seal| outpoints.contains(&seal.outpoint_or(anchor.txid)))
+        };
+        for transition in transitions {
+            for (_, assigns) in transition.assignments.iter() {
+                for assign in assigns.as_fungible() {
+                    if let Some(seal) = owned_seal(assign.revealed_seal()) {
+                        owned += assign
+                            .as_revealed_state()
+                            .map(|state| state.value.as_u64())
+                            .unwrap_or_default();
+                        seals.push(seal);
+                    }
+                }
+                for assign in assigns.as_structured() {
+                    if let Some(seal) = owned_seal(assign.revealed_seal()) {
+                        owned += 1;
+                        seals.push(seal);
+                    }
+                }
+            }
+        }
+    }
+
+    (owned, seals)
+}
+
+/// Witness transactions anchoring the terminal bundles of the transfer
+pub fn witness_txids(transfer: &Transfer) -> BTreeSet<Txid> {
+    transfer
+        .terminals()
+        .filter_map(|(bundle_id, _)| transfer.anchored_bundle(bundle_id))
+        .map(|AnchoredBundle { anchor, .. }| anchor.txid)
+        .collect()
+}
+
+/// Outpoints holding the (revealed) state assigned by the bundles anchored
+/// to any of the given witness transactions.
+pub fn witness_outpoints<const TYPE: bool>(
+    consignment: &Consignment<TYPE>,
+    txids: &[Txid],
+) -> BTreeSet<Outpoint> {
+    let mut outpoints = bset![];
+    for AnchoredBundle { anchor, bundle } in consignment.bundles.iter() {
+        if !txids.contains(&anchor.txid) {
+            continue;
+        }
+        let transitions = consignment
+            .known_transitions_by_bundle_id(bundle.bundle_id())
+            .unwrap_or_default();
+        for transition in transitions {
+            for (_, assigns) in transition.assignments.iter() {
+                let fungible = assigns
+                    .as_fungible()
+                    .iter()
+                    .filter_map(|assign| assign.revealed_seal());
+                let structured = assigns
+                    .as_structured()
+                    .iter()
+                    .filter_map(|assign| assign.revealed_seal());
+                outpoints.extend(
+                    fungible
+                        .chain(structured)
+                        .map(|seal| seal.outpoint_or(anchor.txid)),
+                );
+            }
+        }
+    }
+
+    outpoints
+}
+
+/// Operation leading to an allocation, anchored to a bitcoin transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProvenanceLink {
+    pub opid: OpId,
+    /// Witness transaction of the transition (or the transaction of the
+    /// issuance outpoint, for the genesis). `None` when it is unknown.
+    pub txid: Option<Txid>,
+    pub is_genesis: bool,
+    /// The operation is not part of the consignment, so the history before it
+    /// is unknown
+    pub is_gap: bool,
+}
+
+/// Operations leading to the state assigned to the outpoint, in topological
+/// order: every operation comes after the operations it spends, so the
+/// history starts with the genesis. Operations missing in the consignment are
+/// linked as gaps.
+///
+/// Returns `None` when the consignment assigns no (revealed) state to the
+/// outpoint.
+pub fn provenance_links<const TYPE: bool>(
+    consignment: &Consignment<TYPE>,
+    outpoint: Outpoint,
+) -> Option<Vec<ProvenanceLink>> {
+    let genesis_id = consignment.genesis.id();
+    let mut genesis_txids = bset![];
+    let mut heads = vec![];
+    for (_, assigns) in consignment.genesis.assignments.iter() {
+        let fungible = assigns
+            .as_fungible()
+            .iter()
+            .filter_map(|assign| assign.revealed_seal());
+        let structured = assigns
+            .as_structured()
+            .iter()
+            .filter_map(|assign| assign.revealed_seal());
+        if fungible
+            .chain(structured)
+            .filter_map(|seal| seal.outpoint())
+            .any(|seal| seal == outpoint)
+        {
+            genesis_txids.insert(outpoint.txid);
+            if !heads.contains(&genesis_id) {
+                heads.push(genesis_id);
+            }
+        }
+    }
+
+    let mut known = BTreeMap::new();
+    for AnchoredBundle { anchor, bundle } in consignment.bundles.iter() {
+        let transitions = consignment
+            .known_transitions_by_bundle_id(bundle.bundle_id())
+            .unwrap_or_default();
+        for transition in transitions {
+            let opid = transition.id();
+            known.insert(opid, (anchor.txid, transition));
+            for (_, assigns) in transition.assignments.iter() {
+                let fungible = assigns
+                    .as_fungible()
+                    .iter()
+                    .filter_map(|assign| assign.revealed_seal());
+                let structured = assigns
+                    .as_structured()
+                    .iter()
+                    .filter_map(|assign| assign.revealed_seal());
+                if fungible
+                    .chain(structured)
+                    .any(|seal| seal.outpoint_or(anchor.txid) == outpoint)
+                    && !heads.contains(&opid)
+                {
+                    heads.push(opid);
+                }
+            }
+        }
+    }
+
+    if heads.is_empty() {
+        return None;
     }
+
+    // Seal of the genesis spent by a transition of the history
+    let genesis_seal = |ty, no: u16| {
+        let assigns = consignment.genesis.assignments.get(&ty)?;
+        let fungible = assigns
+            .as_fungible()
+            .get(no as usize)
+            .and_then(|assign| assign.revealed_seal());
+        let structured = assigns
+            .as_structured()
+            .get(no as usize)
+            .and_then(|assign| assign.revealed_seal());
+        fungible.or(structured)?.outpoint()
+    };
+
+    // Walk back the inputs of the transitions (depth first), and sort the
+    // operations topologically: each one comes after the operations it spends
+    let mut visited = bset![];
+    let mut sorted = vec![];
+    let mut stack: Vec<(OpId, bool)> = heads.into_iter().rev().map(|opid| (opid, false)).collect();
+    while let Some((opid, expanded)) = stack.pop() {
+        if expanded {
+            sorted.push(opid);
+            continue;
+        }
+        if !visited.insert(opid) {
+            continue;
+        }
+
+        stack.push((opid, true));
+        if let Some((_, transition)) = known.get(&opid) {
+            let mut inputs: Vec<_> = transition
+                .inputs
+                .iter()
+                .map(|input| input.prev_out)
+                .collect();
+            inputs.sort();
+            for prev_out in inputs.into_iter().rev() {
+                if prev_out.op == genesis_id {
+                    if let Some(seal) = genesis_seal(prev_out.ty, prev_out.no) {
+                        genesis_txids.insert(seal.txid);
+                    }
+                }
+                if !visited.contains(&prev_out.op) {
+                    stack.push((prev_out.op, false));
+                }
+            }
+        }
+    }
+
+    // The history usually reaches a single issuance transaction, the genesis
+    // one is unknown when it spends the seals of several of them
+    let genesis_txid = match genesis_txids.len() {
+        1 => genesis_txids.pop_first(),
+        _ => None,
+    };
+
+    let links = sorted
+        .into_iter()
+        .map(|opid| {
+            if opid == genesis_id {
+                return ProvenanceLink {
+                    opid,
+                    txid: genesis_txid,
+                    is_genesis: true,
+                    is_gap: false,
+                };
+            }
+
+            match known.get(&opid) {
+                Some((txid, _)) => ProvenanceLink {
+                    opid,
+                    txid: Some(*txid),
+                    is_genesis: false,
+                    is_gap: false,
+                },
+                None => ProvenanceLink {
+                    opid,
+                    txid: None,
+                    is_genesis: false,
+                    is_gap: true,
+                },
+            }
+        })
+        .collect();
+    Some(links)
+}
+
+/// Check the PSBT is the witness transaction of the consignment, before it
+/// gets broadcasted: its txid is the anchor of every terminal bundle, its
+/// tapret output commits to the bundles of the anchors, and every seal closed
+/// over the witness transaction points to one of its outputs.
+pub fn verify_psbt_matches_consignment(
+    psbt: &str,
+    consignment: &str,
+) -> Result<(), PsbtConsignmentError> {
+    let psbt =
+        Psbt::from_str(psbt).map_err(|err| PsbtConsignmentError::WrongPsbt(err.to_string()))?;
+    let serialized = Vec::<u8>::from_hex(consignment)
+        .map_err(|err| PsbtConsignmentError::WrongConsig(err.to_string()))?;
+    let confined = Confined::try_from_iter(serialized.iter().copied())
+        .map_err(|err| PsbtConsignmentError::WrongConsig(err.to_string()))?;
+    let transfer = Transfer::from_strict_serialized::<{ U32 }>(confined)
+        .map_err(|err| PsbtConsignmentError::WrongConsig(err.to_string()))?;
+
+    let tapret_key = |subtype| ProprietaryKey {
+        prefix: PSBT_TAPRET_PREFIX.to_vec(),
+        subtype,
+        key: vec![],
+    };
+    let commitments: Vec<_> = psbt
+        .outputs
+        .iter()
+        .filter(|output| {
+            output
+                .proprietary
+                .contains_key(&tapret_key(PSBT_OUT_TAPRET_HOST))
+        })
+        .filter_map(|output| {
+            output
+                .proprietary
+                .get(&tapret_key(PSBT_OUT_TAPRET_COMMITMENT))
+        })
+        .collect();
+    if commitments.is_empty() {
+        return Err(PsbtConsignmentError::NoCommitment);
+    }
+
+    let txid = Txid::from_str(&psbt.to_txid().to_string())
+        .map_err(|err| PsbtConsignmentError::WrongPsbt(err.to_string()))?;
+    let outputs = psbt.outputs.len();
+
+    let mut terminals = 0;
+    for (bundle_id, _) in transfer.terminals() {
+        terminals += 1;
+        let AnchoredBundle { anchor, bundle: _ } = transfer
+            .anchored_bundle(bundle_id)
+            .ok_or(PsbtConsignmentError::NoAnchor(bundle_id.to_string()))?;
+        if anchor.txid != txid {
+            return Err(PsbtConsignmentError::TxidMismatch {
+                psbt: txid.to_string(),
+                anchor: anchor.txid.to_string(),
+            });
+        }
+
+        // Commitment of the anchor, from the bundle and its merkle proof
+        let commitment = anchor
+            .convolve(transfer.contract_id(), bundle_id)
+            .map_err(|_| PsbtConsignmentError::WrongAnchor(bundle_id.to_string()))?;
+        if !commitments
+            .iter()
+            .any(|commit| commit.as_slice() == commitment.as_slice())
+        {
+            return Err(PsbtConsignmentError::CommitmentMismatch(
+                bundle_id.to_string(),
+            ));
+        }
+
+        let transitions = transfer
+            .known_transitions_by_bundle_id(bundle_id)
+            .unwrap_or_default();
+        for transition in transitions {
+            for (_, assigns) in transition.assignments.iter() {
+                let seals = assigns
+                    .as_fungible()
+                    .iter()
+                    .filter_map(|assign| assign.revealed_seal())
+                    .chain(
+                        assigns
+                            .as_structured()
+                            .iter()
+                            .filter_map(|assign| assign.revealed_seal()),
+                    );
+                for seal in seals {
+                    let vout = seal.vout.into_u32();
+                    if seal.outpoint_or(txid).txid == txid && vout as usize >= outputs {
+                        return Err(PsbtConsignmentError::SealOutOfRange { vout, outputs });
+                    }
+                }
+            }
+        }
+    }
+
+    if terminals == 0 {
+        return Err(PsbtConsignmentError::NoTerminals);
+    }
+
+    Ok(())
+}
+
+/// Compute the deterministic identifier of a consignment.
+///
+/// The id is the blake3 digest of the canonical (strict) encoding of the
+/// transfer, so byte-identical or re-encoded equivalent consignments always
+/// produce the same id. The result is hex encoded and can be used
+/// directly as a carbonado file name.
+///
+/// Fails if the consignment exceeds the strict encoding limits.
+pub fn consignment_id(transfer: &Transfer) -> Result<String, SerializeError> {
+    let canonical = transfer.to_strict_serialized::<{ U32 }>()?;
+    Ok(blake3::hash(&canonical).to_hex().to_string())
 }
 
 pub fn extract_transfer(transfer: String) -> Result<(Txid, Bindle<Transfer>), AcceptTransferError> {
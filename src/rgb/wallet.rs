@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     str::FromStr,
 };
 
@@ -21,10 +21,16 @@ use strict_encoding::tn;
 
 use crate::{
     debug,
-    rgb::{resolvers::ResolveSpent, structs::AddressTerminal},
+    rgb::{
+        constants::RGB_DEFAULT_GAP_LIMIT,
+        prefetch::prefetch_resolver_utxos_range,
+        resolvers::{ExplorerResolver, ResolveSpent},
+        structs::AddressTerminal,
+    },
     structs::{
         AllocationDetail, AllocationValue, TxStatus, UDAPosition, UtxoSpentStatus, WatcherDetail,
     },
+    warn,
 };
 
 pub fn create_wallet(
@@ -218,30 +224,127 @@ pub fn next_utxos(
     Ok(next_utxo)
 }
 
+/// List the derivation terminals (addresses) holding more than one utxo
+pub fn reused_addresses(wallet: &RgbWallet) -> BTreeMap<String, Vec<String>> {
+    let mut usage = BTreeMap::<String, Vec<String>>::new();
+    for utxo in wallet.utxos.iter() {
+        let terminal = utxo.derivation.terminal;
+        usage
+            .entry(format!("/{}/{}", terminal.app, terminal.index))
+            .or_default()
+            .push(utxo.outpoint.to_string());
+    }
+
+    usage.retain(|_, utxos| utxos.len() > 1);
+    usage
+}
+
 pub fn sync_wallet(iface_index: u32, wallet: &mut RgbWallet, resolver: &mut impl Resolver) {
-    let step = 20;
-    let index = 0;
+    sync_wallet_with_gap(iface_index, wallet, resolver, RGB_DEFAULT_GAP_LIMIT);
+}
+
+/// Sync the utxos of the wallet, deriving addresses until `gap_limit` (at
+/// least 1) consecutive ones are unused. Returns the last used index (if any).
+///
+/// Warns when a used address follows a run of unused ones close to the gap
+/// limit: other addresses may be used beyond it, so the gap should be larger.
+pub fn sync_wallet_with_gap(
+    iface_index: u32,
+    wallet: &mut RgbWallet,
+    resolver: &mut impl Resolver,
+    gap_limit: u32,
+) -> Option<u32> {
+    let gap_limit = gap_limit.max(1);
+    let mut start = 0;
+    let mut end = gap_limit;
+    let mut last_used: Option<u32> = None;
+    let mut widest_gap = 0;
+
+    loop {
+        let new_scripts = wallet.descr.derive(iface_index, start..end);
+        let new_utxos = resolver
+            .resolve_utxo(new_scripts)
+            .expect("service unavalible");
+
+        let used: BTreeSet<u32> = new_utxos
+            .iter()
+            .map(|utxo| utxo.derivation.terminal.index)
+            .filter(|index| (start..end).contains(index))
+            .collect();
+        for index in used {
+            let gap = match last_used {
+                Some(last) => index - last - 1,
+                None => index,
+            };
+            widest_gap = widest_gap.max(gap);
+            last_used = Some(index);
+        }
 
-    let new_scripts = wallet.descr.derive(iface_index, index..step);
-    let new_utxos = resolver
-        .resolve_utxo(new_scripts)
-        .expect("service unavalible");
-
-    for mut new_utxo in new_utxos {
-        if let Some(current_utxo) = wallet
-            .utxos
-            .clone()
-            .into_iter()
-            .find(|u| u.outpoint == new_utxo.outpoint)
-        {
-            if current_utxo.status == MiningStatus::Mempool {
-                wallet.utxos.remove(&current_utxo);
-
-                new_utxo.derivation = current_utxo.derivation;
+        for mut new_utxo in new_utxos {
+            if let Some(current_utxo) = wallet
+                .utxos
+                .clone()
+                .into_iter()
+                .find(|u| u.outpoint == new_utxo.outpoint)
+            {
+                if current_utxo.status == MiningStatus::Mempool {
+                    wallet.utxos.remove(&current_utxo);
+
+                    new_utxo.derivation = current_utxo.derivation;
+                    wallet.utxos.insert(new_utxo);
+                }
+            } else {
                 wallet.utxos.insert(new_utxo);
             }
-        } else {
-            wallet.utxos.insert(new_utxo);
+        }
+
+        let next_end = last_used.map_or(gap_limit, |last| last + 1 + gap_limit);
+        if next_end <= end {
+            break;
+        }
+        start = end;
+        end = next_end;
+    }
+
+    if last_used.is_some() && widest_gap >= gap_limit - gap_limit / 4 {
+        warn!(format!(
+            "Used addresses of /{iface_index} are up to {widest_gap} unused ones apart, close to \
+             the gap limit of {gap_limit}: sync with a larger gap to find all the allocations"
+        ));
+    }
+
+    last_used
+}
+
+/// Sync the utxos of the wallet as [`sync_wallet_with_gap`], with the explorer.
+///
+/// On wasm the resolver only knows the prefetched utxos, so the addresses are
+/// prefetched one window at a time, until `gap_limit` unused ones follow the
+/// last used address.
+pub async fn sync_explorer_wallet_with_gap(
+    iface_index: u32,
+    wallet: &mut RgbWallet,
+    resolver: &mut ExplorerResolver,
+    gap_limit: u32,
+) -> Option<u32> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return sync_wallet_with_gap(iface_index, wallet, resolver, gap_limit);
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let gap_limit = gap_limit.max(1);
+        let mut start = 0;
+        let mut end = gap_limit;
+        loop {
+            prefetch_resolver_utxos_range(iface_index, wallet, resolver, start..end).await;
+            let last_used = sync_wallet_with_gap(iface_index, wallet, resolver, gap_limit);
+
+            let next_end = last_used.map_or(gap_limit, |last| last + 1 + gap_limit);
+            if next_end <= end {
+                return last_used;
+            }
+            start = end;
+            end = next_end;
         }
     }
 }
@@ -362,6 +465,7 @@ where
                                 ),
                                 is_mine: true,
                                 is_spent,
+                                is_pending: false,
                             });
                         } else {
                             owners.push(AllocationDetail {
@@ -370,6 +474,7 @@ where
                                 derivation: default!(),
                                 is_mine: false,
                                 is_spent,
+                                is_pending: false,
                             });
                         }
                     }
@@ -401,6 +506,7 @@ where
                                 ),
                                 is_mine: true,
                                 is_spent,
+                                is_pending: false,
                             });
                         } else {
                             owners.push(AllocationDetail {
@@ -409,6 +515,7 @@ where
                                 derivation: default!(),
                                 is_mine: false,
                                 is_spent,
+                                is_pending: false,
                             });
                         }
                     }
@@ -469,6 +576,7 @@ where
                             ),
                             is_mine: true,
                             is_spent,
+                            is_pending: false,
                         });
                     } else {
                         owners.push(AllocationDetail {
@@ -477,6 +585,7 @@ where
                             derivation: default!(),
                             is_mine: false,
                             is_spent,
+                            is_pending: false,
                         });
                     }
                 }
@@ -506,6 +615,7 @@ where
                             ),
                             is_mine: true,
                             is_spent,
+                            is_pending: false,
                         });
                     } else {
                         owners.push(AllocationDetail {
@@ -514,6 +624,7 @@ where
                             derivation: default!(),
                             is_mine: false,
                             is_spent,
+                            is_pending: false,
                         });
                     }
                 }
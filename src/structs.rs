@@ -4,7 +4,10 @@ use garde::Validate;
 use psbt::Psbt;
 use rgb::MiningStatus;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub use bdk::{Balance, BlockTime, TransactionDetails};
@@ -13,7 +16,8 @@ use rgbstd::interface::rgb21::Allocation as AllocationUDA;
 
 use crate::{
     rgb::{
-        structs::MediaMetadata,
+        index::IndexedTransfer,
+        structs::{MediaMetadata, RgbTransfersV1},
         swap::{PublicRgbBid, RgbAuctionStrategy, RgbBid, RgbOffer, RgbOfferSwap, RgbSwapStrategy},
     },
     validators::{
@@ -52,6 +56,23 @@ pub struct TransactionData {
     pub fee_rate: f32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationEstimate {
+    /// Transaction is already confirmed
+    pub confirmed: bool,
+    /// Block height of confirmation (if confirmed)
+    pub block_height: Option<u32>,
+    /// Number of confirmations (if confirmed)
+    pub confirmations: u32,
+    /// Fee rate used in estimation (sat/vB)
+    pub fee_rate: f32,
+    /// Estimated number of blocks until confirmation
+    pub blocks: u32,
+    /// Estimated minutes until confirmation
+    pub minutes: u32,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Zeroize, ZeroizeOnDrop, Display, Default)]
 #[display(inner)]
 pub struct SecretString(pub String);
@@ -155,6 +176,10 @@ pub struct FullIssueRequest {
     /// contract metadata (only RGB21/UDA)
     #[garde(skip)]
     pub meta: Option<MediaRequest>,
+    /// contract terms (only RGB20)
+    #[garde(skip)]
+    #[serde(default)]
+    pub terms: Option<IssueTermsRequest>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -190,6 +215,40 @@ pub struct IssueRequest {
     /// contract metadata (only RGB21/UDA)
     #[garde(custom(verify_media_request))]
     pub meta: Option<IssueMediaRequest>,
+    /// contract terms (only RGB20)
+    #[garde(skip)]
+    #[serde(default)]
+    pub terms: Option<IssueTermsRequest>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueTermsRequest {
+    /// Full text of the terms
+    pub text: Option<String>,
+    /// Terms document (hex), when the terms are not a text
+    pub data: Option<String>,
+    /// Media type of the terms document (`text/plain` by default)
+    pub media_type: Option<String>,
+    /// Terms digest (blake3, hex), checked against the text or the document
+    /// when informed
+    pub hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractTermsResponse {
+    /// The contract id
+    pub contract_id: String,
+    /// Terms digest committed in the contract (blake3, hex)
+    pub hash: Option<String>,
+    /// Media type of the terms document committed in the contract
+    pub media_type: Option<String>,
+    /// Full text of the terms (only if available in storage)
+    pub text: Option<String>,
+    /// Terms document (hex), when the terms are not a text (only if
+    /// available in storage)
+    pub data: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -454,6 +513,56 @@ pub struct ImportRequest {
     pub data: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoredAllocationsResponse {
+    /// Outpoints of the allocations hidden from balances and coin selection
+    pub outpoints: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterpartiesRequest {
+    /// The contract id
+    pub contract_id: String,
+    /// Origin pubkeys of the wallets allowed to receive the contract assets
+    /// (see `InvoiceRequest::origin`). When empty, every wallet not denied
+    /// is allowed
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Origin pubkeys of the wallets denied
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterpartiesResponse {
+    /// The contract id
+    pub contract_id: String,
+    /// Origin pubkeys allowed to receive the contract assets
+    pub allow: Vec<String>,
+    /// Origin pubkeys denied
+    pub deny: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousAllocationsResponse {
+    pub allocations: Vec<SuspiciousAllocation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousAllocation {
+    /// The contract id
+    pub contract_id: String,
+    /// Anchored UTXO
+    pub utxo: String,
+    /// Asset Value (in atomic units)
+    pub value: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ContractHiddenResponse {
@@ -488,6 +597,9 @@ pub struct ContractResponse {
     pub balance: u64,
     /// Current balance (Humanized)
     pub balance_normalized: f64,
+    /// Balance waiting for confirmation, not spendable yet
+    #[serde(default)]
+    pub pending_balance: u64,
     /// The contract allocations
     pub allocations: Vec<AllocationDetail>,
     /// The contract state (multiple formats)
@@ -496,6 +608,10 @@ pub struct ContractResponse {
     pub genesis: GenesisFormats,
     /// contract metadata (only RGB21/UDA)
     pub meta: Option<ContractMediaDetail>,
+    /// The contract schema is unavailable, only the raw state of the genesis
+    /// is shown (see `fetch_schema`)
+    #[serde(default)]
+    pub schema_unavailable: bool,
 }
 
 #[deprecated(
@@ -607,6 +723,22 @@ pub struct InvoiceRequest {
     /// Query parameters
     #[garde(skip)]
     pub params: HashMap<String, String>,
+    /// Version of the contract interface (default: any)
+    #[garde(skip)]
+    #[serde(default)]
+    pub iface_version: Option<u8>,
+    /// Sign the invoice with the wallet key, identifying the wallet to the
+    /// counterparty policy of the payer (the `origin` param)
+    #[garde(skip)]
+    #[serde(default)]
+    pub origin: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RebindInvoiceRequest {
+    /// Invoice encoded in Baid58
+    pub invoice: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -628,6 +760,9 @@ pub struct SelfInvoiceRequest {
 pub struct InvoiceResponse {
     /// Invoice encoded in Baid58
     pub invoice: String,
+    /// The seal outpoint was already used by a previous invoice
+    #[serde(default)]
+    pub reused: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -658,6 +793,22 @@ pub struct PsbtRequest {
     /// Allow RBF
     #[garde(skip)]
     pub rbf: bool,
+    /// Time-locked output of the beneficiary (see [`PsbtTimelockRequest`])
+    #[garde(skip)]
+    #[serde(default)]
+    pub lock: Option<PsbtTimelockRequest>,
+    /// Maximum fee allowed in sats (overrides the default safety cap)
+    #[garde(skip)]
+    #[serde(default)]
+    pub max_fee: Option<u64>,
+    /// Maximum number of outputs of the transaction (overrides the default limit)
+    #[garde(skip)]
+    #[serde(default)]
+    pub max_outputs: Option<usize>,
+    /// Data (hex) carried by an extra OP_RETURN output of the transaction
+    #[garde(skip)]
+    #[serde(default)]
+    pub data_output: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -718,6 +869,43 @@ impl Default for PsbtFeeRequest {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PsbtLockRequest {
+    /// Absolute lock (OP_CHECKLOCKTIMEVERIFY) until block height
+    Height(u32),
+    /// Absolute lock (OP_CHECKLOCKTIMEVERIFY) until unix timestamp
+    Time(u32),
+    /// Relative lock (OP_CHECKSEQUENCEVERIFY) by number of blocks
+    RelativeHeight(u16),
+    /// Relative lock (OP_CHECKSEQUENCEVERIFY) by intervals of 512 seconds
+    RelativeTime(u16),
+}
+
+/// Output of the transfer spendable by the beneficiary only once the lock
+/// expires: `<lock> OP_CHECKLOCKTIMEVERIFY|OP_CHECKSEQUENCEVERIFY OP_DROP
+/// <beneficiary> OP_CHECKSIG` (P2WSH).
+///
+/// The assets are bound to the output by paying an invoice to its address
+/// (see `timelock_output`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PsbtTimelockRequest {
+    /// Lock of the output
+    pub lock: PsbtLockRequest,
+    /// Public key (hex) of the beneficiary
+    pub beneficiary: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelockOutputResponse {
+    /// Address of the time-locked output, to be used as invoice beneficiary
+    pub address: String,
+    /// Witness script (hex) spending the output
+    pub script: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 
@@ -728,6 +916,31 @@ pub struct PsbtResponse {
     pub terminal: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePsbtRequest {
+    /// PSBTs to merge (in order)
+    pub psbts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePsbtResponse {
+    /// Merged PSBT
+    pub psbt: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RgbJoinTransferRequest {
+    /// PSBTs of the contributors to join (in order)
+    pub psbts: Vec<String>,
+    /// RGB Invoice paid by the joined PSBT
+    pub rgb_invoice: String,
+    /// Asset UTXO Terminal (ex. /0/0)
+    pub terminal: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[derive(Validate)]
@@ -787,6 +1000,24 @@ pub struct RgbTransferRequest {
     /// Asset UTXO Terminal (ex. /0/0)
     #[garde(custom(verify_terminal_path))]
     pub terminal: String,
+    /// Spend only from these allocations (format: {txid}:{vout})
+    #[garde(skip)]
+    #[serde(default)]
+    pub from_seals: Vec<String>,
+    /// Include a human-readable receipt in the response (default: true)
+    #[garde(skip)]
+    #[serde(default = "include_receipt")]
+    pub receipt: bool,
+    /// Split the change into allocations of these amounts, each on its own
+    /// seal (they must sum up to the change)
+    #[garde(skip)]
+    #[serde(default)]
+    pub change_split: Vec<u64>,
+}
+
+// Receipts are included unless the request opts out
+fn include_receipt() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -817,6 +1048,18 @@ pub struct FullRgbTransferRequest {
     /// Bitcoin Change Addresses (format: {address}:{amount})
     #[garde(length(min = 0, max = 999))]
     pub bitcoin_changes: Vec<String>,
+    /// Spend only from these allocations (format: {txid}:{vout})
+    #[garde(skip)]
+    #[serde(default)]
+    pub from_seals: Vec<String>,
+    /// Maximum fee allowed in sats (overrides the default safety cap)
+    #[garde(skip)]
+    #[serde(default)]
+    pub max_fee: Option<u64>,
+    /// Include a human-readable receipt in the response (default: true)
+    #[garde(skip)]
+    #[serde(default = "include_receipt")]
+    pub receipt: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -918,6 +1161,8 @@ impl RgbInternalSaveTransferRequest {
 pub struct RgbTransferResponse {
     /// Consignment ID
     pub consig_id: String,
+    /// Deterministic Consignment Hash (blake3 over canonical encoding)
+    pub consignment_id: String,
     /// Consignment encoded (in hexadecimal)
     pub consig: String,
     /// PSBT File Information with tapret (in hexadecimal)
@@ -926,6 +1171,34 @@ pub struct RgbTransferResponse {
     pub commit: String,
     /// Transfer Bitcoin L1 transaction id
     pub txid: String,
+    /// Human-readable receipt (when requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<RgbTransferReceipt>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RgbTransferReceipt {
+    /// The contract id
+    pub contract_id: String,
+    /// The contract interface
+    pub iface: String,
+    /// Asset ticker
+    pub ticker: String,
+    /// Asset name
+    pub name: String,
+    /// Amount sent (formatted with the asset precision)
+    pub amount: String,
+    /// Recipient seal (the invoice beneficiary)
+    pub beneficiary: String,
+    /// Bitcoin fee (in sats)
+    pub fee: u64,
+    /// Transfer Bitcoin L1 transaction id
+    pub txid: String,
+    /// Deterministic Consignment Hash (blake3 over canonical encoding)
+    pub consignment_id: String,
+    /// Receipt creation (unix timestamp)
+    pub created_at: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -950,6 +1223,8 @@ pub struct RgbReplaceResponse {
 pub struct RgbInternalTransferResponse {
     /// Consignment ID
     pub consig_id: String,
+    /// Deterministic Consignment Hash (blake3 over canonical encoding)
+    pub consignment_id: String,
     /// Consignment encoded (in hexadecimal)
     pub consig: String,
     /// PSBT File Information with tapret (in hexadecimal)
@@ -964,6 +1239,8 @@ pub struct RgbInternalTransferResponse {
     pub consigs: BTreeMap<String, String>,
     /// Transfer Bitcoin L1 transaction id
     pub txid: String,
+    /// Human-readable receipt (when requested)
+    pub receipt: Option<RgbTransferReceipt>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -978,6 +1255,10 @@ pub struct AcceptRequest {
     /// Force Consignment accept
     #[garde(skip)]
     pub force: bool,
+    /// Originating Invoice (used to check the paid amount)
+    #[garde(skip)]
+    #[serde(default)]
+    pub invoice: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -989,6 +1270,338 @@ pub struct AcceptResponse {
     pub contract_id: String,
     /// Transfer accept status
     pub valid: bool,
+    /// Amount received by the invoice beneficiary (only if invoice is informed)
+    pub paid_amount: Option<u64>,
+    /// Payment status against the invoice (only if invoice is informed)
+    pub payment_status: Option<InvoicePaymentStatus>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateAcceptResponse {
+    /// Transfer ID
+    pub transfer_id: String,
+    /// Contract ID
+    pub contract_id: String,
+    /// Transfer validation status
+    pub valid: bool,
+    /// Validation warnings
+    pub warnings: Vec<String>,
+    /// Balance changes (per contract) if the transfer is accepted
+    pub balances: Vec<BalanceDelta>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceProofRequest {
+    /// Contract ID
+    pub contract_id: String,
+    /// Challenge chosen by the verifier, signed for every outpoint
+    pub challenge: String,
+    /// Descriptor with the keys of the outpoints (e.g. `rgb_assets_descriptor_xprv`)
+    pub descriptor: SecretString,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceProofResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Claimed balance (in the contract raw units)
+    pub balance: u64,
+    /// Outpoints holding the balance
+    pub utxos: Vec<String>,
+    /// Consignment (hex) with the history of the outpoints
+    pub consignment: String,
+    /// Signatures over the challenge, by outpoint
+    #[serde(default)]
+    pub signatures: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationsExportResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Outpoints holding the exported allocations
+    pub utxos: Vec<String>,
+    /// Consignment (hex) with the contract genesis and the history of the outpoints
+    pub consignment: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferHistoryResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Transfers of the contract, from the transfer index
+    pub transfers: Vec<IndexedTransfer>,
+    /// Timestamp of the stock snapshot the index was built from
+    pub indexed_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexResponse {
+    /// A rebuild of the index started (rebuilds are throttled)
+    pub started: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptRecoveryResponse {
+    /// Interrupted transfers accepted again, by consignment id
+    pub recovered: Vec<String>,
+    /// Interrupted transfers which cannot be accepted again, by consignment id
+    pub quarantined: Vec<String>,
+}
+
+/// Wallet backup bundle, as a JSON container:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "network": "bitcoin",
+///   "descriptors": [
+///     { "desc": "tr([fingerprint/86'/0'/0']xpub.../0/*)#checksum", "active": true, "internal": false, "timestamp": 0 },
+///     { "desc": "tr([fingerprint/86'/0'/0']xpub.../1/*)#checksum", "active": true, "internal": true, "timestamp": 0 }
+///   ],
+///   "rgb": {
+///     "wallets": { "default": { "xpub": "xpub...", "taprets": { "20:0": ["..."] } } },
+///     "stock": "...",
+///     "transfers": { "transfers": { "rgb:...": [...] } },
+///     "checksum": "..."
+///   }
+/// }
+/// ```
+///
+/// The `descriptors` entries follow the `importdescriptors` request format of
+/// Bitcoin Core, so the Bitcoin part can be restored by standard tools, which
+/// ignore the RGB addendum. Taproot keys have no SLIP-0132 version bytes, so
+/// keys are serialized as standard xpub/tpub.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletBackup {
+    /// Version of the backup container
+    pub version: u8,
+    /// Network of the wallet
+    pub network: String,
+    /// Bitcoin output descriptors (with checksums)
+    pub descriptors: Vec<BackupDescriptor>,
+    /// RGB addendum required to re-derive the seals
+    pub rgb: RgbBackupAddendum,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDescriptor {
+    /// Output descriptor with checksum
+    pub desc: String,
+    /// Used to derive new addresses
+    pub active: bool,
+    /// Used to derive change addresses
+    pub internal: bool,
+    /// Earliest block time to rescan from
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RgbBackupAddendum {
+    /// RGB wallets (watchers) by name
+    pub wallets: BTreeMap<String, RgbBackupWallet>,
+    /// Stock of the wallet, with its contracts and consignments (strict encoded, hex)
+    pub stock: String,
+    /// Transfers of the wallet, with their consignments
+    pub transfers: RgbTransfersV1,
+    /// SHA-256 (hex) of the serialized wallets, stock and transfers
+    pub checksum: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RgbBackupWallet {
+    /// Account xpub of the watcher
+    pub xpub: String,
+    /// Tapret tweaks by terminal path (`app:index`)
+    pub taprets: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletBackupRestoreResponse {
+    /// Network of the wallet
+    pub network: String,
+    /// Restored RGB wallets (watchers)
+    pub wallets: Vec<String>,
+    /// Restored Bitcoin output descriptors
+    pub descriptors: Vec<String>,
+    /// Whether the stock of the backup replaced the stock of the wallet
+    pub stock: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationProofResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Commitment to the unspent allocations of the contract (hex).
+    ///
+    /// The commitment is self-attested by the prover: it isn't anchored to
+    /// the witness transactions, so it's only as trusted as its source
+    pub commitment: String,
+    /// Anchored UTXO of the allocation
+    pub utxo: String,
+    /// Asset Value of the allocation
+    pub value: AllocationValue,
+    /// Inclusion path, from the allocation to the commitment
+    pub path: Vec<MerkleStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleStep {
+    /// Sibling node (hex)
+    pub hash: String,
+    /// Whether the sibling is on the left of the node
+    pub left: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyAllocationProofResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Anchored UTXO of the allocation
+    pub utxo: String,
+    /// Proof validation status
+    pub valid: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationProvenanceResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Anchored UTXO of the allocation
+    pub utxo: String,
+    /// Operations leading to the allocation, from the genesis to the last
+    /// transition
+    pub steps: Vec<ProvenanceStep>,
+    /// Whether the history reaches the genesis without gaps
+    pub complete: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceStep {
+    /// Operation ID (genesis or state transition)
+    pub opid: String,
+    /// Bitcoin transaction anchoring the operation (unknown for gaps)
+    pub txid: Option<String>,
+    /// Confirmation status of the transaction
+    pub status: TxStatus,
+    /// Whether the operation is the contract genesis
+    pub is_genesis: bool,
+    /// Whether the operation is missing in the stock (the history before it
+    /// is unknown)
+    pub is_gap: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyBalanceProofResponse {
+    /// Contract ID
+    pub contract_id: String,
+    /// Proof validation status
+    pub valid: bool,
+    /// Balance assigned by the consignment to the unspent outpoints
+    pub balance: u64,
+    /// Claimed outpoints already spent at the current chain tip
+    pub spent: Vec<String>,
+    /// Claimed outpoints without a valid signature over the challenge
+    #[serde(default)]
+    pub unproven: Vec<String>,
+    /// Validation warnings
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceDelta {
+    /// Contract ID
+    pub contract_id: String,
+    /// Current balance
+    pub before: u64,
+    /// Amount assigned to our seals by the transfer
+    pub received: u64,
+    /// Balance after accept
+    pub after: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StockSnapshot {
+    /// Snapshot date (timestamp)
+    pub created_at: i64,
+    /// Contracts state (by contract id)
+    pub contracts: BTreeMap<String, ContractSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractSnapshot {
+    /// Current balance
+    pub balance: u64,
+    /// The contract allocations (unspent)
+    pub allocations: BTreeSet<AllocationDetail>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StockDiffResponse {
+    /// Contracts only present in the later snapshot
+    pub added_contracts: Vec<String>,
+    /// Contracts only present in the earlier snapshot
+    pub removed_contracts: Vec<String>,
+    /// Balances changed between the snapshots
+    pub balances: Vec<BalanceChange>,
+    /// New allocations (by contract id)
+    pub added_allocations: BTreeMap<String, Vec<AllocationDetail>>,
+    /// Allocations no longer present (by contract id)
+    pub removed_allocations: BTreeMap<String, Vec<AllocationDetail>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceChange {
+    /// Contract ID
+    pub contract_id: String,
+    /// Balance in the earlier snapshot
+    pub before: u64,
+    /// Balance in the later snapshot
+    pub after: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InvoicePaymentStatus {
+    /// Paid amount is equal to the invoice amount
+    Exact,
+    /// Paid amount is lower than the invoice amount
+    Underpaid,
+    /// Paid amount is greater than the invoice amount
+    Overpaid,
+}
+
+impl InvoicePaymentStatus {
+    pub fn with(invoiced: u64, paid: u64) -> Self {
+        match paid.cmp(&invoiced) {
+            Ordering::Equal => Self::Exact,
+            Ordering::Less => Self::Underpaid,
+            Ordering::Greater => Self::Overpaid,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -1052,6 +1665,9 @@ pub struct InterfaceDetail {
     pub iface: String,
     /// Interface ID
     pub iimpl: String,
+    /// Versions of the interface implemented in the stock
+    #[serde(default)]
+    pub versions: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -1070,6 +1686,30 @@ pub struct SchemaDetail {
     pub ifaces: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStatsResponse {
+    /// Bytes stored by the wallet
+    pub total: u64,
+    /// Bytes stored by category (stock, consignments and other)
+    pub categories: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitiesResponse {
+    /// Crate version
+    pub version: String,
+    /// API version (incremented on breaking changes)
+    pub api_version: u32,
+    /// Optional features (and whether they are enabled)
+    pub features: BTreeMap<String, bool>,
+    /// Supported interfaces
+    pub interfaces: Vec<String>,
+    /// Supported schemas (and their interfaces)
+    pub schemas: Vec<SchemaDetail>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[derive(Validate)]
@@ -1126,6 +1766,9 @@ pub struct AllocationDetail {
     pub is_mine: bool,
     /// Allocation spent?
     pub is_spent: bool,
+    /// Allocation waiting for its witness transaction to confirm?
+    #[serde(default)]
+    pub is_pending: bool,
 }
 
 #[derive(Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize, Debug, Clone, Display)]
@@ -1172,6 +1815,15 @@ impl UDAPosition {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReusedResponse {
+    /// Outpoints used by more than one invoice (format: {outpoint} -> {invoices})
+    pub seals: BTreeMap<String, usize>,
+    /// Derivation terminals receiving more than one utxo (format: {terminal} -> [{outpoint}])
+    pub addresses: BTreeMap<String, Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct NextAddressResponse {
@@ -1324,6 +1976,17 @@ pub struct BatchRgbTransferResponse {
     pub transfers: Vec<BatchRgbTransferItem>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RebroadcastResponse {
+    /// Transactions accepted by the explorer
+    pub accepted: Vec<String>,
+    /// Transactions rejected by the explorer (txid and reason)
+    pub rejected: BTreeMap<String, String>,
+    /// Transactions the explorer no longer knows
+    pub missing: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchRgbTransferItem {
@@ -278,6 +278,21 @@ pub fn bech32m_zip_decode(bech32_str: &str) -> Result<Vec<u8>> {
     }
 }
 
+/// Encode a value as CBOR (the binary wire format of bitmaskd).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cbor_encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    ciborium::into_writer(value, &mut bytes)
+        .map_err(|err| anyhow::anyhow!("Error encoding CBOR: {err}"))?;
+    Ok(bytes)
+}
+
+/// Decode a value encoded by [`cbor_encode`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cbor_decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    ciborium::from_reader(bytes).map_err(|err| anyhow::anyhow!("Error decoding CBOR: {err}"))
+}
+
 pub fn bech32_decode(bech32_str: &str) -> Result<(String, Vec<u8>, Variant)> {
     let (hrp, words, variant) = decode(bech32_str)?;
     Ok((hrp, Vec::<u8>::from_base32(&words)?, variant))
@@ -7,11 +7,13 @@ use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
 use crate::rgb::structs::ContractAmount;
 use crate::structs::{
-    AcceptRequest, FullIssueRequest, FullRgbTransferRequest, ImportRequest, InvoiceRequest,
-    IssueMediaRequest, IssueRequest, MediaRequest, PsbtRequest, PublishPsbtRequest, ReIssueRequest,
-    RgbAuctionBidRequest, RgbAuctionOfferRequest, RgbBidRequest, RgbOfferRequest,
+    AcceptRequest, AllocationProofResponse, AllocationsExportResponse, BalanceProofRequest,
+    BalanceProofResponse, CounterpartiesRequest, FullIssueRequest, FullRgbTransferRequest,
+    ImportRequest, InvoiceRequest, IssueMediaRequest, IssueRequest, MediaRequest, MergePsbtRequest,
+    PsbtRequest, PsbtTimelockRequest, PublishPsbtRequest, ReIssueRequest, RgbAuctionBidRequest,
+    RgbAuctionOfferRequest, RgbBidRequest, RgbJoinTransferRequest, RgbOfferRequest,
     RgbOfferUpdateRequest, RgbRemoveTransferRequest, RgbSaveTransferRequest, RgbSwapRequest,
-    RgbTransferRequest, SecretString, SignPsbtRequest, WatcherRequest,
+    RgbTransferRequest, SecretString, SignPsbtRequest, StockSnapshot, WatcherRequest,
 };
 
 pub fn set_panic_hook() {
@@ -123,6 +125,32 @@ pub mod bitcoin {
             .to_owned()
     }
 
+    #[wasm_bindgen]
+    pub fn wallet_fingerprint(nostr_hex_sk: String) -> Result<String, JsValue> {
+        set_panic_hook();
+
+        crate::bitcoin::wallet_fingerprint(&nostr_hex_sk)
+            .map_err(|err| JsValue::from_string(err.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_response(
+        method: String,
+        path: String,
+        status: u16,
+        timestamp: i64,
+        body: String,
+        sig: String,
+        server_pk: String,
+    ) -> Result<(), JsValue> {
+        set_panic_hook();
+
+        let message =
+            crate::bitcoin::response_message(&method, &path, status, timestamp, body.as_bytes());
+        crate::bitcoin::verify_response(&message, &sig, &server_pk)
+            .map_err(|err| JsValue::from_string(err.to_string()))
+    }
+
     #[wasm_bindgen]
     pub fn new_mnemonic(password: String) -> Promise {
         set_panic_hook();
@@ -400,9 +428,26 @@ pub mod bitcoin {
             }
         })
     }
+
+    #[wasm_bindgen]
+    pub fn estimate_confirmation(txid_or_feerate: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::bitcoin::estimate_confirmation(&txid_or_feerate).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
 }
 
 pub mod rgb {
+    use futures::StreamExt;
+    use wasm_streams::ReadableStream;
+
     use super::*;
 
     #[allow(clippy::too_many_arguments)]
@@ -447,6 +492,7 @@ pub mod rgb {
                 seal: pre_req.seal,
                 iface: pre_req.iface,
                 meta: media,
+                terms: pre_req.terms,
             };
             match crate::rgb::issue_contract(&nostr_hex_sk, req).await {
                 Ok(result) => Ok(JsValue::from_string(
@@ -503,6 +549,63 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn timelock_output(request: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let timelock_req: PsbtTimelockRequest =
+                serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::timelock_output(timelock_req).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn join_psbts(request: JsValue) -> Result<JsValue, JsValue> {
+        set_panic_hook();
+
+        let merge_req: MergePsbtRequest = serde_wasm_bindgen::from_value(request).unwrap();
+        match crate::rgb::join_psbts(merge_req) {
+            Ok(result) => Ok(JsValue::from_string(
+                serde_json::to_string(&result).unwrap(),
+            )),
+            Err(err) => Err(JsValue::from_string(err.to_string())),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn join_transfer_psbts(nostr_hex_sk: String, request: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let join_req: RgbJoinTransferRequest = serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::join_transfer_psbts(&nostr_hex_sk, join_req).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn combine_psbts(request: JsValue) -> Result<JsValue, JsValue> {
+        set_panic_hook();
+
+        let merge_req: MergePsbtRequest = serde_wasm_bindgen::from_value(request).unwrap();
+        match crate::rgb::combine_psbts(merge_req) {
+            Ok(result) => Ok(JsValue::from_string(
+                serde_json::to_string(&result).unwrap(),
+            )),
+            Err(err) => Err(JsValue::from_string(err.to_string())),
+        }
+    }
+
     #[wasm_bindgen]
     pub fn psbt_sign_file(_nostr_hex_sk: String, request: JsValue) -> Promise {
         set_panic_hook();
@@ -519,12 +622,12 @@ pub mod rgb {
     }
 
     #[wasm_bindgen]
-    pub fn psbt_publish_file(_nostr_hex_sk: String, request: JsValue) -> Promise {
+    pub fn psbt_publish_file(nostr_hex_sk: String, request: JsValue) -> Promise {
         set_panic_hook();
 
         future_to_promise(async move {
             let psbt_req: PublishPsbtRequest = serde_wasm_bindgen::from_value(request).unwrap();
-            match crate::bitcoin::publish_psbt_file(psbt_req).await {
+            match crate::rgb::publish_transfer_psbt(&nostr_hex_sk, psbt_req).await {
                 Ok(result) => Ok(JsValue::from_string(
                     serde_json::to_string(&result).unwrap(),
                 )),
@@ -534,12 +637,12 @@ pub mod rgb {
     }
 
     #[wasm_bindgen]
-    pub fn psbt_sign_and_publish_file(_nostr_hex_sk: String, request: JsValue) -> Promise {
+    pub fn psbt_sign_and_publish_file(nostr_hex_sk: String, request: JsValue) -> Promise {
         set_panic_hook();
 
         future_to_promise(async move {
             let psbt_req: SignPsbtRequest = serde_wasm_bindgen::from_value(request).unwrap();
-            match crate::bitcoin::sign_and_publish_psbt_file(psbt_req).await {
+            match crate::rgb::sign_and_publish_transfer_psbt(&nostr_hex_sk, psbt_req).await {
                 Ok(result) => Ok(JsValue::from_string(
                     serde_json::to_string(&result).unwrap(),
                 )),
@@ -593,6 +696,34 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn recover_accept_transfers(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::recover_accept_transfers(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn simulate_accept(nostr_hex_sk: String, consignment: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::simulate_accept(&nostr_hex_sk, consignment).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn verify_transfers(nostr_hex_sk: String) -> Promise {
         set_panic_hook();
@@ -607,6 +738,20 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn rebroadcast_pending(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::rebroadcast_pending(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn extract_transfer(transfer: String) -> Promise {
         use amplify::confinement::{LargeVec, SmallOrdMap};
@@ -654,6 +799,205 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn export_balance_proof(nostr_hex_sk: String, request: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let proof_req: BalanceProofRequest = serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::export_balance_proof(&nostr_hex_sk, proof_req).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_balance_proof(request: JsValue, challenge: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let proof: BalanceProofResponse = serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::verify_balance_proof(proof, &challenge).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn allocations_commitment(nostr_hex_sk: String, contract_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::allocations_commitment(&nostr_hex_sk, &contract_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn allocation_proof(
+        nostr_hex_sk: String,
+        contract_id: String,
+        outpoint: String,
+    ) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::allocation_proof(&nostr_hex_sk, &contract_id, &outpoint).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_allocation_proof(
+        request: JsValue,
+        contract_id: String,
+        commitment: String,
+    ) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let proof: AllocationProofResponse = serde_wasm_bindgen::from_value(request).unwrap();
+            let result = crate::rgb::verify_allocation_proof(proof, &contract_id, &commitment);
+            Ok(JsValue::from_string(
+                serde_json::to_string(&result).unwrap(),
+            ))
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn allocation_provenance(
+        nostr_hex_sk: String,
+        contract_id: String,
+        outpoint: String,
+    ) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::allocation_provenance(&nostr_hex_sk, &contract_id, &outpoint).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn export_allocations(nostr_hex_sk: String, contract_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::export_allocations(&nostr_hex_sk, &contract_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn import_allocations(nostr_hex_sk: String, request: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let export: AllocationsExportResponse =
+                serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::import_allocations(&nostr_hex_sk, export).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn export_backup(nostr_hex_sk: String, key_origins: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let key_origins: Option<Vec<String>> =
+                serde_wasm_bindgen::from_value(key_origins).unwrap();
+            match crate::rgb::export_backup(&nostr_hex_sk, key_origins.as_deref()).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn restore_backup(nostr_hex_sk: String, backup: String, overwrite: bool) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::restore_backup(&nostr_hex_sk, backup.as_bytes(), overwrite).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn snapshot_stock(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::snapshot_stock(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn diff_stock(before: JsValue, after: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let before: StockSnapshot = serde_wasm_bindgen::from_value(before).unwrap();
+            let after: StockSnapshot = serde_wasm_bindgen::from_value(after).unwrap();
+            let result = crate::rgb::diff_stock(before, after);
+            Ok(JsValue::from_string(
+                serde_json::to_string(&result).unwrap(),
+            ))
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn get_contract_terms(nostr_hex_sk: String, contract_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::get_contract_terms(&nostr_hex_sk, &contract_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     pub fn get_simple_contract(nostr_hex_sk: String, contract_id: String) -> Promise {
         set_panic_hook();
 
@@ -681,6 +1025,91 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn ignore_allocation(nostr_hex_sk: String, outpoint: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::ignore_allocation(&nostr_hex_sk, &outpoint).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn unignore_allocation(nostr_hex_sk: String, outpoint: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::unignore_allocation(&nostr_hex_sk, &outpoint).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn list_ignored_allocations(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::list_ignored_allocations(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn set_counterparties(nostr_hex_sk: String, request: JsValue) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let req: CounterpartiesRequest = serde_wasm_bindgen::from_value(request).unwrap();
+            match crate::rgb::set_counterparties(&nostr_hex_sk, req).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn list_counterparties(nostr_hex_sk: String, contract_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::list_counterparties(&nostr_hex_sk, &contract_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn list_suspicious_allocations(nostr_hex_sk: String, threshold: u64) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::list_suspicious_allocations(&nostr_hex_sk, threshold).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn list_contracts(nostr_hex_sk: String) -> Promise {
         set_panic_hook();
@@ -709,6 +1138,24 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn list_contracts_ndjson(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::list_contracts_ndjson(&nostr_hex_sk, true).await {
+                Ok(lines) => {
+                    let lines = lines.map(|line| {
+                        line.map(JsValue::from)
+                            .map_err(|err| JsValue::from_string(err.to_string()))
+                    });
+                    Ok(ReadableStream::from_stream(lines).into_raw().into())
+                }
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn list_interfaces(nostr_hex_sk: String) -> Promise {
         set_panic_hook();
@@ -723,6 +1170,20 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn storage_stats(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::storage_stats(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn list_schemas(nostr_hex_sk: String) -> Promise {
         set_panic_hook();
@@ -737,6 +1198,20 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn fetch_schema(nostr_hex_sk: String, schema_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::fetch_schema(&nostr_hex_sk, &schema_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn import_contract(nostr_hex_sk: String, request: JsValue) -> Promise {
         set_panic_hook();
@@ -865,6 +1340,37 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn recover_watcher(
+        nostr_hex_sk: String,
+        name: String,
+        iface: String,
+        gap_limit: u32,
+    ) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::recover_watcher(&nostr_hex_sk, &name, &iface, gap_limit).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn capabilities() -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            let result = crate::rgb::capabilities().await;
+            Ok(JsValue::from_string(
+                serde_json::to_string(&result).unwrap(),
+            ))
+        })
+    }
+
     #[wasm_bindgen]
     pub fn list_transfers(nostr_hex_sk: String, contract_id: String) -> Promise {
         set_panic_hook();
@@ -879,6 +1385,62 @@ pub mod rgb {
         })
     }
 
+    #[wasm_bindgen]
+    pub fn transfer_history(nostr_hex_sk: String, contract_id: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::transfer_history(&nostr_hex_sk, &contract_id).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn reindex(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::reindex(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn rebind_invoice(nostr_hex_sk: String, invoice: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::rebind_invoice(&nostr_hex_sk, &invoice).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn find_reused(nostr_hex_sk: String) -> Promise {
+        set_panic_hook();
+
+        future_to_promise(async move {
+            match crate::rgb::find_reused(&nostr_hex_sk).await {
+                Ok(result) => Ok(JsValue::from_string(
+                    serde_json::to_string(&result).unwrap(),
+                )),
+                Err(err) => Err(JsValue::from_string(err.to_string())),
+            }
+        })
+    }
+
     #[wasm_bindgen]
     pub fn save_transfer(nostr_hex_sk: String, request: JsValue) -> Promise {
         set_panic_hook();
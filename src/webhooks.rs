@@ -0,0 +1,321 @@
+//! Webhooks notifying integrations of the server events.
+//!
+//! Every [`WebhookEvent`] is POSTed as a JSON [`WebhookPayload`] to the
+//! configured URLs. The body is signed with the server key (see
+//! [`sign_response`]) in the `x-bitmask-signature` header, so receivers can
+//! check it with [`verify_response`](crate::bitcoin::verify_response) and the
+//! server nostr pubkey. Failed deliveries are retried with an exponential
+//! backoff, and appended to a dead-letter log once the attempts run out.
+use std::{
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::{debug, error};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{create_dir_all, read_to_string, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+use crate::{
+    bitcoin::{sign_response, wallet_fingerprint},
+    constants::get_network,
+};
+
+/// Comma-separated URLs receiving the webhooks
+pub const WEBHOOKS_VAR: &str = "BITMASK_WEBHOOKS";
+/// Path of the dead-letter log (default: `webhooks_dead_letter.jsonl` in `CARBONADO_DIR`)
+pub const WEBHOOKS_DEAD_LETTER_VAR: &str = "BITMASK_WEBHOOKS_DEAD_LETTER";
+
+/// Header with the signature of the payload
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-bitmask-signature";
+/// Header with the event of the payload
+pub const WEBHOOK_EVENT_HEADER: &str = "x-bitmask-event";
+
+const WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_millis(500);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_DEAD_LETTER_FILE: &str = "webhooks_dead_letter.jsonl";
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum WebhookError {
+    /// Webhook URL '{0}' is invalid
+    WrongUrl(String),
+    /// Webhook client cannot be built. {0}
+    Client(String),
+    /// Webhook payload cannot be signed. {0}
+    Signature(String),
+    /// Webhook payload cannot be serialized. {0}
+    Serialize(String),
+    /// Webhook dead-letter log cannot be written. {0}
+    DeadLetter(String),
+}
+
+/// Server events sent to the webhooks
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Display, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// Transfer paying an invoice was accepted
+    #[display("invoice_paid")]
+    InvoicePaid,
+    /// Transfer witness transaction was confirmed
+    #[display("transfer_confirmed")]
+    TransferConfirmed,
+    /// Contract was issued
+    #[display("issuance_complete")]
+    IssuanceComplete,
+}
+
+/// Body of the webhook requests
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    /// Unique ID of the notification, kept across retries so receivers can
+    /// discard duplicates
+    pub id: String,
+    /// Event of the notification
+    pub event: WebhookEvent,
+    /// Network of the wallet
+    pub network: String,
+    /// Fingerprint of the wallet (see [`wallet_fingerprint`])
+    pub wallet: String,
+    /// Timestamp of the event, signed with the payload to limit replays
+    pub created_at: i64,
+    /// Response of the operation which raised the event
+    pub data: serde_json::Value,
+}
+
+/// Entry of the dead-letter log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeadLetter {
+    /// Webhook URL
+    pub url: String,
+    /// Undelivered payload
+    pub payload: WebhookPayload,
+    /// Delivery attempts
+    pub attempts: u32,
+    /// Error of the last attempt
+    pub error: String,
+    /// Timestamp of the last attempt
+    pub failed_at: i64,
+}
+
+/// Delivery of a notification to the webhooks
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookReport {
+    /// URLs which acknowledged the notification
+    pub delivered: Vec<String>,
+    /// URLs moved to the dead-letter log
+    pub failed: Vec<String>,
+}
+
+/// Webhooks of the server
+#[derive(Clone)]
+pub struct Webhooks {
+    urls: Vec<String>,
+    server_sk: String,
+    max_attempts: u32,
+    retry_delay: Duration,
+    dead_letter: PathBuf,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new(urls: Vec<String>, server_sk: &str) -> Result<Self, WebhookError> {
+        for url in urls.iter() {
+            reqwest::Url::parse(url).map_err(|_| WebhookError::WrongUrl(url.to_owned()))?;
+        }
+
+        let dir = env::var("CARBONADO_DIR").unwrap_or("/tmp/bitmaskd/carbonado".to_owned());
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|err| WebhookError::Client(err.to_string()))?;
+
+        Ok(Self {
+            urls,
+            server_sk: server_sk.to_owned(),
+            max_attempts: WEBHOOK_MAX_ATTEMPTS,
+            retry_delay: WEBHOOK_RETRY_DELAY,
+            dead_letter: Path::new(&dir).join(WEBHOOK_DEAD_LETTER_FILE),
+            client,
+        })
+    }
+
+    /// Webhooks configured in `BITMASK_WEBHOOKS`, if any.
+    pub fn from_env(server_sk: &str) -> Result<Option<Self>, WebhookError> {
+        let urls: Vec<String> = env::var(WEBHOOKS_VAR)
+            .unwrap_or_default()
+            .split(',')
+            .map(|url| url.trim().to_owned())
+            .filter(|url| !url.is_empty())
+            .collect();
+        if urls.is_empty() {
+            return Ok(None);
+        }
+
+        let mut webhooks = Self::new(urls, server_sk)?;
+        if let Ok(path) = env::var(WEBHOOKS_DEAD_LETTER_VAR) {
+            webhooks = webhooks.with_dead_letter(path);
+        }
+        Ok(Some(webhooks))
+    }
+
+    /// Attempts per URL (at least one) and delay before the first retry,
+    /// doubled on each retry.
+    pub fn with_retries(mut self, max_attempts: u32, retry_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    pub fn with_dead_letter(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dead_letter = path.into();
+        self
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Send the event of the wallet to every webhook.
+    ///
+    /// Only errors of the server itself are returned: URLs which keep
+    /// failing are reported in [`WebhookReport::failed`] and written to the
+    /// dead-letter log.
+    pub async fn notify<T: Serialize>(
+        &self,
+        event: WebhookEvent,
+        wallet_sk: &str,
+        data: &T,
+    ) -> Result<WebhookReport, WebhookError> {
+        let mut rng = StdRng::from_entropy();
+        let payload = WebhookPayload {
+            id: hex::encode(rng.gen::<[u8; 16]>()),
+            event,
+            network: get_network().await,
+            wallet: wallet_fingerprint(wallet_sk)
+                .map_err(|err| WebhookError::Signature(err.to_string()))?,
+            created_at: Utc::now().timestamp(),
+            data: serde_json::to_value(data)
+                .map_err(|err| WebhookError::Serialize(err.to_string()))?,
+        };
+        let body =
+            serde_json::to_vec(&payload).map_err(|err| WebhookError::Serialize(err.to_string()))?;
+        let signature = sign_response(&body, &self.server_sk)
+            .map_err(|err| WebhookError::Signature(err.to_string()))?;
+
+        let mut report = WebhookReport::default();
+        for url in self.urls.iter() {
+            match self.deliver(url, event, &body, &signature).await {
+                Ok(()) => report.delivered.push(url.to_owned()),
+                Err((attempts, err)) => {
+                    error!("Webhook {event} to {url} failed after {attempts} attempts: {err}");
+                    self.dead_letter(WebhookDeadLetter {
+                        url: url.to_owned(),
+                        payload: payload.clone(),
+                        attempts,
+                        error: err,
+                        failed_at: Utc::now().timestamp(),
+                    })
+                    .await?;
+                    report.failed.push(url.to_owned());
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Client errors other than timeouts and rate limits are not retried,
+    // the receiver would reject the same payload again
+    async fn deliver(
+        &self,
+        url: &str,
+        event: WebhookEvent,
+        body: &[u8],
+        signature: &str,
+    ) -> Result<(), (u32, String)> {
+        let mut delay = self.retry_delay;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let response = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json; charset=UTF-8")
+                .header(WEBHOOK_SIGNATURE_HEADER, signature)
+                .header(WEBHOOK_EVENT_HEADER, event.to_string())
+                .body(body.to_vec())
+                .send()
+                .await;
+
+            let (err, retry) = match response {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let retry = status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (format!("status {status}"), retry)
+                }
+                Err(err) => (err.to_string(), true),
+            };
+
+            if !retry || attempts >= self.max_attempts {
+                return Err((attempts, err));
+            }
+            debug!("Retrying webhook {event} to {url} in {delay:?}: {err}");
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    // Each entry is written as a single line, so entries of concurrent
+    // deliveries don't interleave
+    async fn dead_letter(&self, entry: WebhookDeadLetter) -> Result<(), WebhookError> {
+        let write = async {
+            if let Some(dir) = self.dead_letter.parent() {
+                create_dir_all(dir).await?;
+            }
+            let mut line = serde_json::to_vec(&entry)?;
+            line.push(b'\n');
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.dead_letter)
+                .await?;
+            file.write_all(&line).await?;
+            file.flush().await
+        };
+        write
+            .await
+            .map_err(|err: std::io::Error| WebhookError::DeadLetter(err.to_string()))
+    }
+}
+
+/// Read the entries of the dead-letter log, e.g. to replay them.
+pub async fn read_dead_letters(
+    path: impl AsRef<Path>,
+) -> Result<Vec<WebhookDeadLetter>, WebhookError> {
+    let log = match read_to_string(path).await {
+        Ok(log) => log,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(WebhookError::DeadLetter(err.to_string())),
+    };
+
+    log.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| WebhookError::DeadLetter(err.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,88 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{collections::BTreeSet, env};
+
+use anyhow::Result;
+use bitmask_core::apikeys::{
+    is_api_key, issue_api_key, list_api_keys, resolve_api_key, revoke_api_key, ApiKeyError,
+    ApiKeyRequest, ApiScope,
+};
+
+const WALLET_SK: &str = "5a9c1f0e6b7d2c3a4f8e9d0c1b2a39485766a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+
+#[test]
+fn check_api_scopes() {
+    for scope in [
+        ApiScope::Read,
+        ApiScope::Pay,
+        ApiScope::Issue,
+        ApiScope::Admin,
+    ] {
+        assert!(scope.grants(ApiScope::Read));
+        assert!(ApiScope::Admin.grants(scope));
+    }
+    assert!(!ApiScope::Read.grants(ApiScope::Pay));
+    assert!(!ApiScope::Pay.grants(ApiScope::Issue));
+    assert!(!ApiScope::Issue.grants(ApiScope::Pay));
+    assert!(!ApiScope::Pay.grants(ApiScope::Admin));
+}
+
+#[tokio::test]
+async fn allow_issue_resolve_and_revoke_api_keys() -> Result<()> {
+    env::set_var(
+        "CARBONADO_DIR",
+        env::temp_dir().join("bitmask_api_keys").as_os_str(),
+    );
+
+    // 1. Issue a pay-only key
+    let request = ApiKeyRequest {
+        label: "shop".to_string(),
+        scopes: BTreeSet::from([ApiScope::Pay]),
+    };
+    let issued = issue_api_key(WALLET_SK, request).await?;
+    assert!(is_api_key(&issued.token));
+    assert!(!is_api_key(WALLET_SK));
+
+    // 2. The key maps to the wallet, with its scopes only
+    let access = resolve_api_key(&issued.token).await?;
+    assert_eq!(access.wallet_sk.as_str(), WALLET_SK);
+    assert!(access.require(ApiScope::Read).is_ok());
+    assert!(access.require(ApiScope::Pay).is_ok());
+    assert_eq!(
+        access.require(ApiScope::Issue),
+        Err(ApiKeyError::Forbidden(ApiScope::Issue))
+    );
+    assert!(access.require(ApiScope::Admin).is_err());
+
+    // 3. Listed without the token
+    let keys = list_api_keys(WALLET_SK).await?;
+    assert!(keys.keys.contains(&issued.key));
+
+    // 4. Revoked keys (and unknown tokens) are rejected
+    revoke_api_key(WALLET_SK, &issued.key.id).await?;
+    assert!(!list_api_keys(WALLET_SK).await?.keys.contains(&issued.key));
+    assert!(matches!(
+        resolve_api_key(&issued.token).await,
+        Err(ApiKeyError::UnknownKey)
+    ));
+    assert!(matches!(
+        resolve_api_key("bmk_00").await,
+        Err(ApiKeyError::UnknownKey)
+    ));
+    assert!(matches!(
+        revoke_api_key(WALLET_SK, &issued.key.id).await,
+        Err(ApiKeyError::NotFound(_))
+    ));
+
+    // 5. Keys without scopes are not issued
+    let request = ApiKeyRequest {
+        label: "empty".to_string(),
+        scopes: BTreeSet::new(),
+    };
+    assert!(matches!(
+        issue_api_key(WALLET_SK, request).await,
+        Err(ApiKeyError::NoScopes)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::{get_wallet, invalidate_wallet, new_mnemonic},
+    cache::{cache_stats, LruCache},
+    structs::SecretString,
+};
+
+#[test]
+pub fn evict_least_recently_used() {
+    let mut cache = LruCache::new("tests_evict", 2);
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+
+    // "a" is used, so "b" is the least recently used
+    assert_eq!(cache.get(&"a"), Some(&1));
+    cache.insert("c", 3);
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"a"), Some(&1));
+    assert_eq!(cache.get(&"c"), Some(&3));
+
+    let stats = cache_stats()["tests_evict"];
+    assert_eq!(stats.hits, 3);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.evictions, 1);
+    assert_eq!(stats.size, 2);
+    assert_eq!(stats.capacity, 2);
+}
+
+#[test]
+pub fn invalidated_entries_are_never_returned() {
+    let mut cache = LruCache::new("tests_invalidate", 2);
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+
+    assert_eq!(cache.invalidate(&"a"), Some(1));
+    assert_eq!(cache.get(&"a"), None);
+
+    // Updates replace the cached value, without evicting others
+    assert_eq!(cache.insert("b", 20), Some(2));
+    cache.insert("c", 3);
+    assert_eq!(cache.get(&"b"), Some(&20));
+    assert_eq!(cache.get(&"c"), Some(&3));
+
+    let stats = cache_stats()["tests_invalidate"];
+    assert_eq!(stats.invalidations, 1);
+    assert_eq!(stats.evictions, 0);
+}
+
+#[test]
+pub fn shared_lookups_mark_entries_as_used() {
+    let cache = RwLock::new(LruCache::new("tests_shared", 2));
+    {
+        let mut cache = cache.write().unwrap();
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+    }
+
+    // Lookups behind the read lock still move "a" ahead of "b"
+    let (first, second) = {
+        let cache = cache.read().unwrap();
+        (cache.get(&"a").copied(), cache.get(&"a").copied())
+    };
+    assert_eq!(first, Some(1));
+    assert_eq!(second, Some(1));
+
+    cache.write().unwrap().insert("c", 3);
+    let cache = cache.read().unwrap();
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"a"), Some(&1));
+}
+
+#[tokio::test]
+pub async fn invalidated_wallets_are_built_again() -> Result<()> {
+    let keys = new_mnemonic(&SecretString("".to_owned())).await?;
+    let descriptor = SecretString(keys.public.rgb_assets_descriptor_xpub.clone());
+
+    let wallet = get_wallet(&descriptor, None).await?;
+    assert!(Arc::ptr_eq(&wallet, &get_wallet(&descriptor, None).await?));
+
+    invalidate_wallet(&descriptor, None).await;
+    assert!(!Arc::ptr_eq(&wallet, &get_wallet(&descriptor, None).await?));
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{env, net::TcpListener};
+
+use anyhow::Result;
+use axum::{
+    http::Method,
+    routing::{get, post},
+    Router,
+};
+use bitmask_core::carbonado::{
+    check_path_segment, clear_namespace, delete_namespaced_file, error::CarbonadoError,
+    handle_file, handle_namespaced_file, list_namespace, CARBONADO_FILE_ROUTE,
+    CARBONADO_FORCE_ROUTE, CARBONADO_METADATA_ROUTE, CARBONADO_NAMESPACED_FILE_ROUTE,
+    CARBONADO_NAMESPACE_ROUTE,
+};
+use tokio::fs;
+
+const PK: &str = "02a7cf2b1b1e8b4bfd8e3a4d0b1e0f5e4c3b2a19f8e7d6c5b4a39281706f5e4d3c";
+
+#[tokio::test]
+pub async fn isolate_carbonado_namespaces() -> Result<()> {
+    let dir = env::temp_dir().join("bitmask_carbonado_namespaces");
+    let _ = fs::remove_dir_all(&dir).await;
+    env::set_var("CARBONADO_DIR", &dir);
+
+    // Same name in two namespaces and in the two-segment form
+    let consignment = handle_namespaced_file(PK, Some("consignments"), "asset.c15", 1).await?;
+    let media = handle_namespaced_file(PK, Some("media"), "asset.c15", 1).await?;
+    let legacy = handle_file(PK, "asset.c15", 1).await?;
+    assert_ne!(consignment, media);
+    assert_ne!(consignment, legacy);
+    fs::write(&consignment, b"consignment").await?;
+    fs::write(&media, b"media").await?;
+    fs::write(&legacy, b"legacy").await?;
+
+    let consignments = list_namespace(PK, "consignments").await?;
+    assert_eq!(consignments.len(), 1);
+    assert_eq!(list_namespace(PK, "media").await?, consignments);
+    assert!(list_namespace(PK, "labels").await?.is_empty());
+
+    assert!(delete_namespaced_file(PK, "consignments", "asset.c15").await?);
+    assert!(!delete_namespaced_file(PK, "consignments", "asset.c15").await?);
+    assert!(list_namespace(PK, "consignments").await?.is_empty());
+    assert_eq!(fs::read(&media).await?, b"media");
+
+    assert_eq!(clear_namespace(PK, "media").await?, 1);
+    assert!(list_namespace(PK, "media").await?.is_empty());
+    assert_eq!(fs::read(&legacy).await?, b"legacy");
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn reject_unsafe_carbonado_paths() -> Result<()> {
+    assert!(check_path_segment("bitcoin-asset.c15").is_ok());
+    for segment in [
+        "", ".", "..", ".hidden", "a/b", "a\\b", "../media", "name\0",
+    ] {
+        assert!(matches!(
+            check_path_segment(segment),
+            Err(CarbonadoError::UnsafePathSegment(_))
+        ));
+    }
+
+    assert!(handle_namespaced_file(PK, Some(".."), "asset.c15", 0)
+        .await
+        .is_err());
+    assert!(
+        handle_namespaced_file(PK, Some("media/../.."), "asset.c15", 0)
+            .await
+            .is_err()
+    );
+    assert!(
+        handle_namespaced_file("../..", Some("media"), "asset.c15", 0)
+            .await
+            .is_err()
+    );
+    assert!(handle_file(PK, "../asset.c15", 0).await.is_err());
+    assert!(delete_namespaced_file(PK, "media", "../../asset.c15")
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn route_carbonado_namespaced_files() -> Result<()> {
+    let route = |label: &'static str| move || async move { label };
+    let app = Router::new()
+        .route(CARBONADO_FILE_ROUTE, get(route("file")).post(route("file")))
+        .route(CARBONADO_FORCE_ROUTE, post(route("force")))
+        .route(CARBONADO_METADATA_ROUTE, get(route("metadata")))
+        .route(
+            CARBONADO_NAMESPACE_ROUTE,
+            get(route("namespace")).delete(route("namespace")),
+        )
+        .route(
+            CARBONADO_NAMESPACED_FILE_ROUTE,
+            get(route("namespaced"))
+                .post(route("namespaced"))
+                .delete(route("namespaced")),
+        );
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/carbonado", listener.local_addr()?);
+    tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+
+    let client = reqwest::Client::new();
+    let cases = [
+        (Method::GET, format!("{PK}/asset.c15"), "file"),
+        (Method::POST, format!("{PK}/asset.c15"), "file"),
+        (Method::POST, format!("{PK}/asset.c15/force"), "force"),
+        (Method::GET, format!("{PK}/asset.c15/metadata"), "metadata"),
+        (Method::GET, format!("ns/{PK}/media/"), "namespace"),
+        (Method::DELETE, format!("ns/{PK}/media/"), "namespace"),
+        (
+            Method::GET,
+            format!("ns/{PK}/media/asset.c15"),
+            "namespaced",
+        ),
+        // Files named as the routes of the two-segment form
+        (Method::POST, format!("ns/{PK}/media/force"), "namespaced"),
+        (Method::GET, format!("ns/{PK}/media/metadata"), "namespaced"),
+        (Method::DELETE, format!("ns/{PK}/media/force"), "namespaced"),
+    ];
+    for (method, path, label) in cases {
+        let response = client
+            .request(method.clone(), format!("{url}/{path}"))
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{method} {path}");
+        assert_eq!(response.text().await?, label, "{method} {path}");
+    }
+
+    Ok(())
+}
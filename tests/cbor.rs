@@ -0,0 +1,116 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use anyhow::Result;
+use bitmask_core::{
+    structs::{
+        AcceptRequest, AcceptResponse, BatchRgbTransferResponse, InvoiceRequest, IssueRequest,
+        PsbtRequest,
+    },
+    util::{cbor_decode, cbor_encode},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+// Decode the JSON message, and check the CBOR round trip keeps every field
+fn cbor_round_trip<T: Serialize + DeserializeOwned>(message: Value) -> Result<()> {
+    let value: T = serde_json::from_value(message)?;
+    let encoded = cbor_encode(&value)?;
+    let decoded: T = cbor_decode(&encoded)?;
+
+    assert_eq!(
+        serde_json::to_value(&decoded)?,
+        serde_json::to_value(&value)?
+    );
+    Ok(())
+}
+
+#[test]
+fn allow_cbor_round_trip_requests() -> Result<()> {
+    cbor_round_trip::<IssueRequest>(json!({
+        "ticker": "DIBA",
+        "name": "DIBA",
+        "description": "DIBA asset",
+        "supply": 5_000_000_000_u64,
+        "precision": 2,
+        "seal": "tapret1st:70339a6b27f55105da2d050babc759f046c21c26b7b75e9394bc1d818e50ff52:0",
+        "iface": "RGB20",
+        "meta": null
+    }))?;
+
+    cbor_round_trip::<InvoiceRequest>(json!({
+        "contractId": "rgb:2wHxKf2-Zyw9D9MNL-KwqMcHpaz-qqzZsBhPb-nMeC2pKuB-AeSqWbD",
+        "iface": "RGB20",
+        "amount": "10.5",
+        "seal": "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0",
+        "params": { "expiry": "1700000000", "endpoints": "https://proxy.example" },
+        "ifaceVersion": 1
+    }))?;
+
+    cbor_round_trip::<AcceptRequest>(json!({
+        "consignment": "0a0b0c",
+        "force": true,
+        "invoice": "rgb:2wHxKf2-Zyw9D9MNL-KwqMcHpaz-qqzZsBhPb-nMeC2pKuB-AeSqWbD/RGB20/1000+utxob:..."
+    }))?;
+
+    cbor_round_trip::<PsbtRequest>(json!({
+        "assetInputs": [{
+            "descriptor": "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)",
+            "utxo": "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1",
+            "utxoTerminal": "/0/0",
+            "sighHash": null,
+            "tapret": null
+        }],
+        "assetDescriptorChange": null,
+        "assetTerminalChange": "/20/1",
+        "bitcoinInputs": [],
+        "bitcoinChanges": ["bc1pgxpvg7cz0s0lsh9cy4p2v3ttuc6gmgvwdvsh5xwlq3ptvh4j8ywqzxlaq6:1000"],
+        "fee": { "feeRate": 1.5 },
+        "rbf": true,
+        "lock": {
+            "lock": { "relativeHeight": 144 },
+            "beneficiary": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        },
+        "maxFee": 10_000,
+        "dataOutput": "deadbeef"
+    }))?;
+
+    Ok(())
+}
+
+#[test]
+fn allow_cbor_round_trip_responses() -> Result<()> {
+    cbor_round_trip::<AcceptResponse>(json!({
+        "transferId": "bcna:abc",
+        "contractId": "rgb:2wHxKf2-Zyw9D9MNL-KwqMcHpaz-qqzZsBhPb-nMeC2pKuB-AeSqWbD",
+        "valid": true,
+        "paidAmount": 1000,
+        "paymentStatus": "overpaid"
+    }))?;
+
+    cbor_round_trip::<BatchRgbTransferResponse>(json!({
+        "transfers": [{
+            "contractId": "rgb:2wHxKf2-Zyw9D9MNL-KwqMcHpaz-qqzZsBhPb-nMeC2pKuB-AeSqWbD",
+            "consigId": "bcna:abc",
+            "iface": "RGB20",
+            "status": { "block": 800_000 },
+            "isAccept": true,
+            "isMine": false,
+            "txid": "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f"
+        }]
+    }))?;
+
+    Ok(())
+}
+
+#[test]
+fn reject_malformed_cbor() -> Result<()> {
+    let encoded = cbor_encode(&json!({ "consignment": "0a0b0c", "force": true }))?;
+
+    // Truncated message
+    assert!(cbor_decode::<AcceptRequest>(&encoded[..encoded.len() - 2]).is_err());
+    // Missing required fields
+    let encoded = cbor_encode(&json!({ "force": true }))?;
+    assert!(cbor_decode::<AcceptRequest>(&encoded).is_err());
+
+    Ok(())
+}
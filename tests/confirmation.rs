@@ -0,0 +1,57 @@
+#![cfg(not(target_arch = "wasm32"))]
+// Own test binary: the test sets the process-wide `REQUIRE_CONFIRMATION`
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::new_mnemonic,
+    constants::set_env,
+    rgb::{accept_transfer, get_contract, structs::ContractAmount},
+    structs::{AcceptRequest, SecretString},
+};
+
+#[allow(dead_code)]
+#[path = "rgb/integration/utils.rs"]
+mod utils;
+
+use utils::{generate_new_block, issue_and_transfer_to_owner, publish_transfer};
+
+#[tokio::test]
+async fn hold_unconfirmed_allocations_until_confirmed() -> Result<()> {
+    // 1. Issue and transfer to owner, without confirming the witness
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, transfer_resp) =
+        &issue_and_transfer_to_owner(&issuer_keys, &owner_keys).await?;
+    publish_transfer(&issuer_keys, transfer_resp).await?;
+
+    // 2. Deployment requires confirmation, the allocation is pending
+    set_env("REQUIRE_CONFIRMATION", "true").await;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+    let request = AcceptRequest {
+        consignment: transfer_resp.consig.clone(),
+        force: false,
+        invoice: None,
+    };
+    accept_transfer(&owner_sk, request).await?;
+
+    let amount = ContractAmount::with(2, 0, issuer_resp.precision).to_value();
+    let contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(contract.balance, 0);
+    assert_eq!(contract.pending_balance, amount);
+    assert!(contract
+        .allocations
+        .iter()
+        .any(|allocation| allocation.is_mine && allocation.is_pending));
+
+    // 3. Once confirmed, the allocation is spendable
+    generate_new_block().await;
+    let contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(contract.balance, amount);
+    assert_eq!(contract.pending_balance, 0);
+    assert!(contract
+        .allocations
+        .iter()
+        .all(|allocation| !allocation.is_pending));
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+#![cfg(not(target_arch = "wasm32"))]
+// Own test binary: the test sets the process-wide `CARBONADO_DIR`
+use std::{env, time::Duration};
+
+use bitmask_core::{
+    rgb::{
+        fs::store_stock,
+        index::{build_index, transfer_index},
+        reindex, transfer_history,
+    },
+    util::init_logging,
+};
+use rgbstd::persistence::Stock;
+use tokio::time::sleep;
+
+#[allow(dead_code)]
+#[path = "rgb/unit/utils.rs"]
+mod utils;
+
+use utils::create_fake_contract;
+
+#[tokio::test]
+async fn allow_rebuild_transfer_index() -> anyhow::Result<()> {
+    init_logging("rgb_stock=warn");
+    env::set_var(
+        "CARBONADO_DIR",
+        env::temp_dir().join("bitmask_transfer_index").as_os_str(),
+    );
+    let sk = "3b2a1f0e9d8c7b6a5f4e3d2c1b0a99887766554433221100ffeeddccbbaa9988";
+
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+
+    // 1. The index is derived from the stock
+    let index = build_index(&mut stock).await?;
+    assert_eq!(index.contracts.len(), 1);
+    assert!(index.contracts[&contract_id.to_string()].is_empty());
+
+    // 2. The first read builds the index of the stored stock
+    store_stock(sk, stock).await?;
+    let history = transfer_history(sk, &contract_id.to_string()).await?;
+    assert!(history.transfers.is_empty());
+    assert!(history.indexed_at > 0);
+
+    // 3. Stored stocks are indexed in the background
+    store_stock(sk, Stock::default()).await?;
+    let mut index = transfer_index(sk).await?;
+    for _ in 0..50 {
+        if index.contracts.is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        index = transfer_index(sk).await?;
+    }
+    assert!(index.contracts.is_empty());
+
+    // 4. Rebuilds run in the background and are throttled
+    assert!(reindex(sk).await?.started);
+    assert!(!reindex(sk).await?.started);
+    let history = transfer_history(sk, &contract_id.to_string()).await?;
+    assert_eq!(history.contract_id, contract_id.to_string());
+
+    Ok(())
+}
@@ -2,11 +2,16 @@
 
 use anyhow::Result;
 use bitmask_core::{
-    bitcoin::{get_wallet_data, save_mnemonic},
+    bitcoin::{
+        get_wallet_data, hash_password, mnemonic_to_keys, new_mnemonic, response_message,
+        save_mnemonic, sign_response, verify_response, wallet_fingerprint, EncryptionKey,
+    },
     constants::switch_network,
     structs::SecretString,
     util::init_logging,
 };
+use serde_encrypt::AsSharedKey;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 #[tokio::test]
 pub async fn taproot() -> Result<()> {
@@ -73,3 +78,202 @@ pub async fn nip06() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+pub async fn stable_wallet_fingerprint() -> Result<()> {
+    const MNEMONIC: &str =
+        "garment castle exhaust confirm wrong timber earth invest output comfort actress slot";
+
+    let first_restore = save_mnemonic(
+        &SecretString(MNEMONIC.to_owned()),
+        &SecretString("".to_owned()),
+    )
+    .await?;
+    let second_restore = save_mnemonic(
+        &SecretString(MNEMONIC.to_owned()),
+        &SecretString("".to_owned()),
+    )
+    .await?;
+    let another_wallet = new_mnemonic(&SecretString("".to_owned())).await?;
+
+    let fingerprint = wallet_fingerprint(&first_restore.private.nostr_prv)?;
+    assert_eq!(fingerprint.len(), 8);
+    assert_eq!(
+        fingerprint,
+        wallet_fingerprint(&second_restore.private.nostr_prv)?,
+        "same seed has the same fingerprint"
+    );
+    assert_ne!(
+        fingerprint,
+        wallet_fingerprint(&another_wallet.private.nostr_prv)?,
+        "different seeds have different fingerprints"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn passphrase_isolates_wallets() -> Result<()> {
+    const MNEMONIC: &str =
+        "garment castle exhaust confirm wrong timber earth invest output comfort actress slot";
+    let mnemonic = SecretString(MNEMONIC.to_owned());
+
+    let no_passphrase = mnemonic_to_keys(&mnemonic, None).await?;
+    let empty_passphrase = save_mnemonic(&mnemonic, &SecretString("".to_owned())).await?;
+    assert_eq!(
+        no_passphrase.public.xpub, empty_passphrase.public.xpub,
+        "no passphrase is the empty passphrase"
+    );
+
+    let first = mnemonic_to_keys(&mnemonic, Some(&SecretString("first".to_owned()))).await?;
+    let second = mnemonic_to_keys(&mnemonic, Some(&SecretString("second".to_owned()))).await?;
+    for (wallet, other) in [
+        (&no_passphrase, &first),
+        (&no_passphrase, &second),
+        (&first, &second),
+    ] {
+        assert_eq!(wallet.mnemonic, other.mnemonic);
+        assert_ne!(
+            wallet_fingerprint(&wallet.private.nostr_prv)?,
+            wallet_fingerprint(&other.private.nostr_prv)?,
+            "different passphrases have different fingerprints"
+        );
+        assert_ne!(wallet.public.xpub, other.public.xpub);
+        assert_ne!(
+            wallet.public.btc_descriptor_xpub,
+            other.public.btc_descriptor_xpub
+        );
+        assert_ne!(
+            wallet.public.rgb_assets_descriptor_xpub,
+            other.public.rgb_assets_descriptor_xpub
+        );
+        assert_ne!(wallet.public.watcher_xpub, other.public.watcher_xpub);
+        // Stored state (stock, accounts, ...) is indexed by the nostr key
+        assert_ne!(wallet.public.nostr_pub, other.public.nostr_pub);
+    }
+
+    // The passphrase is required to restore the same wallet
+    let restored = mnemonic_to_keys(&mnemonic, Some(&SecretString("first".to_owned()))).await?;
+    assert_eq!(restored.public.xpub, first.public.xpub);
+    assert_eq!(restored.private.nostr_prv, first.private.nostr_prv);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn verify_signed_response() -> Result<()> {
+    let server_keys = new_mnemonic(&SecretString("".to_owned())).await?;
+    let another_keys = new_mnemonic(&SecretString("".to_owned())).await?;
+    let server_sk = &server_keys.private.nostr_prv;
+    let server_pk = &server_keys.public.nostr_pub;
+
+    let body = br#"{"contracts":[]}"#;
+    let timestamp = 1_700_000_000;
+    let message = response_message("GET", "/contracts", 200, timestamp, body);
+    let sig = sign_response(&message, server_sk)?;
+
+    assert!(verify_response(&message, &sig, server_pk).is_ok());
+    assert!(
+        verify_response(
+            &response_message(
+                "GET",
+                "/contracts",
+                200,
+                timestamp,
+                br#"{"contracts":[{}]}"#
+            ),
+            &sig,
+            server_pk
+        )
+        .is_err(),
+        "tampered body"
+    );
+    assert!(
+        verify_response(
+            &response_message("GET", "/interfaces", 200, timestamp, body),
+            &sig,
+            server_pk
+        )
+        .is_err(),
+        "another path"
+    );
+    assert!(
+        verify_response(
+            &response_message("POST", "/contracts", 200, timestamp, body),
+            &sig,
+            server_pk
+        )
+        .is_err(),
+        "another method"
+    );
+    assert!(
+        verify_response(
+            &response_message("GET", "/contracts", 500, timestamp, body),
+            &sig,
+            server_pk
+        )
+        .is_err(),
+        "another status"
+    );
+    assert!(
+        verify_response(
+            &response_message("GET", "/contracts", 200, timestamp + 60, body),
+            &sig,
+            server_pk
+        )
+        .is_err(),
+        "another timestamp"
+    );
+    assert!(
+        verify_response(&message, &sig, &another_keys.public.nostr_pub).is_err(),
+        "another server key"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn wipe_wallet_secrets() -> Result<()> {
+    init_logging("nostr_tests=debug");
+
+    const MNEMONIC: &str =
+        "garment castle exhaust confirm wrong timber earth invest output comfort actress slot";
+
+    let mut wallet_data = save_mnemonic(
+        &SecretString(MNEMONIC.to_owned()),
+        &SecretString("".to_owned()),
+    )
+    .await?;
+    assert!(!wallet_data.private.nostr_prv.is_empty());
+
+    wallet_data.zeroize();
+    assert!(wallet_data.mnemonic.is_empty());
+    assert!(wallet_data.private.xprvkh.is_empty());
+    assert!(wallet_data.private.btc_descriptor_xprv.is_empty());
+    assert!(wallet_data.private.rgb_assets_descriptor_xprv.is_empty());
+    assert!(wallet_data.private.nostr_prv.is_empty());
+    assert!(wallet_data.private.nostr_nsec.is_empty());
+
+    let mut password = SecretString("password".to_owned());
+    password.zeroize();
+    assert!(password.0.is_empty());
+
+    Ok(())
+}
+
+#[test]
+pub fn wipe_encryption_key() -> Result<()> {
+    fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+    zeroize_on_drop::<EncryptionKey>();
+
+    let hash = hash_password(&SecretString("wipe test".to_owned()));
+    let mut key = EncryptionKey::from_hash(&hash)?;
+    assert_eq!(key.shared_key().as_slice(), hex::decode(&hash.0)?);
+
+    key.zeroize();
+    assert_eq!(key.shared_key().as_slice(), [0; 32]);
+
+    // Hashes of other sizes are not keys
+    assert!(EncryptionKey::from_hash(&SecretString("abcd".to_owned())).is_err());
+    Ok(())
+}
@@ -0,0 +1,60 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::env;
+
+use anyhow::Result;
+use bitmask_core::{
+    keystore::{key_endpoint_enabled, read_key_file, write_key_file, KeySource, KEY_ENDPOINT_VAR},
+    structs::SecretString,
+};
+
+const NOSTR_SK: &str = "a7fef3b1fab3e1b27b4aca3ac1b3e5a6bcd1a5e9b5df9e0ba3b2a0c3e5f7a9b1";
+
+#[tokio::test]
+pub async fn key_file_round_trip() -> Result<()> {
+    let path = env::temp_dir().join("bitmask_key_file_round_trip.enc");
+    let password = SecretString("correct horse battery staple".to_owned());
+
+    write_key_file(&path, &SecretString(NOSTR_SK.to_owned()), &password)?;
+    let key = read_key_file(&path, &password)?;
+    assert_eq!(key.0, NOSTR_SK);
+
+    let wrong_password = SecretString("wrong password".to_owned());
+    assert!(read_key_file(&path, &wrong_password).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn key_file_source() -> Result<()> {
+    let path = env::temp_dir().join("bitmask_key_file_source.enc");
+    let password = SecretString("correct horse battery staple".to_owned());
+    write_key_file(&path, &SecretString(NOSTR_SK.to_owned()), &password)?;
+
+    let password_file = env::temp_dir().join("bitmask_key_file_source.pass");
+    std::fs::write(&password_file, format!("{}\n", password.0))?;
+
+    let source = KeySource::File {
+        path,
+        password_file,
+    };
+    assert_eq!(source.load()?.0, NOSTR_SK);
+
+    Ok(())
+}
+
+#[test]
+pub fn key_endpoint_disabled_by_default() {
+    env::remove_var(KEY_ENDPOINT_VAR);
+    assert!(!key_endpoint_enabled());
+
+    env::set_var(KEY_ENDPOINT_VAR, "false");
+    assert!(!key_endpoint_enabled());
+
+    env::set_var(KEY_ENDPOINT_VAR, "true");
+    assert!(key_endpoint_enabled());
+    env::set_var(KEY_ENDPOINT_VAR, "1");
+    assert!(key_endpoint_enabled());
+
+    env::remove_var(KEY_ENDPOINT_VAR);
+}
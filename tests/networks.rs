@@ -0,0 +1,57 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::env;
+
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::new_mnemonic,
+    carbonado::{retrieve, store},
+    constants::{get_network, storage_keys::ASSETS_STOCK, with_network},
+    structs::SecretString,
+};
+
+const SK: &str = "5a9c1f0e6b7d2c3a4f8e9d0c1b2a39485766a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+
+async fn store_and_retrieve(data: Vec<u8>) -> Result<(String, Vec<u8>, String)> {
+    store(SK, ASSETS_STOCK, &data, true, None).await?;
+    tokio::task::yield_now().await;
+
+    let keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let (stored, _) = retrieve(SK, ASSETS_STOCK, vec![]).await?;
+    Ok((get_network().await, stored, keys.public.btc_descriptor_xpub))
+}
+
+#[tokio::test]
+async fn allow_run_networks_in_parallel() -> Result<()> {
+    env::set_var(
+        "CARBONADO_DIR",
+        env::temp_dir().join("bitmask_networks").as_os_str(),
+    );
+    let process_network = get_network().await;
+
+    // 1. Both networks store the same file at once
+    let (testnet, mainnet) = tokio::join!(
+        with_network("testnet", store_and_retrieve(vec![1; 32])),
+        with_network("bitcoin", store_and_retrieve(vec![2; 32])),
+    );
+    let (testnet_network, testnet_data, testnet_descriptor) = testnet??;
+    let (mainnet_network, mainnet_data, mainnet_descriptor) = mainnet??;
+
+    // 2. Each request only sees the state of its network
+    assert_eq!(testnet_network, "testnet");
+    assert_eq!(mainnet_network, "bitcoin");
+    assert_eq!(testnet_data, vec![1; 32]);
+    assert_eq!(mainnet_data, vec![2; 32]);
+    assert!(testnet_descriptor.contains("tpub"));
+    assert!(mainnet_descriptor.contains("xpub"));
+
+    // 3. The process network is unchanged
+    assert_eq!(get_network().await, process_network);
+    let (stored, _) = with_network("testnet", retrieve(SK, ASSETS_STOCK, vec![])).await??;
+    assert_eq!(stored, vec![1; 32]);
+
+    // 4. Unknown networks are rejected
+    assert!(with_network("moonnet", get_network()).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{
+    env,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use axum::{extract::State, routing::get, Router};
+use bitmask_core::{
+    bitcoin::estimate_fee_rate,
+    constants::{get_explorer_api, set_env, with_network, BITCOIN_EXPLORER_API},
+    replay::{RecordReplay, RecordReplayError},
+    util::init_logging,
+};
+
+// Fake explorer, counting the requests it answers
+fn start_explorer(hits: Arc<AtomicUsize>) -> Result<String> {
+    let app = Router::new()
+        .route(
+            "/fee-estimates",
+            get(|State(hits): State<Arc<AtomicUsize>>| async move {
+                hits.fetch_add(1, Ordering::SeqCst);
+                r#"{"1":20.5,"6":5.0}"#
+            }),
+        )
+        .with_state(hits);
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+    Ok(format!("http://{addr}"))
+}
+
+#[tokio::test]
+async fn allow_record_and_replay_backend() -> Result<()> {
+    init_logging("replay=warn");
+
+    let recording = env::temp_dir().join("bitmask_record_replay.json");
+    let hits = Arc::new(AtomicUsize::new(0));
+    let explorer = start_explorer(hits.clone())?;
+    *BITCOIN_EXPLORER_API.write().await = explorer.clone();
+
+    let backend = RecordReplay::record(&recording).await?;
+    assert_eq!(*BITCOIN_EXPLORER_API.read().await, backend.url());
+    assert_eq!(estimate_fee_rate(6).await?, Some(5.0));
+    assert_eq!(estimate_fee_rate(1).await?, Some(20.5));
+    backend.finish().await?;
+    assert_eq!(*BITCOIN_EXPLORER_API.read().await, explorer);
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // Replayed requests never reach the explorer
+    let backend = RecordReplay::replay(&recording).await?;
+    assert_eq!(estimate_fee_rate(6).await?, Some(5.0));
+    assert_eq!(estimate_fee_rate(3).await?, Some(20.5));
+    assert_eq!(estimate_fee_rate(6).await?, Some(5.0));
+    backend.finish().await?;
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // Requests missing in the recording fail the replay
+    let backend = RecordReplay::replay(&recording).await?;
+    let height = reqwest::get(format!("{}/blocks/tip/height", backend.url())).await?;
+    assert!(!height.status().is_success());
+    assert!(matches!(
+        backend.finish().await,
+        Err(RecordReplayError::Unmatched(requests)) if requests == vec!["GET /blocks/tip/height"]
+    ));
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_replay_backend_of_request_network() -> Result<()> {
+    init_logging("replay=warn");
+
+    let recording = env::temp_dir().join("bitmask_record_replay_signet.json");
+    let hits = Arc::new(AtomicUsize::new(0));
+    let explorer = start_explorer(hits.clone())?;
+    set_env("BITCOIN_EXPLORER_API_SIGNET", &explorer).await;
+
+    // Requests on another network than the process one go through the
+    // backend wrapper as well
+    with_network("signet", async {
+        let backend = RecordReplay::record(&recording).await?;
+        assert_eq!(get_explorer_api().await, backend.url());
+        assert_eq!(estimate_fee_rate(6).await?, Some(5.0));
+        backend.finish().await?;
+        assert_eq!(get_explorer_api().await, explorer);
+
+        let backend = RecordReplay::replay(&recording).await?;
+        assert_eq!(estimate_fee_rate(6).await?, Some(5.0));
+        backend.finish().await?;
+        anyhow::Ok(())
+    })
+    .await??;
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_record_electrum_backend() -> Result<()> {
+    init_logging("replay=warn");
+
+    let recording = env::temp_dir().join("bitmask_record_replay_electrum.json");
+    let electrum = "ssl://electrum.blockstream.info:60002";
+    set_env("BITCOIN_EXPLORER_API_TESTNET", electrum).await;
+
+    // Only Esplora explorers are recorded, the explorer stays untouched
+    with_network("testnet", async {
+        assert!(matches!(
+            RecordReplay::record(&recording).await,
+            Err(RecordReplayError::Unsupported(backend)) if backend == electrum
+        ));
+        assert_eq!(get_explorer_api().await, electrum);
+        anyhow::Ok(())
+    })
+    .await??;
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 mod rgb {
 
     mod unit {
+        mod accounting;
         mod amount;
+        mod backup;
         mod invoice;
         mod issue;
         mod psbt;
@@ -16,19 +18,24 @@ mod rgb {
         mod accept;
         mod batch;
         mod cambria;
+        mod collaborative;
         mod collectibles;
         mod crdt;
         mod drain;
         mod dustless;
         mod fungibles;
+        mod ignored;
         mod import;
         mod inspect;
         mod issue;
+        mod proofs;
         mod proxy;
         mod rbf;
+        mod rebroadcast;
         mod sign_hash;
         mod states;
         mod swaps;
+        mod timelock;
         mod transfers;
         mod udas;
         pub mod utils;
@@ -1,20 +1,27 @@
 #![cfg(not(target_arch = "wasm32"))]
+use amplify::hex::FromHex;
 use anyhow::Result;
 use bitmask_core::{
-    bitcoin::{save_mnemonic, sign_and_publish_psbt_file},
+    bitcoin::{new_mnemonic, save_mnemonic, sign_and_publish_psbt_file},
     rgb::{
-        create_watcher, list_transfers, remove_transfer, save_transfer, structs::ContractAmount,
-        verify_transfers, watcher_next_address,
+        accept_transfer, accept_transfer_until, create_watcher,
+        fs::{retrieve_accept_journal, retrieve_unconfirmed_transfers, store_accept_journal},
+        get_contract, list_contracts, list_transfers, recover_accept_transfers, remove_transfer,
+        save_transfer,
+        structs::{ContractAmount, RgbAcceptJournal},
+        transfer::check_consignment_structure,
+        verify_transfers, watcher_next_address, AcceptCheckpoint, TransferError,
     },
     structs::{
-        DecryptedWalletData, RgbRemoveTransferRequest, RgbSaveTransferRequest, SecretString,
-        SignPsbtRequest, TransferType, TxStatus, WatcherRequest,
+        AcceptRequest, DecryptedWalletData, RgbRemoveTransferRequest, RgbSaveTransferRequest,
+        SecretString, SignPsbtRequest, TransferType, TxStatus, WatcherRequest,
     },
 };
 
 use crate::rgb::integration::utils::{
-    create_new_invoice, create_new_psbt, create_new_transfer, issuer_issue_contract_v2,
-    send_some_coins, UtxoFilter, ISSUER_MNEMONIC, OWNER_MNEMONIC,
+    create_new_invoice, create_new_psbt, create_new_transfer, generate_new_block,
+    issue_and_transfer_to_owner, issuer_issue_contract_v2, publish_transfer, send_some_coins,
+    UtxoFilter, ISSUER_MNEMONIC, OWNER_MNEMONIC,
 };
 
 #[tokio::test]
@@ -294,3 +301,132 @@ pub async fn accept_all_transfers() -> Result<()> {
     }
     Ok(())
 }
+
+#[tokio::test]
+pub async fn check_consignment_structure_before_store() -> Result<()> {
+    // 1. Issue and transfer
+    let issuer_keys = save_mnemonic(
+        &SecretString(ISSUER_MNEMONIC.to_string()),
+        &SecretString("".to_string()),
+    )
+    .await?;
+    let owner_keys = save_mnemonic(
+        &SecretString(OWNER_MNEMONIC.to_string()),
+        &SecretString("".to_string()),
+    )
+    .await?;
+
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        None,
+    )
+    .await?;
+    let issuer_resp = &issuer_resp[0];
+
+    let owner_invoice = &create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(2, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+    let psbt_resp = create_new_psbt(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        vec![issuer_resp.issue_utxo.clone()],
+        issuer_keys.clone(),
+    )
+    .await?;
+    let transfer_resp =
+        &create_new_transfer(issuer_keys.clone(), owner_invoice.clone(), psbt_resp).await?;
+
+    // 2. Well-formed consignment
+    let consig = Vec::<u8>::from_hex(&transfer_resp.consig)?;
+    let contract_id = check_consignment_structure(&consig)?;
+    assert_eq!(contract_id.to_string(), issuer_resp.contract_id);
+
+    // 3. Truncated consignment and arbitrary data
+    assert!(check_consignment_structure(&consig[..consig.len() / 2]).is_err());
+    assert!(check_consignment_structure(b"not a consignment").is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn allow_recover_interrupted_accept_transfer() -> Result<()> {
+    // 1. Issue and transfer to owner
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, transfer_resp) =
+        &issue_and_transfer_to_owner(&issuer_keys, &owner_keys).await?;
+    publish_transfer(&issuer_keys, transfer_resp).await?;
+    generate_new_block().await;
+
+    // 2. A failure after validating the consignment leaves the stock unchanged
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+    let request = AcceptRequest {
+        consignment: transfer_resp.consig.clone(),
+        force: false,
+        invoice: None,
+    };
+    let result = accept_transfer_until(&owner_sk, request.clone(), |_| false).await;
+    assert_eq!(result.err(), Some(TransferError::Expired));
+    assert!(list_contracts(&owner_sk, false).await?.contracts.is_empty());
+    assert!(retrieve_accept_journal(&owner_sk).await?.is_empty());
+
+    // 3. A failure between the stock and the wallet writes is recovered
+    let result = accept_transfer_until(&owner_sk, request, |checkpoint| {
+        checkpoint != AcceptCheckpoint::StockStored
+    })
+    .await;
+    assert_eq!(result.err(), Some(TransferError::Interrupted));
+    let unconfirmed = retrieve_unconfirmed_transfers(&owner_sk).await?;
+    assert!(!unconfirmed.transfers.contains_key(&issuer_resp.contract_id));
+
+    let journal = retrieve_accept_journal(&owner_sk).await?;
+    assert_eq!(journal.entries.len(), 1);
+    let recovery = recover_accept_transfers(&owner_sk).await?;
+    assert_eq!(
+        recovery.recovered,
+        journal.entries.keys().cloned().collect::<Vec<_>>()
+    );
+    assert!(recovery.quarantined.is_empty());
+    assert!(retrieve_accept_journal(&owner_sk).await?.is_empty());
+
+    let unconfirmed = retrieve_unconfirmed_transfers(&owner_sk).await?;
+    assert!(unconfirmed.transfers.contains_key(&issuer_resp.contract_id));
+    let amount = ContractAmount::with(2, 0, issuer_resp.precision).to_value();
+    let contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(contract.balance, amount);
+
+    // 4. Entries which cannot be accepted again are quarantined
+    let mut journal = RgbAcceptJournal::default();
+    journal
+        .entries
+        .insert("broken".to_string(), "0a0b0c".to_string());
+    store_accept_journal(&owner_sk, journal).await?;
+
+    let recovery = recover_accept_transfers(&owner_sk).await?;
+    assert!(recovery.recovered.is_empty());
+    assert_eq!(recovery.quarantined, vec!["broken".to_string()]);
+
+    let journal = retrieve_accept_journal(&owner_sk).await?;
+    assert!(journal.is_empty());
+    assert_eq!(journal.quarantine["broken"].consignment, "0a0b0c");
+    assert_eq!(
+        recover_accept_transfers(&owner_sk).await?,
+        Default::default()
+    );
+
+    Ok(())
+}
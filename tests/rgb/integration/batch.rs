@@ -59,6 +59,7 @@ pub async fn create_strict_transfer() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(issuer_sk, issue_uda_req).await?;
@@ -128,6 +129,7 @@ pub async fn create_strict_transfer() -> Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -176,6 +178,7 @@ pub async fn create_transfer_rbf() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(issuer_sk, issue_uda_req).await?;
@@ -279,6 +282,7 @@ pub async fn create_transfer_rbf() -> Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -329,6 +333,7 @@ pub async fn create_batch_transfer() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(issuer_sk, issue_uda_req).await?;
@@ -438,6 +443,9 @@ pub async fn create_batch_transfer() -> Result<()> {
         psbt: psbt_resp_2.psbt,
         rgb_invoice: other_resp.invoice.clone(),
         terminal: psbt_resp_2.terminal,
+        from_seals: vec![],
+        receipt: false,
+        change_split: vec![],
     };
 
     let rgb_invoice = RgbInvoice::from_str(&owner_resp.invoice)?;
@@ -472,6 +480,7 @@ pub async fn create_batch_transfer() -> Result<()> {
         let request = AcceptRequest {
             consignment: consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -482,6 +491,7 @@ pub async fn create_batch_transfer() -> Result<()> {
     let request = AcceptRequest {
         consignment: prev_consig.clone(),
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -556,6 +566,7 @@ pub async fn create_transfer_skipping_invalid_states() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(issuer_sk, issue_uda_req).await?;
@@ -663,6 +674,7 @@ pub async fn create_transfer_skipping_invalid_states() -> Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -688,6 +700,10 @@ pub async fn create_transfer_skipping_invalid_states() -> Result<()> {
         change_terminal: "/20/1".to_string(),
         fee: PsbtFeeRequest::Value(546),
         bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
+        change_split: vec![],
     };
 
     let full_transfer_resp = full_transfer_asset(issuer_sk, full_transfer_req).await;
@@ -710,6 +726,7 @@ pub async fn create_transfer_skipping_invalid_states() -> Result<()> {
         let request = AcceptRequest {
             consignment: consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
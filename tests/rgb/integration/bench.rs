@@ -5,11 +5,12 @@ use std::str::FromStr;
 use anyhow::Result;
 use bitcoin::Txid;
 use bitmask_core::{
-    bitcoin::{get_blockchain, new_mnemonic, sign_and_publish_psbt_file},
+    bitcoin::{get_blockchain, get_new_address, new_mnemonic, sign_and_publish_psbt_file},
+    constants::{get_env, set_env},
     rgb::{
         accept_transfer, consignmnet::NewTransferOptions, create_watcher, get_contract,
         internal_replace_transfer, issue_contract, list_contracts, structs::ContractAmount,
-        transfer::extract_transfer, watcher_next_address,
+        transfer::extract_transfer, watcher_next_address, watcher_unspent_utxos,
     },
     structs::{
         AcceptRequest, IssueRequest, PsbtFeeRequest, PublishedPsbtResponse, RgbReplaceResponse,
@@ -59,6 +60,7 @@ pub async fn measure_batch_operation() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(&issuer_sk, issue_uda_req).await?;
@@ -128,6 +130,7 @@ pub async fn measure_batch_operation() -> Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -177,6 +180,7 @@ pub async fn create_transfer_rbf() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(&issuer_sk, issue_uda_req).await?;
@@ -280,6 +284,7 @@ pub async fn create_transfer_rbf() -> Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -330,6 +335,7 @@ pub async fn create_batch_transfer() -> Result<()> {
         seal: issue_seal.to_owned(),
         iface: "RGB21".to_string(),
         meta,
+        terms: None,
     };
 
     let _uda_resp = issue_contract(&issuer_sk, issue_uda_req).await?;
@@ -439,6 +445,9 @@ pub async fn create_batch_transfer() -> Result<()> {
         psbt: psbt_resp_2.psbt,
         rgb_invoice: other_resp.invoice.clone(),
         terminal: psbt_resp_2.terminal,
+        from_seals: vec![],
+        receipt: false,
+        change_split: vec![],
     };
 
     let rgb_invoice = RgbInvoice::from_str(&owner_resp.invoice)?;
@@ -475,6 +484,7 @@ pub async fn create_batch_transfer() -> Result<()> {
         let request = AcceptRequest {
             consignment: consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -488,6 +498,7 @@ pub async fn create_batch_transfer() -> Result<()> {
     let request = AcceptRequest {
         consignment: prev_consig.clone(),
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -523,3 +534,51 @@ pub async fn create_batch_transfer() -> Result<()> {
 
     Ok(())
 }
+
+#[ignore = "Just running locally"]
+#[tokio::test]
+pub async fn measure_watcher_sync_batches() -> Result<()> {
+    // 1. Initial Setup (a wallet with many used addresses)
+    let used_addresses = 20;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_sk = owner_keys.private.nostr_prv.clone();
+    let watcher_name = "default";
+    let create_watch_req = WatcherRequest {
+        name: watcher_name.to_string(),
+        xpub: owner_keys.public.watcher_xpub.clone(),
+        force: false,
+    };
+    create_watcher(&owner_sk, create_watch_req).await?;
+
+    let descriptor = SecretString(owner_keys.public.rgb_assets_descriptor_xpub.clone());
+    for _ in 0..used_addresses {
+        let address = get_new_address(&descriptor, None).await?;
+        send_some_coins(&address, "0.001").await;
+    }
+    generate_new_block().await;
+
+    // 2. Full scan with one request at a time vs. batched requests
+    let default_batch_size = get_env("EXPLORER_BATCH_SIZE").await;
+    let mut results = vec![];
+    for batch_size in ["1", "6", "25"] {
+        set_env("EXPLORER_BATCH_SIZE", batch_size).await;
+        let start = std::time::Instant::now();
+        let resp = watcher_unspent_utxos(&owner_sk, watcher_name, "RGB20").await?;
+        println!(
+            "batch size {batch_size}: {} utxos in {:?}",
+            resp.utxos.len(),
+            start.elapsed()
+        );
+
+        let mut utxos: Vec<_> = resp.utxos.into_iter().map(|x| x.outpoint).collect();
+        utxos.sort();
+        results.push(utxos);
+    }
+    set_env("EXPLORER_BATCH_SIZE", &default_batch_size).await;
+
+    // 3. Batching doesn't change what is found
+    assert_eq!(results[0].len(), used_addresses);
+    assert!(results.iter().all(|utxos| utxos == &results[0]));
+
+    Ok(())
+}
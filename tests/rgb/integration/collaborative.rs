@@ -0,0 +1,158 @@
+#![cfg(not(target_arch = "wasm32"))]
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::{new_mnemonic, sign_and_publish_psbt_file},
+    rgb::{
+        accept_transfer, combine_psbts, create_psbt, create_watcher, get_contract, join_psbts,
+        join_transfer_psbts, structs::ContractAmount, watcher_next_address, watcher_next_utxo,
+        PsbtError,
+    },
+    structs::{
+        AcceptRequest, MergePsbtRequest, PsbtFeeRequest, PsbtInputRequest, PsbtRequest,
+        RgbJoinTransferRequest, SecretString, SignPsbtRequest, WatcherRequest,
+    },
+};
+
+use crate::rgb::integration::utils::{
+    create_new_invoice, create_new_psbt, issuer_issue_contract_v2, send_some_coins, UtxoFilter,
+};
+
+#[tokio::test]
+async fn allow_join_contributors_psbts_into_rgb_transfer() -> Result<()> {
+    // 1. Issuer (first contributor) and Owner
+    let whatever_address = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let contributor_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = &issuer_resp[0];
+
+    let owner_invoice = &create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(2, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+
+    let issuer_psbt = create_new_psbt(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        vec![issuer_resp.issue_utxo.clone()],
+        issuer_keys.clone(),
+    )
+    .await?;
+
+    // 2. Second contributor adds its own (bitcoin) input and output
+    let watcher_name = "default";
+    let contributor_sk = contributor_keys.private.nostr_prv.to_string();
+    let create_watch_req = WatcherRequest {
+        name: watcher_name.to_string(),
+        xpub: contributor_keys.public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(&contributor_sk, create_watch_req).await?;
+
+    let address = watcher_next_address(&contributor_sk, watcher_name, "RGB20").await?;
+    send_some_coins(&address.address, "0.01").await;
+    let contributor_utxo = watcher_next_utxo(&contributor_sk, watcher_name, "RGB20")
+        .await?
+        .utxo
+        .unwrap();
+
+    let contributor_desc = contributor_keys.public.rgb_assets_descriptor_xpub.clone();
+    let contributor_req = PsbtRequest {
+        asset_descriptor_change: None,
+        asset_terminal_change: Some("/20/1".to_owned()),
+        asset_inputs: vec![],
+        bitcoin_inputs: vec![PsbtInputRequest {
+            descriptor: SecretString(contributor_desc),
+            utxo: contributor_utxo.outpoint.clone(),
+            utxo_terminal: "/20/0".to_owned(),
+            sigh_hash: None,
+            tapret: None,
+        }],
+        bitcoin_changes: vec![format!("{whatever_address}:1000")],
+        fee: PsbtFeeRequest::Value(1000),
+        rbf: true,
+        lock: None,
+        max_fee: None,
+        max_outputs: None,
+        data_output: None,
+    };
+    let contributor_psbt = create_psbt(&contributor_sk, contributor_req).await?;
+
+    // 3. Join PSBTs (the same input can't be added twice)
+    let duplicated = join_psbts(MergePsbtRequest {
+        psbts: vec![issuer_psbt.psbt.clone(), issuer_psbt.psbt.clone()],
+    });
+    assert!(matches!(duplicated, Err(PsbtError::Merge(_))));
+
+    // (combine only merges signatures of the same transaction)
+    let combined = combine_psbts(MergePsbtRequest {
+        psbts: vec![issuer_psbt.psbt.clone(), contributor_psbt.psbt.clone()],
+    });
+    assert!(matches!(combined, Err(PsbtError::Merge(_))));
+
+    let joined = join_psbts(MergePsbtRequest {
+        psbts: vec![issuer_psbt.psbt.clone(), contributor_psbt.psbt.clone()],
+    })?;
+    assert!(!joined.psbt.is_empty());
+
+    // 4. Pay the invoice with the joined PSBT (the transfer is rebuilt from the stock)
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+    let transfer_resp = &join_transfer_psbts(
+        &issuer_sk,
+        RgbJoinTransferRequest {
+            psbts: vec![issuer_psbt.psbt.clone(), contributor_psbt.psbt.clone()],
+            rgb_invoice: owner_invoice.invoice.clone(),
+            terminal: issuer_psbt.terminal.clone(),
+        },
+    )
+    .await?;
+
+    // 5. Both contributors sign their inputs
+    let request = SignPsbtRequest {
+        psbt: transfer_resp.psbt.clone(),
+        descriptors: vec![
+            SecretString(issuer_keys.private.rgb_assets_descriptor_xprv.clone()),
+            SecretString(contributor_keys.private.rgb_assets_descriptor_xprv.clone()),
+        ],
+    };
+    let resp = sign_and_publish_psbt_file(request).await;
+    assert!(resp.is_ok());
+    send_some_coins(whatever_address, "0.001").await;
+
+    // 6. Accept and check balances
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+    for sk in [issuer_sk.clone(), owner_sk.clone()] {
+        let request = AcceptRequest {
+            consignment: transfer_resp.consig.clone(),
+            force: false,
+            invoice: None,
+        };
+        let accept_resp = accept_transfer(&sk, request).await?;
+        assert!(accept_resp.valid);
+    }
+
+    let owner_contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(2.0, owner_contract.balance_normalized);
+    let issuer_contract = get_contract(&issuer_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(3.0, issuer_contract.balance_normalized);
+
+    Ok(())
+}
@@ -10,7 +10,8 @@ use bitmask_core::{
         sync_wallet,
     },
     rgb::{
-        accept_transfer, create_watcher, full_transfer_asset, get_contract, structs::ContractAmount,
+        accept_transfer, create_watcher, full_transfer_asset, get_contract,
+        structs::ContractAmount, TransferError,
     },
     structs::{
         AcceptRequest, FullRgbTransferRequest, PsbtFeeRequest, PsbtInputRequest, SecretString,
@@ -111,6 +112,7 @@ async fn create_dustless_transfer_with_fee_value() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
 
     let resp = accept_transfer(&sk, request).await;
@@ -211,6 +213,9 @@ async fn create_dustless_transfer_with_fee_rate() -> anyhow::Result<()> {
         change_terminal: "/20/1".to_string(),
         fee: PsbtFeeRequest::FeeRate(1.1),
         bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
     };
 
     let transfer_resp = full_transfer_asset(&sk, request).await?;
@@ -229,6 +234,7 @@ async fn create_dustless_transfer_with_fee_rate() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
 
     let resp = accept_transfer(&sk, request).await;
@@ -236,3 +242,88 @@ async fn create_dustless_transfer_with_fee_rate() -> anyhow::Result<()> {
     assert!(resp?.valid);
     Ok(())
 }
+
+#[tokio::test]
+async fn create_change_output_from_single_utxo() -> anyhow::Result<()> {
+    // 1. The issuer wallet only has the (3000 sats) issue utxo
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.00003".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(3000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = issuer_resp[0].clone();
+    let owner_resp = &create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(1, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+
+    let sk = issuer_keys.private.nostr_prv.to_string();
+    let request = FullRgbTransferRequest {
+        contract_id: issuer_resp.contract_id.clone(),
+        iface: issuer_resp.iface.clone(),
+        rgb_invoice: owner_resp.invoice.to_string(),
+        descriptor: SecretString(issuer_keys.public.rgb_assets_descriptor_xpub.to_string()),
+        change_terminal: "/20/1".to_string(),
+        fee: PsbtFeeRequest::Value(2000),
+        bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
+    };
+
+    // 2. Not even the smallest change output can be afforded
+    let expensive_request = FullRgbTransferRequest {
+        fee: PsbtFeeRequest::Value(2800),
+        ..request.clone()
+    };
+    let resp = full_transfer_asset(&sk, expensive_request).await;
+    assert!(matches!(
+        resp,
+        Err(TransferError::NoChangeFunds {
+            required: 3346,
+            available: 3000,
+            shortfall: 346,
+        })
+    ));
+
+    // 3. The partial transfer keeps the change in a new output
+    let transfer_resp = full_transfer_asset(&sk, request).await?;
+    let request = SignPsbtRequest {
+        psbt: transfer_resp.psbt.clone(),
+        descriptors: vec![SecretString(
+            issuer_keys.private.rgb_assets_descriptor_xprv.clone(),
+        )],
+    };
+    let resp = sign_and_publish_psbt_file(request).await;
+    assert!(resp.is_ok());
+
+    let whatever_address = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+    send_some_coins(whatever_address, "0.1").await;
+
+    let request = AcceptRequest {
+        consignment: transfer_resp.consig.clone(),
+        force: false,
+        invoice: None,
+    };
+    let resp = accept_transfer(&sk, request).await?;
+    assert!(resp.valid);
+
+    let issuer_contract = get_contract(&sk, &issuer_resp.contract_id).await?;
+    assert_eq!(4.0, issuer_contract.balance_normalized);
+    Ok(())
+}
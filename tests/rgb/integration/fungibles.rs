@@ -68,6 +68,7 @@ async fn accept_fungible_transfer() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
 
     let resp = accept_transfer(&sk, request).await;
@@ -0,0 +1,68 @@
+#![cfg(not(target_arch = "wasm32"))]
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::new_mnemonic,
+    rgb::{
+        get_contract, ignore_allocation, list_ignored_allocations, list_suspicious_allocations,
+        structs::ContractAmount, unignore_allocation,
+    },
+    structs::SecretString,
+};
+
+use crate::rgb::integration::utils::{issuer_issue_contract_v2, UtxoFilter};
+
+#[tokio::test]
+async fn allow_ignore_and_unignore_allocations() -> Result<()> {
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = &issuer_resp[0];
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+    let outpoint = issuer_resp.issue_utxo.clone();
+
+    // 1. Below the threshold, the issuance looks unsolicited (no invoice pays it)
+    let small = ContractAmount::with(1, 0, 2).to_value();
+    let suspicious = list_suspicious_allocations(&issuer_sk, small).await?;
+    assert!(suspicious.allocations.is_empty());
+
+    let large = ContractAmount::with(10, 0, 2).to_value();
+    let suspicious = list_suspicious_allocations(&issuer_sk, large).await?;
+    assert_eq!(suspicious.allocations.len(), 1);
+    assert_eq!(suspicious.allocations[0].utxo, outpoint);
+
+    // 2. Ignored allocations are hidden from balances (calling twice is harmless)
+    ignore_allocation(&issuer_sk, &outpoint).await?;
+    let ignored = ignore_allocation(&issuer_sk, &outpoint).await?;
+    assert_eq!(ignored.outpoints, vec![outpoint.clone()]);
+    assert_eq!(
+        list_ignored_allocations(&issuer_sk).await?.outpoints.len(),
+        1
+    );
+
+    let contract = get_contract(&issuer_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(contract.balance, 0);
+    assert!(contract.allocations.iter().all(|a| a.utxo != outpoint));
+    let suspicious = list_suspicious_allocations(&issuer_sk, large).await?;
+    assert!(suspicious.allocations.is_empty());
+
+    // 3. And back again once un-ignored
+    let ignored = unignore_allocation(&issuer_sk, &outpoint).await?;
+    assert!(ignored.outpoints.is_empty());
+    let contract = get_contract(&issuer_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(contract.balance_normalized, 5.0);
+
+    assert!(ignore_allocation(&issuer_sk, "not an outpoint")
+        .await
+        .is_err());
+    Ok(())
+}
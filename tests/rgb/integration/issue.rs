@@ -1,5 +1,18 @@
 #![cfg(not(target_arch = "wasm32"))]
-use crate::rgb::integration::utils::{get_uda_data, issuer_issue_contract};
+use bitmask_core::{
+    bitcoin::{new_mnemonic, save_mnemonic},
+    rgb::{
+        create_watcher, get_contract_terms, issue_contract, list_contracts, list_contracts_ndjson,
+        structs::ContractAmount, watcher_next_address, watcher_next_utxo,
+    },
+    structs::{ContractResponse, IssueRequest, IssueTermsRequest, SecretString, WatcherRequest},
+};
+use futures::TryStreamExt;
+
+use crate::rgb::integration::utils::{
+    get_uda_data, issuer_issue_contract, issuer_issue_contract_v2, send_some_coins, UtxoFilter,
+    ISSUER_MNEMONIC,
+};
 
 #[tokio::test]
 async fn allow_issuer_issue_fungible_contract() -> anyhow::Result<()> {
@@ -16,6 +29,95 @@ async fn allow_issuer_issue_uda_contract() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn allow_issuer_issue_contract_with_terms() -> anyhow::Result<()> {
+    let issuer_keys = save_mnemonic(
+        &SecretString(ISSUER_MNEMONIC.to_string()),
+        &SecretString("".to_string()),
+    )
+    .await?;
+    let watcher_name = "default";
+    let sk = &issuer_keys.private.nostr_prv;
+    let create_watch_req = WatcherRequest {
+        name: watcher_name.to_string(),
+        xpub: issuer_keys.public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(sk, create_watch_req).await?;
+
+    let next_address = watcher_next_address(sk, watcher_name, "RGB20").await?;
+    send_some_coins(&next_address.address, "0.01").await;
+    let next_utxo = watcher_next_utxo(sk, watcher_name, "RGB20").await?;
+    let issue_seal = format!("tapret1st:{}", next_utxo.utxo.unwrap().outpoint);
+
+    let text = "The holder is entitled to one share of DIBA Inc.".to_string();
+    let request = IssueRequest {
+        ticker: "DIBA".to_string(),
+        name: "DIBA".to_string(),
+        description: "DIBA".to_string(),
+        precision: 2,
+        supply: 5,
+        seal: issue_seal,
+        iface: "RGB20".to_string(),
+        meta: None,
+        terms: Some(IssueTermsRequest {
+            text: Some(text.clone()),
+            ..Default::default()
+        }),
+    };
+
+    let wrong_hash = IssueRequest {
+        terms: Some(IssueTermsRequest {
+            text: Some(text.clone()),
+            hash: Some(blake3::hash(b"other terms").to_hex().to_string()),
+            ..Default::default()
+        }),
+        ..request.clone()
+    };
+    assert!(issue_contract(sk, wrong_hash).await.is_err());
+
+    let wrong_media_type = IssueRequest {
+        terms: Some(IssueTermsRequest {
+            text: Some(text.clone()),
+            media_type: Some("text plain".to_string()),
+            ..Default::default()
+        }),
+        ..request.clone()
+    };
+    assert!(issue_contract(sk, wrong_media_type).await.is_err());
+
+    let issuer_resp = issue_contract(sk, request.clone()).await?;
+    let terms = get_contract_terms(sk, &issuer_resp.contract_id).await?;
+    assert_eq!(
+        terms.hash,
+        Some(blake3::hash(text.as_bytes()).to_hex().to_string())
+    );
+    assert_eq!(terms.media_type, Some("text/plain".to_string()));
+    assert_eq!(terms.text, Some(text));
+    assert_eq!(terms.data, None);
+
+    // Terms documents keep their media type
+    let document = b"%PDF-1.7 DIBA shares".to_vec();
+    let request = IssueRequest {
+        terms: Some(IssueTermsRequest {
+            data: Some(hex::encode(&document)),
+            media_type: Some("application/pdf".to_string()),
+            ..Default::default()
+        }),
+        ..request
+    };
+    let issuer_resp = issue_contract(sk, request).await?;
+    let terms = get_contract_terms(sk, &issuer_resp.contract_id).await?;
+    assert_eq!(
+        terms.hash,
+        Some(blake3::hash(&document).to_hex().to_string())
+    );
+    assert_eq!(terms.media_type, Some("application/pdf".to_string()));
+    assert_eq!(terms.text, None);
+    assert_eq!(terms.data, Some(hex::encode(document)));
+    Ok(())
+}
+
 // TODO: Review after support multi-token transfer
 // async fn _allow_issuer_issue_collectible_contract() -> anyhow::Result<()> {
 //     let collectible = Some(get_collectible_data());
@@ -23,3 +125,36 @@ async fn allow_issuer_issue_uda_contract() -> anyhow::Result<()> {
 //     assert!(issuer_resp.is_ok());
 //     Ok(())
 // }
+
+#[tokio::test]
+async fn allow_list_contracts_as_ndjson() -> anyhow::Result<()> {
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    issuer_issue_contract_v2(
+        2,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+
+    let contracts = list_contracts(&issuer_sk, true).await?.contracts;
+    let lines = list_contracts_ndjson(&issuer_sk, true)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(lines.len(), contracts.len());
+
+    for (line, contract) in lines.iter().zip(contracts) {
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+        let parsed: ContractResponse = serde_json::from_str(line)?;
+        assert_eq!(parsed.contract_id, contract.contract_id);
+    }
+    Ok(())
+}
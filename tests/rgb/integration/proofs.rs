@@ -0,0 +1,281 @@
+#![cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use bitmask_core::{
+    bitcoin::new_mnemonic,
+    rgb::{
+        allocation_proof, allocation_provenance, allocations_commitment, create_watcher,
+        export_allocations, export_balance_proof, get_contract, import_allocations, list_contracts,
+        merkle::{allocation_leaf, merkle_fold},
+        structs::ContractAmount,
+        verify_allocation_proof, verify_balance_proof,
+    },
+    structs::{
+        AllocationProofResponse, AllocationValue, AllocationsExportResponse, BalanceProofRequest,
+        BalanceProofResponse, MerkleStep, SecretString, TxStatus, WatcherRequest,
+    },
+};
+
+use crate::rgb::integration::utils::issue_and_accept_to_owner;
+
+#[tokio::test]
+async fn allow_export_and_verify_balance_proof() -> Result<()> {
+    // 1. Issue and transfer to owner
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, _) = &issue_and_accept_to_owner(&issuer_keys, &owner_keys).await?;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    // 2. Owner exports the proof of its balance, for the challenge of the verifier
+    let challenge = "verifier nonce 9f2c";
+    let owner_contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    let proof_req = BalanceProofRequest {
+        contract_id: issuer_resp.contract_id.clone(),
+        challenge: challenge.to_string(),
+        descriptor: SecretString(owner_keys.private.rgb_assets_descriptor_xprv.clone()),
+    };
+    let proof = export_balance_proof(&owner_sk, proof_req.clone()).await?;
+    assert_eq!(proof.balance, owner_contract.balance);
+    assert!(!proof.utxos.is_empty());
+    assert_eq!(proof.signatures.len(), proof.utxos.len());
+
+    // 3. Anyone can verify it without the owner stock
+    let verification = verify_balance_proof(proof.clone(), challenge).await?;
+    assert!(verification.valid);
+    assert_eq!(verification.balance, owner_contract.balance);
+    assert!(verification.spent.is_empty());
+    assert!(verification.unproven.is_empty());
+
+    // 4. Claiming more than the consignment assigns is rejected
+    let inflated = BalanceProofResponse {
+        balance: proof.balance + 1,
+        ..proof.clone()
+    };
+    let verification = verify_balance_proof(inflated, challenge).await?;
+    assert!(!verification.valid);
+
+    // 5. Proofs are bound to the challenge
+    let verification = verify_balance_proof(proof.clone(), "another nonce").await?;
+    assert!(!verification.valid);
+    assert_eq!(verification.unproven, proof.utxos);
+
+    // 6. Anyone holding the (public) consignment, but not the owner key, is rejected
+    let unsigned = BalanceProofResponse {
+        signatures: BTreeMap::new(),
+        ..proof.clone()
+    };
+    let verification = verify_balance_proof(unsigned, challenge).await?;
+    assert!(!verification.valid);
+    assert_eq!(verification.balance, 0);
+
+    let another_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let another_req = BalanceProofRequest {
+        descriptor: SecretString(another_keys.private.rgb_assets_descriptor_xprv.clone()),
+        ..proof_req
+    };
+    let forged = export_balance_proof(&owner_sk, another_req).await?;
+    let verification = verify_balance_proof(forged, challenge).await?;
+    assert!(!verification.valid);
+    assert_eq!(verification.unproven, proof.utxos);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_export_and_import_contract_allocations() -> Result<()> {
+    // 1. Issue and transfer to owner
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let another_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, _) = &issue_and_accept_to_owner(&issuer_keys, &owner_keys).await?;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    // 2. Owner exports the allocations of the contract
+    let export = export_allocations(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(export.contract_id, issuer_resp.contract_id);
+    assert!(!export.utxos.is_empty());
+
+    // 3. Another stock of the owner wallet, without the contract genesis,
+    // imports them
+    let split_sk = another_keys.private.nostr_prv.to_string();
+    let split_watcher = WatcherRequest {
+        name: "default".to_string(),
+        xpub: owner_keys.public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(&split_sk, split_watcher).await?;
+    let contracts = list_contracts(&split_sk, false).await?;
+    assert!(!contracts
+        .contracts
+        .iter()
+        .any(|contract| contract.contract_id == issuer_resp.contract_id));
+
+    let imported = import_allocations(&split_sk, export.clone()).await?;
+    assert_eq!(imported.contract_id, issuer_resp.contract_id);
+    for utxo in &export.utxos {
+        assert!(imported
+            .allocations
+            .iter()
+            .any(|allocation| &allocation.utxo == utxo && allocation.is_mine));
+    }
+    let owner_contract = get_contract(&owner_sk, &issuer_resp.contract_id).await?;
+    assert_eq!(imported.balance, owner_contract.balance);
+    assert_eq!(
+        imported.balance,
+        ContractAmount::with(2, 0, issuer_resp.precision).to_value()
+    );
+
+    // 4. Allocations of another wallet are rejected
+    let foreign_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let foreign_sk = foreign_keys.private.nostr_prv.to_string();
+    let foreign_watcher = WatcherRequest {
+        name: "default".to_string(),
+        xpub: foreign_keys.public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(&foreign_sk, foreign_watcher).await?;
+    assert!(import_allocations(&foreign_sk, export.clone())
+        .await
+        .is_err());
+
+    // 5. Outpoints without an allocation of the contract are rejected
+    let unknown = AllocationsExportResponse {
+        utxos: vec![format!("{}:0", "00".repeat(32))],
+        ..export.clone()
+    };
+    assert!(import_allocations(&split_sk, unknown).await.is_err());
+
+    // 6. Allocations of another contract are rejected
+    let mismatched = AllocationsExportResponse {
+        contract_id: "rgb:wrong-contract".to_string(),
+        ..export
+    };
+    assert!(import_allocations(&split_sk, mismatched).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_prove_and_verify_allocation_inclusion() -> Result<()> {
+    // 1. Issue and transfer to owner
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, _) = &issue_and_accept_to_owner(&issuer_keys, &owner_keys).await?;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    // 2. Every unspent allocation is proven against the contract commitment
+    let contract_id = &issuer_resp.contract_id;
+    let commitment = allocations_commitment(&owner_sk, contract_id).await?;
+    let owner_contract = get_contract(&owner_sk, contract_id).await?;
+    let mut proofs = vec![];
+    for allocation in owner_contract.allocations.iter().filter(|x| !x.is_spent) {
+        let proof = allocation_proof(&owner_sk, contract_id, &allocation.utxo).await?;
+        assert_eq!(proof.commitment, commitment);
+        assert!(verify_allocation_proof(proof.clone(), contract_id, &commitment).valid);
+        proofs.push(proof);
+    }
+    assert!(!proofs.is_empty());
+
+    // 3. Tampered proofs are rejected, even when the commitment they carry is
+    // forged to match the tampered allocation
+    let proof = proofs[0].clone();
+    let forge = |utxo: &str, value: &AllocationValue, path: &[MerkleStep]| {
+        let leaf = allocation_leaf(contract_id, utxo, value);
+        let commitment = hex::encode(merkle_fold(leaf, path).expect("well formed path"));
+        AllocationProofResponse {
+            contract_id: contract_id.clone(),
+            commitment,
+            utxo: utxo.to_string(),
+            value: value.clone(),
+            path: path.to_vec(),
+        }
+    };
+
+    let value = match proof.value {
+        AllocationValue::Value(value) => AllocationValue::Value(value + 1),
+        ref value => value.clone(),
+    };
+    let tampered = forge(&proof.utxo, &value, &proof.path);
+    assert!(verify_allocation_proof(tampered.clone(), contract_id, &tampered.commitment).valid);
+    assert!(!verify_allocation_proof(tampered, contract_id, &commitment).valid);
+
+    let tampered = forge(&issuer_resp.issue_utxo, &proof.value, &proof.path);
+    assert!(!verify_allocation_proof(tampered, contract_id, &commitment).valid);
+
+    let mut path = proof.path.clone();
+    path.push(MerkleStep {
+        hash: commitment.clone(),
+        left: false,
+    });
+    let tampered = forge(&proof.utxo, &proof.value, &path);
+    assert!(!verify_allocation_proof(tampered, contract_id, &commitment).valid);
+
+    // A valid path with a foreign commitment is rejected as well
+    let tampered = AllocationProofResponse {
+        commitment: "00".repeat(32),
+        ..proof.clone()
+    };
+    assert!(!verify_allocation_proof(tampered, contract_id, &commitment).valid);
+    assert!(!verify_allocation_proof(proof.clone(), contract_id, &"00".repeat(32)).valid);
+
+    // 4. Proofs of another contract are rejected
+    let other_contract_id = "rgb:2whNnbXb-LmhdDGx-tCkHTqS-SiZotoB-UqhHnMp-fxQkecdr";
+    assert!(!verify_allocation_proof(proof, other_contract_id, &commitment).valid);
+
+    // 5. Outpoints without allocations cannot be proven
+    assert!(
+        allocation_proof(&owner_sk, contract_id, &issuer_resp.issue_utxo)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_trace_allocation_provenance() -> Result<()> {
+    // 1. Issue and transfer to owner
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, transfer_resp) =
+        &issue_and_accept_to_owner(&issuer_keys, &owner_keys).await?;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    // 2. The owner allocation comes from the genesis and the transfer
+    let contract_id = &issuer_resp.contract_id;
+    let owner_contract = get_contract(&owner_sk, contract_id).await?;
+    let allocation = owner_contract
+        .allocations
+        .iter()
+        .find(|x| x.is_mine && !x.is_spent)
+        .expect("owner allocation");
+    let provenance = allocation_provenance(&owner_sk, contract_id, &allocation.utxo).await?;
+    assert!(provenance.complete);
+    assert_eq!(provenance.steps.len(), 2);
+
+    let (issue_txid, _) = issuer_resp.issue_utxo.split_once(':').unwrap();
+    let genesis = &provenance.steps[0];
+    assert!(genesis.is_genesis);
+    assert_eq!(genesis.txid.as_deref(), Some(issue_txid));
+
+    let transition = &provenance.steps[1];
+    assert!(!transition.is_genesis && !transition.is_gap);
+    assert_eq!(
+        transition.txid.as_deref(),
+        Some(transfer_resp.txid.as_str())
+    );
+    assert_ne!(transition.status, TxStatus::NotFound);
+
+    // 3. Outpoints without allocations have no provenance
+    let unknown = format!("{}:99", transfer_resp.txid);
+    assert!(allocation_provenance(&owner_sk, contract_id, &unknown)
+        .await
+        .is_err());
+
+    Ok(())
+}
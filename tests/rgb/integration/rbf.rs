@@ -8,7 +8,9 @@ use bdk::{
 };
 use bitcoin::{secp256k1::Secp256k1, Network, Txid};
 use bitmask_core::{
-    bitcoin::{bump_fee, get_blockchain, new_mnemonic, sign_and_publish_psbt_file},
+    bitcoin::{
+        bump_fee, estimate_confirmation, get_blockchain, new_mnemonic, sign_and_publish_psbt_file,
+    },
     rgb::{get_contract, structs::ContractAmount},
     structs::{PsbtFeeRequest, PsbtResponse, SecretString, SignPsbtRequest},
 };
@@ -209,3 +211,16 @@ pub async fn create_bdk_rbf_transaction() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+pub async fn estimate_confirmation_by_fee_rate() -> Result<()> {
+    let estimate = estimate_confirmation("1.0").await?;
+    assert!(!estimate.confirmed);
+    assert!(estimate.blocks >= 1);
+    assert_eq!(estimate.minutes, estimate.blocks * 10);
+
+    let estimate = estimate_confirmation("not a fee rate").await;
+    assert!(estimate.is_err());
+
+    Ok(())
+}
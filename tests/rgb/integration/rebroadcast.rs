@@ -0,0 +1,64 @@
+#![cfg(not(target_arch = "wasm32"))]
+use bitmask_core::{
+    bitcoin::new_mnemonic,
+    rgb::{create_watcher, rebroadcast_pending, sign_and_publish_transfer_psbt},
+    structs::{SecretString, SignPsbtRequest, WatcherRequest},
+};
+
+use crate::rgb::integration::utils::{generate_new_block, issue_and_transfer_to_owner};
+
+#[tokio::test]
+async fn allow_rebroadcast_published_transfers() -> anyhow::Result<()> {
+    // 0. Create Watchers
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+    for (sk, xpub) in [
+        (&issuer_sk, &issuer_keys.public.watcher_xpub),
+        (&owner_sk, &owner_keys.public.watcher_xpub),
+    ] {
+        let create_watch_req = WatcherRequest {
+            name: "default".to_string(),
+            xpub: xpub.to_owned(),
+            force: true,
+        };
+        create_watcher(sk, create_watch_req).await?;
+    }
+
+    // 1. Issue and transfer to owner
+    let (_, transfer_resp) = &issue_and_transfer_to_owner(&issuer_keys, &owner_keys).await?;
+
+    // 2. Publish the witness transaction (a copy is kept for rebroadcasts)
+    let request = SignPsbtRequest {
+        psbt: transfer_resp.psbt.clone(),
+        descriptors: vec![SecretString(
+            issuer_keys.private.rgb_assets_descriptor_xprv.clone(),
+        )],
+    };
+    let publish_resp = sign_and_publish_transfer_psbt(&issuer_sk, request).await?;
+    assert!(publish_resp.sign);
+    let txid = publish_resp.txid;
+
+    // 3. Transactions still in the mempool are accepted again
+    let rebroadcast_resp = rebroadcast_pending(&issuer_sk).await?;
+    assert!(rebroadcast_resp.accepted.contains(&txid));
+    assert!(rebroadcast_resp.rejected.is_empty());
+    assert!(rebroadcast_resp.missing.is_empty());
+
+    // 4. Confirmed transactions are not rebroadcasted
+    generate_new_block().await;
+    let rebroadcast_resp = rebroadcast_pending(&issuer_sk).await?;
+    assert!(!rebroadcast_resp.accepted.contains(&txid));
+    assert!(!rebroadcast_resp.rejected.contains_key(&txid));
+    assert!(!rebroadcast_resp.missing.contains(&txid));
+
+    // 5. Wallets without transfers have nothing to rebroadcast
+    let rebroadcast_resp = rebroadcast_pending(&owner_sk).await?;
+    assert!(rebroadcast_resp.accepted.is_empty());
+    assert!(rebroadcast_resp.rejected.is_empty());
+    assert!(rebroadcast_resp.missing.is_empty());
+
+    Ok(())
+}
@@ -1,13 +1,31 @@
 #![cfg(not(target_arch = "wasm32"))]
+use std::str::FromStr;
+
 use bitmask_core::{
     bitcoin::{new_mnemonic, sign_and_publish_psbt_file},
-    rgb::{accept_transfer, create_watcher, get_contract, structs::ContractAmount},
-    structs::{AcceptRequest, SecretString, SignPsbtRequest, WatcherRequest},
+    constants::get_explorer_api,
+    rgb::{
+        accept_transfer,
+        constants::RGB_DEFAULT_NAME,
+        contract::{export_contract, export_raw_contract},
+        create_watcher, diff_stock,
+        fs::retrieve_stock_account,
+        get_contract,
+        resolvers::ExplorerResolver,
+        snapshot_stock,
+        structs::ContractAmount,
+        wallet::sync_wallet,
+    },
+    structs::{
+        AcceptRequest, AllocationDetail, AssetType, SecretString, SignPsbtRequest, WatcherRequest,
+    },
 };
+use rgbstd::contract::ContractId;
 
 use crate::rgb::integration::utils::{
     create_new_invoice, create_new_psbt, create_new_transfer, get_uda_data, import_new_contract,
-    issuer_issue_contract, issuer_issue_contract_v2, send_some_coins, UtxoFilter,
+    issue_and_accept_to_owner, issuer_issue_contract, issuer_issue_contract_v2, send_some_coins,
+    UtxoFilter,
 };
 
 #[tokio::test]
@@ -90,6 +108,7 @@ async fn check_fungible_allocations() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: true,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request).await;
     assert!(resp.is_ok());
@@ -99,6 +118,7 @@ async fn check_fungible_allocations() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -186,6 +206,7 @@ async fn check_uda_allocations() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request).await;
     assert!(resp.is_ok());
@@ -195,6 +216,7 @@ async fn check_uda_allocations() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig.clone(),
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -223,3 +245,159 @@ async fn check_uda_allocations() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn allow_diff_stock_snapshots() -> anyhow::Result<()> {
+    // 1. Issue and snapshot both wallets
+    let whatever_address = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = &issuer_resp[0];
+    let contract_id = &issuer_resp.contract_id;
+
+    let create_watch_req = WatcherRequest {
+        name: "default".to_string(),
+        xpub: owner_keys.clone().public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(&owner_sk, create_watch_req).await?;
+
+    let issuer_before = snapshot_stock(&issuer_sk).await?;
+    let owner_before = snapshot_stock(&owner_sk).await?;
+
+    // 2. Transfer to owner and accept on both sides
+    let owner_resp = &create_new_invoice(
+        contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(1, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+    let psbt_resp = create_new_psbt(
+        contract_id,
+        &issuer_resp.iface,
+        vec![issuer_resp.issue_utxo.clone()],
+        issuer_keys.clone(),
+    )
+    .await?;
+    let transfer_resp =
+        &create_new_transfer(issuer_keys.clone(), owner_resp.clone(), psbt_resp).await?;
+
+    let request = SignPsbtRequest {
+        psbt: transfer_resp.psbt.clone(),
+        descriptors: vec![SecretString(
+            issuer_keys.private.rgb_assets_descriptor_xprv.clone(),
+        )],
+    };
+    sign_and_publish_psbt_file(request).await?;
+    send_some_coins(whatever_address, "0.001").await;
+
+    for (sk, force) in [(&issuer_sk, true), (&owner_sk, false)] {
+        let request = AcceptRequest {
+            consignment: transfer_resp.consig.clone(),
+            force,
+            invoice: None,
+        };
+        accept_transfer(sk, request).await?;
+    }
+
+    // 3. Issuer sees its balance decrease and the issue allocation spent
+    let issuer_after = snapshot_stock(&issuer_sk).await?;
+    let diff = diff_stock(issuer_before.clone(), issuer_after.clone());
+    assert!(diff.added_contracts.is_empty());
+    assert!(diff.removed_contracts.is_empty());
+    let change = diff
+        .balances
+        .iter()
+        .find(|change| &change.contract_id == contract_id)
+        .expect("balance change missing");
+    assert_eq!(change.before, ContractAmount::with(5, 0, 2).to_value());
+    assert_eq!(change.after, ContractAmount::with(4, 0, 2).to_value());
+    assert!(diff.added_allocations.contains_key(contract_id));
+    assert!(diff.removed_allocations[contract_id]
+        .iter()
+        .any(|allocation| allocation.utxo == issuer_resp.issue_utxo));
+
+    // 4. Owner sees the contract and its allocation arrive
+    let owner_after = snapshot_stock(&owner_sk).await?;
+    let diff = diff_stock(owner_before, owner_after);
+    assert_eq!(diff.added_contracts, vec![contract_id.clone()]);
+    assert_eq!(diff.added_allocations[contract_id].len(), 1);
+    assert!(diff.removed_allocations.is_empty());
+
+    // 5. Identical snapshots have no changes
+    let diff = diff_stock(issuer_after.clone(), issuer_after);
+    assert!(diff.balances.is_empty());
+    assert!(diff.added_allocations.is_empty());
+    assert!(diff.removed_allocations.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_export_raw_contract_state_from_stock() -> anyhow::Result<()> {
+    // 1. Issue, transfer to owner and accept
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let (issuer_resp, _) = &issue_and_accept_to_owner(&issuer_keys, &owner_keys).await?;
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+
+    // 2. Export the contract of the owner with and without its schema
+    let mut resolver = ExplorerResolver {
+        explorer_url: get_explorer_api().await,
+        ..Default::default()
+    };
+    let (mut stock, rgb_account) = retrieve_stock_account(&owner_sk).await?;
+    let mut wallet = rgb_account.wallets.get(RGB_DEFAULT_NAME).cloned();
+    if let Some(wallet) = wallet.as_mut() {
+        sync_wallet(AssetType::RGB20 as u32, wallet, &mut resolver);
+    }
+
+    let contract_id = ContractId::from_str(&issuer_resp.contract_id)?;
+    let contract = export_contract(contract_id, &mut stock, &mut resolver, &mut wallet)?;
+    let raw_contract = export_raw_contract(contract_id, &mut stock, &mut resolver, &mut wallet)?;
+
+    // 3. The raw state comes from the stock, not from the genesis
+    assert_eq!(raw_contract.contract_id, contract.contract_id);
+    assert_eq!(raw_contract.supply, contract.supply);
+    assert_eq!(raw_contract.balance, contract.balance);
+    assert_eq!(
+        raw_contract.balance,
+        ContractAmount::with(2, 0, 2).to_value()
+    );
+
+    let unspent = |allocations: &[AllocationDetail]| {
+        let mut unspent: Vec<_> = allocations
+            .iter()
+            .filter(|allocation| allocation.is_mine && !allocation.is_spent)
+            .map(|allocation| (allocation.utxo.clone(), allocation.value.clone()))
+            .collect();
+        unspent.sort();
+        unspent
+    };
+    assert_eq!(
+        unspent(&raw_contract.allocations),
+        unspent(&contract.allocations)
+    );
+
+    Ok(())
+}
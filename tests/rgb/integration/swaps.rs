@@ -452,6 +452,7 @@ async fn create_hotswap_swap_for_uda() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: final_consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -0,0 +1,226 @@
+#![cfg(not(target_arch = "wasm32"))]
+use std::str::FromStr;
+
+use anyhow::Result;
+use bdk::blockchain::GetHeight;
+use bitcoin::{
+    secp256k1::{Message, Secp256k1, SecretKey},
+    util::sighash::SighashCache,
+    EcdsaSighashType, Network, OutPoint, PackedLockTime, PrivateKey, Script, Sequence, Transaction,
+    TxIn, TxOut, Txid, Witness,
+};
+use bitmask_core::{
+    bitcoin::{get_blockchain, new_mnemonic, sign_and_publish_psbt_file},
+    rgb::{create_psbt, get_contract, structs::ContractAmount, timelock_output},
+    structs::{
+        PsbtFeeRequest, PsbtInputRequest, PsbtLockRequest, PsbtRequest, PsbtResponse,
+        PsbtTimelockRequest, SecretString, SignPsbtRequest,
+    },
+};
+
+use crate::rgb::integration::utils::{generate_new_block, issuer_issue_contract_v2, UtxoFilter};
+
+const WHATEVER_ADDRESS: &str = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+
+fn beneficiary_key() -> PrivateKey {
+    let sk = SecretKey::from_slice(&[0x42; 32]).expect("valid secret key");
+    PrivateKey::new(sk, Network::Regtest)
+}
+
+async fn create_timelock_psbt(lock: PsbtLockRequest) -> Result<(SecretString, PsbtResponse)> {
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.10000000".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10_000_000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = &issuer_resp[0];
+
+    let issuer_sk = &issuer_keys.private.nostr_prv;
+    let issuer_contract = get_contract(issuer_sk, &issuer_resp.contract_id).await?;
+    let new_alloc = issuer_contract
+        .allocations
+        .into_iter()
+        .find(|x| x.is_mine)
+        .unwrap();
+
+    let secp = Secp256k1::new();
+    let beneficiary = beneficiary_key().public_key(&secp).to_string();
+    let descriptor_pub = issuer_keys.public.rgb_assets_descriptor_xpub.clone();
+    let req = PsbtRequest {
+        asset_descriptor_change: Some(SecretString(descriptor_pub.clone())),
+        asset_terminal_change: Some("/20/1".to_owned()),
+        asset_inputs: vec![PsbtInputRequest {
+            descriptor: SecretString(descriptor_pub),
+            utxo: new_alloc.utxo,
+            utxo_terminal: new_alloc.derivation,
+            sigh_hash: None,
+            tapret: None,
+        }],
+        bitcoin_inputs: vec![],
+        bitcoin_changes: vec![format!("{WHATEVER_ADDRESS}:1000")],
+        fee: PsbtFeeRequest::Value(1000),
+        rbf: true,
+        lock: Some(PsbtTimelockRequest { lock, beneficiary }),
+        max_fee: None,
+        max_outputs: None,
+        data_output: None,
+    };
+
+    let psbt_resp = create_psbt(issuer_sk, req).await?;
+    Ok((
+        SecretString(issuer_keys.private.rgb_assets_descriptor_xprv),
+        psbt_resp,
+    ))
+}
+
+// Publish the PSBT and build the transaction of the beneficiary spending its
+// time-locked output
+async fn publish_and_spend_timelock(lock: PsbtLockRequest) -> Result<Transaction> {
+    let (descriptor, PsbtResponse { psbt, .. }) = create_timelock_psbt(lock).await?;
+    let psbt_req = SignPsbtRequest {
+        psbt,
+        descriptors: vec![descriptor],
+    };
+    let psbt_resp = sign_and_publish_psbt_file(psbt_req).await?;
+    let txid = Txid::from_str(&psbt_resp.txid)?;
+
+    let secp = Secp256k1::new();
+    let beneficiary = beneficiary_key();
+    let output = timelock_output(PsbtTimelockRequest {
+        lock,
+        beneficiary: beneficiary.public_key(&secp).to_string(),
+    })
+    .await?;
+    let script = Script::from_str(&output.script)?;
+
+    let explorer = get_blockchain().await;
+    let funding = explorer
+        .get_tx(&txid)
+        .await?
+        .expect("published transaction");
+    let script_pubkey = Script::new_v0_p2wsh(&script.wscript_hash());
+    let (vout, locked) = funding
+        .output
+        .iter()
+        .enumerate()
+        .find(|(_, txout)| txout.script_pubkey == script_pubkey)
+        .expect("time-locked output");
+
+    let (lock_time, sequence) = match lock {
+        PsbtLockRequest::Height(value) | PsbtLockRequest::Time(value) => {
+            (PackedLockTime(value), Sequence::ENABLE_LOCKTIME_NO_RBF)
+        }
+        PsbtLockRequest::RelativeHeight(blocks) => {
+            (PackedLockTime::ZERO, Sequence::from_height(blocks))
+        }
+        PsbtLockRequest::RelativeTime(intervals) => (
+            PackedLockTime::ZERO,
+            Sequence::from_512_second_intervals(intervals),
+        ),
+    };
+    let mut spend = Transaction {
+        version: 2,
+        lock_time,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(txid, vout as u32),
+            script_sig: Script::new(),
+            sequence,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: locked.value - 500,
+            script_pubkey: bitcoin::Address::from_str(WHATEVER_ADDRESS)?.script_pubkey(),
+        }],
+    };
+
+    let sighash = SighashCache::new(&spend).segwit_signature_hash(
+        0,
+        &script,
+        locked.value,
+        EcdsaSighashType::All,
+    )?;
+    let msg = Message::from_slice(&sighash[..])?;
+    let mut sig = secp
+        .sign_ecdsa(&msg, &beneficiary.inner)
+        .serialize_der()
+        .to_vec();
+    sig.push(EcdsaSighashType::All as u8);
+    spend.input[0].witness = Witness::from_vec(vec![sig, script.to_bytes()]);
+
+    Ok(spend)
+}
+
+#[tokio::test]
+pub async fn reject_absolute_timelock_spend_before_height() -> Result<()> {
+    let explorer = get_blockchain().await;
+    let current_height = explorer.get_height().await?;
+
+    let lock = PsbtLockRequest::Height(current_height + 100);
+    let spend = publish_and_spend_timelock(lock).await?;
+    assert!(explorer.broadcast(&spend).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn allow_absolute_timelock_spend_after_height() -> Result<()> {
+    let explorer = get_blockchain().await;
+    let current_height = explorer.get_height().await?;
+
+    let lock = PsbtLockRequest::Height(current_height + 3);
+    let spend = publish_and_spend_timelock(lock).await?;
+    assert!(explorer.broadcast(&spend).await.is_err());
+
+    while explorer.get_height().await? < current_height + 3 {
+        generate_new_block().await;
+    }
+    assert!(explorer.broadcast(&spend).await.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn reject_relative_timelock_spend_before_blocks() -> Result<()> {
+    let explorer = get_blockchain().await;
+
+    let lock = PsbtLockRequest::RelativeHeight(100);
+    let spend = publish_and_spend_timelock(lock).await?;
+    // Mined once, still 99 blocks to go
+    generate_new_block().await;
+    assert!(explorer.broadcast(&spend).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn allow_relative_timelock_spend_after_blocks() -> Result<()> {
+    let explorer = get_blockchain().await;
+
+    let lock = PsbtLockRequest::RelativeHeight(2);
+    let spend = publish_and_spend_timelock(lock).await?;
+    generate_new_block().await;
+    assert!(explorer.broadcast(&spend).await.is_err());
+
+    generate_new_block().await;
+    assert!(explorer.broadcast(&spend).await.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn reject_invalid_timelock() -> Result<()> {
+    let lock = PsbtLockRequest::Height(0);
+    let result = create_timelock_psbt(lock).await;
+    assert!(result.is_err());
+
+    Ok(())
+}
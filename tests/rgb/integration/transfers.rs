@@ -1,5 +1,8 @@
 #![cfg(not(target_arch = "wasm32"))]
-use std::collections::{BTreeSet, HashMap};
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
 
 use bdk::wallet::AddressIndex;
 use bitmask_core::{
@@ -15,6 +18,7 @@ use bitmask_core::{
         SecretString, SignPsbtRequest, WatcherRequest,
     },
 };
+use rgbwallet::RgbInvoice;
 
 use crate::rgb::integration::utils::{
     create_new_invoice, create_new_invoice_v2, create_new_psbt, create_new_psbt_v2,
@@ -105,6 +109,7 @@ async fn create_conseq_transfers_from_issuer() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let accept_resp = accept_transfer(&sk, request).await;
         assert!(accept_resp.is_ok());
@@ -175,6 +180,7 @@ async fn create_conseq_transfers_from_issuer() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: issuer_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -187,6 +193,7 @@ async fn create_conseq_transfers_from_issuer() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: issuer_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -251,6 +258,7 @@ async fn create_conseq_transfers_from_owner() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request).await;
     assert!(resp.is_ok());
@@ -269,6 +277,7 @@ async fn create_conseq_transfers_from_owner() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: true,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -328,6 +337,7 @@ async fn create_conseq_transfers_from_owner() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -393,6 +403,7 @@ async fn create_conseq_transfers_from_owner() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&owner_sk, request).await;
     assert!(resp.is_ok());
@@ -499,6 +510,7 @@ async fn create_transfers_from_3_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let accept_resp = accept_transfer(&sk, request).await;
         assert!(accept_resp.is_ok());
@@ -603,6 +615,7 @@ async fn create_transfers_from_3_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: issuer_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -615,6 +628,7 @@ async fn create_transfers_from_3_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: owner_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -734,6 +748,7 @@ async fn create_transfers_between_2_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let accept_resp = accept_transfer(&sk, request).await;
         assert!(accept_resp.is_ok());
@@ -770,6 +785,8 @@ async fn create_transfers_between_2_owners() -> anyhow::Result<()> {
         amount: "1.00".to_string(),
         seal: another_owner_seal,
         params: HashMap::default(),
+        iface_version: None,
+        origin: false,
     };
     let import_req = ImportRequest {
         import: AssetType::RGB20,
@@ -845,6 +862,7 @@ async fn create_transfers_between_2_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: issuer_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -857,6 +875,7 @@ async fn create_transfers_between_2_owners() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: owner_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -924,6 +943,7 @@ async fn create_transfers_between_2_owners() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: another_transfer_to_issuer.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&another_owner_sk, request).await;
     assert!(resp.is_ok());
@@ -1029,6 +1049,7 @@ async fn create_transfers_with_2_transitions() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: transfer_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let accept_resp = accept_transfer(&sk, request).await;
         assert!(accept_resp.is_ok());
@@ -1140,6 +1161,7 @@ async fn create_transfers_with_2_transitions() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: issuer_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -1152,6 +1174,7 @@ async fn create_transfers_with_2_transitions() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: owner_transfer_to_another_resp.clone().consig,
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(&sk, request).await;
         assert!(resp.is_ok());
@@ -1229,6 +1252,7 @@ async fn create_transfers_with_2_transitions() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: another_transfer_to_issuer.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&another_owner_sk, request).await;
     assert!(resp.is_ok());
@@ -1323,6 +1347,7 @@ async fn create_transfers_with_2_contracts_in_same_utxo() -> anyhow::Result<()>
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request.clone()).await;
     assert!(resp.is_ok());
@@ -1390,6 +1415,7 @@ async fn create_transfers_with_2_contracts_in_same_utxo() -> anyhow::Result<()>
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request.clone()).await;
     assert!(resp.is_ok());
@@ -1511,6 +1537,7 @@ async fn create_transfers_with_2_ifaces_in_same_utxo() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: false,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request.clone()).await;
     assert!(resp.is_ok());
@@ -1581,6 +1608,7 @@ async fn create_transfers_with_2_ifaces_in_same_utxo() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.clone().consig,
         force: true,
+        invoice: None,
     };
     let resp = accept_transfer(&issuer_sk, request.clone()).await;
     assert!(resp.is_ok());
@@ -1669,6 +1697,9 @@ async fn allow_fungible_full_transfer_op() -> anyhow::Result<()> {
         change_terminal: "/20/1".to_string(),
         fee: PsbtFeeRequest::Value(1000),
         bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
     };
 
     let issue_sk = issuer_keys.private.nostr_prv.to_string();
@@ -1678,6 +1709,92 @@ async fn allow_fungible_full_transfer_op() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn allow_full_transfer_with_receipt() -> anyhow::Result<()> {
+    // 1. Initial Setup
+    let issuer_keys = save_mnemonic(
+        &SecretString(ISSUER_MNEMONIC.to_string()),
+        &SecretString("".to_string()),
+    )
+    .await?;
+    let owner_keys = save_mnemonic(
+        &SecretString(OWNER_MNEMONIC.to_string()),
+        &SecretString("".to_string()),
+    )
+    .await?;
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        5,
+        false,
+        true,
+        None,
+        Some("0.00000546".to_string()),
+        Some(UtxoFilter::with_amount_less_than(546)),
+        None,
+    )
+    .await?;
+
+    // 2. Get Invoice
+    let issuer_resp = issuer_resp[0].clone();
+    let owner_resp = &create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(2, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+
+    // 3. Get Bitcoin UTXO
+    let issuer_btc_desc = &issuer_keys.public.btc_change_descriptor_xpub;
+    let issuer_vault = get_wallet(&SecretString(issuer_btc_desc.to_string()), None).await?;
+    let issuer_address = &issuer_vault
+        .lock()
+        .await
+        .get_address(AddressIndex::LastUnused)?
+        .address
+        .to_string();
+
+    send_some_coins(issuer_address, "0.001").await;
+    sync_wallet(&issuer_vault).await?;
+
+    // 4. Make a Self Payment, asking for the receipt
+    let self_pay_req = FullRgbTransferRequest {
+        contract_id: issuer_resp.contract_id.clone(),
+        iface: issuer_resp.iface.clone(),
+        rgb_invoice: owner_resp.invoice.to_string(),
+        descriptor: SecretString(issuer_keys.public.rgb_assets_descriptor_xpub.to_string()),
+        change_terminal: "/20/1".to_string(),
+        fee: PsbtFeeRequest::Value(1000),
+        bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: true,
+    };
+
+    let issue_sk = issuer_keys.private.nostr_prv.to_string();
+    let resp = full_transfer_asset(&issue_sk, self_pay_req).await?;
+
+    // 5. Check Receipt
+    let receipt = resp.receipt.expect("receipt was requested");
+    assert_eq!(receipt.contract_id, issuer_resp.contract_id);
+    assert_eq!(receipt.iface, issuer_resp.iface);
+    assert_eq!(receipt.ticker, issuer_resp.ticker);
+    assert_eq!(receipt.name, issuer_resp.name);
+    assert_eq!(
+        receipt.amount,
+        ContractAmount::with(2, 0, issuer_resp.precision).to_string()
+    );
+    let invoice = RgbInvoice::from_str(&owner_resp.invoice)?;
+    assert_eq!(receipt.beneficiary, invoice.beneficiary.to_string());
+    assert_eq!(receipt.fee, 1000);
+    assert_eq!(receipt.txid, resp.txid);
+    assert_eq!(receipt.consignment_id, resp.consignment_id);
+    Ok(())
+}
+
 #[ignore]
 #[tokio::test]
 async fn allow_uda_full_transfer_op() -> anyhow::Result<()> {
@@ -1740,6 +1857,9 @@ async fn allow_uda_full_transfer_op() -> anyhow::Result<()> {
         change_terminal: "/21/1".to_string(),
         fee: PsbtFeeRequest::Value(546),
         bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
     };
 
     let issue_sk = issuer_keys.private.nostr_prv.to_string();
@@ -1895,6 +2015,9 @@ async fn allow_consecutive_full_transfer_bidirectional() -> anyhow::Result<()> {
             change_terminal: "/20/1".to_string(),
             fee: PsbtFeeRequest::Value(546),
             bitcoin_changes: vec![],
+            from_seals: vec![],
+            max_fee: None,
+            receipt: false,
         };
 
         let full_transfer_resp = full_transfer_asset(wallet_a_sk, self_pay_req).await;
@@ -1923,12 +2046,14 @@ async fn allow_consecutive_full_transfer_bidirectional() -> anyhow::Result<()> {
         let request = AcceptRequest {
             consignment: consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(wallet_a_sk, request.clone()).await;
         assert!(resp.is_ok());
         let request = AcceptRequest {
             consignment: consig.clone(),
             force: false,
+            invoice: None,
         };
         let resp = accept_transfer(wallet_b_sk, request.clone()).await;
         assert!(resp.is_ok());
@@ -1959,6 +2084,9 @@ async fn allow_consecutive_full_transfer_bidirectional() -> anyhow::Result<()> {
                 change_terminal: "/20/1".to_string(),
                 fee: PsbtFeeRequest::Value(546),
                 bitcoin_changes: vec![],
+                from_seals: vec![],
+                max_fee: None,
+                receipt: false,
             };
 
             let full_transfer_resp = full_transfer_asset(wallet_b_sk, self_pay_req).await;
@@ -1987,12 +2115,14 @@ async fn allow_consecutive_full_transfer_bidirectional() -> anyhow::Result<()> {
             let request = AcceptRequest {
                 consignment: consig.clone(),
                 force: false,
+                invoice: None,
             };
             let resp = accept_transfer(wallet_a_sk, request.clone()).await;
             assert!(resp.is_ok());
             let request = AcceptRequest {
                 consignment: consig.clone(),
                 force: false,
+                invoice: None,
             };
             let resp = accept_transfer(wallet_b_sk, request.clone()).await;
             assert!(resp.is_ok());
@@ -2075,6 +2205,9 @@ async fn allow_save_transfer_and_verify() -> anyhow::Result<()> {
         change_terminal: "/20/1".to_string(),
         fee: PsbtFeeRequest::Value(1000),
         bitcoin_changes: vec![],
+        from_seals: vec![],
+        max_fee: None,
+        receipt: false,
     };
 
     let resp = full_transfer_asset(&issuer_sk, self_pay_req).await?;
@@ -2114,3 +2247,85 @@ async fn allow_save_transfer_and_verify() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn allow_full_transfer_from_selected_seals() -> anyhow::Result<()> {
+    // 1. Initial Setup
+    let issuer_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let owner_keys = new_mnemonic(&SecretString("".to_string())).await?;
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.00000546".to_string()),
+        Some(UtxoFilter::with_amount_less_than(546)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = issuer_resp[0].clone();
+
+    let issuer_sk = issuer_keys.private.nostr_prv.to_string();
+    let issuer_contract = get_contract(&issuer_sk, &issuer_resp.contract_id).await?;
+    let selected_alloc = issuer_contract
+        .allocations
+        .into_iter()
+        .find(|x| x.is_mine && !x.is_spent)
+        .unwrap();
+
+    // 2. Get Invoice
+    let owner_resp = &create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(1, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+
+    // 3. Get Bitcoin UTXO
+    let issuer_btc_desc = &issuer_keys.public.btc_change_descriptor_xpub;
+    let issuer_vault = get_wallet(&SecretString(issuer_btc_desc.to_string()), None).await?;
+    let issuer_address = &issuer_vault
+        .lock()
+        .await
+        .get_address(AddressIndex::LastUnused)?
+        .address
+        .to_string();
+
+    send_some_coins(issuer_address, "0.001").await;
+    sync_wallet(&issuer_vault).await?;
+
+    // 4. Reject Payment from Unknown Seals
+    let whatever_outpoint =
+        "0000000000000000000000000000000000000000000000000000000000000001:0".to_string();
+    let self_pay_req = FullRgbTransferRequest {
+        contract_id: issuer_resp.contract_id.clone(),
+        iface: issuer_resp.iface.clone(),
+        rgb_invoice: owner_resp.invoice.to_string(),
+        descriptor: SecretString(issuer_keys.public.rgb_assets_descriptor_xpub.to_string()),
+        change_terminal: "/20/1".to_string(),
+        fee: PsbtFeeRequest::Value(1000),
+        bitcoin_changes: vec![],
+        from_seals: vec![whatever_outpoint],
+        max_fee: None,
+        receipt: false,
+    };
+
+    let resp = full_transfer_asset(&issuer_sk, self_pay_req.clone()).await;
+    assert!(resp.is_err());
+
+    // 5. Pay from Selected Seal
+    let self_pay_req = FullRgbTransferRequest {
+        from_seals: vec![selected_alloc.utxo.clone()],
+        ..self_pay_req
+    };
+
+    let resp = full_transfer_asset(&issuer_sk, self_pay_req).await;
+    assert!(resp.is_ok());
+
+    Ok(())
+}
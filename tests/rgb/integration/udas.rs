@@ -67,6 +67,7 @@ async fn accept_uda_transfer() -> anyhow::Result<()> {
     let request = AcceptRequest {
         consignment: transfer_resp.consig,
         force: false,
+        invoice: None,
     };
 
     let resp = accept_transfer(&sk, request).await;
@@ -3,8 +3,11 @@
 use amplify::bmap;
 use bdk::wallet::AddressIndex;
 use bitmask_core::{
-    bitcoin::{get_wallet, get_wallet_data, save_mnemonic, sync_wallet},
+    bitcoin::{
+        get_wallet, get_wallet_data, save_mnemonic, sign_and_publish_psbt_file, sync_wallet,
+    },
     rgb::{
+        accept_transfer,
         crdt::{RawRgbWallet, RawUtxo},
         create_invoice, create_psbt, create_watcher, import, issue_contract,
         structs::ContractAmount,
@@ -12,10 +15,11 @@ use bitmask_core::{
         watcher_unspent_utxos,
     },
     structs::{
-        AllocationDetail, AssetType, ContractResponse, DecryptedWalletData, ImportRequest,
-        InvoiceRequest, InvoiceResponse, IssueMediaRequest, IssueRequest, IssueResponse, MediaInfo,
-        MediaItemRequest, MediaRequest, PsbtFeeRequest, PsbtInputRequest, PsbtRequest,
-        PsbtResponse, RgbTransferRequest, RgbTransferResponse, SecretString, WatcherRequest,
+        AcceptRequest, AllocationDetail, AssetType, ContractResponse, DecryptedWalletData,
+        ImportRequest, InvoiceRequest, InvoiceResponse, IssueMediaRequest, IssueRequest,
+        IssueResponse, MediaInfo, MediaItemRequest, MediaRequest, PsbtFeeRequest, PsbtInputRequest,
+        PsbtRequest, PsbtResponse, RgbTransferRequest, RgbTransferResponse, SecretString,
+        SignPsbtRequest, WatcherRequest,
     },
 };
 use std::{collections::HashMap, env, process::Stdio};
@@ -244,6 +248,7 @@ pub async fn issuer_issue_contract(
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta,
+        terms: None,
     };
 
     let resp = issue_contract(sk, request).await?;
@@ -342,6 +347,8 @@ pub async fn create_new_invoice(
         amount: amount.to_string(),
         seal,
         params,
+        iface_version: None,
+        origin: false,
     };
 
     let resp = create_invoice(&sk, invoice_req).await?;
@@ -502,6 +509,7 @@ pub async fn issuer_issue_contract_v2(
             seal: issue_seal.to_owned(),
             iface: iface.to_string(),
             meta: meta.clone(),
+            terms: None,
         };
         let contract = issue_contract(sk, request).await?;
         contracts.push(contract);
@@ -548,6 +556,8 @@ pub async fn create_new_invoice_v2(
         amount: amount.to_string(),
         seal,
         params,
+        iface_version: None,
+        origin: false,
     };
 
     let resp = create_invoice(&sk, invoice_req).await?;
@@ -600,6 +610,10 @@ pub async fn create_new_psbt_v2(
         bitcoin_changes,
         fee: default_fee,
         rbf: true,
+        lock: None,
+        max_fee: None,
+        max_outputs: None,
+        data_output: None,
     };
 
     let resp = create_psbt(&sk, req).await?;
@@ -616,6 +630,9 @@ pub async fn create_new_transfer(
         psbt: psbt_resp.psbt,
         rgb_invoice: invoice_resp.invoice,
         terminal: psbt_resp.terminal,
+        from_seals: vec![],
+        receipt: false,
+        change_split: vec![],
     };
 
     let sk = owner_keys.private.nostr_prv.clone();
@@ -623,6 +640,85 @@ pub async fn create_new_transfer(
     Ok(resp)
 }
 
+/// Issue a contract (5 units) and transfer 2 units to the owner. The witness
+/// transaction is left unsigned, for the tests to publish it
+pub async fn issue_and_transfer_to_owner(
+    issuer_keys: &DecryptedWalletData,
+    owner_keys: &DecryptedWalletData,
+) -> Result<(IssueResponse, RgbTransferResponse), anyhow::Error> {
+    let issuer_resp = issuer_issue_contract_v2(
+        1,
+        "RGB20",
+        ContractAmount::with(5, 0, 2).to_value(),
+        false,
+        true,
+        None,
+        Some("0.1".to_string()),
+        Some(UtxoFilter::with_amount_equal_than(10000000)),
+        Some(issuer_keys.clone()),
+    )
+    .await?;
+    let issuer_resp = issuer_resp[0].clone();
+
+    let owner_invoice = create_new_invoice(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        ContractAmount::with(2, 0, issuer_resp.precision),
+        owner_keys.clone(),
+        None,
+        Some(issuer_resp.clone().contract.strict),
+    )
+    .await?;
+    let psbt_resp = create_new_psbt(
+        &issuer_resp.contract_id,
+        &issuer_resp.iface,
+        vec![issuer_resp.issue_utxo.clone()],
+        issuer_keys.clone(),
+    )
+    .await?;
+    let transfer_resp = create_new_transfer(issuer_keys.clone(), owner_invoice, psbt_resp).await?;
+
+    Ok((issuer_resp, transfer_resp))
+}
+
+/// Sign and publish the witness transaction of the transfer of the issuer
+pub async fn publish_transfer(
+    issuer_keys: &DecryptedWalletData,
+    transfer_resp: &RgbTransferResponse,
+) -> Result<(), anyhow::Error> {
+    let request = SignPsbtRequest {
+        psbt: transfer_resp.psbt.clone(),
+        descriptors: vec![SecretString(
+            issuer_keys.private.rgb_assets_descriptor_xprv.clone(),
+        )],
+    };
+    sign_and_publish_psbt_file(request).await?;
+    Ok(())
+}
+
+/// Issue and transfer to the owner (see [`issue_and_transfer_to_owner`]),
+/// then publish the witness transaction, confirm it and accept the transfer
+pub async fn issue_and_accept_to_owner(
+    issuer_keys: &DecryptedWalletData,
+    owner_keys: &DecryptedWalletData,
+) -> Result<(IssueResponse, RgbTransferResponse), anyhow::Error> {
+    let whatever_address = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+    let (issuer_resp, transfer_resp) = issue_and_transfer_to_owner(issuer_keys, owner_keys).await?;
+
+    publish_transfer(issuer_keys, &transfer_resp).await?;
+    send_some_coins(whatever_address, "0.001").await;
+
+    let owner_sk = owner_keys.private.nostr_prv.to_string();
+    let request = AcceptRequest {
+        consignment: transfer_resp.consig.clone(),
+        force: false,
+        invoice: None,
+    };
+    accept_transfer(&owner_sk, request).await?;
+
+    Ok((issuer_resp, transfer_resp))
+}
+
 pub fn get_uda_data() -> IssueMediaRequest {
     IssueMediaRequest {
         media: Some(MediaInfo {
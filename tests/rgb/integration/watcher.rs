@@ -1,7 +1,11 @@
 #![cfg(not(target_arch = "wasm32"))]
+use bitcoin_scripts::address::AddressNetwork;
 use bitmask_core::{
-    bitcoin::{get_wallet, save_mnemonic, sync_wallet},
-    rgb::{create_watcher, watcher_address, watcher_next_address, watcher_next_utxo, watcher_utxo},
+    bitcoin::{get_wallet, new_mnemonic, save_mnemonic, sync_wallet},
+    rgb::{
+        create_watcher, fs::retrieve_account, recover_watcher, wallet::get_address,
+        watcher_address, watcher_next_address, watcher_next_utxo, watcher_utxo,
+    },
     structs::{SecretString, WatcherRequest},
 };
 
@@ -193,3 +197,33 @@ async fn allow_migrate_watcher() -> anyhow::Result<()> {
     assert!(resp.migrate);
     Ok(())
 }
+
+#[tokio::test]
+async fn allow_recover_watcher_with_larger_gap() -> anyhow::Result<()> {
+    let keys = new_mnemonic(&SecretString("".to_string())).await?;
+
+    // Create Watcher
+    let watcher_name = "default";
+    let sk = keys.private.nostr_prv.clone();
+    let create_watch_req = WatcherRequest {
+        name: watcher_name.to_string(),
+        xpub: keys.public.watcher_xpub.clone(),
+        force: true,
+    };
+    create_watcher(&sk, create_watch_req).await?;
+
+    // Fund the address of index 30 only
+    let rgb_account = retrieve_account(&sk).await?;
+    let wallet = rgb_account.wallets.get(watcher_name).unwrap().to_owned();
+    let address = get_address(20, 30, wallet, AddressNetwork::Regtest)?;
+    assert_eq!(address.terminal.index, 30);
+    send_some_coins(&address.address.to_string(), "0.01").await;
+
+    let resp = recover_watcher(&sk, watcher_name, "RGB20", 20).await?;
+    assert!(resp.utxos.is_empty());
+
+    let resp = recover_watcher(&sk, watcher_name, "RGB20", 40).await?;
+    assert_eq!(resp.utxos.len(), 1);
+    assert_eq!(resp.utxos[0].amount, 1_000_000);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+#![cfg(not(target_arch = "wasm32"))]
+use bitmask_core::{
+    rgb::accounting::{contract_csv, AccountingEntry},
+    structs::{AllocationDetail, AllocationValue, TransferType},
+};
+
+#[tokio::test]
+async fn allow_export_contract_csv() -> anyhow::Result<()> {
+    let txid = "9d0ae5dfd4e4ab6f1fe9d7bbe059d3c9df0d5fc5443ff1d0b4ac3b0ff4d38c54";
+    let entries = vec![
+        AccountingEntry {
+            date: Some(1_700_000_000),
+            direction: TransferType::Sended,
+            amount: 250,
+            counterparty: vec!["utxob:seal".to_string()],
+            txid: txid.to_string(),
+            fee: Some(1000),
+        },
+        AccountingEntry {
+            date: None,
+            direction: TransferType::Received,
+            amount: 5,
+            counterparty: vec![],
+            txid: txid.to_string(),
+            fee: None,
+        },
+    ];
+    let allocations = vec![
+        AllocationDetail {
+            utxo: format!("{txid}:0"),
+            value: AllocationValue::Value(750),
+            derivation: "/20/0".to_string(),
+            is_mine: true,
+            is_spent: false,
+            is_pending: false,
+        },
+        AllocationDetail {
+            utxo: format!("{txid}:1"),
+            value: AllocationValue::Value(250),
+            derivation: "/20/1".to_string(),
+            is_mine: false,
+            is_spent: false,
+            is_pending: false,
+        },
+    ];
+
+    let csv = contract_csv(2, &entries, &allocations);
+    let expected = [
+        "Date,Direction,Amount,Counterparty Seal,Txid,Fee".to_string(),
+        format!("2023-11-14T22:13:20+00:00,sent,2.50,utxob:seal,{txid},1000"),
+        format!(",received,0.05,,{txid},"),
+        "".to_string(),
+        "Utxo,Amount".to_string(),
+        format!("{txid}:0,7.50"),
+    ];
+    assert_eq!(csv, expected.join("\n"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_export_contract_csv_with_quoted_fields() -> anyhow::Result<()> {
+    let entries = vec![AccountingEntry {
+        date: None,
+        direction: TransferType::Received,
+        amount: 5,
+        counterparty: vec!["seal,\"one\"\nseal two".to_string()],
+        txid: "tx,id".to_string(),
+        fee: None,
+    }];
+
+    let csv = contract_csv(0, &entries, &[]);
+    let expected = [
+        "Date,Direction,Amount,Counterparty Seal,Txid,Fee",
+        ",received,5,\"seal,\"\"one\"\"\nseal two\",\"tx,id\",",
+        "",
+        "Utxo,Amount",
+    ];
+    assert_eq!(csv, expected.join("\n"));
+
+    Ok(())
+}
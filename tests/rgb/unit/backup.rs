@@ -0,0 +1,177 @@
+#![cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+use bitcoin::Network;
+use bitcoin_30::bip32::ExtendedPubKey;
+use bitmask_core::rgb::{
+    backup::{
+        merge_transfers, merge_wallets, read_backup, restore_stock, restore_wallets, wallet_backup,
+        BackupError,
+    },
+    structs::{RgbAccountV1, RgbTransferV1, RgbTransfersV1},
+    wallet::create_wallet,
+};
+use bp::Txid;
+use rgb::RgbDescr;
+use rgbstd::persistence::{Inventory, Stock};
+
+const WATCHER_XPUB: &str = "tpubDCBwP45jcvCdTBZSxn8TcCyQGx5YgietksRRptV9YJ1xnom6edMwb2JcBnNU15t6TmotHETmgnvHQ2Nki7N7CsgFhka6D91UgMaEYpTRuSh";
+const OTHER_XPUB: &str = "tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu";
+const KEY_ORIGIN: &str = "[280a5963/86'/1'/0']";
+
+fn fake_transfers(consig_id: &str) -> RgbTransfersV1 {
+    let transfer = RgbTransferV1 {
+        consig_id: consig_id.to_string(),
+        tx_id: Txid::from_str("5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f")
+            .expect("valid txid"),
+        iface: "RGB20".to_string(),
+        consig: "00".to_string(),
+        sender: false,
+        rbf: false,
+        utxos: vec![],
+        beneficiaries: vec![],
+    };
+    RgbTransfersV1 {
+        transfers: [("rgb:contract".to_string(), vec![transfer])].into(),
+    }
+}
+
+#[tokio::test]
+async fn allow_backup_round_trip() -> anyhow::Result<()> {
+    let xpub = ExtendedPubKey::from_str(WATCHER_XPUB)?;
+    let mut wallets = HashMap::new();
+    create_wallet("default", xpub, &mut wallets)?;
+    let account = RgbAccountV1 {
+        wallets,
+        ..Default::default()
+    };
+
+    let stock = Stock::default();
+    let transfers = fake_transfers("bcna:abc");
+    let key_origins = vec![format!("tr({KEY_ORIGIN}{WATCHER_XPUB}/0/*)")];
+    let backup = wallet_backup(
+        &account,
+        &stock,
+        &transfers,
+        Some(&key_origins),
+        Network::Regtest,
+    )?;
+    assert_eq!(backup.network, "regtest");
+    assert_eq!(backup.descriptors.len(), 6);
+    assert!(backup.descriptors.iter().all(|descriptor| descriptor
+        .desc
+        .starts_with(&format!("tr({KEY_ORIGIN}{WATCHER_XPUB}/"))
+        && descriptor.desc.contains('#')));
+    // Only the Bitcoin terminals derive new addresses
+    for app in [0, 1, 9, 10, 20, 21] {
+        let terminal = format!("{WATCHER_XPUB}/{app}/*)");
+        let descriptor = backup
+            .descriptors
+            .iter()
+            .find(|descriptor| descriptor.desc.contains(&terminal))
+            .expect("descriptor of the terminal");
+        assert_eq!(descriptor.active, app < 2);
+        assert_eq!(descriptor.internal, app == 1);
+    }
+
+    let bytes = serde_json::to_vec(&backup)?;
+    let restored = restore_wallets(&read_backup(&bytes)?, Network::Regtest)?;
+    let RgbDescr::Tapret(tapret) = &restored["default"].descr;
+    assert_eq!(tapret.xpub, xpub);
+    assert!(restore_stock(&backup)?.contract_ids()?.is_empty());
+    assert_eq!(backup.rgb.transfers, transfers);
+
+    // Wallets without key origin export bare xpubs
+    let bare = wallet_backup(&account, &stock, &transfers, None, Network::Regtest)?;
+    assert_eq!(bare.descriptors.len(), 6);
+    assert!(bare
+        .descriptors
+        .iter()
+        .all(|descriptor| descriptor.desc.starts_with(&format!("tr({WATCHER_XPUB}/"))));
+    // Key origin not matching the xpub depth
+    assert!(matches!(
+        wallet_backup(
+            &account,
+            &stock,
+            &transfers,
+            Some(&[format!("[280a5963/86'/1']{WATCHER_XPUB}")]),
+            Network::Regtest
+        ),
+        Err(BackupError::WrongKeyOrigin(..))
+    ));
+
+    // Wrong network
+    assert_eq!(
+        Err(BackupError::WrongNetwork(
+            "regtest".to_string(),
+            "bitcoin".to_string()
+        )),
+        restore_wallets(&backup, Network::Bitcoin)
+    );
+
+    // Tampered descriptor
+    let mut tampered = backup.clone();
+    tampered.descriptors[0].desc = tampered.descriptors[0].desc.replace("/0/*", "/2/*");
+    assert!(matches!(
+        restore_wallets(&tampered, Network::Regtest),
+        Err(BackupError::WrongDescriptorChecksum(_))
+    ));
+
+    // Tampered RGB addendum
+    let mut tampered = backup.clone();
+    tampered.rgb.wallets.get_mut("default").unwrap().xpub = xpub.to_string().replace('t', "x");
+    assert_eq!(
+        Err(BackupError::WrongAddendumChecksum),
+        restore_wallets(&tampered, Network::Regtest)
+    );
+
+    // Tampered stock
+    let mut tampered = backup;
+    tampered.rgb.stock.push_str("00");
+    assert_eq!(
+        Err(BackupError::WrongAddendumChecksum),
+        restore_wallets(&tampered, Network::Regtest)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_merge_restored_wallets() -> anyhow::Result<()> {
+    let xpub = ExtendedPubKey::from_str(WATCHER_XPUB)?;
+    let other_xpub = ExtendedPubKey::from_str(OTHER_XPUB)?;
+    let mut restored = HashMap::new();
+    create_wallet("default", xpub, &mut restored)?;
+    let restored: BTreeMap<_, _> = restored.into_iter().collect();
+
+    // Same name and xpub
+    let mut wallets = HashMap::new();
+    create_wallet("default", xpub, &mut wallets)?;
+    merge_wallets(&mut wallets, restored.clone(), false)?;
+    assert_eq!(wallets.len(), 1);
+
+    // Same name, another xpub
+    let mut wallets = HashMap::new();
+    create_wallet("default", other_xpub, &mut wallets)?;
+    assert_eq!(
+        Err(BackupError::WalletConflict("default".to_string())),
+        merge_wallets(&mut wallets, restored.clone(), false)
+    );
+    let RgbDescr::Tapret(tapret) = &wallets["default"].descr;
+    assert_eq!(tapret.xpub, other_xpub);
+
+    merge_wallets(&mut wallets, restored, true)?;
+    let RgbDescr::Tapret(tapret) = &wallets["default"].descr;
+    assert_eq!(tapret.xpub, xpub);
+
+    // Transfers are added once
+    let mut transfers = fake_transfers("bcna:abc");
+    merge_transfers(&mut transfers, &fake_transfers("bcna:abc"));
+    merge_transfers(&mut transfers, &fake_transfers("bcna:def"));
+    assert_eq!(transfers.transfers["rgb:contract"].len(), 2);
+
+    Ok(())
+}
@@ -1,19 +1,41 @@
 #![cfg(not(target_arch = "wasm32"))]
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+    sync::Arc,
+};
 
 use amplify::{confinement::U32, hex::ToHex};
+use bitcoin_30::psbt::Psbt as PSBT;
 use bitmask_core::{
     rgb::{
-        consignment::NewTransferOptions,
-        transfer::{accept_transfer, create_invoice, pay_invoice},
+        consignment::{ConsignmentEx, NewTransferOptions, PayAllError},
+        consignment_id,
+        constants::{RGB_ORIGIN_PARAM, RGB_ORIGIN_SIG_PARAM},
+        seal::{SealDefinition, SealDefinitionError},
+        structs::RgbCounterparties,
+        transfer::{
+            accept_transfer, check_invoice_chain, check_revoked_seals,
+            consignment_id as transfer_consignment_id, contract_iface_version, create_invoice,
+            invoice_origin, invoice_paid_amount, outpoints_owned_state, pay_invoice,
+            rebind_invoice, reused_seals, seal_usage, sign_invoice_origin,
+            verify_psbt_matches_consignment, witness_txids, AcceptTransferError,
+            CounterpartyPolicy, NewInvoiceError, NewPaymentError, PsbtConsignmentError,
+        },
     },
+    structs::InvoicePaymentStatus,
     util::init_logging,
 };
-use rgbstd::persistence::Stock;
-use strict_encoding::StrictSerialize;
+use bp::{Chain, Outpoint};
+use psbt::serialize::Serialize;
+use rgb::psbt::PSBT_OUT_TAPRET_COMMITMENT;
+use rgbstd::{interface::TypedState, persistence::Stock};
+use rgbwallet::RgbInvoice;
+use seals::txout::CloseMethod;
+use strict_encoding::{StrictSerialize, TypeName};
 
 use crate::rgb::unit::utils::{
-    create_fake_contract, create_fake_invoice, create_fake_psbt, DumbResolve,
+    create_fake_contract, create_fake_invoice, create_fake_psbt, DumbResolve, FAKE_SECRET,
 };
 
 #[tokio::test]
@@ -32,6 +54,7 @@ async fn allow_create_invoice() -> anyhow::Result<()> {
         iface,
         amount,
         seal,
+        FAKE_SECRET,
         "regtest",
         params,
         &mut stock,
@@ -41,6 +64,103 @@ async fn allow_create_invoice() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn allow_create_invoice_with_iface_version() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let iface = "RGB20";
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let psbt = create_fake_psbt();
+
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+    // The version comes from the interface implementation of the contract
+    assert_eq!(contract_iface_version(&stock, contract_id, iface), Some(1));
+    assert_eq!(contract_iface_version(&stock, contract_id, "RGB21"), None);
+
+    // 1. The invoice encodes the supported version, and can be paid
+    let params = HashMap::from([("iface_version".to_string(), "1".to_string())]);
+    let invoice = create_invoice(
+        &contract_id.to_string(),
+        iface,
+        1,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        params,
+        &mut stock,
+    )?;
+    let invoice = RgbInvoice::from_str(&invoice.to_string())?;
+    assert_eq!(
+        invoice.unknown_query.get("iface_version"),
+        Some(&"1".to_string())
+    );
+
+    let options = NewTransferOptions::default();
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert!(result.is_ok());
+
+    // 2. Unsupported versions are rejected by the payee and the payer
+    let params = HashMap::from([("iface_version".to_string(), "2".to_string())]);
+    let result = create_invoice(
+        &contract_id.to_string(),
+        iface,
+        1,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        params,
+        &mut stock,
+    );
+    assert_eq!(
+        result.err(),
+        Some(NewInvoiceError::UnsupportedIfaceVersion(
+            iface.to_string(),
+            "2".to_string()
+        ))
+    );
+
+    let mut invoice = invoice;
+    invoice
+        .unknown_query
+        .insert("iface_version".to_string(), "2".to_string());
+    let options = NewTransferOptions::default();
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert_eq!(
+        result.err(),
+        Some(NewPaymentError::UnsupportedIfaceVersion(
+            iface.to_string(),
+            "2".to_string()
+        ))
+    );
+
+    // 3. Interfaces the contract does not implement are rejected
+    let result = create_invoice(
+        &contract_id.to_string(),
+        "RGB21",
+        1,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        HashMap::new(),
+        &mut stock,
+    );
+    assert!(result.is_err());
+
+    invoice.unknown_query.remove("iface_version");
+    invoice.iface = Some(TypeName::from_str("RGB21")?);
+    let options = NewTransferOptions::default();
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert_eq!(
+        result.err(),
+        Some(NewPaymentError::UnsupportedIface(
+            contract_id.to_string(),
+            "RGB21".to_string()
+        ))
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn allow_pay_invoice() -> anyhow::Result<()> {
     init_logging("rgb_invoice=warn");
@@ -66,6 +186,104 @@ async fn allow_pay_invoice() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn check_counterparty_policy() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    const ORIGIN_SK: &str = "5a9c1f0e6b7d2c3a4f8e9d0c1b2a39485766a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+    const OTHER_SK: &str = "a7fef3b1fab3e1b27b4aca3ac1b3e5a6bcd1a5e9b5df9e0ba3b2a0c3e5f7a9b1";
+
+    let mut stock = Stock::default();
+    let psbt = create_fake_psbt();
+
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let other_seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:1";
+    let mut invoice = create_fake_invoice(contract_id, seal, &mut stock);
+    sign_invoice_origin(&mut invoice, ORIGIN_SK)?;
+    let origin = invoice_origin(&invoice).expect("invoice origin");
+    assert_eq!(
+        invoice_origin(&RgbInvoice::from_str(&invoice.to_string())?),
+        Some(origin.clone())
+    );
+
+    // The invoices of a wallet have the same origin, whatever their seal
+    let mut other_invoice = create_fake_invoice(contract_id, other_seal, &mut stock);
+    sign_invoice_origin(&mut other_invoice, ORIGIN_SK)?;
+    assert_ne!(invoice.beneficiary, other_invoice.beneficiary);
+    assert_eq!(invoice_origin(&other_invoice), Some(origin.clone()));
+
+    // Origins copied to another invoice, or signed by another key, are not valid
+    let mut forged = create_fake_invoice(contract_id, other_seal, &mut stock);
+    for param in [RGB_ORIGIN_PARAM, RGB_ORIGIN_SIG_PARAM] {
+        forged
+            .unknown_query
+            .insert(param.to_string(), invoice.unknown_query[param].clone());
+    }
+    assert_eq!(invoice_origin(&forged), None);
+
+    let mut foreign = create_fake_invoice(contract_id, other_seal, &mut stock);
+    sign_invoice_origin(&mut foreign, OTHER_SK)?;
+    foreign
+        .unknown_query
+        .insert(RGB_ORIGIN_PARAM.to_string(), origin.clone());
+    assert_eq!(invoice_origin(&foreign), None);
+
+    // Denied origins are blocked, even when allowed
+    let mut counterparties = RgbCounterparties::default();
+    counterparties
+        .allow
+        .insert(contract_id.to_string(), BTreeSet::from([origin.clone()]));
+    counterparties
+        .deny
+        .insert(contract_id.to_string(), BTreeSet::from([origin.clone()]));
+    assert!(!counterparties.allows(&contract_id, Some(&origin)));
+
+    let options = NewTransferOptions {
+        counterparty_policy: Some(Arc::new(counterparties.clone())),
+        ..Default::default()
+    };
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert_eq!(
+        result.err(),
+        Some(NewPaymentError::CounterpartyDenied(origin.clone()))
+    );
+
+    // Allowlists block every other wallet, and the unidentified ones
+    counterparties.deny.clear();
+    assert!(counterparties.allows(&contract_id, Some(&origin)));
+    assert!(!counterparties.allows(&contract_id, None));
+
+    let options = NewTransferOptions {
+        counterparty_policy: Some(Arc::new(counterparties.clone())),
+        ..Default::default()
+    };
+    let result = pay_invoice(forged.to_string(), psbt.to_string(), options, &mut stock);
+    assert_eq!(
+        result.err(),
+        Some(NewPaymentError::CounterpartyDenied(
+            forged.beneficiary.to_string()
+        ))
+    );
+
+    let options = NewTransferOptions {
+        counterparty_policy: Some(Arc::new(counterparties)),
+        ..Default::default()
+    };
+    let result = pay_invoice(
+        other_invoice.to_string(),
+        psbt.to_string(),
+        options,
+        &mut stock,
+    );
+    assert!(result.is_ok());
+
+    // Without lists every wallet is allowed
+    assert!(RgbCounterparties::default().allows(&contract_id, None));
+    Ok(())
+}
+
 #[tokio::test]
 async fn allow_accept_invoice() -> anyhow::Result<()> {
     init_logging("rgb_invoice=warn");
@@ -91,3 +309,452 @@ async fn allow_accept_invoice() -> anyhow::Result<()> {
     assert!(pay_status.is_ok());
     Ok(())
 }
+
+#[tokio::test]
+async fn allow_deterministic_consignment_id() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let mut stock = Stock::default();
+    let psbt = create_fake_psbt();
+
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let options = NewTransferOptions::default();
+    let (_, transfer) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    let transfer = &transfer[0];
+    let transfer_hex = transfer.to_strict_serialized::<U32>()?.to_hex();
+
+    let expected = transfer_consignment_id(transfer)?;
+    assert_eq!(expected, transfer_consignment_id(&transfer.clone())?);
+    assert_eq!(expected, consignment_id(transfer_hex.clone()).await?);
+    assert_eq!(expected, consignment_id(transfer_hex.clone()).await?);
+
+    // Truncated consignments are rejected instead of panicking
+    assert!(consignment_id(transfer_hex[..64].to_owned()).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_invoice_payment_status() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let mut stock = Stock::default();
+    let psbt = create_fake_psbt();
+
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let options = NewTransferOptions::default();
+    let (_, transfer) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    let transfer = &transfer[0];
+
+    // Exact: same amount of the invoice
+    let paid = invoice_paid_amount(transfer, &invoice).expect("blinded seal beneficiary");
+    assert_eq!(paid, 1);
+    assert_eq!(
+        InvoicePaymentStatus::with(1, paid),
+        InvoicePaymentStatus::Exact
+    );
+
+    // Underpaid: receiver expected more than was sent
+    let mut expected_more = invoice.clone();
+    expected_more.owned_state = TypedState::Amount(2);
+    let paid = invoice_paid_amount(transfer, &expected_more).expect("blinded seal beneficiary");
+    assert_eq!(
+        InvoicePaymentStatus::with(2, paid),
+        InvoicePaymentStatus::Underpaid
+    );
+
+    // Overpaid: receiver expected less than was sent
+    let invoice = create_invoice(
+        &contract_id.to_string(),
+        "RGB20",
+        3,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        HashMap::new(),
+        &mut stock,
+    )?;
+    let options = NewTransferOptions::default();
+    let (_, overpaid) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+
+    let mut expected_less = invoice.clone();
+    expected_less.owned_state = TypedState::Amount(2);
+    let paid = invoice_paid_amount(&overpaid[0], &expected_less).expect("blinded seal beneficiary");
+    assert_eq!(paid, 3);
+    assert_eq!(
+        InvoicePaymentStatus::with(2, paid),
+        InvoicePaymentStatus::Overpaid
+    );
+
+    // Unrelated invoice: nothing is paid to its beneficiary
+    let other_invoice = create_fake_invoice(contract_id, seal, &mut stock);
+    let paid = invoice_paid_amount(transfer, &other_invoice).expect("blinded seal beneficiary");
+    assert_eq!(paid, 0);
+    assert_eq!(
+        InvoicePaymentStatus::with(1, paid),
+        InvoicePaymentStatus::Underpaid
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_reused_invoice_seals() -> anyhow::Result<()> {
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let other_seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:1";
+
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+
+    create_fake_invoice(contract_id, seal, &mut stock);
+    create_fake_invoice(contract_id, other_seal, &mut stock);
+    assert_eq!(seal_usage(seal, &mut stock)?, 1);
+    assert!(reused_seals(&mut stock)?.is_empty());
+
+    create_fake_invoice(contract_id, seal, &mut stock);
+    assert_eq!(seal_usage(seal, &mut stock)?, 2);
+    assert_eq!(seal_usage(other_seal, &mut stock)?, 1);
+
+    let reused = reused_seals(&mut stock)?;
+    assert_eq!(reused.len(), 1);
+    assert_eq!(reused.values().next(), Some(&2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_round_trip_invoice_seals() -> anyhow::Result<()> {
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+    let invoice = create_invoice(
+        &contract_id.to_string(),
+        "RGB20",
+        1,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        HashMap::new(),
+        &mut stock,
+    )?;
+
+    // Stored blinding → seal of the invoice
+    let stored = stock.seal_secrets()?.into_iter().next().unwrap();
+    let definition = SealDefinition::from_parts(
+        &format!("{}:{}", stored.txid, stored.vout),
+        &stored.blinding.to_string(),
+        "tapret1st",
+    )?;
+    assert_eq!(definition.to_graph_seal(), stored);
+    assert_eq!(definition.to_invoice_seal(), seal);
+    assert_eq!(invoice.beneficiary, definition.to_concealed_seal().into());
+
+    // Invoice seal → same seal (the first one on the outpoint)
+    let parsed = SealDefinition::from_invoice_seal(&definition.to_invoice_seal(), FAKE_SECRET, 0)?;
+    assert_eq!(parsed, definition);
+    assert_eq!(SealDefinition::try_from(stored)?, definition);
+
+    // Other invoices on the outpoint, or from other wallets, are blinded apart
+    let next = SealDefinition::from_invoice_seal(seal, FAKE_SECRET, 1)?;
+    let other_wallet = SealDefinition::from_invoice_seal(seal, b"other wallet secret", 0)?;
+    assert_eq!(next.outpoint, definition.outpoint);
+    assert_ne!(next.blinding, definition.blinding);
+    assert_ne!(other_wallet.blinding, definition.blinding);
+
+    let reused = create_invoice(
+        &contract_id.to_string(),
+        "RGB20",
+        1,
+        seal,
+        FAKE_SECRET,
+        "regtest",
+        HashMap::new(),
+        &mut stock,
+    )?;
+    assert_eq!(reused.beneficiary, next.to_concealed_seal().into());
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_malformed_seal_definitions() -> anyhow::Result<()> {
+    let outpoint = "ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+
+    assert_eq!(
+        SealDefinition::from_parts("ed823b41:0", "1", "tapret1st"),
+        Err(SealDefinitionError::WrongOutpoint("ed823b41:0".to_string()))
+    );
+    assert_eq!(
+        SealDefinition::from_parts(outpoint, "-1", "tapret1st"),
+        Err(SealDefinitionError::WrongBlinding("-1".to_string()))
+    );
+    assert_eq!(
+        SealDefinition::from_parts(outpoint, "1", "tapret"),
+        Err(SealDefinitionError::WrongMethod("tapret".to_string()))
+    );
+    assert_eq!(
+        SealDefinition::from_invoice_seal(outpoint, FAKE_SECRET, 0),
+        Err(SealDefinitionError::WrongSeal(outpoint.to_string()))
+    );
+
+    let concealed = SealDefinition::from_parts(outpoint, "1", "tapret1st")?
+        .to_concealed_seal()
+        .to_string();
+    assert_eq!(
+        SealDefinition::from_invoice_seal(&concealed, FAKE_SECRET, 0),
+        Err(SealDefinitionError::ConcealedSeal(concealed))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_psbt_matches_consignment() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let mut stock = Stock::default();
+    let psbt = create_fake_psbt();
+
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let options = NewTransferOptions::default();
+    let (psbt, transfer) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    let transfer_hex = transfer[0].to_strict_serialized::<U32>()?.to_hex();
+
+    let psbt_hex = Serialize::serialize(&psbt).to_hex();
+    assert_eq!(
+        Ok(()),
+        verify_psbt_matches_consignment(&psbt_hex, &transfer_hex)
+    );
+
+    // Tampered witness transaction: the txid is not the consignment anchor
+    let mut tampered = psbt.clone();
+    tampered.outputs[0].amount += 1;
+    let tampered_hex = Serialize::serialize(&tampered).to_hex();
+    assert!(matches!(
+        verify_psbt_matches_consignment(&tampered_hex, &transfer_hex),
+        Err(PsbtConsignmentError::TxidMismatch { .. })
+    ));
+
+    // Tampered commitment: same witness transaction, other anchored bundle
+    let mut tampered = psbt.clone();
+    for output in tampered.outputs.iter_mut() {
+        for (key, value) in output.proprietary.iter_mut() {
+            if key.subtype == PSBT_OUT_TAPRET_COMMITMENT {
+                *value = vec![0xab; 32];
+            }
+        }
+    }
+    let tampered_hex = Serialize::serialize(&tampered).to_hex();
+    assert!(matches!(
+        verify_psbt_matches_consignment(&tampered_hex, &transfer_hex),
+        Err(PsbtConsignmentError::CommitmentMismatch(_))
+    ));
+
+    // Tampered outputs: the tapret commitment is gone
+    let mut tampered = psbt;
+    for output in tampered.outputs.iter_mut() {
+        output.proprietary.clear();
+    }
+    let tampered_hex = Serialize::serialize(&tampered).to_hex();
+    assert_eq!(
+        Err(PsbtConsignmentError::NoCommitment),
+        verify_psbt_matches_consignment(&tampered_hex, &transfer_hex)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_invoice_chain_mismatch() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let mut invoice = create_fake_invoice(contract_id, seal, &mut stock);
+    assert_eq!(invoice.chain, Some(Chain::Regtest));
+    assert!(check_invoice_chain(&invoice, "regtest").is_ok());
+    assert!(matches!(
+        check_invoice_chain(&invoice, "testnet"),
+        Err(NewPaymentError::ChainMismatch { .. })
+    ));
+
+    invoice.chain = Some(Chain::Testnet3);
+    assert!(check_invoice_chain(&invoice, "testnet").is_ok());
+    assert!(matches!(
+        check_invoice_chain(&invoice, "bitcoin"),
+        Err(NewPaymentError::ChainMismatch { .. })
+    ));
+
+    // Invoices without chain are payable on mainnet only
+    invoice.chain = None;
+    assert!(check_invoice_chain(&invoice, "bitcoin").is_ok());
+    assert!(matches!(
+        check_invoice_chain(&invoice, "regtest"),
+        Err(NewPaymentError::ChainMismatch { .. })
+    ));
+
+    invoice.chain = Some(Chain::Bitcoin);
+    assert!(check_invoice_chain(&invoice, "bitcoin").is_ok());
+
+    invoice.chain = Some(Chain::LiquidV1);
+    for network in ["bitcoin", "testnet", "signet", "regtest"] {
+        assert!(matches!(
+            check_invoice_chain(&invoice, network),
+            Err(NewPaymentError::ChainMismatch { .. })
+        ));
+    }
+
+    // The chain survives the invoice encoding
+    invoice.chain = Some(Chain::Testnet3);
+    let decoded = RgbInvoice::from_str(&invoice.to_string())?;
+    assert_eq!(decoded.chain, Some(Chain::Testnet3));
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_rebind_invoice_seal() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let mut stock = Stock::default();
+    let psbt = create_fake_psbt();
+    let contract_id = create_fake_contract(&mut stock);
+
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    // 1. Swap in a fresh seal, keeping the rest of the invoice
+    let new_seal = "tapret1st:70339a6b27f55105da2d050babc759f046c21c26b7b75e9394bc1d818e50ff52:1";
+    let (rebound, revoked) = rebind_invoice(&invoice, new_seal, FAKE_SECRET, &mut stock)?;
+    assert_ne!(rebound.beneficiary, invoice.beneficiary);
+    assert_eq!(invoice.beneficiary, revoked.into());
+    assert_eq!(rebound.contract, invoice.contract);
+    assert_eq!(rebound.iface, invoice.iface);
+    assert_eq!(rebound.owned_state, invoice.owned_state);
+    assert_eq!(rebound.chain, invoice.chain);
+    assert_eq!(rebound.expiry, invoice.expiry);
+    assert_eq!(seal_usage(new_seal, &mut stock)?, 1);
+
+    // 2. Payments to the stale seal are rejected
+    let revoked_seals = BTreeSet::from([revoked.to_string()]);
+    let options = NewTransferOptions::default();
+    let (_, transfer) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    let stale_hex = transfer[0].to_strict_serialized::<U32>()?.to_hex();
+    assert!(matches!(
+        check_revoked_seals(&stale_hex, &revoked_seals),
+        Err(AcceptTransferError::RevokedSeal(_))
+    ));
+
+    // 3. Payments to the new seal are accepted
+    let options = NewTransferOptions::default();
+    let (_, transfer) = pay_invoice(rebound.to_string(), psbt.to_string(), options, &mut stock)?;
+    let rebound_hex = transfer[0].to_strict_serialized::<U32>()?.to_hex();
+    assert!(check_revoked_seals(&rebound_hex, &revoked_seals).is_ok());
+    assert!(check_revoked_seals(&stale_hex, &BTreeSet::new()).is_ok());
+
+    // 4. Invoices of other wallets cannot be rebound
+    let mut other_stock = Stock::default();
+    let other_contract_id = create_fake_contract(&mut other_stock);
+    let other_seal = "tapret1st:70339a6b27f55105da2d050babc759f046c21c26b7b75e9394bc1d818e50ff52:2";
+    let other_invoice = create_fake_invoice(other_contract_id, other_seal, &mut other_stock);
+    assert!(matches!(
+        rebind_invoice(&other_invoice, new_seal, FAKE_SECRET, &mut stock),
+        Err(NewInvoiceError::ForeignSeal(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_pay_invoice_with_change_split() -> anyhow::Result<()> {
+    init_logging("rgb_invoice=warn");
+
+    let psbt = create_fake_psbt();
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+
+    // 1. Change of 9 units split three ways, each on its own output (and address)
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let options = NewTransferOptions {
+        change_split: vec![2, 3, 4],
+        ..Default::default()
+    };
+    let (psbt_split, transfer) =
+        pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    assert_eq!(psbt_split.outputs.len(), 3);
+    let scripts: BTreeSet<_> = psbt_split
+        .outputs
+        .iter()
+        .map(|output| output.script.clone())
+        .collect();
+    assert_eq!(scripts.len(), 3);
+    let transfer = &transfer[0];
+
+    let consignment = transfer.clone().unbindle();
+    let txid = witness_txids(&consignment)
+        .into_iter()
+        .next()
+        .expect("witness txid");
+    for (vout, expected) in [(0, 2), (1, 3), (2, 4)] {
+        let (change, seals) = outpoints_owned_state(&consignment, &[Outpoint::new(txid, vout)]);
+        assert_eq!(change, expected);
+        assert_eq!(seals.len(), 1);
+    }
+
+    // 2. Split not matching the change
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let options = NewTransferOptions {
+        change_split: vec![2, 3],
+        ..Default::default()
+    };
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert!(matches!(
+        result,
+        Err(NewPaymentError::ChangeSplitMismatch {
+            expected: 9,
+            actual: 5
+        })
+    ));
+
+    let options = NewTransferOptions {
+        change_split: vec![2, 3],
+        ..Default::default()
+    };
+    let mut psbt_final = PSBT::deserialize(&psbt.serialize())?;
+    let result = stock.pay_all(
+        invoice.clone(),
+        &mut psbt_final,
+        CloseMethod::TapretFirst,
+        options,
+    );
+    assert!(matches!(
+        result,
+        Err(PayAllError::ChangeSplitMismatch {
+            expected: 9,
+            actual: 5
+        })
+    ));
+
+    // 3. Split with empty allocations
+    let options = NewTransferOptions {
+        change_split: vec![0, 9],
+        ..Default::default()
+    };
+    let result = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock);
+    assert!(matches!(result, Err(NewPaymentError::EmptyChangeSplit)));
+
+    Ok(())
+}
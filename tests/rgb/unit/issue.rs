@@ -1,7 +1,11 @@
 #![cfg(not(target_arch = "wasm32"))]
 use anyhow::Result;
 use bitmask_core::{
-    rgb::issue::issue_contract, structs::IssueRequest, util::init_logging, validators::RGBContext,
+    constants::{BMC_API_VERSION, BMC_VERSION},
+    rgb::{capabilities, issue::issue_contract},
+    structs::IssueRequest,
+    util::init_logging,
+    validators::RGBContext,
 };
 use garde::Validate;
 use rgbstd::persistence::Stock;
@@ -31,6 +35,7 @@ async fn issue_request_params_check() -> Result<()> {
         seal: seal.to_string(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
     assert!(rgb20.validate(ctx).is_ok());
 
@@ -43,6 +48,7 @@ async fn issue_request_params_check() -> Result<()> {
         seal: seal.to_string(),
         iface: iface.to_string(),
         meta: Some(get_uda_data()),
+        terms: None,
     };
     assert!(rgb21.validate(ctx).is_ok());
 
@@ -76,6 +82,7 @@ async fn issue_contract_test() -> Result<()> {
         seal,
         network,
         None,
+        None,
         &mut resolver,
         &mut stock,
     );
@@ -83,3 +90,37 @@ async fn issue_contract_test() -> Result<()> {
     assert!(contract.is_ok());
     Ok(())
 }
+
+#[tokio::test]
+async fn capabilities_list_supported_schemas() -> Result<()> {
+    let capabilities = capabilities().await;
+    assert_eq!(capabilities.version, BMC_VERSION);
+    assert_eq!(capabilities.api_version, BMC_API_VERSION);
+    assert_eq!(capabilities.interfaces, vec!["RGB20", "RGB21"]);
+    assert!(capabilities.features.contains_key("lightning"));
+
+    let mut stock = Stock::default();
+    let mut resolver = DumbResolve {};
+    let seal = "tapret1st:70339a6b27f55105da2d050babc759f046c21c26b7b75e9394bc1d818e50ff52:0";
+    let contract = issue_contract(
+        "DIBA",
+        "DIBA",
+        "DIBA",
+        8,
+        10,
+        "RGB20",
+        seal,
+        "regtest",
+        None,
+        None,
+        &mut resolver,
+        &mut stock,
+    )?;
+    let schema_id = contract.genesis.schema_id.to_string();
+    assert!(capabilities
+        .schemas
+        .iter()
+        .any(|schema| schema.schema == schema_id && schema.ifaces == vec!["RGB20"]));
+
+    Ok(())
+}
@@ -2,13 +2,19 @@
 use crate::rgb::unit::utils::{
     create_fake_contract, create_fake_invoice, create_fake_psbt, DumbResolve,
 };
+use bitcoin::{psbt::PartiallySignedTransaction as PsbtV0, Script};
+use bitcoin_blockchain::locks::{LockTime, SeqNo};
+use bitcoin_scripts::PubkeyScript;
 use bitmask_core::{
     rgb::{
         consignment::NewTransferOptions,
-        psbt::{create_psbt, extract_output_commit, NewPsbtOptions},
+        psbt::{
+            create_psbt, extract_output_commit, join_psbts, set_tapret_output, CreatePsbtError,
+            MergePsbtError, NewPsbtOptions,
+        },
         transfer::pay_invoice,
     },
-    structs::{PsbtInputRequest, SecretString},
+    structs::{PsbtInputRequest, PsbtLockRequest, SecretString},
     util::init_logging,
 };
 use rgb::persistence::Stock;
@@ -64,3 +70,387 @@ async fn allow_extract_output_commit_from_psbt() -> anyhow::Result<()> {
     assert!(commit.is_ok());
     Ok(())
 }
+
+#[tokio::test]
+async fn reject_create_psbt_above_fee_cap() -> anyhow::Result<()> {
+    let desc = "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)#8an50cqp";
+    let asset_utxo = "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1";
+    let asset_utxo_terminal = "/0/0";
+
+    // Half of the input value (1 BTC) goes to fees
+    let fee = 50_000_000;
+    let tx_resolver = DumbResolve {};
+    let inputs = vec![PsbtInputRequest {
+        descriptor: SecretString(desc.to_string()),
+        utxo: asset_utxo.to_string(),
+        utxo_terminal: asset_utxo_terminal.to_string(),
+        sigh_hash: None,
+        tapret: None,
+    }];
+
+    let psbt = create_psbt(
+        inputs.clone(),
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        NewPsbtOptions::default(),
+    );
+    assert_eq!(
+        psbt.err(),
+        Some(CreatePsbtError::FeeTooHigh {
+            fee,
+            max: 10_000_000
+        })
+    );
+
+    let options = NewPsbtOptions {
+        max_fee: Some(fee),
+        ..Default::default()
+    };
+    let psbt = create_psbt(
+        inputs,
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    );
+    assert!(psbt.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_create_psbt_above_max_outputs() -> anyhow::Result<()> {
+    let desc = "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)#8an50cqp";
+    let asset_utxo = "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1";
+    let asset_utxo_terminal = "/0/0";
+    let whatever_address = "bcrt1p76gtucrxhmn8s5622r859dpnmkj0kgfcel9xy0sz6yj84x6ppz2qk5hpsw";
+
+    let fee = 1000;
+    let tx_resolver = DumbResolve {};
+    let inputs = vec![PsbtInputRequest {
+        descriptor: SecretString(desc.to_string()),
+        utxo: asset_utxo.to_string(),
+        utxo_terminal: asset_utxo_terminal.to_string(),
+        sigh_hash: None,
+        tapret: None,
+    }];
+
+    // Two payments and the change
+    let outputs = vec![
+        format!("{whatever_address}:1000"),
+        format!("{whatever_address}:2000"),
+    ];
+
+    // 1. Exactly at the limit
+    let options = NewPsbtOptions {
+        max_outputs: Some(3),
+        ..Default::default()
+    };
+    let psbt = create_psbt(
+        inputs.clone(),
+        outputs.clone(),
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    );
+    assert!(psbt.is_ok());
+
+    // 2. Over the limit
+    let options = NewPsbtOptions {
+        max_outputs: Some(2),
+        ..Default::default()
+    };
+    let psbt = create_psbt(
+        inputs,
+        outputs,
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    );
+    assert_eq!(
+        psbt.err(),
+        Some(CreatePsbtError::TooManyOutputs { count: 3, max: 2 })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_create_psbt_with_anti_fee_sniping() -> anyhow::Result<()> {
+    let desc = "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)#8an50cqp";
+    let asset_utxo = "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1";
+    let asset_utxo_terminal = "/0/0";
+
+    let fee = 1000;
+    let tip_height = 800_000;
+    let tx_resolver = DumbResolve {};
+    let inputs = vec![PsbtInputRequest {
+        descriptor: SecretString(desc.to_string()),
+        utxo: asset_utxo.to_string(),
+        utxo_terminal: asset_utxo_terminal.to_string(),
+        sigh_hash: None,
+        tapret: None,
+    }];
+
+    // 1. No time-lock requested: the tip (or a recent block) is used
+    let options = NewPsbtOptions {
+        tip_height: Some(tip_height),
+        ..Default::default()
+    };
+    let (psbt, _) = create_psbt(
+        inputs.clone(),
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    )?;
+    assert!(((tip_height - 100)..=tip_height)
+        .any(|height| LockTime::from_height(height) == psbt.fallback_locktime));
+    assert_eq!(psbt.inputs[0].sequence_number, Some(SeqNo::rbf()));
+
+    // 2. Without RBF the inputs stay non-final, so the nLockTime is enforced
+    let options = NewPsbtOptions {
+        rbf: false,
+        tip_height: Some(tip_height),
+        ..Default::default()
+    };
+    let (psbt, _) = create_psbt(
+        inputs.clone(),
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    )?;
+    assert!(psbt.fallback_locktime.is_some());
+    assert_eq!(
+        psbt.inputs[0].sequence_number,
+        Some(SeqNo::from_consensus(0xFFFFFFFE))
+    );
+
+    // 3. Requested time-lock takes precedence
+    let options = NewPsbtOptions {
+        lock: Some(PsbtLockRequest::Height(tip_height + 100)),
+        tip_height: Some(tip_height),
+        ..Default::default()
+    };
+    let (psbt, _) = create_psbt(
+        inputs,
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    )?;
+    assert_eq!(
+        psbt.fallback_locktime,
+        LockTime::from_height(tip_height + 100)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_create_psbt_with_data_output() -> anyhow::Result<()> {
+    let desc = "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)#8an50cqp";
+    let asset_utxo = "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1";
+    let asset_utxo_terminal = "/0/0";
+
+    let fee = 1000;
+    let tx_resolver = DumbResolve {};
+    let inputs = vec![PsbtInputRequest {
+        descriptor: SecretString(desc.to_string()),
+        utxo: asset_utxo.to_string(),
+        utxo_terminal: asset_utxo_terminal.to_string(),
+        sigh_hash: None,
+        tapret: None,
+    }];
+
+    // 1. Data output along with the change, which stays the commitment host
+    let data = b"bridge memo commitment".to_vec();
+    let options = NewPsbtOptions {
+        data: Some(data.clone()),
+        ..Default::default()
+    };
+    let (psbt, _) = create_psbt(
+        inputs.clone(),
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    )?;
+    let data_script: PubkeyScript = Script::new_op_return(&data).into();
+    assert_eq!(psbt.outputs.len(), 2);
+    assert_eq!(psbt.outputs[0].script, data_script);
+    assert_eq!(psbt.outputs[0].amount, 0);
+    assert_ne!(psbt.outputs[1].script, data_script);
+
+    // 2. The RGB commitment goes to the host, the data output is kept
+    let mut stock = Stock::default();
+    let contract_id = create_fake_contract(&mut stock);
+    let seal = "tapret1st:ed823b41d8b9309933826b18e4af530363b359f05919c02bbe72f28cec6dec3e:0";
+    let invoice = create_fake_invoice(contract_id, seal, &mut stock);
+
+    let psbt = set_tapret_output(psbt, 1)?;
+    let options = NewTransferOptions::default();
+    let (psbt, _) = pay_invoice(invoice.to_string(), psbt.to_string(), options, &mut stock)?;
+    assert!(psbt
+        .outputs
+        .iter()
+        .any(|output| output.script == data_script));
+
+    let commit = extract_output_commit(psbt);
+    assert!(commit.is_ok());
+
+    // 3. Oversized data
+    let options = NewPsbtOptions {
+        data: Some(vec![0; 81]),
+        ..Default::default()
+    };
+    let psbt = create_psbt(
+        inputs,
+        vec![],
+        fee,
+        Some("/0/1".to_string()),
+        None,
+        &tx_resolver,
+        options,
+    );
+    assert_eq!(
+        psbt.err(),
+        Some(CreatePsbtError::DataTooLarge { size: 81, max: 80 })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allow_create_psbt_with_time_locks() -> anyhow::Result<()> {
+    let desc = "tr(m=[280a5963]/86h/1h/0h=[tpubDCa3US185mM8yGTXtPWY1wNRMCiX89kzN4dwTMKUJyiJnnq486MTeyYShvHiS8Dd1zR2myy5xyJFDs5YacVHn6JZbVaDAtkrXZE3tTVRHPu]/*/*)#8an50cqp";
+    let asset_utxo = "5ca6cd1f54c081c8b3a7b4bcc988e55fe3c420ac87512b53a58c55233e15ba4f:1";
+    let asset_utxo_terminal = "/0/0";
+
+    let fee = 1000;
+    let tx_resolver = DumbResolve {};
+    let inputs = vec![PsbtInputRequest {
+        descriptor: SecretString(desc.to_string()),
+        utxo: asset_utxo.to_string(),
+        utxo_terminal: asset_utxo_terminal.to_string(),
+        sigh_hash: None,
+        tapret: None,
+    }];
+
+    // (lock, rbf, version, sequence, locktime) of the serialized transaction
+    let cases = [
+        (
+            PsbtLockRequest::Height(800_100),
+            false,
+            2,
+            0xFFFFFFFE,
+            800_100,
+        ),
+        (
+            PsbtLockRequest::Height(800_100),
+            true,
+            2,
+            0xFFFFFFFD,
+            800_100,
+        ),
+        (
+            PsbtLockRequest::Time(1_700_000_000),
+            false,
+            2,
+            0xFFFFFFFE,
+            1_700_000_000,
+        ),
+        (
+            PsbtLockRequest::Time(1_700_000_000),
+            true,
+            2,
+            0xFFFFFFFD,
+            1_700_000_000,
+        ),
+        // Relative time-locks signal RBF by themselves (below 0xFFFFFFFE)
+        (PsbtLockRequest::RelativeHeight(144), false, 2, 144, 0),
+        (PsbtLockRequest::RelativeHeight(144), true, 2, 144, 0),
+        (
+            PsbtLockRequest::RelativeTime(10),
+            false,
+            2,
+            (1 << 22) | 10,
+            0,
+        ),
+        (
+            PsbtLockRequest::RelativeTime(10),
+            true,
+            2,
+            (1 << 22) | 10,
+            0,
+        ),
+    ];
+    for (lock, rbf, version, sequence, locktime) in cases {
+        let (psbt, _) = create_psbt(
+            inputs.clone(),
+            vec![],
+            fee,
+            Some("/0/1".to_string()),
+            None,
+            &tx_resolver,
+            NewPsbtOptions::with_lock(rbf, Some(lock)),
+        )?;
+        assert!(psbt.tx_version >= lock.min_tx_version());
+
+        let tx = PsbtV0::from(psbt).unsigned_tx;
+        assert_eq!(tx.version, version, "version of {lock:?} (rbf: {rbf})");
+        assert_eq!(
+            tx.input[0].sequence.0, sequence,
+            "sequence of {lock:?} (rbf: {rbf})"
+        );
+        assert_eq!(
+            tx.lock_time.0, locktime,
+            "locktime of {lock:?} (rbf: {rbf})"
+        );
+    }
+
+    // Joined PSBTs of version 1 would not enforce the relative time-lock
+    let other_input = PsbtInputRequest {
+        utxo: "4fba153e23558ca5532b5187ac20c4e35fe588c9bcb4a7b3c881c0541fcda65c:1".to_string(),
+        ..inputs[0].clone()
+    };
+    let mut psbts = vec![];
+    for input in [inputs[0].clone(), other_input] {
+        let (psbt, _) = create_psbt(
+            vec![input],
+            vec![],
+            fee,
+            Some("/0/1".to_string()),
+            None,
+            &tx_resolver,
+            NewPsbtOptions::with_lock(false, Some(PsbtLockRequest::RelativeHeight(144))),
+        )?;
+        let mut psbt = PsbtV0::from(psbt);
+        psbt.unsigned_tx.version = 1;
+        psbts.push(psbt);
+    }
+    assert!(matches!(
+        join_psbts(psbts),
+        Err(MergePsbtError::WrongTxVersion(_, 1))
+    ));
+
+    Ok(())
+}
@@ -22,6 +22,10 @@ use rgbstd::{
 use rgbwallet::RgbInvoice;
 use wallet::onchain::ResolveTx;
 
+// Wallet secret blinding the invoice seals
+#[allow(dead_code)]
+pub const FAKE_SECRET: &[u8] = b"bitmask fake wallet secret";
+
 // Resolvers
 pub struct DumbResolve {}
 
@@ -108,6 +112,7 @@ pub fn create_fake_contract(stock: &mut Stock) -> ContractId {
         seal,
         network,
         None,
+        None,
         &mut resolver,
         stock,
     )
@@ -135,6 +140,7 @@ pub fn create_fake_invoice(contract_id: ContractId, seal: &str, stock: &mut Stoc
         iface,
         amount,
         seal,
+        FAKE_SECRET,
         "regtest",
         params,
         stock,
@@ -102,6 +102,7 @@ async fn allow_issue_and_list_contracts() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
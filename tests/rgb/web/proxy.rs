@@ -167,6 +167,7 @@ async fn import_and_get_consig_from_proxy() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -244,6 +245,8 @@ async fn import_and_get_consig_from_proxy() {
             amount: invoice_amount.to_string(),
             seal: receiver_seal,
             params,
+            iface_version: None,
+            origin: false,
         };
 
         let invoice_req = serde_wasm_bindgen::to_value(&invoice_req).expect("");
@@ -264,6 +267,9 @@ async fn import_and_get_consig_from_proxy() {
             change_terminal: "/20/1".to_string(),
             fee: PsbtFeeRequest::Value(1000),
             bitcoin_changes: vec![],
+            from_seals: vec![],
+            max_fee: None,
+            receipt: false,
         };
 
         let full_transfer_req = serde_wasm_bindgen::to_value(&full_transfer_req).expect("");
@@ -377,6 +383,7 @@ async fn create_uda_with_medias() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: Some(import_media_req),
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
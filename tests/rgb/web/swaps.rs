@@ -197,6 +197,7 @@ async fn create_hotswap_flow() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -540,6 +541,7 @@ async fn create_p2p_flow() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -728,6 +730,7 @@ async fn create_p2p_flow() {
             let accept_req = AcceptRequest {
                 consignment: final_consig.clone(),
                 force: false,
+                invoice: None,
             };
             let accept_req = serde_wasm_bindgen::to_value(&accept_req).expect("");
 
@@ -918,6 +921,7 @@ async fn create_auction_flow() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -1244,6 +1248,7 @@ async fn create_airdrop_flow() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -164,6 +164,7 @@ async fn create_transfer_with_fee_value() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -247,6 +248,8 @@ async fn create_transfer_with_fee_value() {
             amount: invoice_amount.to_string(),
             seal: receiver_seal,
             params,
+            iface_version: None,
+            origin: false,
         };
 
         let invoice_req = serde_wasm_bindgen::to_value(&invoice_req).expect("");
@@ -267,6 +270,9 @@ async fn create_transfer_with_fee_value() {
             change_terminal: "/20/1".to_string(),
             fee: PsbtFeeRequest::Value(1000),
             bitcoin_changes: vec![],
+            from_seals: vec![],
+            max_fee: None,
+            receipt: false,
         };
 
         let full_transfer_req = serde_wasm_bindgen::to_value(&full_transfer_req).expect("");
@@ -469,6 +475,7 @@ async fn create_transfer_with_fee_rate() {
         seal: issue_seal.to_owned(),
         iface: iface.to_string(),
         meta: None,
+        terms: None,
     };
 
     let issue_req = serde_wasm_bindgen::to_value(&issue_req).expect("");
@@ -549,6 +556,8 @@ async fn create_transfer_with_fee_rate() {
             amount: invoice_amount.to_string(),
             seal: receiver_seal,
             params,
+            iface_version: None,
+            origin: false,
         };
 
         let invoice_req = serde_wasm_bindgen::to_value(&invoice_req).expect("");
@@ -569,6 +578,9 @@ async fn create_transfer_with_fee_rate() {
             change_terminal: "/20/1".to_string(),
             fee: PsbtFeeRequest::FeeRate(1.1),
             bitcoin_changes: vec![],
+            from_seals: vec![],
+            max_fee: None,
+            receipt: false,
         };
 
         let full_transfer_req = serde_wasm_bindgen::to_value(&full_transfer_req).expect("");
@@ -0,0 +1,59 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::env;
+
+use anyhow::Result;
+use bitmask_core::{
+    carbonado::{handle_namespaced_file, store, stored_size},
+    constants::storage_keys::{ASSETS_STOCK, ASSETS_TRANSFERS, ASSETS_WALLETS},
+    rgb::{carbonado::storage_file_prefix, storage_stats},
+};
+use tokio::fs;
+
+const SK: &str = "5a9c1f0e6b7d2c3a4f8e9d0c1b2a39485766a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+
+#[tokio::test]
+pub async fn allow_storage_stats_by_category() -> Result<()> {
+    let dir = env::temp_dir().join("bitmask_storage_stats");
+    let _ = fs::remove_dir_all(&dir).await;
+    env::set_var("CARBONADO_DIR", &dir);
+
+    // Empty wallets use no storage, and only the wallet categories are reported
+    let stats = storage_stats(SK).await?;
+    assert_eq!(stats.total, 0);
+    assert_eq!(
+        stats.categories.keys().collect::<Vec<_>>(),
+        vec!["consignments", "other", "stock"]
+    );
+
+    let mut sizes = vec![];
+    for name in [ASSETS_STOCK, ASSETS_TRANSFERS, ASSETS_WALLETS] {
+        let file_name = format!("{}.c15", storage_file_prefix(name));
+        store(SK, &file_name, &[1; 4096], false, None).await?;
+        sizes.push(stored_size(SK, &file_name).await?);
+    }
+    assert!(sizes.iter().all(|size| *size > 0));
+
+    let stats = storage_stats(SK).await?;
+    assert_eq!(stats.categories["stock"], sizes[0]);
+    assert_eq!(stats.categories["consignments"], sizes[1]);
+    assert_eq!(stats.categories["other"], sizes[2]);
+    assert_eq!(stats.total, sizes.iter().sum::<u64>());
+
+    // Files outside of the wallet state don't count
+    let pk = dir
+        .read_dir()?
+        .next()
+        .expect("wallet directory")?
+        .file_name()
+        .to_string_lossy()
+        .to_string();
+    let media = handle_namespaced_file(&pk, Some("media"), "preview.c15", 1).await?;
+    fs::write(&media, [0; 100]).await?;
+
+    let stats = storage_stats(SK).await?;
+    assert_eq!(stats.total, sizes.iter().sum::<u64>());
+    assert!(!stats.categories.contains_key("media"));
+
+    Ok(())
+}
@@ -2,12 +2,14 @@
 use std::env;
 
 use anyhow::Result;
+use bdk::blockchain::GetHeight;
 use bitmask_core::{
     bitcoin::{
-        decrypt_wallet, encrypt_wallet, get_wallet_data, hash_password, new_wallet, send_sats,
-        BitcoinError,
+        decrypt_wallet, encrypt_wallet, get_blockchain, get_wallet, get_wallet_data, hash_password,
+        last_synced_height, new_wallet, parse_fee_policy, policy_fee_rate, policy_target,
+        send_sats, sync_wallet, BitcoinError, FeeOperation,
     },
-    constants::{get_network, switch_network},
+    constants::{get_network, switch_network, FEE_POLICY_DEFAULT},
     structs::SecretString,
     util::init_logging,
     warn,
@@ -159,3 +161,53 @@ async fn wrong_network() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn coalesce_overlapping_syncs() -> Result<()> {
+    init_logging("wallet=info");
+
+    let hash = hash_password(&SecretString(ENCRYPTION_PASSWORD.to_owned()));
+    let encrypted_descriptors = new_wallet(&hash, &SecretString(SEED_PASSWORD.to_owned())).await?;
+    let decrypted_wallet = decrypt_wallet(&hash, &encrypted_descriptors)?;
+
+    let wallet = get_wallet(
+        &SecretString(decrypted_wallet.private.btc_descriptor_xprv.clone()),
+        None,
+    )
+    .await?;
+    assert_eq!(last_synced_height(&wallet).await, None);
+
+    let syncs = (0..8).map(|_| sync_wallet(&wallet));
+    let results = futures::future::join_all(syncs).await;
+    assert!(results.iter().all(|result| result.is_ok()));
+
+    let current_height = get_blockchain().await.get_height().await?;
+    let synced_height = last_synced_height(&wallet).await;
+    assert!(matches!(synced_height, Some(height) if height <= current_height));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn confirmation_targets_by_fee_policy() -> Result<()> {
+    let policy = parse_fee_policy("issue:6, pay:1")?;
+    assert_eq!(policy.get("issue"), Some(&6));
+    assert_eq!(policy.get("pay"), Some(&1));
+    assert!(parse_fee_policy("pay:0").is_err());
+    assert!(parse_fee_policy("pay").is_err());
+
+    assert_eq!(policy_target("pay:2", FeeOperation::Pay), 2);
+    // Operations missing from the policy keep the default targets
+    assert_eq!(policy_target("pay:2", FeeOperation::Issue), 6);
+    // Invalid policies fall back to the default one
+    assert_eq!(policy_target("pay:soon", FeeOperation::Pay), 1);
+    assert_eq!(policy_target(FEE_POLICY_DEFAULT, FeeOperation::Drain), 6);
+
+    // Explicit fee rates always override the policy
+    assert_eq!(
+        policy_fee_rate("pay:2", FeeOperation::Pay, Some(3.5)).await,
+        Some(3.5)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{
+    env,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use bitcoin_30::secp256k1::{KeyPair, Secp256k1};
+use bitmask_core::{
+    bitcoin::{verify_response, wallet_fingerprint},
+    webhooks::{
+        read_dead_letters, WebhookEvent, WebhookPayload, Webhooks, WEBHOOK_EVENT_HEADER,
+        WEBHOOK_SIGNATURE_HEADER,
+    },
+};
+use serde_json::json;
+
+const SERVER_SK: &str = "a7fef3b1fab3e1b27b4aca3ac1b3e5a6bcd1a5e9b5df9e0ba3b2a0c3e5f7a9b1";
+const WALLET_SK: &str = "5a9c1f0e6b7d2c3a4f8e9d0c1b2a39485766a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+
+#[derive(Clone)]
+struct Receiver {
+    status: StatusCode,
+    requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    receiver.requests.lock().unwrap().push((headers, body));
+    receiver.status
+}
+
+fn serve_receiver(status: StatusCode) -> Result<(String, Receiver)> {
+    let receiver = Receiver {
+        status,
+        requests: Arc::new(Mutex::new(vec![])),
+    };
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(receiver.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/hook", listener.local_addr()?);
+    let server = axum::Server::from_tcp(listener)?.serve(app.into_make_service());
+    tokio::spawn(server);
+
+    Ok((url, receiver))
+}
+
+#[tokio::test]
+pub async fn allow_signed_webhook_delivery() -> Result<()> {
+    let (url, receiver) = serve_receiver(StatusCode::OK)?;
+    let webhooks = Webhooks::new(vec![url.clone()], SERVER_SK)?;
+
+    let data = json!({ "contractId": "rgb:2wHxKf2-..." });
+    let report = webhooks
+        .notify(WebhookEvent::IssuanceComplete, WALLET_SK, &data)
+        .await?;
+    assert_eq!(report.delivered, vec![url]);
+    assert!(report.failed.is_empty());
+
+    let requests = receiver.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    let (headers, body) = &requests[0];
+    assert_eq!(headers[WEBHOOK_EVENT_HEADER], "issuance_complete");
+
+    let secp = Secp256k1::new();
+    let server_pk = KeyPair::from_seckey_str(&secp, SERVER_SK)?
+        .x_only_public_key()
+        .0
+        .to_string();
+    let signature = headers[WEBHOOK_SIGNATURE_HEADER].to_str()?;
+    verify_response(body, signature, &server_pk)?;
+    assert!(verify_response(b"{}", signature, &server_pk).is_err());
+
+    let payload: WebhookPayload = serde_json::from_slice(body)?;
+    assert_eq!(payload.event, WebhookEvent::IssuanceComplete);
+    assert_eq!(payload.wallet, wallet_fingerprint(WALLET_SK)?);
+    assert_eq!(payload.data, data);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn allow_dead_letter_failed_webhooks() -> Result<()> {
+    let dead_letter = env::temp_dir().join("bitmask_webhooks_dead_letter.jsonl");
+    let _ = tokio::fs::remove_file(&dead_letter).await;
+
+    // Server errors are retried until the attempts run out
+    let (url, receiver) = serve_receiver(StatusCode::SERVICE_UNAVAILABLE)?;
+    let webhooks = Webhooks::new(vec![url.clone()], SERVER_SK)?
+        .with_retries(3, Duration::from_millis(1))
+        .with_dead_letter(&dead_letter);
+
+    let data = json!({ "transferId": "utxob:..." });
+    let report = webhooks
+        .notify(WebhookEvent::InvoicePaid, WALLET_SK, &data)
+        .await?;
+    assert!(report.delivered.is_empty());
+    assert_eq!(report.failed, vec![url.clone()]);
+    assert_eq!(receiver.requests.lock().unwrap().len(), 3);
+
+    // Rejected payloads are not
+    let (rejected_url, rejected) = serve_receiver(StatusCode::BAD_REQUEST)?;
+    let webhooks = Webhooks::new(vec![rejected_url.clone()], SERVER_SK)?
+        .with_retries(3, Duration::from_millis(1))
+        .with_dead_letter(&dead_letter);
+    webhooks
+        .notify(WebhookEvent::TransferConfirmed, WALLET_SK, &data)
+        .await?;
+    assert_eq!(rejected.requests.lock().unwrap().len(), 1);
+
+    let entries = read_dead_letters(&dead_letter).await?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].url, url);
+    assert_eq!(entries[0].attempts, 3);
+    assert_eq!(entries[0].payload.event, WebhookEvent::InvoicePaid);
+    assert_eq!(entries[0].payload.data, data);
+    assert_eq!(entries[1].url, rejected_url);
+    assert_eq!(entries[1].attempts, 1);
+
+    Ok(())
+}